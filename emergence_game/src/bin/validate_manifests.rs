@@ -0,0 +1,36 @@
+//! Checks all manifests of the game for errors, without starting the game.
+//!
+//! Run with `cargo run --bin validate_manifests`.
+//! By default, the manifests in the `assets` folder of the game are checked.
+//! Pass the path to a different asset folder as the first argument to check that one instead.
+
+use std::{path::PathBuf, process::ExitCode};
+
+use emergence_lib::asset_management::manifest::{
+    plugin::ManifestLayers, validation::validate_manifests,
+};
+
+fn main() -> ExitCode {
+    let asset_folder = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("assets"));
+
+    let problems = validate_manifests(&asset_folder, &ManifestLayers::default());
+
+    if problems.is_empty() {
+        println!("All manifests in {} are valid.", asset_folder.display());
+        return ExitCode::SUCCESS;
+    }
+
+    for problem in &problems {
+        eprintln!("{problem}");
+    }
+    eprintln!(
+        "Found {} problem(s) in the manifests in {}.",
+        problems.len(),
+        asset_folder.display()
+    );
+
+    ExitCode::FAILURE
+}
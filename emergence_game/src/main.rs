@@ -5,15 +5,23 @@ use emergence_lib::world_gen::GenerationConfig;
 
 fn main() {
     App::new()
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                title: "Emergence".to_string(),
-                present_mode: PresentMode::AutoNoVsync,
-                mode: WindowMode::BorderlessFullscreen,
-                ..default()
-            }),
-            ..Default::default()
-        }))
+        .add_plugins(
+            DefaultPlugins
+                .set(WindowPlugin {
+                    primary_window: Some(Window {
+                        title: "Emergence".to_string(),
+                        present_mode: PresentMode::AutoNoVsync,
+                        mode: WindowMode::BorderlessFullscreen,
+                        ..default()
+                    }),
+                    ..Default::default()
+                })
+                // Manifests and other assets are reloaded when their files change on disk.
+                .set(AssetPlugin {
+                    watch_for_changes: true,
+                    ..Default::default()
+                }),
+        )
         // This is turned on and off in the world gen state management code.
         .add_plugin(FramepacePlugin)
         .add_plugin(emergence_lib::asset_management::AssetManagementPlugin)
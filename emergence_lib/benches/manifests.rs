@@ -0,0 +1,98 @@
+use bevy::utils::HashMap;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use emergence_lib::asset_management::manifest::{loader::IsRawManifest, Id};
+use emergence_lib::crafting::recipe::{
+    ConsumptionMode, OverflowPolicy, QualityRule, RawOutputCount, RawRecipeData, RawRecipeInput,
+    RawRecipeManifest, TimePhase,
+};
+use emergence_lib::items::item_manifest::Item;
+
+/// The number of recipes in the benchmarked manifest, roughly the size of a large modded game.
+const N_RECIPES: usize = 5_000;
+
+/// The number of distinct items used by the recipes.
+const N_ITEMS: usize = 500;
+
+/// A raw recipe manifest with [`N_RECIPES`] recipes, each using three inputs and two outputs.
+fn large_recipe_manifest() -> RawRecipeManifest {
+    let item_name = |i: usize| format!("modded_item_{}", i % N_ITEMS);
+
+    let recipes = (0..N_RECIPES)
+        .map(|i| {
+            let raw_data = RawRecipeData {
+                inputs: RawRecipeInput::Exact(HashMap::from_iter(
+                    (0..3).map(|j| (item_name(i + j), 1)),
+                )),
+                catalysts: HashMap::new(),
+                byproducts: HashMap::new(),
+                output_one_of: Vec::new(),
+                outputs: HashMap::from_iter(
+                    (3..5).map(|j| (item_name(i + j), RawOutputCount::Count(1.))),
+                ),
+                craft_time: 1.,
+                work_units: None,
+                conditions: None,
+                energy: None,
+                allow_mass_gain: false,
+                allow_self_production: false,
+                requires: Vec::new(),
+                research_cost: HashMap::new(),
+                fluid_inputs: HashMap::new(),
+                fluid_outputs: HashMap::new(),
+                consumption: ConsumptionMode::AtStart,
+                overflow: OverflowPolicy::Block,
+                output_slots: HashMap::new(),
+                max_batch: 1,
+                next_recipe: None,
+                output_tier: None,
+                output_quality: QualityRule::default(),
+                category: RawRecipeData::DEFAULT_CATEGORY.to_string(),
+                active_phase: TimePhase::Always,
+                requires_environment: None,
+                enabled: true,
+            };
+
+            (format!("modded_recipe_{i}"), raw_data)
+        })
+        .collect();
+
+    RawRecipeManifest {
+        version: RawRecipeManifest::CURRENT_VERSION,
+        recipes,
+    }
+}
+
+pub fn process_recipe_manifest(c: &mut Criterion) {
+    let raw_recipe_manifest = large_recipe_manifest();
+
+    c.bench_function("process_recipe_manifest", |b| {
+        b.iter(|| black_box(&raw_recipe_manifest).process())
+    });
+}
+
+pub fn load_recipe_manifest(c: &mut Criterion) {
+    let json = serde_json::to_string(&large_recipe_manifest()).unwrap();
+
+    c.bench_function("load_recipe_manifest", |b| {
+        b.iter(|| {
+            serde_json::from_str::<RawRecipeManifest>(black_box(&json))
+                .unwrap()
+                .process()
+                .unwrap()
+        })
+    });
+}
+
+pub fn id_from_name(c: &mut Criterion) {
+    c.bench_function("id_from_name", |b| {
+        b.iter(|| Id::<Item>::from_name(black_box("modded_item_123".to_string())))
+    });
+}
+
+criterion_group!(
+    benches,
+    process_recipe_manifest,
+    load_recipe_manifest,
+    id_from_name
+);
+criterion_main!(benches);
@@ -0,0 +1,411 @@
+//! Property tests that check that raw manifests survive being written to and read from files.
+//!
+//! The raw data is generated randomly, but only refers to items from a fixed list of names,
+//! so that the generated recipes can also be processed against a matching item manifest.
+
+use bevy::utils::HashMap;
+use proptest::{collection, option, prelude::*, sample::select};
+
+use crate::{
+    crafting::{
+        item_tags::ItemTag,
+        recipe::{
+            ConsumptionMode, OutputSlot, OverflowPolicy, QualityRule, RawInputAlternative,
+            RawOutputCount, RawRecipeData, RawRecipeInput, RawRecipeManifest, RawWeightedOutput,
+            RecipeConditions, Threshold, TierRule, TimePhase,
+        },
+    },
+    environment::RawEnvironmentRequirement,
+    items::item_manifest::{RawItemData, RawItemManifest},
+    light::Illuminance,
+    organisms::{energy::Energy, RawOrganismId},
+};
+
+use super::loader::IsRawManifest;
+
+/// The names of all items that generated manifests may refer to.
+const ITEM_NAMES: [&str; 5] = ["leaf", "seed", "pollen", "honey", "mud"];
+
+/// The names of all free-form item categories that generated manifests may refer to.
+const CATEGORY_NAMES: [&str; 2] = ["food", "fuel"];
+
+/// Generates the name of one of the [`ITEM_NAMES`].
+fn item_name() -> impl Strategy<Value = String> {
+    select(&ITEM_NAMES[..]).prop_map(str::to_string)
+}
+
+/// Generates the name of one of the [`CATEGORY_NAMES`].
+fn category_name() -> impl Strategy<Value = String> {
+    select(&CATEGORY_NAMES[..]).prop_map(str::to_string)
+}
+
+/// Generates a map from item names to small, positive counts.
+fn item_counts() -> impl Strategy<Value = HashMap<String, u32>> {
+    collection::hash_map(item_name(), 1..20u32, 0..3)
+        .prop_map(|item_counts| item_counts.into_iter().collect())
+}
+
+/// Generates a positive quantity that can be written to a file without losing precision.
+fn quantity() -> impl Strategy<Value = f32> {
+    0.0..100.0f32
+}
+
+impl Arbitrary for RawItemData {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        let seed = option::of(prop_oneof![
+            item_name().prop_map(RawOrganismId::Structure),
+            item_name().prop_map(RawOrganismId::Unit),
+        ]);
+
+        (
+            (1..100u32, any::<bool>(), any::<bool>(), any::<bool>(), seed),
+            (
+                quantity().prop_map(Energy),
+                collection::vec(category_name(), 0..3),
+                quantity(),
+                option::of(quantity()),
+                option::of(item_name()),
+            ),
+            (
+                any::<u8>(),
+                option::of(any::<String>()),
+                option::of(any::<String>()),
+                option::of(any::<String>()),
+            ),
+        )
+            .prop_map(
+                |(
+                    (stack_size, compostable, fluid, buoyant, seed),
+                    (energy_content, tags, mass, decay_time, decays_into),
+                    (tier, name, description, icon),
+                )| RawItemData {
+                    stack_size,
+                    compostable,
+                    fluid,
+                    buoyant,
+                    seed,
+                    energy_content,
+                    tags,
+                    mass,
+                    decay_time,
+                    decays_into,
+                    tier,
+                    name,
+                    description,
+                    icon,
+                },
+            )
+            .boxed()
+    }
+}
+
+/// Generates any light level.
+fn illuminance() -> impl Strategy<Value = Illuminance> {
+    prop_oneof![
+        Just(Illuminance::Dark),
+        Just(Illuminance::DimlyLit),
+        Just(Illuminance::BrightlyLit),
+    ]
+}
+
+impl Arbitrary for RecipeConditions {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        let light_range = option::of((illuminance(), illuminance()).prop_map(|(a, b)| {
+            if a <= b {
+                Threshold::new(a, b)
+            } else {
+                Threshold::new(b, a)
+            }
+        }));
+
+        (any::<u8>(), light_range)
+            .prop_map(
+                |(workers_required, allowable_light_range)| RecipeConditions {
+                    workers_required,
+                    allowable_light_range,
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for RawRecipeInput {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        let tag = prop_oneof![
+            Just(ItemTag::Compostable),
+            Just(ItemTag::Seed),
+            Just(ItemTag::Fluid),
+            Just(ItemTag::Buoyant),
+        ];
+
+        prop_oneof![
+            item_counts().prop_map(RawRecipeInput::Exact),
+            (tag, 1..10u32).prop_map(|(tag, count)| RawRecipeInput::Flexible { tag, count }),
+            (category_name(), 1..10u32)
+                .prop_map(|(tag, count)| RawRecipeInput::Category { tag, count }),
+            collection::vec(
+                (item_name(), 1..10u32)
+                    .prop_map(|(item, count)| RawInputAlternative { item, count }),
+                1..3
+            )
+            .prop_map(RawRecipeInput::AnyOf),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for RawOutputCount {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        prop_oneof![
+            (1..10u32).prop_map(|count| RawOutputCount::Count(count as f32)),
+            (0.1..10.0f32).prop_map(RawOutputCount::Count),
+            (1..10u32, 0.0..=1.0f32)
+                .prop_map(|(count, chance)| RawOutputCount::Chance { count, chance }),
+            (0..5u32, 1..5u32).prop_map(|(min, spread)| RawOutputCount::Range {
+                min,
+                max: min + spread
+            }),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for RawRecipeData {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        // At least one output, as recipes that produce nothing are rejected
+        // Some of the outputs are routed to the waste slot, which is only valid for items that are produced
+        let outputs =
+            collection::hash_map(item_name(), (any::<RawOutputCount>(), any::<bool>()), 1..3)
+                .prop_map(|outputs| {
+                    let output_slots = outputs
+                        .iter()
+                        .filter(|(_, (_, is_waste))| *is_waste)
+                        .map(|(item_name, _)| (item_name.clone(), OutputSlot::Waste))
+                        .collect::<HashMap<_, _>>();
+                    let outputs = outputs
+                        .into_iter()
+                        .map(|(item_name, (output_count, _))| (item_name, output_count))
+                        .collect::<HashMap<_, _>>();
+                    (outputs, output_slots)
+                });
+        // Multiples of an eighth, so that the amounts are exactly representable in every format
+        let fluid_amounts = || {
+            collection::hash_map(
+                item_name(),
+                (1..800u32).prop_map(|eighths| eighths as f64 / 8.),
+                0..3,
+            )
+            .prop_map(|amounts| amounts.into_iter().collect::<HashMap<_, _>>())
+        };
+        let weighted_output =
+            (item_name(), 1..10u32, quantity()).prop_map(|(item, count, weight)| {
+                RawWeightedOutput {
+                    item,
+                    count,
+                    weight,
+                }
+            });
+
+        (
+            (
+                any::<RawRecipeInput>(),
+                item_counts(),
+                outputs,
+                collection::vec(weighted_output, 0..3),
+                item_counts(),
+                fluid_amounts(),
+                fluid_amounts(),
+                option::of(0.1..100.0f32),
+                any::<bool>(),
+                any::<bool>(),
+                prop_oneof![
+                    Just(QualityRule::Average),
+                    Just(QualityRule::Min),
+                    (0.0..1.0f32).prop_map(QualityRule::Fixed),
+                ],
+                option::of((0..5u32).prop_map(|i| format!("recipe_{i}"))),
+            ),
+            (
+                (0.1..10.0f32, 1..5u32),
+                option::of(any::<RecipeConditions>()),
+                option::of((-100.0..100.0f32).prop_map(Energy)),
+                collection::vec((0..5u32).prop_map(|i| format!("recipe_{i}")), 0..3),
+                item_counts(),
+                prop_oneof![
+                    Just(ConsumptionMode::AtStart),
+                    Just(ConsumptionMode::AtEnd),
+                    Just(ConsumptionMode::Continuous),
+                ],
+                prop_oneof![
+                    Just(OverflowPolicy::Block),
+                    Just(OverflowPolicy::Discard),
+                    Just(OverflowPolicy::Void),
+                ],
+                option::of(prop_oneof![
+                    (0..5u8).prop_map(TierRule::Fixed),
+                    Just(TierRule::MatchInput),
+                    Just(TierRule::InputPlusOne),
+                ]),
+                select(vec!["Food", "Construction", "Uncategorized"]).prop_map(String::from),
+                prop_oneof![
+                    Just(TimePhase::Always),
+                    Just(TimePhase::Day),
+                    Just(TimePhase::Night),
+                ],
+                option::of(
+                    (
+                        select(vec!["temperature", "humidity"]),
+                        -50.0..50.0f32,
+                        0.0..50.0f32,
+                    )
+                        .prop_map(|(key, min, width)| {
+                            RawEnvironmentRequirement {
+                                key: key.to_string(),
+                                min,
+                                max: min + width,
+                            }
+                        }),
+                ),
+                any::<bool>(),
+            ),
+        )
+            .prop_map(
+                |(
+                    (
+                        inputs,
+                        catalysts,
+                        (outputs, output_slots),
+                        output_one_of,
+                        byproducts,
+                        fluid_inputs,
+                        fluid_outputs,
+                        work_units,
+                        allow_mass_gain,
+                        allow_self_production,
+                        output_quality,
+                        next_recipe,
+                    ),
+                    (
+                        (craft_time, max_batch),
+                        conditions,
+                        energy,
+                        requires,
+                        research_cost,
+                        consumption,
+                        overflow,
+                        output_tier,
+                        category,
+                        active_phase,
+                        requires_environment,
+                        enabled,
+                    ),
+                )| RawRecipeData {
+                    inputs,
+                    catalysts,
+                    outputs,
+                    output_one_of,
+                    byproducts,
+                    fluid_inputs,
+                    fluid_outputs,
+                    craft_time,
+                    work_units,
+                    conditions,
+                    energy,
+                    allow_mass_gain,
+                    allow_self_production,
+                    requires,
+                    research_cost,
+                    consumption,
+                    overflow,
+                    output_slots,
+                    max_batch,
+                    next_recipe,
+                    output_tier,
+                    output_quality,
+                    category,
+                    active_phase,
+                    requires_environment,
+                    enabled,
+                },
+            )
+            .boxed()
+    }
+}
+
+proptest! {
+    #[test]
+    fn raw_items_round_trip_through_json(raw_item_data in any::<RawItemData>()) {
+        let json = serde_json::to_string(&raw_item_data).unwrap();
+        let deserialized: RawItemData = serde_json::from_str(&json).unwrap();
+
+        prop_assert_eq!(deserialized, raw_item_data);
+    }
+
+    #[test]
+    fn raw_recipes_round_trip_through_json(raw_recipe_data in any::<RawRecipeData>()) {
+        let json = serde_json::to_string(&raw_recipe_data).unwrap();
+        let deserialized: RawRecipeData = serde_json::from_str(&json).unwrap();
+
+        prop_assert_eq!(deserialized, raw_recipe_data);
+    }
+
+    #[test]
+    fn recipes_with_known_items_can_be_processed(
+        raw_items in collection::vec(any::<RawItemData>(), ITEM_NAMES.len()),
+        raw_recipes in collection::vec(any::<RawRecipeData>(), 1..5),
+    ) {
+        // Every category must have at least one item for the category inputs to be valid
+        let raw_item_manifest = RawItemManifest {
+            version: RawItemManifest::CURRENT_VERSION,
+            items: ITEM_NAMES
+                .iter()
+                .zip(raw_items)
+                .map(|(name, raw_item_data)| {
+                    let tags = CATEGORY_NAMES.iter().map(|tag| tag.to_string()).collect();
+                    (name.to_string(), RawItemData { tags, ..raw_item_data })
+                })
+                .collect(),
+        };
+        let raw_recipe_manifest = RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: raw_recipes
+                .into_iter()
+                .enumerate()
+                .map(|(i, raw_recipe_data)| {
+                    // Random prerequisites and chains are likely to be cyclic or refer to disabled recipes
+                    let raw_recipe_data = RawRecipeData {
+                        requires: Vec::new(),
+                        next_recipe: None,
+                        ..raw_recipe_data
+                    };
+                    (format!("recipe_{i}"), raw_recipe_data)
+                })
+                .collect(),
+        };
+
+        let item_manifest = raw_item_manifest.process().unwrap();
+        let recipe_manifest = raw_recipe_manifest.process().unwrap();
+
+        prop_assert_eq!(raw_recipe_manifest.validate_items(&item_manifest), Ok(()));
+        for (_, recipe_data) in &recipe_manifest {
+            recipe_data.input_inventory(&item_manifest);
+            recipe_data.output_inventory(&item_manifest);
+            recipe_data.items_per_second();
+        }
+    }
+}
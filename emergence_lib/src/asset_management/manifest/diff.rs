@@ -0,0 +1,153 @@
+//! Comparing two versions of a manifest, to see what changed between them.
+//!
+//! This is intended for balancing: the differences can be used to write changelogs,
+//! or to review changes to the manifests.
+
+use std::fmt::Debug;
+
+use super::Manifest;
+
+/// Manifest data that can report what changed between two versions of an entry.
+pub trait Diffable {
+    /// A single change to an entry.
+    type Change: Debug + Clone + PartialEq;
+
+    /// Lists the changes needed to turn `self` into `other`.
+    ///
+    /// This is empty if the entries are the same.
+    fn changes_to(&self, other: &Self) -> Vec<Self::Change>;
+}
+
+/// Do the two lists hold the same elements, no matter their order?
+///
+/// Many lists in manifest data are built from maps, so their order carries no meaning.
+pub(crate) fn same_elements<E: PartialEq>(a: &[E], b: &[E]) -> bool {
+    a.len() == b.len() && a.iter().all(|element| b.contains(element))
+}
+
+/// An entry that exists in both versions of a manifest, but with different data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModifiedEntry<Change> {
+    /// The name of the entry.
+    pub name: String,
+    /// What changed about the entry.
+    pub changes: Vec<Change>,
+}
+
+/// The differences between two versions of a manifest, as created by [`Manifest::diff`].
+///
+/// All entries are sorted by name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestDiff<Change> {
+    /// The names of the entries that only exist in the new version.
+    pub added: Vec<String>,
+    /// The names of the entries that only exist in the old version.
+    pub removed: Vec<String>,
+    /// The entries that exist in both versions, but have changed.
+    pub modified: Vec<ModifiedEntry<Change>>,
+}
+
+impl<Change> ManifestDiff<Change> {
+    /// Are both versions of the manifest the same?
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+impl<T, Data> Manifest<T, Data>
+where
+    Data: Debug + Diffable,
+{
+    /// Compares this manifest, as the old version, with the `other` manifest, as the new version.
+    pub fn diff(&self, other: &Self) -> ManifestDiff<Data::Change> {
+        let mut added: Vec<String> = Vec::new();
+        let mut modified = Vec::new();
+
+        for (id, new_data) in other {
+            let name = other.name(id).to_string();
+
+            match self.data_map.get(&id) {
+                None => added.push(name),
+                Some(old_data) => {
+                    let changes = old_data.changes_to(new_data);
+                    if !changes.is_empty() {
+                        modified.push(ModifiedEntry { name, changes });
+                    }
+                }
+            }
+        }
+
+        let mut removed: Vec<String> = self
+            .iter()
+            .filter(|(id, _)| !other.data_map.contains_key(id))
+            .map(|(id, _)| self.name(id).to_string())
+            .collect();
+
+        added.sort();
+        removed.sort();
+        modified.sort_by(|a, b| a.name.cmp(&b.name));
+
+        ManifestDiff {
+            added,
+            removed,
+            modified,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A marker type for the test manifest.
+    struct Fruit;
+
+    /// The weight of a fruit, which changes by the difference between the weights.
+    #[derive(Debug, Clone, PartialEq)]
+    struct Weight(i32);
+
+    impl Diffable for Weight {
+        type Change = i32;
+
+        fn changes_to(&self, other: &Self) -> Vec<i32> {
+            match other.0 - self.0 {
+                0 => Vec::new(),
+                delta => vec![delta],
+            }
+        }
+    }
+
+    /// A manifest of fruits with the given weights.
+    fn fruit_manifest(fruits: &[(&str, i32)]) -> Manifest<Fruit, Weight> {
+        let mut manifest = Manifest::new();
+        for &(name, weight) in fruits {
+            manifest.insert(name.to_string(), Weight(weight));
+        }
+        manifest
+    }
+
+    #[test]
+    fn identical_manifests_have_no_differences() {
+        let manifest = fruit_manifest(&[("apple", 150), ("pear", 170)]);
+
+        assert!(manifest.diff(&manifest).is_empty());
+    }
+
+    #[test]
+    fn added_removed_and_modified_entries_are_sorted() {
+        let old = fruit_manifest(&[("apple", 150), ("pear", 170), ("plum", 40), ("fig", 50)]);
+        let new = fruit_manifest(&[("pear", 160), ("apple", 150), ("lime", 60), ("kiwi", 70)]);
+
+        assert_eq!(
+            old.diff(&new),
+            ManifestDiff {
+                added: vec!["kiwi".to_string(), "lime".to_string()],
+                removed: vec!["fig".to_string(), "plum".to_string()],
+                modified: vec![ModifiedEntry {
+                    name: "pear".to_string(),
+                    changes: vec![-10],
+                }],
+            }
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Durations in manifest files, which may be written as a number of seconds or as a humanized string such as `"1m30s"`.
+
+use std::{fmt::Display, time::Duration};
+
+use schemars::JsonSchema;
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+
+/// A duration as written in a manifest file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum RawDuration {
+    /// A number of seconds, such as `90`.
+    Seconds(f32),
+    /// A sequence of amounts with units, such as `"1m30s"`.
+    ///
+    /// The units are `ms`, `s`, `m` and `h`, and the amounts may be fractional, as in `"1.5h"`.
+    Humanized(String),
+}
+
+impl RawDuration {
+    /// The number of seconds that this duration lasts.
+    ///
+    /// Numbers are returned as they are, so that out-of-range values can be reported by the caller.
+    pub fn as_secs_f32(&self) -> Result<f32, InvalidDuration> {
+        match self {
+            RawDuration::Seconds(seconds) => Ok(*seconds),
+            RawDuration::Humanized(text) => {
+                parse_duration(text).map(|duration| duration.as_secs_f32())
+            }
+        }
+    }
+}
+
+/// A humanized duration that could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidDuration(pub String);
+
+impl Display for InvalidDuration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "`{}` is not a valid duration, expected a number of seconds or a string such as \"1m30s\"",
+            self.0
+        )
+    }
+}
+
+/// Parses a humanized duration, such as `"90s"`, `"1m30s"` or `"1.5h"`.
+///
+/// The duration is the sum of all of its amounts, each of which must be followed by one of the units `ms`, `s`, `m` or `h`.
+/// Whitespace between the amounts is allowed.
+pub fn parse_duration(text: &str) -> Result<Duration, InvalidDuration> {
+    let invalid = || InvalidDuration(text.to_string());
+
+    let mut rest = text.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut seconds = 0.;
+    while !rest.is_empty() {
+        let (amount, after_amount) = rest.split_at(
+            rest.find(|c: char| !c.is_ascii_digit() && c != '.')
+                .unwrap_or(rest.len()),
+        );
+        let (unit, after_unit) = after_amount.split_at(
+            after_amount
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(after_amount.len()),
+        );
+
+        let amount: f64 = amount.parse().map_err(|_| invalid())?;
+        let unit_seconds = match unit {
+            "ms" => 0.001,
+            "s" => 1.,
+            "m" => 60.,
+            "h" => 3600.,
+            _ => return Err(invalid()),
+        };
+
+        seconds += amount * unit_seconds;
+        rest = after_unit.trim_start();
+    }
+
+    Duration::try_from_secs_f64(seconds).map_err(|_| invalid())
+}
+
+/// Deserializes a [`RawDuration`] into a number of seconds.
+///
+/// Use this with `#[serde(deserialize_with = "...")]` on fields that store seconds as a plain number,
+/// so that they serialize as numbers but can be written either way.
+pub fn deserialize_seconds<'de, D>(deserializer: D) -> Result<f32, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    RawDuration::deserialize(deserializer)?
+        .as_secs_f32()
+        .map_err(D::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn humanized_durations_add_up_their_amounts() {
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("1m30s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("1m 30s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("1.5h"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+    }
+
+    #[test]
+    fn malformed_durations_are_rejected() {
+        for text in ["", "90", "s", "1x", "1m30", "-5s", "1..5s"] {
+            assert_eq!(
+                parse_duration(text),
+                Err(InvalidDuration(text.to_string())),
+                "`{text}` should not parse"
+            );
+        }
+    }
+
+    #[test]
+    fn numbers_and_strings_deserialize_to_the_same_seconds() {
+        /// A manifest entry with a duration.
+        #[derive(Deserialize)]
+        struct Entry {
+            /// The duration, in seconds.
+            #[serde(deserialize_with = "deserialize_seconds")]
+            time: f32,
+        }
+
+        let numeric: Entry = serde_json::from_str(r#"{ "time": 90 }"#).unwrap();
+        let humanized: Entry = serde_json::from_str(r#"{ "time": "1m30s" }"#).unwrap();
+        assert_eq!(numeric.time, 90.);
+        assert_eq!(humanized.time, 90.);
+
+        assert!(serde_json::from_str::<Entry>(r#"{ "time": "soon" }"#).is_err());
+    }
+}
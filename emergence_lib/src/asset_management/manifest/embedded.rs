@@ -0,0 +1,121 @@
+//! Manifest files that are baked into the binary, for builds that should not depend on loose files.
+//!
+//! Only the files of the base game are embedded.
+//! Each file is keyed by its path relative to the asset folder,
+//! as returned by [`IsRawManifest::layer_path_with_format`].
+//! Layers without an embedded file are still loaded from the asset folder.
+
+use std::path::Path;
+
+use super::{
+    errors::ManifestError,
+    loader::{IsRawManifest, ManifestFormat},
+    plugin::ManifestLayers,
+    validation::{parse_layer_files, LayerFile},
+};
+
+/// Embeds the manifest file at `path`, relative to the asset folder of the game.
+macro_rules! embed_manifest {
+    ($path:literal) => {
+        (
+            $path,
+            include_bytes!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/../emergence_game/assets/",
+                $path
+            ))
+            .as_slice(),
+        )
+    };
+}
+
+/// The contents of every embedded manifest file, keyed by their path relative to the asset folder.
+const EMBEDDED_FILES: &[(&str, &[u8])] = &[
+    embed_manifest!("manifests/base_game.environment_manifest.json"),
+    embed_manifest!("manifests/base_game.item_manifest.json"),
+    embed_manifest!("manifests/base_game.recipe_manifest.json"),
+    embed_manifest!("manifests/base_game.starter_manifest.json"),
+    embed_manifest!("manifests/base_game.structure_manifest.json"),
+    embed_manifest!("manifests/base_game.terrain_manifest.json"),
+    embed_manifest!("manifests/base_game.unit_manifest.json"),
+];
+
+/// The contents of the embedded file at `path`, relative to the asset folder.
+pub fn embedded_file(path: &Path) -> Option<&'static [u8]> {
+    EMBEDDED_FILES
+        .iter()
+        .find(|(embedded_path, _)| Path::new(embedded_path) == path)
+        .map(|(_, bytes)| *bytes)
+}
+
+/// The embedded file of the given `layer` of the manifest type `M`, if there is one.
+///
+/// Like the asset loader, this uses the first format that has a file.
+pub fn embedded_layer_file<M>(layer: &str) -> Option<LayerFile>
+where
+    M: IsRawManifest,
+{
+    ManifestFormat::ALL.into_iter().find_map(|format| {
+        let path = M::layer_path_with_format(layer, format);
+        let bytes = embedded_file(&path)?;
+
+        Some(LayerFile {
+            path,
+            format,
+            bytes: bytes.to_vec(),
+        })
+    })
+}
+
+/// Parses and merges the embedded files of all layers of the manifest type `M`, without touching the filesystem.
+///
+/// Layers without an embedded file are skipped.
+/// The [`ManifestLayers::directories`] are not searched, as only files listed ahead of time are embedded.
+/// Fails with [`ManifestError::FileNotFound`] if no layer has an embedded file.
+pub fn read_embedded_manifest<M>(manifest_layers: &ManifestLayers) -> Result<M, ManifestError>
+where
+    M: IsRawManifest,
+{
+    let layer_files: Vec<LayerFile> = manifest_layers
+        .layers
+        .iter()
+        .filter_map(|layer| embedded_layer_file::<M>(layer))
+        .collect();
+
+    parse_layer_files(&layer_files, manifest_layers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        asset_management::manifest::{loader::BASE_GAME_LAYER, Id},
+        items::item_manifest::{Item, ItemManifest, RawItemManifest},
+    };
+
+    #[test]
+    fn item_manifest_loads_from_embedded_data() {
+        let manifest_layers = ManifestLayers {
+            // The second layer is neither embedded nor on disk, and is skipped
+            layers: vec![BASE_GAME_LAYER.to_string(), "mods/missing".to_string()],
+            directories: Vec::new(),
+            strict: false,
+        };
+
+        let manifest: ItemManifest = read_embedded_manifest::<RawItemManifest>(&manifest_layers)
+            .unwrap()
+            .process()
+            .unwrap();
+
+        assert!(!manifest.is_empty());
+        assert!(manifest
+            .get(Id::<Item>::from_name("leuco_chunk".to_string()))
+            .is_ok());
+    }
+
+    #[test]
+    fn only_known_paths_are_embedded() {
+        assert!(embedded_file(Path::new("manifests/base_game.item_manifest.json")).is_some());
+        assert!(embedded_file(Path::new("manifests/missing.item_manifest.json")).is_none());
+    }
+}
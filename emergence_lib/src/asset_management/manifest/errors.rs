@@ -0,0 +1,410 @@
+//! Errors related to loading and processing manifests.
+
+use std::{fmt::Display, path::PathBuf};
+
+/// A reference from one manifest entry to a name that does not exist.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DanglingReference {
+    /// The name of the entry that contains the reference.
+    pub entry: String,
+    /// The name that could not be resolved.
+    pub name: String,
+}
+
+impl Display for DanglingReference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}` references unknown `{}`", self.entry, self.name)
+    }
+}
+
+/// A recipe that produces much more energy than its inputs contain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnergyImbalance {
+    /// The name of the recipe.
+    pub recipe: String,
+    /// The total energy contained in the inputs of the recipe.
+    pub input_energy: f32,
+    /// The energy produced by the recipe.
+    pub output_energy: f32,
+}
+
+impl Display for EnergyImbalance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "`{}` produces {} energy from inputs containing {} energy",
+            self.recipe, self.output_energy, self.input_energy
+        )
+    }
+}
+
+/// An output of a recipe that is not of the tier required by its [`TierRule`](crate::crafting::recipe::TierRule).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TierMismatch {
+    /// The name of the recipe.
+    pub recipe: String,
+    /// The name of the output item.
+    pub item: String,
+    /// The tier that the outputs of the recipe must have.
+    pub expected: u8,
+    /// The tier of the output item.
+    pub found: u8,
+}
+
+impl Display for TierMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "`{}` produces `{}` of tier {}, but its outputs must be of tier {}",
+            self.recipe, self.item, self.found, self.expected
+        )
+    }
+}
+
+/// A numeric field of a manifest entry with a value that makes no sense for it.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct OutOfRangeValue {
+    /// The name of the entry that contains the value.
+    pub entry: String,
+    /// The path to the field within the entry, such as `inputs.leaf`.
+    pub field: String,
+    /// The value that was found, as written.
+    pub value: String,
+    /// A description of the values that are allowed, such as `at least 1`.
+    pub expected: &'static str,
+}
+
+impl Display for OutOfRangeValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "`{}`: {} is {}, but must be {}",
+            self.entry, self.field, self.value, self.expected
+        )
+    }
+}
+
+/// A problem that prevents a raw recipe from being converted into a usable recipe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RecipeProblem {
+    /// The recipe produces no items, fluids or energy, so crafting it would only waste its inputs.
+    NoOutputs,
+    /// The craft time is negative, infinite or not a number.
+    InvalidCraftTime,
+    /// The recipe needs one of several alternative inputs, but lists none of them.
+    NoAlternatives,
+    /// The recipe assigns an output slot to an item that it does not produce.
+    OutputSlotOfMissingItem,
+}
+
+impl Display for RecipeProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecipeProblem::NoOutputs => write!(f, "produces nothing"),
+            RecipeProblem::InvalidCraftTime => write!(f, "has an invalid craft time"),
+            RecipeProblem::NoAlternatives => write!(f, "has no alternative inputs to choose from"),
+            RecipeProblem::OutputSlotOfMissingItem => {
+                write!(
+                    f,
+                    "assigns an output slot to an item that it does not produce"
+                )
+            }
+        }
+    }
+}
+
+/// A recipe that could not be converted from its raw form.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct InvalidRecipe {
+    /// The name of the recipe.
+    pub recipe: String,
+    /// What is wrong with the recipe.
+    pub problem: RecipeProblem,
+}
+
+impl Display for InvalidRecipe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}` {}", self.recipe, self.problem)
+    }
+}
+
+/// Failed to process or validate a manifest.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ManifestError {
+    /// No file exists for the manifest at the given path, in any of the supported formats.
+    ///
+    /// The path does not include the extension of the format.
+    FileNotFound(PathBuf),
+    /// The manifest file could not be parsed.
+    ParseError {
+        /// The path to the manifest file.
+        path: PathBuf,
+        /// The line of the file at which the problem was found, starting at 1.
+        line: usize,
+        /// The column of the line at which the problem was found, starting at 1.
+        column: usize,
+        /// A description of the problem.
+        message: String,
+    },
+    /// An [`Id`](super::Id) was looked up in a manifest that does not contain it.
+    UnknownId {
+        /// The name of the type identified by the ID.
+        kind: &'static str,
+        /// The number that identifies the ID.
+        id: u64,
+        /// The human-readable name of the ID, if it is known.
+        name: Option<String>,
+    },
+    /// Recipes are defined, but the item manifest is empty, so none of their items can exist.
+    NoItems,
+    /// One or more entries refer to names that are not defined in the manifest they point to.
+    UnknownReference(Vec<DanglingReference>),
+    /// The same names were defined more than once where entries may not override each other.
+    ///
+    /// This happens when a single file repeats a name, or when layers overlap in strict mode.
+    DuplicateId(Vec<String>),
+    /// One or more recipes produce more energy than allowed by the [`RecipeEnergyBalance`](crate::crafting::recipe::RecipeEnergyBalance).
+    UnbalancedEnergy(Vec<EnergyImbalance>),
+    /// One or more recipes produce items of a different tier than their [`TierRule`](crate::crafting::recipe::TierRule) requires.
+    MismatchedTier(Vec<TierMismatch>),
+    /// One or more recipes that need no workers have no craft time, and would complete every frame.
+    ZeroCraftTime(Vec<String>),
+    /// One or more recipes produce more of one of their own inputs than they consume, without being allowed to.
+    ///
+    /// Such recipes can be crafted over and over to create an endless supply of items.
+    SelfProducing(Vec<String>),
+    /// Some recipes require each other to be discovered first, so none of them can ever become available.
+    ///
+    /// The recipes are listed in order, with each one requiring the next, and the last one requiring the first.
+    CyclicPrerequisites(Vec<String>),
+    /// Some recipes lead into each other as their [next recipe](crate::crafting::recipe::RecipeData::next_recipe),
+    /// so crafters following the chain would never finish it.
+    ///
+    /// The recipes are listed in order, with each one leading into the next, and the last one leading into the first.
+    CyclicRecipeChain(Vec<String>),
+    /// One or more numeric fields hold values that are out of range, such as a stack size of zero.
+    OutOfRange(Vec<OutOfRangeValue>),
+    /// One or more recipes could not be converted into usable recipes, such as recipes that produce nothing.
+    InvalidRecipe(Vec<InvalidRecipe>),
+    /// The manifest file was written for a different version of the manifest format.
+    VersionMismatch {
+        /// The version that the game can read.
+        expected: u32,
+        /// The version declared by the manifest file, if any.
+        found: Option<u32>,
+    },
+}
+
+impl Display for ManifestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ManifestError::FileNotFound(path) => {
+                write!(f, "Manifest file {} not found", path.display())
+            }
+            ManifestError::ParseError {
+                path,
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "Could not parse {} at line {line}, column {column}: {message}",
+                path.display()
+            ),
+            ManifestError::UnknownId {
+                kind,
+                id,
+                name: Some(name),
+            } => write!(f, "Unknown {kind} `{name}` (ID {id})"),
+            ManifestError::UnknownId {
+                kind,
+                id,
+                name: None,
+            } => write!(f, "Unknown {kind} with ID {id}"),
+            ManifestError::NoItems => {
+                write!(f, "No items are defined, but recipes require them")
+            }
+            ManifestError::UnknownReference(references) => {
+                write!(f, "Unknown references:")?;
+                for reference in references {
+                    write!(f, "\n- {reference}")?;
+                }
+                Ok(())
+            }
+            ManifestError::DuplicateId(names) => {
+                write!(f, "Names defined more than once:")?;
+                for name in names {
+                    write!(f, "\n- `{name}`")?;
+                }
+                Ok(())
+            }
+            ManifestError::UnbalancedEnergy(imbalances) => {
+                write!(f, "Recipes with unbalanced energy:")?;
+                for imbalance in imbalances {
+                    write!(f, "\n- {imbalance}")?;
+                }
+                Ok(())
+            }
+            ManifestError::MismatchedTier(mismatches) => {
+                write!(f, "Recipes with outputs of the wrong tier:")?;
+                for mismatch in mismatches {
+                    write!(f, "\n- {mismatch}")?;
+                }
+                Ok(())
+            }
+            ManifestError::ZeroCraftTime(recipes) => {
+                write!(f, "Recipes without workers or craft time:")?;
+                for recipe in recipes {
+                    write!(f, "\n- `{recipe}`")?;
+                }
+                Ok(())
+            }
+            ManifestError::SelfProducing(recipes) => {
+                write!(f, "Recipes that multiply their own inputs:")?;
+                for recipe in recipes {
+                    write!(f, "\n- `{recipe}`")?;
+                }
+                Ok(())
+            }
+            ManifestError::CyclicPrerequisites(recipes) => {
+                write!(f, "Recipes require each other to be discovered first:")?;
+                for recipe in recipes {
+                    write!(f, " `{recipe}` ->")?;
+                }
+                match recipes.first() {
+                    Some(first) => write!(f, " `{first}`"),
+                    None => Ok(()),
+                }
+            }
+            ManifestError::CyclicRecipeChain(recipes) => {
+                write!(f, "Recipes lead into each other forever:")?;
+                for recipe in recipes {
+                    write!(f, " `{recipe}` ->")?;
+                }
+                match recipes.first() {
+                    Some(first) => write!(f, " `{first}`"),
+                    None => Ok(()),
+                }
+            }
+            ManifestError::OutOfRange(values) => {
+                write!(f, "Values out of range:")?;
+                for value in values {
+                    write!(f, "\n- {value}")?;
+                }
+                Ok(())
+            }
+            ManifestError::InvalidRecipe(recipes) => {
+                write!(f, "Invalid recipes:")?;
+                for recipe in recipes {
+                    write!(f, "\n- {recipe}")?;
+                }
+                Ok(())
+            }
+            ManifestError::VersionMismatch {
+                expected,
+                found: Some(found),
+            } => write!(
+                f,
+                "The manifest is version {found}, but version {expected} was expected"
+            ),
+            ManifestError::VersionMismatch {
+                expected,
+                found: None,
+            } => write!(
+                f,
+                "The manifest does not declare a version, but version {expected} was expected"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ManifestError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_files_and_parse_errors_name_the_path() {
+        let path = PathBuf::from("manifests/items");
+        assert_eq!(
+            ManifestError::FileNotFound(path.clone()).to_string(),
+            "Manifest file manifests/items not found"
+        );
+
+        let parse_error = ManifestError::ParseError {
+            path,
+            line: 3,
+            column: 14,
+            message: "expected `:`".to_string(),
+        };
+        assert_eq!(
+            parse_error.to_string(),
+            "Could not parse manifests/items at line 3, column 14: expected `:`"
+        );
+    }
+
+    #[test]
+    fn unknown_ids_name_the_kind_and_id() {
+        let named = ManifestError::UnknownId {
+            kind: "Item",
+            id: 42,
+            name: Some("honey".to_string()),
+        };
+        assert_eq!(named.to_string(), "Unknown Item `honey` (ID 42)");
+
+        let unnamed = ManifestError::UnknownId {
+            kind: "Item",
+            id: 42,
+            name: None,
+        };
+        assert_eq!(unnamed.to_string(), "Unknown Item with ID 42");
+    }
+
+    #[test]
+    fn duplicates_and_unknown_references_list_every_name() {
+        let duplicates = ManifestError::DuplicateId(vec!["leaf".to_string(), "honey".to_string()]);
+        assert_eq!(
+            duplicates.to_string(),
+            "Names defined more than once:\n- `leaf`\n- `honey`"
+        );
+
+        let references = ManifestError::UnknownReference(vec![DanglingReference {
+            entry: "make_honey".to_string(),
+            name: "pollen".to_string(),
+        }]);
+        assert_eq!(
+            references.to_string(),
+            "Unknown references:\n- `make_honey` references unknown `pollen`"
+        );
+    }
+
+    #[test]
+    fn validation_errors_name_the_offending_entries() {
+        let out_of_range = ManifestError::OutOfRange(vec![OutOfRangeValue {
+            entry: "make_honey".to_string(),
+            field: "max_batch".to_string(),
+            value: "0".to_string(),
+            expected: "at least 1",
+        }]);
+        assert_eq!(
+            out_of_range.to_string(),
+            "Values out of range:\n- `make_honey`: max_batch is 0, but must be at least 1"
+        );
+
+        let invalid_recipe = ManifestError::InvalidRecipe(vec![InvalidRecipe {
+            recipe: "make_nothing".to_string(),
+            problem: RecipeProblem::NoOutputs,
+        }]);
+        assert_eq!(
+            invalid_recipe.to_string(),
+            "Invalid recipes:\n- `make_nothing` produces nothing"
+        );
+
+        let cycle = ManifestError::CyclicPrerequisites(vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(
+            cycle.to_string(),
+            "Recipes require each other to be discovered first: `a` -> `b` -> `a`"
+        );
+    }
+}
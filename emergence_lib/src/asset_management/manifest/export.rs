@@ -0,0 +1,257 @@
+//! Writing processed manifests back to their raw form, so that changes made at runtime can be saved to disk.
+//!
+//! Processed data only stores [`Id`]s, which cannot be turned back into names on their own.
+//! Names are instead looked up in a [`NameLookup`], which collects them from other manifests.
+//! The same lookup can be put [in scope](NameLookup::scope) to write [`Id`]s by name in save files.
+
+use std::{cell::RefCell, fmt::Debug};
+
+use bevy::utils::HashMap;
+use serde::Serialize;
+
+use super::{errors::ManifestError, loader::IsRawManifest, Id, Manifest};
+
+/// The names of [`Id`]s, used to turn processed manifest data back into raw data.
+///
+/// The [`Id`] of a name does not depend on its type, so a single lookup can hold names of all kinds.
+#[derive(Debug, Clone, Default)]
+pub struct NameLookup {
+    /// The name that created the ID with this value.
+    names: HashMap<u64, String>,
+}
+
+impl NameLookup {
+    /// Creates a lookup without any names.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the names of all entries of the `manifest`.
+    pub fn add_manifest<T, Data: Debug>(&mut self, manifest: &Manifest<T, Data>) {
+        for (id, name) in manifest.name_map() {
+            self.names.insert(id.value(), name.clone());
+        }
+    }
+
+    /// Adds names that are not the entries of any manifest, such as the names of item categories.
+    pub fn add_names(&mut self, names: impl IntoIterator<Item = String>) {
+        for name in names {
+            self.names
+                .insert(Id::<()>::from_name(name.clone()).value(), name);
+        }
+    }
+
+    /// The name of the `id`.
+    ///
+    /// Fails with [`ManifestError::UnknownId`] if the name was never added.
+    pub fn name<T>(&self, id: Id<T>) -> Result<String, ManifestError> {
+        self.names
+            .get(&id.value())
+            .cloned()
+            .ok_or_else(|| ManifestError::UnknownId {
+                kind: std::any::type_name::<T>(),
+                id: id.value(),
+                name: None,
+            })
+    }
+
+    /// Runs `f` with this lookup in scope, so that every [`Id`] serialized by `f` is written as its name.
+    ///
+    /// Names do not depend on how IDs are computed, so save files written this way keep working
+    /// when the manifests change, and can be read by people.
+    /// IDs are read back from either form, so no scope is needed to deserialize them.
+    ///
+    /// Only human-readable formats, such as JSON, are affected.
+    /// [Displaying](std::fmt::Display) an [`Id`] also shows its name while this lookup is in scope.
+    /// Serializing an [`Id`] whose name is not in this lookup fails with [`ManifestError::UnknownId`].
+    pub fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        /// Restores the previous scope once `f` is done, even if it panics.
+        struct ScopeGuard(Option<NameLookup>);
+
+        impl Drop for ScopeGuard {
+            fn drop(&mut self) {
+                NAMES_IN_SCOPE.with(|names| *names.borrow_mut() = self.0.take());
+            }
+        }
+
+        let _guard = ScopeGuard(NAMES_IN_SCOPE.with(|names| names.replace(Some(self.clone()))));
+        f()
+    }
+
+    /// The name of the `id`, as found in the lookup that is currently [in scope](Self::scope).
+    ///
+    /// Returns [`None`] if no lookup is in scope.
+    pub(crate) fn name_in_scope<T>(id: Id<T>) -> Option<Result<String, ManifestError>> {
+        NAMES_IN_SCOPE.with(|names| names.borrow().as_ref().map(|names| names.name(id)))
+    }
+}
+
+thread_local! {
+    /// The lookup used to serialize [`Id`]s by name, as set by [`NameLookup::scope`].
+    static NAMES_IN_SCOPE: RefCell<Option<NameLookup>> = const { RefCell::new(None) };
+}
+
+/// Processed manifest data that can be turned back into the raw data that it was created from.
+pub trait ToRaw: Sized {
+    /// The raw manifest that stores this data.
+    type RawManifest: IsRawManifest + Serialize;
+
+    /// Converts this entry back into its raw form, looking up the names of all [`Id`]s in `names`.
+    fn to_raw(
+        &self,
+        names: &NameLookup,
+    ) -> Result<<Self::RawManifest as IsRawManifest>::RawData, ManifestError>;
+
+    /// Creates a raw manifest of the current version that contains the `entries`.
+    fn raw_manifest(
+        entries: HashMap<String, <Self::RawManifest as IsRawManifest>::RawData>,
+    ) -> Self::RawManifest;
+}
+
+impl<T, Data> Manifest<T, Data>
+where
+    Data: Debug + ToRaw,
+{
+    /// Converts this manifest back into its raw form.
+    ///
+    /// The names of the entries themselves are always known;
+    /// only the names of other [`Id`]s that the data refers to need to be in `names`.
+    pub fn to_raw(&self, names: &NameLookup) -> Result<Data::RawManifest, ManifestError> {
+        let mut names = names.clone();
+        names.add_manifest(self);
+
+        let entries = self
+            .iter()
+            .map(|(id, data)| Ok((self.name(id).to_string(), data.to_raw(&names)?)))
+            .collect::<Result<HashMap<_, _>, ManifestError>>()?;
+
+        Ok(Data::raw_manifest(entries))
+    }
+
+    /// Converts this manifest back into its raw form, written as JSON in the same style as the manifest files.
+    ///
+    /// Keys are sorted, so that saving an unchanged manifest always gives the same file.
+    pub fn to_raw_json(&self, names: &NameLookup) -> Result<String, ManifestError> {
+        let raw_manifest = self.to_raw(names)?;
+
+        // Going through a `Value` sorts the keys of all maps
+        let value = serde_json::to_value(raw_manifest)
+            .expect("Raw manifests only contain data that can be written as JSON");
+
+        let mut json = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(b"\t");
+        let mut serializer = serde_json::Serializer::with_formatter(&mut json, formatter);
+        value
+            .serialize(&mut serializer)
+            .expect("JSON values can always be written");
+
+        Ok(String::from_utf8(json).expect("JSON is always valid UTF-8"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A marker type for the test manifest.
+    struct Fruit;
+
+    #[test]
+    fn names_are_found_for_ids_of_any_type() {
+        let mut manifest = Manifest::<Fruit, ()>::new();
+        manifest.insert("apple".to_string(), ());
+        let mut names = NameLookup::new();
+        names.add_manifest(&manifest);
+        names.add_names(["sweet".to_string()]);
+
+        assert_eq!(
+            names.name(Id::<Fruit>::from_name("apple".to_string())),
+            Ok("apple".to_string())
+        );
+        assert_eq!(
+            names.name(Id::<u8>::from_name("sweet".to_string())),
+            Ok("sweet".to_string())
+        );
+    }
+
+    #[test]
+    fn ids_are_written_by_name_in_scope() {
+        let mut manifest = Manifest::<Fruit, ()>::new();
+        manifest.insert("apple".to_string(), ());
+        let mut names = NameLookup::new();
+        names.add_manifest(&manifest);
+        let id = Id::<Fruit>::from_name("apple".to_string());
+
+        let json = names.scope(|| serde_json::to_string(&id)).unwrap();
+        assert_eq!(json, r#""apple""#);
+        assert_eq!(serde_json::from_str::<Id<Fruit>>(&json).unwrap(), id);
+
+        // Outside of the scope, the number is written instead
+        let json = serde_json::to_string(&id).unwrap();
+        assert_eq!(json, format!(r#"{{"value":{}}}"#, id.value()));
+        assert_eq!(serde_json::from_str::<Id<Fruit>>(&json).unwrap(), id);
+    }
+
+    #[test]
+    fn ids_without_a_name_cannot_be_written_in_scope() {
+        let names = NameLookup::new();
+        let id = Id::<Fruit>::from_name("pear".to_string());
+
+        assert!(names.scope(|| serde_json::to_string(&id)).is_err());
+    }
+
+    #[test]
+    fn ids_are_written_by_number_in_binary_formats() {
+        let mut names = NameLookup::new();
+        names.add_names(["apple".to_string()]);
+        let id = Id::<Fruit>::from_name("apple".to_string());
+
+        let bytes = names.scope(|| bincode::serialize(&id)).unwrap();
+        assert_eq!(bincode::deserialize::<Id<Fruit>>(&bytes).unwrap(), id);
+        assert_eq!(bytes, bincode::serialize(&id).unwrap());
+    }
+
+    #[test]
+    fn unknown_ids_have_no_name() {
+        let names = NameLookup::new();
+        let id = Id::<Fruit>::from_name("pear".to_string());
+
+        assert_eq!(
+            names.name(id),
+            Err(ManifestError::UnknownId {
+                kind: std::any::type_name::<Fruit>(),
+                id: id.value(),
+                name: None,
+            })
+        );
+    }
+
+    #[test]
+    fn ids_are_parsed_from_their_name() {
+        let id: Id<Fruit> = "apple".parse().unwrap();
+
+        assert_eq!(id, Id::from_name("apple".to_string()));
+    }
+
+    #[test]
+    fn ids_are_displayed_by_name_in_scope() {
+        let mut names = NameLookup::new();
+        names.add_names(["apple".to_string()]);
+        let apple = Id::<Fruit>::from_name("apple".to_string());
+        let pear = Id::<Fruit>::from_name("pear".to_string());
+
+        assert_eq!(names.scope(|| apple.to_string()), "apple");
+        // Names that are not in the lookup fall back to the number
+        assert_eq!(
+            names.scope(|| pear.to_string()),
+            format!("#{}", pear.value())
+        );
+    }
+
+    #[test]
+    fn ids_are_displayed_by_number_without_a_scope() {
+        let id = Id::<Fruit>::from_name("apple".to_string());
+
+        assert_eq!(id.to_string(), format!("#{}", id.value()));
+    }
+}
@@ -1,8 +1,16 @@
 //! Code for a generic identifier type
 
 use bevy::{prelude::Component, reflect::Reflect};
-use serde::{Deserialize, Serialize};
-use std::{fmt::Debug, hash::Hash, marker::PhantomData};
+use serde::{de::Error as _, ser::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    convert::Infallible,
+    fmt::{Debug, Display},
+    hash::Hash,
+    marker::PhantomData,
+    str::FromStr,
+};
+
+use super::export::NameLookup;
 
 /// The unique identifier of type `T`.
 ///
@@ -10,7 +18,14 @@ use std::{fmt::Debug, hash::Hash, marker::PhantomData};
 /// Unlike enum variants, these can be read from disk and constructed at runtime.
 ///
 /// It can be stored as a component to identify the variety of game object used.
-#[derive(Component, Reflect, Serialize, Deserialize)]
+///
+/// IDs are serialized as their number, unless a [`NameLookup`] is [in scope](NameLookup::scope).
+/// Human-readable formats are then written with the name of the ID instead.
+/// Both forms are read back, with names turned into IDs by [`Id::from_name`].
+///
+/// The same goes for text: IDs are [displayed](Display) by name while a lookup is in scope,
+/// and can be [parsed](FromStr) from their name at any time.
+#[derive(Component, Reflect)]
 pub struct Id<T> {
     /// The unique identifier.
     ///
@@ -20,7 +35,6 @@ pub struct Id<T> {
 
     /// Marker to make the compiler happy
     #[reflect(ignore)]
-    #[serde(skip)]
     _phantom: PhantomData<T>,
 }
 
@@ -47,6 +61,9 @@ impl<T> Id<T> {
     /// Creates a new ID from human-readable string identifier.
     ///
     /// This ID is created as a hash of the string.
+    ///
+    /// The hash is cheap enough that caching it does not pay off:
+    /// interning names behind a lock made processing large manifests slower (see the `manifests` benchmark).
     pub fn from_name(name: String) -> Self {
         // Algorithm adopted from <https://cp-algorithms.com/string/string-hashing.html>
 
@@ -60,6 +77,65 @@ impl<T> Id<T> {
 
         Self::new(value)
     }
+
+    /// The number that uniquely identifies this ID.
+    pub(crate) const fn value(&self) -> u64 {
+        self.value
+    }
+}
+
+/// The form of an [`Id`] that is written when no names are in scope.
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Id")]
+struct NumericId {
+    /// The number that uniquely identifies the ID.
+    value: u64,
+}
+
+/// The forms of an [`Id`] that can be read from human-readable formats.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ReadableId {
+    /// The name that the ID was created from.
+    Name(String),
+    /// The number of the ID itself.
+    Numeric(NumericId),
+}
+
+impl<T> Serialize for Id<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            match NameLookup::name_in_scope(*self) {
+                Some(Ok(name)) => return serializer.serialize_str(&name),
+                Some(Err(error)) => return Err(S::Error::custom(error)),
+                None => (),
+            }
+        }
+
+        NumericId { value: self.value }.serialize(serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Id<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            match ReadableId::deserialize(deserializer) {
+                Ok(ReadableId::Name(name)) => Ok(Id::from_name(name)),
+                Ok(ReadableId::Numeric(NumericId { value })) => Ok(Id::new(value)),
+                Err(_) => Err(D::Error::custom(
+                    "expected the name of an ID, or an object with its `value`",
+                )),
+            }
+        } else {
+            NumericId::deserialize(deserializer).map(|NumericId { value }| Id::new(value))
+        }
+    }
 }
 
 impl<T> Debug for Id<T> {
@@ -68,6 +144,30 @@ impl<T> Debug for Id<T> {
     }
 }
 
+/// Shows the name of the ID if a [`NameLookup`] is [in scope](NameLookup::scope) and knows it.
+///
+/// Otherwise, the number of the ID is shown after a `#`, such as `#1234`,
+/// so that it cannot be mistaken for a name.
+impl<T> Display for Id<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match NameLookup::name_in_scope(*self) {
+            Some(Ok(name)) => write!(f, "{name}"),
+            Some(Err(_)) | None => write!(f, "#{}", self.value),
+        }
+    }
+}
+
+/// Parses the name of an ID, as if by [`Id::from_name`].
+///
+/// Every string is a valid name, so this never fails, and no [`NameLookup`] needs to be in scope.
+impl<T> FromStr for Id<T> {
+    type Err = Infallible;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(Id::from_name(name.to_string()))
+    }
+}
+
 impl<T> PartialEq for Id<T> {
     fn eq(&self, other: &Self) -> bool {
         self.value == other.value
@@ -0,0 +1,206 @@
+//! Reads JSON manifests that use the more forgiving syntax of [JSON5](https://json5.org/).
+//!
+//! Only the parts of JSON5 that help with editing manifests by hand are supported:
+//! comments, trailing commas, unquoted keys and single-quoted strings.
+//! Everything else, such as hexadecimal numbers, must still be written as plain JSON.
+//!
+//! Files are rewritten to plain JSON before they are deserialized.
+//! Comments are replaced by whitespace, so errors still point to the right line,
+//! although columns may be off by a few characters on lines with unquoted keys or single-quoted strings.
+
+/// Rewrites the JSON5 `source` into plain JSON.
+///
+/// Plain JSON is returned unchanged.
+pub(super) fn to_json(source: &[u8]) -> Vec<u8> {
+    let mut json = Vec::with_capacity(source.len());
+    let mut i = 0;
+
+    while i < source.len() {
+        match source[i] {
+            b'"' => i = copy_string(source, i, &mut json),
+            b'\'' => i = convert_single_quoted_string(source, i, &mut json),
+            b'/' if matches!(source.get(i + 1), Some(b'/' | b'*')) => {
+                let end = skip_comment(source, i);
+                blank_out(&source[i..end], &mut json);
+                i = end;
+            }
+            b',' if matches!(next_significant(source, i + 1), Some(b'}' | b']')) => {
+                json.push(b' ');
+                i += 1;
+            }
+            byte if is_identifier_start(byte) => {
+                let end = i + source[i..]
+                    .iter()
+                    .take_while(|&&byte| is_identifier_continue(byte))
+                    .count();
+
+                // Literals such as `true` are never followed by a colon, so only keys are quoted
+                if next_significant(source, end) == Some(b':') {
+                    json.push(b'"');
+                    json.extend_from_slice(&source[i..end]);
+                    json.push(b'"');
+                } else {
+                    json.extend_from_slice(&source[i..end]);
+                }
+                i = end;
+            }
+            byte => {
+                json.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    json
+}
+
+/// Copies the double-quoted string starting at `start` to `json`, returning the index after it.
+fn copy_string(source: &[u8], start: usize, json: &mut Vec<u8>) -> usize {
+    let mut i = start + 1;
+    while i < source.len() && source[i] != b'"' {
+        // Skip over escaped characters, so escaped quotes do not end the string
+        i += if source[i] == b'\\' { 2 } else { 1 };
+    }
+    let end = (i + 1).min(source.len());
+
+    json.extend_from_slice(&source[start..end]);
+    end
+}
+
+/// Writes the single-quoted string starting at `start` to `json` as a double-quoted string, returning the index after it.
+fn convert_single_quoted_string(source: &[u8], start: usize, json: &mut Vec<u8>) -> usize {
+    json.push(b'"');
+
+    let mut i = start + 1;
+    while i < source.len() && source[i] != b'\'' {
+        match (source[i], source.get(i + 1)) {
+            // Single quotes need no escaping in double-quoted strings
+            (b'\\', Some(b'\'')) => {
+                json.push(b'\'');
+                i += 2;
+            }
+            (b'\\', Some(&escaped)) => {
+                json.extend_from_slice(&[b'\\', escaped]);
+                i += 2;
+            }
+            (b'"', _) => {
+                json.extend_from_slice(b"\\\"");
+                i += 1;
+            }
+            (byte, _) => {
+                json.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    json.push(b'"');
+    (i + 1).min(source.len())
+}
+
+/// Returns the index after the line or block comment starting at `start`.
+///
+/// Line comments end before their newline, and unterminated block comments run to the end of the file.
+fn skip_comment(source: &[u8], start: usize) -> usize {
+    let rest = &source[start + 2..];
+
+    let length = if source[start + 1] == b'/' {
+        rest.iter()
+            .position(|&byte| byte == b'\n')
+            .unwrap_or(rest.len())
+    } else {
+        rest.windows(2)
+            .position(|window| window == b"*/")
+            .map_or(rest.len(), |position| position + 2)
+    };
+
+    start + 2 + length
+}
+
+/// Writes whitespace in place of the `removed` text, keeping its line breaks.
+fn blank_out(removed: &[u8], json: &mut Vec<u8>) {
+    json.extend(
+        removed
+            .iter()
+            .map(|&byte| if byte == b'\n' { b'\n' } else { b' ' }),
+    );
+}
+
+/// Finds the first byte at or after `start` that is neither whitespace nor part of a comment.
+fn next_significant(source: &[u8], start: usize) -> Option<u8> {
+    let mut i = start;
+
+    loop {
+        match (source.get(i)?, source.get(i + 1)) {
+            (byte, _) if byte.is_ascii_whitespace() => i += 1,
+            (b'/', Some(b'/' | b'*')) => i = skip_comment(source, i),
+            (&byte, _) => return Some(byte),
+        }
+    }
+}
+
+/// Can an unquoted key start with this byte?
+fn is_identifier_start(byte: u8) -> bool {
+    byte.is_ascii_alphabetic() || byte == b'_' || byte == b'$'
+}
+
+/// Can an unquoted key contain this byte after its first one?
+fn is_identifier_continue(byte: u8) -> bool {
+    is_identifier_start(byte) || byte.is_ascii_digit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rewrites the `source` and parses it as plain JSON.
+    fn parse(source: &str) -> serde_json::Value {
+        serde_json::from_slice(&to_json(source.as_bytes())).unwrap()
+    }
+
+    #[test]
+    fn plain_json_is_unchanged() {
+        let source = r#"{ "leaf": { "stack_size": 10, "tags": ["fuel"], "seed": null } }"#;
+
+        assert_eq!(to_json(source.as_bytes()), source.as_bytes());
+    }
+
+    #[test]
+    fn comments_are_blanked_out_without_moving_lines() {
+        let source = "{\n  // A line comment\n  \"a\": /* inline */ 1,\n  /* a block\n  comment */\n  \"b\": 2\n}";
+        let json = String::from_utf8(to_json(source.as_bytes())).unwrap();
+
+        assert_eq!(json.lines().count(), source.lines().count());
+        assert_eq!(parse(source), serde_json::json!({ "a": 1, "b": 2 }));
+    }
+
+    #[test]
+    fn trailing_commas_unquoted_keys_and_single_quotes_are_accepted() {
+        let source = r#"{
+            stack_size: 10,
+            tags: ['fuel', 'it\'s "dry"',],
+            $schema: 'items.schema.json',
+            compostable: true,
+        }"#;
+
+        assert_eq!(
+            parse(source),
+            serde_json::json!({
+                "stack_size": 10,
+                "tags": ["fuel", "it's \"dry\""],
+                "$schema": "items.schema.json",
+                "compostable": true,
+            })
+        );
+    }
+
+    #[test]
+    fn comment_markers_inside_strings_are_kept() {
+        let source = r#"{ "path": "textures//leaf.png", "glob": "/*.json", key: "a,}" }"#;
+
+        assert_eq!(
+            parse(source),
+            serde_json::json!({ "path": "textures//leaf.png", "glob": "/*.json", "key": "a,}" })
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//! Finds content that is valid, but likely unintended, such as items that can never be obtained.
+//!
+//! Unlike [validation](super::validation), these findings never prevent the game from loading.
+
+use bevy::utils::HashSet;
+
+use crate::{
+    crafting::recipe::{RecipeInput, RecipeManifest},
+    items::item_manifest::{Item, ItemManifest},
+};
+
+use super::Id;
+
+/// The findings of [`lint_manifests`].
+///
+/// Every list is sorted by name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ManifestLint {
+    /// The items that no recipe produces, and that do not decay from any other item.
+    pub unproduced_items: Vec<String>,
+    /// The items that no recipe uses, whether as an input, a catalyst or a research cost.
+    pub unconsumed_items: Vec<String>,
+    /// The recipes that produce items, none of which are used by any recipe.
+    pub dead_end_recipes: Vec<String>,
+}
+
+impl ManifestLint {
+    /// Was nothing suspicious found?
+    pub fn is_clean(&self) -> bool {
+        self.unproduced_items.is_empty()
+            && self.unconsumed_items.is_empty()
+            && self.dead_end_recipes.is_empty()
+    }
+}
+
+/// Walks the items and recipes, reporting content that is disconnected from the rest of the production chains.
+///
+/// Only enabled recipes are considered, as disabled ones are not part of the [`RecipeManifest`].
+/// Recipes without any item outputs, such as those that only produce energy, are never reported as dead ends.
+pub fn lint_manifests(
+    item_manifest: &ItemManifest,
+    recipe_manifest: &RecipeManifest,
+) -> ManifestLint {
+    let mut produced: HashSet<Id<Item>> = HashSet::new();
+    let mut consumed: HashSet<Id<Item>> = HashSet::new();
+
+    for (_, item_data) in item_manifest {
+        produced.extend(item_data.decays_into);
+    }
+
+    for (_, recipe_data) in recipe_manifest {
+        produced.extend(recipe_data.output_item_ids());
+        produced.extend(recipe_data.fluid_outputs.iter().map(|fluid| fluid.item_id));
+
+        match &recipe_data.inputs {
+            RecipeInput::Exact(inputs) | RecipeInput::AnyOf(inputs) => {
+                consumed.extend(inputs.iter().map(|input| input.item_id));
+            }
+            RecipeInput::Flexible { tag, .. } => consumed.extend(
+                item_manifest
+                    .variants()
+                    .into_iter()
+                    .filter(|&item_id| item_manifest.has_tag(item_id, *tag)),
+            ),
+            RecipeInput::Category { category, .. } => {
+                consumed.extend(item_manifest.items_with_tag(*category));
+            }
+        }
+        consumed.extend(
+            recipe_data
+                .catalysts
+                .iter()
+                .map(|catalyst| catalyst.item_id),
+        );
+        consumed.extend(recipe_data.fluid_inputs.iter().map(|fluid| fluid.item_id));
+        consumed.extend(recipe_data.research_cost.iter().map(|cost| cost.item_id));
+    }
+
+    let mut lint = ManifestLint::default();
+
+    for (item_id, _) in item_manifest {
+        let name = item_manifest.name(item_id).to_string();
+        if !produced.contains(&item_id) {
+            lint.unproduced_items.push(name.clone());
+        }
+        if !consumed.contains(&item_id) {
+            lint.unconsumed_items.push(name);
+        }
+    }
+
+    for (recipe_id, recipe_data) in recipe_manifest {
+        let mut outputs = recipe_data.output_item_ids();
+        outputs.extend(recipe_data.fluid_outputs.iter().map(|fluid| fluid.item_id));
+
+        if !outputs.is_empty() && outputs.iter().all(|item_id| !consumed.contains(item_id)) {
+            lint.dead_end_recipes
+                .push(recipe_manifest.name(recipe_id).to_string());
+        }
+    }
+
+    lint.unproduced_items.sort();
+    lint.unconsumed_items.sort();
+    lint.dead_end_recipes.sort();
+    lint
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        asset_management::manifest::loader::IsRawManifest, crafting::recipe::RawRecipeManifest,
+        items::item_manifest::RawItemManifest,
+    };
+
+    /// Items that form a chain from pollen to wax, with an orphaned `glitter` item that nothing touches.
+    const ITEMS: &str = r#"{
+        "version": 1,
+        "items": {
+            "pollen": { "stack_size": 10, "compostable": true, "fluid": false, "buoyant": true },
+            "honey": { "stack_size": 10, "compostable": true, "fluid": false, "buoyant": false },
+            "wax": { "stack_size": 10, "compostable": false, "fluid": false, "buoyant": true },
+            "glitter": { "stack_size": 10, "compostable": false, "fluid": false, "buoyant": true }
+        }
+    }"#;
+
+    /// Recipes that gather pollen, turn it into honey and turn the honey into wax.
+    const RECIPES: &str = r#"{
+        "version": 1,
+        "recipes": {
+            "gather_pollen": {
+                "inputs": { "Exact": {} },
+                "outputs": { "pollen": 1 },
+                "craft_time": 1
+            },
+            "make_honey": {
+                "inputs": { "Exact": { "pollen": 2 } },
+                "outputs": { "honey": 1 },
+                "craft_time": 1
+            },
+            "make_wax": {
+                "inputs": { "Exact": { "honey": 1 } },
+                "outputs": { "wax": 1 },
+                "craft_time": 1
+            }
+        }
+    }"#;
+
+    fn manifests(items: &str, recipes: &str) -> (ItemManifest, RecipeManifest) {
+        let raw_items: RawItemManifest = serde_json::from_str(items).unwrap();
+        let raw_recipes: RawRecipeManifest = serde_json::from_str(recipes).unwrap();
+
+        (raw_items.process().unwrap(), raw_recipes.process().unwrap())
+    }
+
+    #[test]
+    fn orphaned_items_are_reported() {
+        let (item_manifest, recipe_manifest) = manifests(ITEMS, RECIPES);
+
+        let lint = lint_manifests(&item_manifest, &recipe_manifest);
+
+        assert!(!lint.is_clean());
+        assert_eq!(lint.unproduced_items, vec!["glitter"]);
+        assert_eq!(lint.unconsumed_items, vec!["glitter", "wax"]);
+        assert_eq!(lint.dead_end_recipes, vec!["make_wax"]);
+    }
+
+    #[test]
+    fn connected_manifests_are_clean() {
+        let items = r#"{
+            "version": 1,
+            "items": {
+                "pollen": { "stack_size": 10, "compostable": true, "fluid": false, "buoyant": true },
+                "honey": { "stack_size": 10, "compostable": true, "fluid": false, "buoyant": false }
+            }
+        }"#;
+        let recipes = r#"{
+            "version": 1,
+            "recipes": {
+                "make_honey": {
+                    "inputs": { "Exact": { "pollen": 2 } },
+                    "outputs": { "honey": 1 },
+                    "craft_time": 1
+                },
+                "ferment_honey": {
+                    "inputs": { "Exact": { "honey": 1 } },
+                    "outputs": { "pollen": 2 },
+                    "craft_time": 1
+                }
+            }
+        }"#;
+        let (item_manifest, recipe_manifest) = manifests(items, recipes);
+
+        assert_eq!(
+            lint_manifests(&item_manifest, &recipe_manifest),
+            ManifestLint::default()
+        );
+    }
+
+    #[test]
+    fn flexible_inputs_consume_every_matching_item() {
+        let recipes = r#"{
+            "version": 1,
+            "recipes": {
+                "gather_pollen": {
+                    "inputs": { "Exact": {} },
+                    "outputs": { "pollen": 1 },
+                    "craft_time": 1
+                },
+                "compost": {
+                    "inputs": { "Flexible": { "tag": "Compostable", "count": 1 } },
+                    "outputs": {},
+                    "energy": 5.0,
+                    "craft_time": 1
+                }
+            }
+        }"#;
+        let (item_manifest, recipe_manifest) = manifests(ITEMS, recipes);
+
+        let lint = lint_manifests(&item_manifest, &recipe_manifest);
+
+        assert_eq!(lint.unconsumed_items, vec!["glitter", "wax"]);
+        assert!(lint.dead_end_recipes.is_empty());
+    }
+}
@@ -1,54 +1,466 @@
 //! A loader for manifest assets.
 
 use std::{
+    collections::BTreeSet,
     marker::PhantomData,
     path::{Path, PathBuf},
 };
 
 use bevy::{
     asset::{AssetLoader, LoadContext, LoadedAsset},
-    utils::BoxedFuture,
+    prelude::World,
+    utils::{BoxedFuture, HashMap, HashSet},
 };
 
 use bevy::reflect::TypeUuid;
-use serde::Deserialize;
+use serde::{
+    de::{DeserializeOwned, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
 
-use super::Manifest;
+use super::{errors::ManifestError, Manifest};
 
 /// The raw manifest data before it has been processed.
 ///
 /// The processing will primarily remove the string IDs and replace them by numbers.
+///
+/// Raw manifests made of a `version` and a single map of entries can derive this trait with [`emergence_macros::RawManifest`].
 pub trait IsRawManifest:
-    std::fmt::Debug + TypeUuid + Send + Sync + for<'de> Deserialize<'de> + 'static
+    std::fmt::Debug + Clone + TypeUuid + Send + Sync + for<'de> Deserialize<'de> + 'static
 {
-    /// The file extension of this manifest type.
+    /// The file extension of this manifest type, without the [`ManifestFormat`] suffix.
     ///
     /// This is used to determine which manifest loader to use.
     /// Note that this must be unique across all manifest types,
     /// otherwise the wrong loader will be used.
     const EXTENSION: &'static str;
 
+    /// The version of the manifest format that this type can read.
+    ///
+    /// Raise this whenever the format changes in a way that existing manifest files no longer fit,
+    /// so that outdated files are rejected instead of being misread.
+    const CURRENT_VERSION: u32 = 1;
+
     /// The marker type for the manifest ID.
     type Marker: 'static + Send + Sync;
 
     /// The type of the processed manifest data.
     type Data: std::fmt::Debug + Send + Sync;
 
-    /// Returns the path to the manifest file.
+    /// The type of the data of each entry, before it has been processed.
+    type RawData: std::fmt::Debug + Clone + Send + Sync;
+
+    /// Returns the path to the base game manifest file, without the [`ManifestFormat`] suffix.
     fn path() -> PathBuf {
-        Path::new("manifests/base_game").with_extension(Self::EXTENSION)
+        Self::layer_path(BASE_GAME_LAYER)
+    }
+
+    /// Returns the path to the manifest file of the given `layer`, without the [`ManifestFormat`] suffix.
+    ///
+    /// Layers are named by their path relative to the asset folder, excluding the extension.
+    fn layer_path(layer: &str) -> PathBuf {
+        Path::new(layer).with_extension(Self::EXTENSION)
+    }
+
+    /// Returns the path to the manifest file of the given `layer`, when written in the given `format`.
+    fn layer_path_with_format(layer: &str, format: ManifestFormat) -> PathBuf {
+        Self::layer_path(layer).with_extension(format!(
+            "{}.{}",
+            Self::EXTENSION,
+            format.extension()
+        ))
+    }
+
+    /// The version of the manifest format that this manifest was written in.
+    fn version(&self) -> u32;
+
+    /// The raw data of every entry, keyed by its name.
+    fn entries(&self) -> &HashMap<String, Self::RawData>;
+
+    /// The raw data of every entry, keyed by its name, mutably.
+    fn entries_mut(&mut self) -> &mut HashMap<String, Self::RawData>;
+
+    /// Adds all entries of the `other` manifest to this one.
+    ///
+    /// Entries in `other` replace entries in `self` with the same name in their entirety.
+    fn merge(&mut self, mut other: Self) {
+        let other_entries = std::mem::take(other.entries_mut());
+        self.entries_mut().extend(other_entries);
     }
 
     /// Process the raw manifest from the asset file to the manifest data used in-game.
-    fn process(&self) -> Manifest<Self::Marker, Self::Data>;
+    ///
+    /// Fails if the raw data cannot be turned into a usable manifest.
+    /// Problems that need to look at other manifests are found by [`IsRawManifest::validate`] instead.
+    ///
+    /// The processed data must not depend on the arbitrary order of the maps in the raw data,
+    /// such as by sorting lists that are built from them, so that processing the same raw manifest always produces equal manifests.
+    fn process(&self) -> Result<Manifest<Self::Marker, Self::Data>, ManifestError>;
+
+    /// Checks the raw manifest against the rest of the game data.
+    ///
+    /// This runs once all manifests have been processed,
+    /// so the processed manifests can be read from the `world`.
+    fn validate(&self, _world: &World) -> Result<(), ManifestError> {
+        Ok(())
+    }
+}
+
+/// The manifest layer that holds the data of the base game.
+pub const BASE_GAME_LAYER: &str = "manifests/base_game";
+
+/// Merges the raw manifests of several layers, in order.
+///
+/// When multiple layers define the same entry, the last one wins.
+/// Returns [`None`] if there are no layers.
+pub fn merge_layers<'a, M>(layers: impl IntoIterator<Item = &'a M>) -> Option<M>
+where
+    M: IsRawManifest,
+{
+    let mut layers = layers.into_iter();
+    let mut merged = layers.next()?.clone();
+
+    for layer in layers {
+        merged.merge(layer.clone());
+    }
+
+    Some(merged)
+}
+
+/// Finds the names of all entries that are defined by more than one of the `layers`.
+///
+/// The names are sorted and each is only listed once.
+pub fn names_defined_by_multiple_layers<'a, M>(
+    layers: impl IntoIterator<Item = &'a M>,
+) -> Vec<String>
+where
+    M: IsRawManifest,
+{
+    let mut seen = HashSet::new();
+    let mut duplicates = BTreeSet::new();
+
+    for layer in layers {
+        for name in layer.entries().keys() {
+            if !seen.insert(name) {
+                duplicates.insert(name.clone());
+            }
+        }
+    }
+
+    duplicates.into_iter().collect()
+}
+
+/// Finds the layers of the manifest type `M` among the `paths` of the files in a single directory.
+///
+/// A file is a layer if its name ends in the [`IsRawManifest::EXTENSION`] of `M`,
+/// followed by the extension of one of the [`ManifestFormat`]s, such as `my_mod.item_manifest.json`.
+/// The layers are named like any other, by their path without the extension.
+///
+/// The layers are sorted lexicographically by file name, so that later files deterministically override earlier ones.
+/// Layers with files in several formats are only listed once.
+pub fn discover_layers<M>(paths: impl IntoIterator<Item = PathBuf>) -> Vec<String>
+where
+    M: IsRawManifest,
+{
+    let suffixes =
+        ManifestFormat::ALL.map(|format| format!(".{}.{}", M::EXTENSION, format.extension()));
+
+    let layers: BTreeSet<String> = paths
+        .into_iter()
+        .filter_map(|path| {
+            let path = path.to_str()?;
+            suffixes
+                .iter()
+                .find_map(|suffix| path.strip_suffix(suffix.as_str()))
+                .map(str::to_string)
+        })
+        .collect();
+
+    layers.into_iter().collect()
+}
+
+/// The file formats that manifests can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ManifestFormat {
+    /// Plain JSON, in `.json` files.
+    ///
+    /// With the `json5_manifests` feature, these files may also use comments, trailing commas,
+    /// unquoted keys and single-quoted strings, as in JSON5.
+    ///
+    /// A top-level `$schema` key, which points editors to the schema of the manifest, is ignored.
+    Json,
+    /// Rusty Object Notation, in `.ron` files.
+    ///
+    /// Unlike JSON, this supports comments and trailing commas.
+    Ron,
+}
+
+impl ManifestFormat {
+    /// All supported formats, in order of preference when more than one file exists for a manifest.
+    pub const ALL: [ManifestFormat; 2] = [ManifestFormat::Ron, ManifestFormat::Json];
+
+    /// The file extension used by this format.
+    pub const fn extension(&self) -> &'static str {
+        match self {
+            ManifestFormat::Json => "json",
+            ManifestFormat::Ron => "ron",
+        }
+    }
+
+    /// Determines the format of the file at `path` from its extension.
+    ///
+    /// Files with unknown extensions are treated as JSON.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("ron") => ManifestFormat::Ron,
+            _ => ManifestFormat::Json,
+        }
+    }
+
+    /// Deserializes a raw manifest written in this format, read from the file at `path`.
+    ///
+    /// Fails with [`ManifestError::ParseError`] if the file is malformed,
+    /// and with [`ManifestError::DuplicateId`] if the file defines an entry more than once.
+    ///
+    /// Unknown fields in the entries are ignored, unless the `strict_manifests` feature is enabled,
+    /// in which case they are reported as a [`ManifestError::ParseError`] that points to the misspelled field.
+    pub fn deserialize<M>(&self, path: &Path, bytes: &[u8]) -> Result<M, ManifestError>
+    where
+        M: IsRawManifest,
+    {
+        let duplicates = self.duplicate_names(path, bytes)?;
+        if !duplicates.is_empty() {
+            return Err(ManifestError::DuplicateId(duplicates));
+        }
+
+        // Check the version before anything else, as outdated files may not fit the current format at all
+        let found = self.version(path, bytes)?;
+        if found != Some(M::CURRENT_VERSION) {
+            return Err(ManifestError::VersionMismatch {
+                expected: M::CURRENT_VERSION,
+                found,
+            });
+        }
+
+        self.parse(path, bytes)
+    }
+
+    /// Reads the version of a raw manifest written in this format, without reading the rest of it.
+    ///
+    /// Returns [`None`] if the manifest does not declare a version.
+    pub fn version(&self, path: &Path, bytes: &[u8]) -> Result<Option<u32>, ManifestError> {
+        let manifest_version: ManifestVersion = self.parse(path, bytes)?;
+
+        Ok(manifest_version.version)
+    }
+
+    /// Finds the names of all entries that are defined more than once in a raw manifest written in this format.
+    ///
+    /// Deserializing into a map silently keeps only the last of several identical keys,
+    /// so this has to scan the file itself.
+    /// The names are sorted and each is only listed once.
+    pub fn duplicate_names(&self, path: &Path, bytes: &[u8]) -> Result<Vec<String>, ManifestError> {
+        let duplicate_names: DuplicateNames = self.parse(path, bytes)?;
+
+        Ok(duplicate_names.0)
+    }
+
+    /// Deserializes any value written in this format, read from the file at `path`.
+    ///
+    /// Errors are converted into a [`ManifestError::ParseError`] that points to the problem in the file.
+    fn parse<T>(&self, path: &Path, bytes: &[u8]) -> Result<T, ManifestError>
+    where
+        T: DeserializeOwned,
+    {
+        match self {
+            ManifestFormat::Json => {
+                #[cfg(feature = "json5_manifests")]
+                let bytes = &super::json5::to_json(bytes);
+
+                serde_json::from_slice(bytes).map_err(|error| {
+                    // The message of JSON errors ends with their location, which is stored separately
+                    let location = format!(" at line {} column {}", error.line(), error.column());
+                    let message = error.to_string();
+
+                    ManifestError::ParseError {
+                        path: path.to_path_buf(),
+                        line: error.line(),
+                        column: error.column(),
+                        message: message
+                            .strip_suffix(&location)
+                            .unwrap_or(&message)
+                            .to_string(),
+                    }
+                })
+            }
+            ManifestFormat::Ron => {
+                ron::de::from_bytes(bytes).map_err(|error| ManifestError::ParseError {
+                    path: path.to_path_buf(),
+                    line: error.position.line,
+                    column: error.position.col,
+                    message: error.code.to_string(),
+                })
+            }
+        }
+    }
+}
+
+/// The names of the entries that are defined more than once in a raw manifest file.
+///
+/// A raw manifest is a map of fields, some of which map entry names to their data.
+/// Only the keys of those inner maps are inspected; everything else is skipped.
+struct DuplicateNames(Vec<String>);
+
+impl<'de> Deserialize<'de> for DuplicateNames {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        /// Collects the duplicates of every field of the manifest.
+        struct ManifestVisitor;
+
+        impl<'de> Visitor<'de> for ManifestVisitor {
+            type Value = DuplicateNames;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a manifest")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut duplicates = BTreeSet::new();
+
+                while let Some((_field, field_duplicates)) =
+                    map.next_entry::<IgnoredAny, FieldDuplicates>()?
+                {
+                    duplicates.extend(field_duplicates.0);
+                }
+
+                Ok(DuplicateNames(duplicates.into_iter().collect()))
+            }
+        }
+
+        deserializer.deserialize_any(ManifestVisitor)
+    }
+}
+
+/// The version declared by a raw manifest, ignoring all of its other fields.
+#[derive(Deserialize)]
+struct ManifestVersion {
+    /// The declared version, if any.
+    ///
+    /// The version is written as a plain number, even in formats that have explicit optional values.
+    #[serde(default, deserialize_with = "ManifestVersion::declared")]
+    version: Option<u32>,
+}
+
+impl ManifestVersion {
+    /// Reads a declared version number.
+    fn declared<'de, D>(deserializer: D) -> Result<Option<u32>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        u32::deserialize(deserializer).map(Some)
+    }
+}
+
+/// The keys that occur more than once in a single field of a raw manifest.
+///
+/// Fields that are not maps have no duplicates.
+struct FieldDuplicates(Vec<String>);
+
+impl<'de> Deserialize<'de> for FieldDuplicates {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        /// Records repeated map keys and accepts any other value.
+        struct FieldVisitor;
+
+        impl<'de> Visitor<'de> for FieldVisitor {
+            type Value = FieldDuplicates;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("any manifest field")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut seen = HashSet::new();
+                let mut duplicates = Vec::new();
+
+                while let Some((name, IgnoredAny)) = map.next_entry::<String, IgnoredAny>()? {
+                    if seen.contains(&name) {
+                        duplicates.push(name);
+                    } else {
+                        seen.insert(name);
+                    }
+                }
+
+                Ok(FieldDuplicates(duplicates))
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                while seq.next_element::<IgnoredAny>()?.is_some() {}
+                Ok(FieldDuplicates(Vec::new()))
+            }
+
+            fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                deserializer.deserialize_any(self)
+            }
+
+            fn visit_none<E>(self) -> Result<Self::Value, E> {
+                Ok(FieldDuplicates(Vec::new()))
+            }
+
+            fn visit_unit<E>(self) -> Result<Self::Value, E> {
+                Ok(FieldDuplicates(Vec::new()))
+            }
+
+            fn visit_bool<E>(self, _value: bool) -> Result<Self::Value, E> {
+                Ok(FieldDuplicates(Vec::new()))
+            }
+
+            fn visit_i64<E>(self, _value: i64) -> Result<Self::Value, E> {
+                Ok(FieldDuplicates(Vec::new()))
+            }
+
+            fn visit_u64<E>(self, _value: u64) -> Result<Self::Value, E> {
+                Ok(FieldDuplicates(Vec::new()))
+            }
+
+            fn visit_f64<E>(self, _value: f64) -> Result<Self::Value, E> {
+                Ok(FieldDuplicates(Vec::new()))
+            }
+
+            fn visit_str<E>(self, _value: &str) -> Result<Self::Value, E> {
+                Ok(FieldDuplicates(Vec::new()))
+            }
+        }
+
+        deserializer.deserialize_any(FieldVisitor)
+    }
 }
 
-/// A loader for `.manifest.json` files.
+/// A loader for `.manifest.json` and `.manifest.ron` files.
 #[derive(Debug, Clone)]
 pub(crate) struct RawManifestLoader<M>
 where
     M: IsRawManifest,
 {
+    /// The full file extensions handled by this loader, one per [`ManifestFormat`].
+    extensions: Vec<&'static str>,
+
     /// Use the generic to make the compiler happy.
     _phantom_manifest: PhantomData<M>,
 }
@@ -58,7 +470,7 @@ where
     M: IsRawManifest,
 {
     fn extensions(&self) -> &[&str] {
-        &[<M as IsRawManifest>::EXTENSION]
+        &self.extensions
     }
 
     fn load<'a>(
@@ -67,7 +479,8 @@ where
         load_context: &'a mut LoadContext,
     ) -> BoxedFuture<'a, anyhow::Result<(), anyhow::Error>> {
         Box::pin(async move {
-            let raw_manifest = serde_json::from_slice::<M>(bytes)?;
+            let format = ManifestFormat::from_path(load_context.path());
+            let raw_manifest = format.deserialize::<M>(load_context.path(), bytes)?;
             load_context.set_default_asset(LoadedAsset::<M>::new(raw_manifest));
             Ok(())
         })
@@ -79,8 +492,470 @@ where
     M: IsRawManifest,
 {
     fn default() -> Self {
+        // Asset loaders must return their extensions as borrowed strings.
+        // Each loader is created once per app, so leaking these few bytes is harmless.
+        let extensions = ManifestFormat::ALL
+            .iter()
+            .map(|format| {
+                &*Box::leak(format!("{}.{}", M::EXTENSION, format.extension()).into_boxed_str())
+            })
+            .collect();
+
         Self {
+            extensions,
             _phantom_manifest: PhantomData::default(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as emergence_lib;
+    use crate::items::item_manifest::{RawItemData, RawItemManifest};
+    use crate::organisms::energy::Energy;
+    use emergence_macros::RawManifest;
+
+    /// A raw item with the given `stack_size`.
+    fn raw_item(stack_size: u32) -> RawItemData {
+        RawItemData {
+            stack_size,
+            compostable: false,
+            fluid: false,
+            buoyant: false,
+            seed: None,
+            energy_content: Energy(0.),
+            tags: Vec::new(),
+            mass: 1.,
+            decay_time: None,
+            decays_into: None,
+            tier: 0,
+            name: None,
+            description: None,
+            icon: None,
+        }
+    }
+
+    /// A raw item manifest containing the provided items.
+    fn raw_item_manifest(items: &[(&str, u32)]) -> RawItemManifest {
+        RawItemManifest {
+            version: RawItemManifest::CURRENT_VERSION,
+            items: items
+                .iter()
+                .map(|(name, stack_size)| (name.to_string(), raw_item(*stack_size)))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn merging_no_layers_returns_none() {
+        assert_eq!(merge_layers::<RawItemManifest>([]), None);
+    }
+
+    #[test]
+    fn later_layers_add_new_items() {
+        let base = raw_item_manifest(&[("leaf", 10)]);
+        let layer = raw_item_manifest(&[("pollen", 20)]);
+
+        let merged = merge_layers([&base, &layer]).unwrap();
+
+        assert_eq!(merged, raw_item_manifest(&[("leaf", 10), ("pollen", 20)]));
+    }
+
+    #[test]
+    fn later_layers_override_existing_items() {
+        let base = raw_item_manifest(&[("leaf", 10), ("pollen", 20)]);
+        let layer = raw_item_manifest(&[("leaf", 5)]);
+
+        let merged = merge_layers([&base, &layer]).unwrap();
+
+        assert_eq!(merged.items.get("leaf"), Some(&raw_item(5)));
+        assert_eq!(merged.items.get("pollen"), Some(&raw_item(20)));
+    }
+
+    #[test]
+    fn layer_order_determines_the_winner() {
+        let first = raw_item_manifest(&[("leaf", 1)]);
+        let second = raw_item_manifest(&[("leaf", 2)]);
+
+        let first_then_second = merge_layers([&first, &second]).unwrap();
+        let second_then_first = merge_layers([&second, &first]).unwrap();
+
+        assert_eq!(
+            first_then_second.items,
+            HashMap::from_iter([("leaf".to_string(), raw_item(2))])
+        );
+        assert_eq!(
+            second_then_first.items,
+            HashMap::from_iter([("leaf".to_string(), raw_item(1))])
+        );
+    }
+
+    #[test]
+    fn names_shared_by_layers_are_reported() {
+        let base = raw_item_manifest(&[("leaf", 10), ("pollen", 20)]);
+        let first = raw_item_manifest(&[("pollen", 5), ("honey", 5)]);
+        let second = raw_item_manifest(&[("leaf", 1), ("pollen", 1)]);
+
+        assert_eq!(
+            names_defined_by_multiple_layers([&base, &first, &second]),
+            vec!["leaf".to_string(), "pollen".to_string()]
+        );
+        assert!(names_defined_by_multiple_layers([&base]).is_empty());
+    }
+
+    #[test]
+    fn layers_are_discovered_in_order_of_file_name() {
+        let paths = [
+            "manifests/items/b_mod.item_manifest.json",
+            "manifests/items/a_base.item_manifest.json",
+            "manifests/items/a_base.item_manifest.ron",
+            "manifests/items/c_mod.recipe_manifest.json",
+            "manifests/items/notes.txt",
+        ]
+        .map(PathBuf::from);
+
+        assert_eq!(
+            discover_layers::<RawItemManifest>(paths),
+            vec![
+                "manifests/items/a_base".to_string(),
+                "manifests/items/b_mod".to_string()
+            ]
+        );
+    }
+
+    /// An item manifest file that defines `leaf` twice.
+    const DUPLICATE_JSON: &str = r#"{
+        "version": 1,
+        "items": {
+            "leaf": { "stack_size": 10, "compostable": true, "fluid": false, "buoyant": true },
+            "pollen": { "stack_size": 20, "compostable": false, "fluid": false, "buoyant": true },
+            "leaf": { "stack_size": 5, "compostable": true, "fluid": false, "buoyant": true }
+        }
+    }"#;
+
+    #[test]
+    fn duplicate_names_in_one_file_are_rejected() {
+        let error = ManifestFormat::Json
+            .deserialize::<RawItemManifest>(
+                Path::new("duplicate.item_manifest.json"),
+                DUPLICATE_JSON.as_bytes(),
+            )
+            .unwrap_err();
+
+        assert_eq!(error, ManifestError::DuplicateId(vec!["leaf".to_string()]));
+    }
+
+    #[test]
+    fn duplicate_names_are_found_in_ron_files() {
+        let ron = r#"(
+            version: 1,
+            items: {
+                "leaf": (stack_size: 10, compostable: true, fluid: false, buoyant: true),
+                "leaf": (stack_size: 5, compostable: true, fluid: false, buoyant: true),
+            },
+        )"#;
+
+        assert_eq!(
+            ManifestFormat::Ron
+                .duplicate_names(Path::new("duplicate.item_manifest.ron"), ron.as_bytes())
+                .unwrap(),
+            vec!["leaf".to_string()]
+        );
+    }
+
+    #[test]
+    fn unique_names_are_not_duplicates() {
+        let json = r#"{ "$schema": "./items.schema.json", "items": { "leaf": {}, "pollen": {} } }"#;
+
+        assert!(ManifestFormat::Json
+            .duplicate_names(Path::new("unique.item_manifest.json"), json.as_bytes())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn manifests_of_the_current_version_are_loaded() {
+        let json = r#"{
+            "version": 1,
+            "items": { "leaf": { "stack_size": 10, "compostable": true, "fluid": false, "buoyant": true } }
+        }"#;
+
+        let raw_manifest = ManifestFormat::Json
+            .deserialize::<RawItemManifest>(Path::new("test.item_manifest.json"), json.as_bytes())
+            .unwrap();
+
+        assert_eq!(raw_manifest.version, RawItemManifest::CURRENT_VERSION);
+        assert!(raw_manifest.items.contains_key("leaf"));
+    }
+
+    /// An item manifest in which the `stack_size` of `leaf` is misspelled.
+    const MISSPELLED_ITEM_MANIFEST: &str = r#"{
+        "version": 1,
+        "items": { "leaf": { "stack_sise": 10, "compostable": true, "fluid": false, "buoyant": true } }
+    }"#;
+
+    #[test]
+    #[cfg(not(feature = "strict_manifests"))]
+    fn unknown_fields_are_ignored_by_default() {
+        let raw_manifest = ManifestFormat::Json
+            .deserialize::<RawItemManifest>(
+                Path::new("misspelled.item_manifest.json"),
+                MISSPELLED_ITEM_MANIFEST.as_bytes(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            raw_manifest.items["leaf"].stack_size,
+            RawItemData::DEFAULT_STACK_SIZE
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "strict_manifests")]
+    fn unknown_fields_are_rejected_in_strict_mode() {
+        let error = ManifestFormat::Json
+            .deserialize::<RawItemManifest>(
+                Path::new("misspelled.item_manifest.json"),
+                MISSPELLED_ITEM_MANIFEST.as_bytes(),
+            )
+            .unwrap_err();
+
+        let ManifestError::ParseError { line, message, .. } = error else {
+            panic!("Expected a parse error, got {error:?}");
+        };
+        assert_eq!(line, 3);
+        assert!(message.contains("unknown field `stack_sise`"), "{message}");
+    }
+
+    #[test]
+    fn schema_keys_are_ignored() {
+        let json = r#"{
+            "$schema": "../schemas/items.schema.json",
+            "version": 1,
+            "items": { "leaf": { "compostable": true, "fluid": false, "buoyant": true } }
+        }"#;
+
+        let raw_manifest = ManifestFormat::Json
+            .deserialize::<RawItemManifest>(Path::new("test.item_manifest.json"), json.as_bytes())
+            .unwrap();
+
+        assert_eq!(raw_manifest.items.len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "json5_manifests")]
+    fn commented_json5_manifests_are_loaded() {
+        let json5 = r#"{
+            "$schema": "../schemas/items.schema.json",
+            version: 1,
+            items: {
+                // Falls from trees in autumn
+                leaf: { stack_size: 10, compostable: true, fluid: false, buoyant: true, },
+                /* Sticky, and does not float */
+                'sap': { stack_size: 5, compostable: false, fluid: true, buoyant: false },
+            },
+        }"#;
+
+        let raw_manifest = ManifestFormat::Json
+            .deserialize::<RawItemManifest>(Path::new("test.item_manifest.json"), json5.as_bytes())
+            .unwrap();
+
+        assert_eq!(raw_manifest.items.len(), 2);
+        assert_eq!(raw_manifest.items["sap"].stack_size, 5);
+    }
+
+    #[test]
+    fn manifests_of_other_versions_are_rejected() {
+        // The entries of outdated manifests may not match the current format
+        let ron = r#"(
+            version: 0,
+            items: { "leaf": (size: 10) },
+        )"#;
+
+        let error = ManifestFormat::Ron
+            .deserialize::<RawItemManifest>(Path::new("test.item_manifest.ron"), ron.as_bytes())
+            .unwrap_err();
+
+        assert_eq!(
+            error,
+            ManifestError::VersionMismatch {
+                expected: RawItemManifest::CURRENT_VERSION,
+                found: Some(0),
+            }
+        );
+    }
+
+    #[test]
+    fn manifests_without_a_version_are_rejected() {
+        let json = r#"{ "items": {} }"#;
+
+        let error = ManifestFormat::Json
+            .deserialize::<RawItemManifest>(Path::new("test.item_manifest.json"), json.as_bytes())
+            .unwrap_err();
+
+        assert_eq!(
+            error,
+            ManifestError::VersionMismatch {
+                expected: RawItemManifest::CURRENT_VERSION,
+                found: None,
+            }
+        );
+    }
+
+    #[test]
+    // Trailing commas are allowed in JSON5
+    #[cfg(not(feature = "json5_manifests"))]
+    fn malformed_json_is_reported_with_its_location() {
+        let json = r#"{
+            "version": 1,
+            "items": {
+                "leaf": { "stack_size": 10, "compostable": true, "fluid": false, "buoyant": true },
+            }
+        }"#;
+
+        let error = ManifestFormat::Json
+            .deserialize::<RawItemManifest>(Path::new("broken.item_manifest.json"), json.as_bytes())
+            .unwrap_err();
+
+        let ManifestError::ParseError { path, line, column, message } = error else {
+            panic!("Expected a parse error, got {error:?}");
+        };
+        assert_eq!(path, Path::new("broken.item_manifest.json"));
+        // The trailing comma is only noticed once the map is closed on the next line
+        assert_eq!(line, 5);
+        assert_eq!(column, 13);
+        assert!(message.contains("trailing comma"), "{message}");
+    }
+
+    #[test]
+    fn invalid_entries_are_reported_with_their_location() {
+        let json = r#"{
+            "version": 1,
+            "items": {
+                "leaf": { "stack_size": "ten", "compostable": true, "fluid": false, "buoyant": true }
+            }
+        }"#;
+
+        let error = ManifestFormat::Json
+            .deserialize::<RawItemManifest>(Path::new("broken.item_manifest.json"), json.as_bytes())
+            .unwrap_err();
+
+        assert!(matches!(error, ManifestError::ParseError { line: 4, .. }));
+        assert!(
+            error.to_string().contains("broken.item_manifest.json"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn malformed_ron_is_reported_with_its_location() {
+        let ron = "(\n    version: 1,\n    items: {\n        \"leaf\": (stack_size: 10,\n    },\n)";
+
+        let error = ManifestFormat::Ron
+            .deserialize::<RawItemManifest>(Path::new("broken.item_manifest.ron"), ron.as_bytes())
+            .unwrap_err();
+
+        let ManifestError::ParseError { path, line, .. } = error else {
+            panic!("Expected a parse error, got {error:?}");
+        };
+        assert_eq!(path, Path::new("broken.item_manifest.ron"));
+        assert_eq!(line, 5);
+    }
+
+    /// The marker type for the fruit manifests.
+    struct Fruit;
+
+    /// The raw data of a fruit, which is processed into its weight.
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    struct RawFruitData {
+        /// The weight of the fruit, in grams.
+        weight: u32,
+    }
+
+    impl From<RawFruitData> for u32 {
+        fn from(raw: RawFruitData) -> u32 {
+            raw.weight
+        }
+    }
+
+    /// A fruit manifest that implements [`IsRawManifest`] by hand.
+    #[derive(Debug, Clone, PartialEq, Deserialize, TypeUuid)]
+    #[uuid = "2cd71250-5c29-4358-bb25-727658d6c422"]
+    struct HandWrittenFruitManifest {
+        /// The version of the manifest format.
+        version: u32,
+        /// The data for each fruit.
+        fruits: HashMap<String, RawFruitData>,
+    }
+
+    impl IsRawManifest for HandWrittenFruitManifest {
+        const EXTENSION: &'static str = "fruit_manifest";
+
+        type Marker = Fruit;
+        type Data = u32;
+        type RawData = RawFruitData;
+
+        fn process(&self) -> Result<Manifest<Self::Marker, Self::Data>, ManifestError> {
+            let mut manifest = Manifest::new();
+
+            let mut fruits: Vec<_> = self.fruits.clone().into_iter().collect();
+            fruits.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+            for (raw_id, raw_data) in fruits {
+                manifest.insert(raw_id, raw_data.into())
+            }
+
+            Ok(manifest)
+        }
+
+        fn version(&self) -> u32 {
+            self.version
+        }
+
+        fn entries(&self) -> &HashMap<String, Self::RawData> {
+            &self.fruits
+        }
+
+        fn entries_mut(&mut self) -> &mut HashMap<String, Self::RawData> {
+            &mut self.fruits
+        }
+    }
+
+    /// The same fruit manifest as [`HandWrittenFruitManifest`], with a derived implementation.
+    #[derive(Debug, Clone, PartialEq, Deserialize, TypeUuid, RawManifest)]
+    #[uuid = "663469d5-a9b2-47c8-ba19-5cb2970fc666"]
+    #[raw_manifest(extension = "fruit_manifest", marker = Fruit, data = u32)]
+    struct DerivedFruitManifest {
+        /// The version of the manifest format.
+        version: u32,
+        /// The data for each fruit.
+        fruits: HashMap<String, RawFruitData>,
+    }
+
+    #[test]
+    fn derived_manifests_match_hand_written_ones() {
+        let json = r#"{
+            "version": 1,
+            "fruits": { "apple": { "weight": 150 }, "plum": { "weight": 40 } }
+        }"#;
+        let mut hand_written: HandWrittenFruitManifest = serde_json::from_str(json).unwrap();
+        let mut derived: DerivedFruitManifest = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            HandWrittenFruitManifest::EXTENSION,
+            DerivedFruitManifest::EXTENSION
+        );
+        assert_eq!(hand_written.version(), derived.version());
+        assert_eq!(hand_written.entries(), derived.entries());
+        assert!(hand_written.process().unwrap() == derived.process().unwrap());
+        assert_eq!(
+            hand_written.validate(&World::new()),
+            derived.validate(&World::new())
+        );
+
+        hand_written.entries_mut().remove("apple");
+        derived.entries_mut().remove("apple");
+        assert!(hand_written.process().unwrap() == derived.process().unwrap());
+    }
+}
@@ -4,14 +4,34 @@
 //! Other systems should look up the data contained here,
 //! in order to populate the properties of in-game entities.
 
+#[cfg(test)]
+mod arbitrary;
 mod identifier;
 
 pub use self::identifier::*;
+pub mod diff;
+pub mod duration;
+#[cfg(feature = "embedded_manifests")]
+pub mod embedded;
+pub mod errors;
+pub mod export;
+#[cfg(feature = "json5_manifests")]
+mod json5;
+pub mod lint;
 pub mod loader;
 pub mod plugin;
+pub mod registry;
+pub mod set;
+pub mod validation;
+
+use self::errors::ManifestError;
 
 use bevy::{prelude::*, utils::HashMap};
-use std::{any::type_name, fmt::Debug};
+use std::{
+    any::type_name,
+    fmt::{Debug, Display},
+    mem::size_of,
+};
 
 /// Write-only data definitions.
 ///
@@ -47,6 +67,11 @@ where
         }
     }
 
+    /// Starts building a manifest entry by entry, using a [`ManifestBuilder`].
+    pub fn builder() -> ManifestBuilder<T, Data> {
+        ManifestBuilder::new()
+    }
+
     /// Returns a reference to the internal data map.
     pub fn data_map(&self) -> &HashMap<Id<T>, Data> {
         &self.data_map
@@ -67,16 +92,60 @@ where
         self.name_map.insert(id, name);
     }
 
+    /// Adds an entry to the manifest, like [`Manifest::insert`], but never overwrites an existing entry.
+    ///
+    /// Returns the [`Id`] of the new entry,
+    /// or fails with [`ManifestError::DuplicateId`] if an entry with the same [`Id`] is already in the manifest.
+    pub fn try_insert(&mut self, name: String, data: Data) -> Result<Id<T>, ManifestError> {
+        let id = Id::from_name(name.clone());
+        if self.data_map.contains_key(&id) {
+            return Err(ManifestError::DuplicateId(vec![name]));
+        }
+
+        self.data_map.insert(id, data);
+        self.name_map.insert(id, name);
+        Ok(id)
+    }
+
     /// Get the data entry for the given ID.
     ///
+    /// Fails with [`ManifestError::UnknownId`] if the ID is not in the manifest,
+    /// for example because it was read from an outdated save or the entry was removed while reloading.
+    pub fn get(&self, id: Id<T>) -> Result<&Data, ManifestError> {
+        self.data_map
+            .get(&id)
+            .ok_or_else(|| ManifestError::UnknownId {
+                kind: type_name::<T>(),
+                id: id.value(),
+                name: self.name_of(id).map(str::to_string),
+            })
+    }
+
+    /// Get the data entry for the given ID, when it is known to be in the manifest.
+    ///
     /// # Panics
     ///
     /// This function panics when the given ID does not exist in the manifest.
     /// We assume that all IDs are valid and the manifests are complete.
-    pub fn get(&self, id: Id<T>) -> &Data {
+    pub fn get_unchecked(&self, id: Id<T>) -> &Data {
         self.data_map
             .get(&id)
-            .unwrap_or_else(|| panic!("ID {id:?} {} not found in manifest", self.name(id)))
+            .unwrap_or_else(|| panic!("{:?} not found in manifest", self.named(id)))
+    }
+
+    /// Returns the human-readable name associated with the provided `id`, if it is in the manifest.
+    pub fn name_of(&self, id: Id<T>) -> Option<&str> {
+        self.name_map.get(&id).map(String::as_str)
+    }
+
+    /// Pairs the `id` with its name from this manifest, for use in logs and error messages.
+    ///
+    /// Unlike [`Manifest::name`], this does not panic for unknown IDs.
+    pub fn named(&self, id: Id<T>) -> NamedId<'_, T> {
+        NamedId {
+            id,
+            name: self.name_of(id),
+        }
     }
 
     /// Returns the human-readable name associated with the provided `id`.
@@ -86,7 +155,7 @@ where
     /// This function panics when the given ID does not exist in the manifest.
     /// We assume that all IDs are valid and the manifests are complete.
     pub fn name(&self, id: Id<T>) -> &str {
-        self.name_map.get(&id).unwrap_or_else(|| {
+        self.name_of(id).unwrap_or_else(|| {
             panic!(
                 "ID {:?} of type {:?} not found in manifest",
                 id,
@@ -98,9 +167,20 @@ where
     /// Returns the complete list of names of the loaded options.
     ///
     /// The order is arbitrary.
-    pub fn names(&self) -> impl IntoIterator<Item = &str> {
-        let variants = self.variants();
-        variants.into_iter().map(|id| self.name(id))
+    pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.name_map.values().map(String::as_str)
+    }
+
+    /// Is there an entry with exactly this `name` in the manifest?
+    pub fn contains_name(&self, name: &str) -> bool {
+        self.name_of(Id::from_name(name.to_string())) == Some(name)
+    }
+
+    /// Get the data entry with the given `name`, if it is in the manifest.
+    ///
+    /// This is a shorthand for looking up the [`Id::from_name`] of the name with [`Manifest::get`].
+    pub fn get_by_name(&self, name: &str) -> Option<&Data> {
+        self.data_map.get(&Id::from_name(name.to_string()))
     }
 
     /// The complete list of loaded options.
@@ -109,6 +189,184 @@ where
     pub fn variants(&self) -> impl IntoIterator<Item = Id<T>> + '_ {
         self.data_map.keys().copied()
     }
+
+    /// Iterates over every entry in the manifest, together with its [`Id`].
+    ///
+    /// The order is arbitrary.
+    pub fn iter(&self) -> Iter<'_, T, Data> {
+        self.data_map.iter().map(|(id, data)| (*id, data))
+    }
+
+    /// The number of entries in the manifest.
+    pub fn len(&self) -> usize {
+        self.data_map.len()
+    }
+
+    /// Does the manifest contain no entries at all?
+    pub fn is_empty(&self) -> bool {
+        self.data_map.is_empty()
+    }
+
+    /// An estimate of the memory taken up by this manifest, in bytes.
+    ///
+    /// This counts the space reserved for the entries of both maps, and the text of the retained names.
+    /// Memory that the data itself points to, such as the contents of a [`Vec`], is not counted,
+    /// and the bookkeeping of the maps is ignored, so this is a lower bound.
+    pub fn approx_memory_bytes(&self) -> usize {
+        let data_entry_bytes = size_of::<Id<T>>() + size_of::<Data>();
+        let name_entry_bytes = size_of::<Id<T>>() + size_of::<String>();
+        let name_bytes: usize = self.name_map.values().map(String::capacity).sum();
+
+        size_of::<Self>()
+            + self.data_map.capacity() * data_entry_bytes
+            + self.name_map.capacity() * name_entry_bytes
+            + name_bytes
+    }
+
+    /// Creates a new manifest with the same entries, whose data is derived from this manifest using `f`.
+    ///
+    /// Every entry keeps its [`Id`] and name, so the new manifest can be queried in the same way.
+    pub fn map<U: Debug>(&self, f: impl Fn(&Data) -> U) -> Manifest<T, U> {
+        Manifest {
+            data_map: self
+                .data_map
+                .iter()
+                .map(|(&id, data)| (id, f(data)))
+                .collect(),
+            name_map: self.name_map.clone(),
+        }
+    }
+}
+
+/// Manifests are equal if they hold the same entries under the same names, no matter the order they were inserted in.
+///
+/// This is implemented by hand, as the marker type does not need to be comparable.
+impl<T, Data> PartialEq for Manifest<T, Data>
+where
+    Data: Debug + PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.data_map == other.data_map && self.name_map == other.name_map
+    }
+}
+
+impl<T, Data> Eq for Manifest<T, Data> where Data: Debug + Eq {}
+
+/// An iterator over the entries of a [`Manifest`], as created by [`Manifest::iter`].
+pub type Iter<'a, T, Data> = std::iter::Map<
+    bevy::utils::hashbrown::hash_map::Iter<'a, Id<T>, Data>,
+    fn((&'a Id<T>, &'a Data)) -> (Id<T>, &'a Data),
+>;
+
+impl<'a, T, Data: Debug> IntoIterator for &'a Manifest<T, Data> {
+    type Item = (Id<T>, &'a Data);
+    type IntoIter = Iter<'a, T, Data>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Collects `(name, data)` pairs into a manifest, as if each were added with [`Manifest::insert`].
+///
+/// Later entries overwrite earlier entries with the same name.
+impl<T, Data: Debug> FromIterator<(String, Data)> for Manifest<T, Data> {
+    fn from_iter<I: IntoIterator<Item = (String, Data)>>(iter: I) -> Self {
+        let mut manifest = Manifest::new();
+        manifest.extend(iter);
+        manifest
+    }
+}
+
+impl<T, Data: Debug> Extend<(String, Data)> for Manifest<T, Data> {
+    fn extend<I: IntoIterator<Item = (String, Data)>>(&mut self, iter: I) {
+        for (name, data) in iter {
+            self.insert(name, data);
+        }
+    }
+}
+
+/// Builds a [`Manifest`] one entry at a time, for tests and procedurally generated content.
+///
+/// Unlike collecting into a manifest, repeated names are reported rather than silently overwritten.
+#[derive(Debug)]
+pub struct ManifestBuilder<T: 'static, Data: Debug> {
+    /// The entries added so far.
+    manifest: Manifest<T, Data>,
+    /// The names that were added more than once.
+    duplicates: Vec<String>,
+}
+
+impl<T, Data: Debug> ManifestBuilder<T, Data> {
+    /// Starts building an empty manifest.
+    pub fn new() -> Self {
+        ManifestBuilder {
+            manifest: Manifest::new(),
+            duplicates: Vec::new(),
+        }
+    }
+
+    /// Adds an entry called `name` to the manifest.
+    pub fn with(mut self, name: impl Into<String>, data: Data) -> Self {
+        let name = name.into();
+        if self.manifest.try_insert(name.clone(), data).is_err() {
+            self.duplicates.push(name);
+        }
+        self
+    }
+
+    /// Finishes the manifest.
+    ///
+    /// Fails with [`ManifestError::DuplicateId`] if any name was added more than once.
+    /// The first entry with each name is kept.
+    pub fn build(mut self) -> Result<Manifest<T, Data>, ManifestError> {
+        if self.duplicates.is_empty() {
+            Ok(self.manifest)
+        } else {
+            self.duplicates.sort();
+            self.duplicates.dedup();
+            Err(ManifestError::DuplicateId(self.duplicates))
+        }
+    }
+}
+
+impl<T, Data: Debug> Default for ManifestBuilder<T, Data> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An [`Id`] together with its human-readable name, as created by [`Manifest::named`].
+///
+/// Its [`Display`] implementation shows the name, falling back to the raw ID if it is unknown.
+/// Its [`Debug`] implementation always shows both.
+#[derive(Clone, Copy)]
+pub struct NamedId<'a, T> {
+    /// The identifier.
+    pub id: Id<T>,
+    /// The name of the identifier, if it is in the manifest.
+    pub name: Option<&'a str>,
+}
+
+impl<T> Display for NamedId<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.name {
+            Some(name) => write!(f, "{name}"),
+            None => write!(f, "unknown {:?}", self.id),
+        }
+    }
+}
+
+impl<T> Debug for NamedId<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} ({}) of type {}",
+            self.id,
+            self.name.unwrap_or("unknown"),
+            type_name::<T>()
+        )
+    }
 }
 
 /// A plugin that adds the default manifests to the app.
@@ -120,7 +378,8 @@ impl Plugin for DummyManifestPlugin {
     fn build(&self, app: &mut App) {
         use crate::{
             crafting::recipe::RecipeManifest,
-            items::item_manifest::ItemManifest,
+            environment::EnvironmentManifest,
+            items::{item_manifest::ItemManifest, starter_manifest::StarterInventory},
             structures::structure_manifest::{StructureData, StructureManifest},
             terrain::terrain_manifest::{TerrainData, TerrainManifest},
             units::basic_needs::Diet,
@@ -153,5 +412,255 @@ impl Plugin for DummyManifestPlugin {
 
         let recipe_manifest = RecipeManifest::default();
         app.insert_resource(recipe_manifest);
+
+        let starter_inventory = StarterInventory::default();
+        app.insert_resource(starter_inventory);
+
+        let environment_manifest = EnvironmentManifest::default();
+        app.insert_resource(environment_manifest);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A marker type for the test manifest.
+    struct Fruit;
+
+    /// A manifest containing an apple.
+    fn fruit_manifest() -> Manifest<Fruit, ()> {
+        let mut manifest = Manifest::new();
+        manifest.insert("apple".to_string(), ());
+        manifest
+    }
+
+    #[test]
+    fn iterating_yields_every_entry() {
+        let mut manifest = Manifest::<Fruit, u32>::new();
+        for (name, weight) in [("apple", 150), ("pear", 170), ("plum", 40)] {
+            manifest.insert(name.to_string(), weight);
+        }
+
+        assert_eq!(manifest.len(), 3);
+        assert!(!manifest.is_empty());
+        assert_eq!(manifest.iter().count(), 3);
+        for (id, data) in &manifest {
+            assert_eq!(manifest.get(id), Ok(data));
+        }
+    }
+
+    #[test]
+    fn memory_footprint_grows_with_the_number_of_entries() {
+        let mut manifest = Manifest::<Fruit, u32>::new();
+        let mut footprint = manifest.approx_memory_bytes();
+        assert!(footprint >= size_of::<Manifest<Fruit, u32>>());
+
+        for i in 0..100 {
+            manifest.insert(format!("fruit_{i}"), i);
+            let grown = manifest.approx_memory_bytes();
+
+            // The maps only grow now and then, but the names always take up more space
+            assert!(grown > footprint);
+            footprint = grown;
+        }
+
+        // Longer names take up more space
+        let mut long_names = Manifest::<Fruit, u32>::new();
+        for i in 0..100 {
+            long_names.insert(format!("very_long_fruit_name_{i}"), i);
+        }
+        assert!(long_names.approx_memory_bytes() > manifest.approx_memory_bytes());
+    }
+
+    #[test]
+    fn entries_can_be_looked_up_by_name() {
+        let mut manifest = Manifest::<Fruit, u32>::new();
+        manifest.insert("apple".to_string(), 150);
+
+        assert_eq!(manifest.get_by_name("apple"), Some(&150));
+        assert_eq!(manifest.get_by_name("pear"), None);
+        for name in ["apple", "pear"] {
+            assert_eq!(
+                manifest.get_by_name(name),
+                manifest.get(Id::from_name(name.to_string())).ok()
+            );
+        }
+    }
+
+    #[test]
+    fn manifests_with_the_same_entries_are_equal() {
+        let entries = [("apple", 150), ("pear", 170), ("plum", 40)];
+        let mut forwards = Manifest::<Fruit, u32>::new();
+        for (name, weight) in entries {
+            forwards.insert(name.to_string(), weight);
+        }
+        let mut backwards = Manifest::<Fruit, u32>::new();
+        for (name, weight) in entries.into_iter().rev() {
+            backwards.insert(name.to_string(), weight);
+        }
+
+        assert!(forwards == backwards);
+
+        backwards.insert("plum".to_string(), 45);
+        assert!(forwards != backwards);
+    }
+
+    #[test]
+    fn try_insert_adds_fresh_names() {
+        let mut manifest = fruit_manifest();
+
+        let id = manifest.try_insert("pear".to_string(), ());
+
+        assert_eq!(id, Ok(Id::from_name("pear".to_string())));
+        assert_eq!(manifest.len(), 2);
+        assert!(manifest.contains_name("pear"));
+    }
+
+    #[test]
+    fn try_insert_rejects_repeated_names() {
+        let mut manifest = Manifest::<Fruit, u32>::new();
+        manifest.insert("apple".to_string(), 150);
+
+        assert_eq!(
+            manifest.try_insert("apple".to_string(), 200),
+            Err(ManifestError::DuplicateId(vec!["apple".to_string()]))
+        );
+        assert_eq!(manifest.len(), 1);
+        assert_eq!(manifest.get(Id::from_name("apple".to_string())), Ok(&150));
+    }
+
+    #[test]
+    fn collected_manifests_keep_the_last_entry_for_each_name() {
+        let manifest: Manifest<Fruit, u32> = [("apple", 150), ("pear", 170), ("apple", 200)]
+            .into_iter()
+            .map(|(name, weight)| (name.to_string(), weight))
+            .collect();
+
+        assert_eq!(manifest.len(), 2);
+        assert_eq!(manifest.get(Id::from_name("apple".to_string())), Ok(&200));
+    }
+
+    #[test]
+    fn built_manifests_match_inserted_ones() {
+        let built = Manifest::<Fruit, u32>::builder()
+            .with("apple", 150)
+            .with("pear", 170)
+            .build();
+
+        let mut inserted = Manifest::new();
+        inserted.insert("apple".to_string(), 150);
+        inserted.insert("pear".to_string(), 170);
+
+        assert!(built.unwrap() == inserted);
+    }
+
+    #[test]
+    fn builders_reject_repeated_names() {
+        let built = Manifest::<Fruit, u32>::builder()
+            .with("plum", 40)
+            .with("apple", 150)
+            .with("plum", 45)
+            .with("apple", 200)
+            .build();
+
+        assert_eq!(
+            built.err(),
+            Some(ManifestError::DuplicateId(vec![
+                "apple".to_string(),
+                "plum".to_string()
+            ]))
+        );
+    }
+
+    #[test]
+    fn new_manifests_are_empty() {
+        let manifest = Manifest::<Fruit, ()>::new();
+
+        assert_eq!(manifest.len(), 0);
+        assert!(manifest.is_empty());
+        assert_eq!(manifest.iter().next(), None);
+    }
+
+    #[test]
+    fn same_name_gives_same_id() {
+        assert_eq!(
+            Id::<Fruit>::from_name("apple".to_string()),
+            Id::<Fruit>::from_name("apple".to_string())
+        );
+        assert_ne!(
+            Id::<Fruit>::from_name("apple".to_string()),
+            Id::<Fruit>::from_name("pear".to_string())
+        );
+    }
+
+    #[test]
+    fn names_round_trip_through_ids() {
+        let manifest = fruit_manifest();
+        let id = Id::<Fruit>::from_name("apple".to_string());
+
+        assert_eq!(manifest.name_of(id), Some("apple"));
+    }
+
+    #[test]
+    fn only_inserted_names_are_contained() {
+        let manifest = fruit_manifest();
+
+        assert!(manifest.contains_name("apple"));
+        assert!(!manifest.contains_name("pear"));
+        assert!(!manifest.contains_name("Apple"));
+        assert!(!manifest.contains_name(""));
+    }
+
+    #[test]
+    fn names_lists_every_entry() {
+        let mut manifest = fruit_manifest();
+        manifest.insert("pear".to_string(), ());
+        manifest.insert("plum".to_string(), ());
+
+        let mut names: Vec<&str> = manifest.names().collect();
+        names.sort_unstable();
+        assert_eq!(names, vec!["apple", "pear", "plum"]);
+    }
+
+    #[test]
+    fn unknown_ids_have_no_name() {
+        let manifest = fruit_manifest();
+        let id = Id::<Fruit>::from_name("pear".to_string());
+
+        assert_eq!(manifest.name_of(id), None);
+        assert_eq!(manifest.named(id).to_string(), format!("unknown {id:?}"));
+    }
+
+    #[test]
+    fn named_ids_display_their_name() {
+        let manifest = fruit_manifest();
+        let id = Id::<Fruit>::from_name("apple".to_string());
+
+        assert_eq!(manifest.named(id).to_string(), "apple");
+        assert!(format!("{:?}", manifest.named(id)).contains("apple"));
+    }
+
+    #[test]
+    fn known_ids_can_be_looked_up() {
+        let manifest = fruit_manifest();
+        let id = Id::<Fruit>::from_name("apple".to_string());
+
+        assert_eq!(manifest.get(id), Ok(&()));
+    }
+
+    #[test]
+    fn unknown_ids_fail_to_be_looked_up() {
+        let manifest = fruit_manifest();
+        let id = Id::<Fruit>::from_name("pear".to_string());
+
+        assert_eq!(
+            manifest.get(id),
+            Err(ManifestError::UnknownId {
+                kind: type_name::<Fruit>(),
+                id: id.value(),
+                name: None,
+            })
+        );
     }
 }
@@ -1,13 +1,25 @@
 //! The plugin to handle loading of manifest assets.
 
-use std::marker::PhantomData;
+use std::{
+    marker::PhantomData,
+    path::{Path, PathBuf},
+};
 
-use bevy::prelude::*;
+use bevy::{
+    asset::{HandleId, LoadState},
+    ecs::event::ManualEventReader,
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
 
-use crate::asset_management::{AssetCollectionExt, AssetState, Loadable};
+use crate::asset_management::{AssetCollectionExt, AssetState, AssetsToLoad, Loadable};
 
 use super::{
-    loader::{IsRawManifest, RawManifestLoader},
+    errors::ManifestError,
+    loader::{
+        discover_layers, merge_layers, names_defined_by_multiple_layers, IsRawManifest,
+        ManifestFormat, RawManifestLoader, BASE_GAME_LAYER,
+    },
     Manifest,
 };
 
@@ -32,6 +44,155 @@ where
     }
 }
 
+/// The ordered list of manifest layers to load.
+///
+/// Each layer can provide a file for every manifest type, such as `manifests/my_mod.item_manifest.json`.
+/// Layers that lack a file for a manifest type are skipped for that type.
+/// Their entries are merged in order, so later layers override entries of earlier ones.
+///
+/// Insert this resource before the manifest plugins are added to load additional layers, such as mods.
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct ManifestLayers {
+    /// The layers, named by their path relative to the asset folder without the extension.
+    pub layers: Vec<String>,
+    /// Directories relative to the asset folder, in which every manifest file is loaded as an additional layer.
+    ///
+    /// This allows new files to be dropped in, without listing them in [`ManifestLayers::layers`].
+    /// The discovered layers are loaded after the listed ones, one directory after another,
+    /// in the order given by [`discover_layers`].
+    /// Directories are always read from disk, even with the `embedded_manifests` feature.
+    pub directories: Vec<String>,
+    /// Should entries that are defined by more than one layer be rejected, rather than overridden?
+    ///
+    /// This is useful for layers that are meant to only add content.
+    pub strict: bool,
+}
+
+impl Default for ManifestLayers {
+    fn default() -> Self {
+        ManifestLayers {
+            layers: vec![BASE_GAME_LAYER.to_string()],
+            directories: Vec::new(),
+            strict: false,
+        }
+    }
+}
+
+impl ManifestLayers {
+    /// All layers to load for the manifest type `M`, in order.
+    ///
+    /// These are the listed [`ManifestLayers::layers`], followed by the layers discovered in the [`ManifestLayers::directories`].
+    /// `read_directory` lists the paths of the files in a directory, relative to the asset folder.
+    /// Directories that cannot be read are skipped.
+    pub fn layers_for<M>(
+        &self,
+        mut read_directory: impl FnMut(&Path) -> Option<Vec<PathBuf>>,
+    ) -> Vec<String>
+    where
+        M: IsRawManifest,
+    {
+        let mut layers = self.layers.clone();
+
+        for directory in &self.directories {
+            if let Some(paths) = read_directory(Path::new(directory)) {
+                layers.extend(discover_layers::<M>(paths));
+            }
+        }
+
+        layers
+    }
+}
+
+/// Tracks how many of the registered manifest types have been processed, and which of them failed to process.
+///
+/// This can be used to show the loading progress, for example on a loading screen.
+/// Loading only moves on to [`AssetState::LoadAssets`] once [`ManifestLoadState::is_complete`] is true.
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct ManifestLoadState {
+    /// The raw manifest types that have been registered, named by their [`IsRawManifest::EXTENSION`].
+    registered: HashSet<&'static str>,
+    /// The registered raw manifest types that have been processed.
+    processed: HashSet<&'static str>,
+    /// The registered raw manifest types that could not be processed, and why.
+    failed: HashMap<&'static str, ManifestError>,
+}
+
+impl ManifestLoadState {
+    /// Registers the raw manifest type `M`, which needs to be processed before loading is complete.
+    pub fn register<M>(&mut self)
+    where
+        M: IsRawManifest,
+    {
+        self.registered.insert(M::EXTENSION);
+    }
+
+    /// Records that the raw manifest type `M` has been processed.
+    pub fn mark_processed<M>(&mut self)
+    where
+        M: IsRawManifest,
+    {
+        self.processed.insert(M::EXTENSION);
+    }
+
+    /// Has the raw manifest type `M` been processed?
+    pub fn is_processed<M>(&self) -> bool
+    where
+        M: IsRawManifest,
+    {
+        self.processed.contains(M::EXTENSION)
+    }
+
+    /// Records that the raw manifest type `M` could not be processed, because of the `error`.
+    pub fn mark_failed<M>(&mut self, error: ManifestError)
+    where
+        M: IsRawManifest,
+    {
+        self.failed.insert(M::EXTENSION, error);
+    }
+
+    /// The reason that the raw manifest type `M` could not be processed, if it failed.
+    pub fn error<M>(&self) -> Option<&ManifestError>
+    where
+        M: IsRawManifest,
+    {
+        self.failed.get(M::EXTENSION)
+    }
+
+    /// Did any of the registered manifest types fail to process?
+    ///
+    /// Failed manifest types are never processed, so loading cannot complete.
+    pub fn has_failed(&self) -> bool {
+        !self.failed.is_empty()
+    }
+
+    /// The reasons that manifest types could not be processed, named by their [`IsRawManifest::EXTENSION`] and sorted by it.
+    pub fn errors(&self) -> Vec<(&'static str, &ManifestError)> {
+        let mut errors: Vec<_> = self
+            .failed
+            .iter()
+            .map(|(extension, error)| (*extension, error))
+            .collect();
+        errors.sort_unstable_by_key(|(extension, _)| *extension);
+        errors
+    }
+
+    /// The fraction of registered manifest types that have been processed, between 0 and 1.
+    ///
+    /// If no manifest types are registered, there is nothing to wait for and the progress is 1.
+    pub fn progress(&self) -> f32 {
+        if self.registered.is_empty() {
+            return 1.;
+        }
+
+        self.processed.intersection(&self.registered).count() as f32 / self.registered.len() as f32
+    }
+
+    /// Have all registered manifest types been processed?
+    pub fn is_complete(&self) -> bool {
+        self.processed.is_superset(&self.registered)
+    }
+}
+
 /// System set for all [`detect_manifest_creation`] systems
 #[derive(Debug, PartialEq, Eq, Hash, Clone, SystemSet)]
 pub struct DetectManifestCreationSet;
@@ -43,14 +204,22 @@ where
     fn build(&self, app: &mut App) {
         info!("Building RawManifestPlugin for {}", M::path().display());
 
+        app.init_resource::<ManifestLayers>()
+            .init_resource::<ManifestLoadState>();
+        app.world
+            .resource_mut::<ManifestLoadState>()
+            .register::<M>();
+
         app.init_asset_loader::<RawManifestLoader<M>>()
             .add_asset::<M>()
             .add_asset_collection::<RawManifestHandle<M>>()
             .add_system(
                 detect_manifest_creation::<M>
                     .in_set(DetectManifestCreationSet)
-                    .in_schedule(OnExit(AssetState::LoadManifests)),
+                    .run_if(in_state(AssetState::LoadManifests))
+                    .run_if(resource_exists::<RawManifestHandle<M>>()),
             )
+            .add_system(validate_manifest::<M>.in_schedule(OnEnter(AssetState::LoadAssets)))
             .add_system(
                 detect_manifest_modification::<M>
                     .run_if(resource_exists::<Manifest<M::Marker, M::Data>>()),
@@ -58,7 +227,7 @@ where
     }
 }
 
-/// Resource to store the handle to a [`IsRawManifest`] type while it is being loaded.
+/// Resource to store the handles to a [`IsRawManifest`] type while it is being loaded.
 ///
 /// This is necessary to stop the asset from being discarded.
 #[derive(Debug, Clone, Resource)]
@@ -66,10 +235,57 @@ pub struct RawManifestHandle<M>
 where
     M: IsRawManifest,
 {
-    /// The handle to the raw manifest asset.
+    /// The handles to the raw manifest assets, one for each layer, in the order of [`ManifestLayers`].
+    ///
+    /// We mainly need this for the assets to not be unloaded.
+    handles: Vec<Handle<M>>,
+}
+
+impl<M> RawManifestHandle<M>
+where
+    M: IsRawManifest,
+{
+    /// Does this collection contain the provided `handle`?
+    fn contains(&self, handle: &Handle<M>) -> bool {
+        self.handles.contains(handle)
+    }
+
+    /// The raw manifests of all layers, in order.
+    ///
+    /// Returns [`None`] if any of the assets are not available.
+    fn layers<'a>(&self, raw_manifests: &'a Assets<M>) -> Option<Vec<&'a M>> {
+        self.handles
+            .iter()
+            .map(|handle| raw_manifests.get(handle))
+            .collect()
+    }
+
+    /// Merges the raw manifests of all layers.
     ///
-    /// We mainly need this for the asset to not be unloaded.
-    handle: Handle<M>,
+    /// Returns [`None`] if any of the assets are not available.
+    fn merged(&self, raw_manifests: &Assets<M>) -> Option<M> {
+        merge_layers(self.layers(raw_manifests)?)
+    }
+
+    /// Checks that no entry is defined by more than one layer, if the [`ManifestLayers`] are strict.
+    fn check_strict(
+        &self,
+        raw_manifests: &Assets<M>,
+        manifest_layers: &ManifestLayers,
+    ) -> Result<(), ManifestError> {
+        if !manifest_layers.strict {
+            return Ok(());
+        }
+
+        let duplicates =
+            names_defined_by_multiple_layers(self.layers(raw_manifests).unwrap_or_default());
+
+        if duplicates.is_empty() {
+            Ok(())
+        } else {
+            Err(ManifestError::DuplicateId(duplicates))
+        }
+    }
 }
 
 impl<M> Loadable for RawManifestHandle<M>
@@ -78,60 +294,805 @@ where
 {
     const STAGE: AssetState = AssetState::LoadManifests;
 
+    /// # Panics
+    ///
+    /// Panics with [`ManifestError::FileNotFound`] if no layer has a file for the manifest.
+    /// With the `embedded_manifests` feature, this also panics if an embedded file cannot be read.
     fn initialize(world: &mut World) {
-        let asset_server = world.resource::<AssetServer>();
-        let handle: Handle<M> = asset_server.load(M::path());
+        let manifest_layers = world.resource::<ManifestLayers>().clone();
+        let layers = manifest_layers.layers_for::<M>(|directory| {
+            let asset_server = world.resource::<AssetServer>();
+            let paths = asset_server.asset_io().read_directory(directory).ok()?;
+            Some(paths.collect())
+        });
+        let mut handles = Vec::new();
+
+        for layer in &layers {
+            // Prefer the files baked into the binary, which do not need to be loaded
+            #[cfg(feature = "embedded_manifests")]
+            if let Some(layer_file) = super::embedded::embedded_layer_file::<M>(layer) {
+                let raw_manifest = layer_file
+                    .format
+                    .deserialize::<M>(&layer_file.path, &layer_file.bytes)
+                    .unwrap_or_else(|error| panic!("Embedded manifest file is invalid. {error}"));
+                handles.push(world.resource_mut::<Assets<M>>().add(raw_manifest));
+                continue;
+            }
+
+            // Use the first format that has a file on disk
+            let asset_server = world.resource::<AssetServer>();
+            if let Some(path) = ManifestFormat::ALL
+                .into_iter()
+                .map(|format| M::layer_path_with_format(layer, format))
+                .find(|path| asset_server.asset_io().is_file(path))
+            {
+                handles.push(asset_server.load(path));
+            }
+        }
 
-        world.insert_resource(Self { handle });
+        if handles.is_empty() {
+            panic!("{}", ManifestError::FileNotFound(M::path()));
+        }
+
+        world.insert_resource(Self { handles });
     }
 
-    fn load_state(&self, asset_server: &AssetServer) -> bevy::asset::LoadState {
-        let load_state = asset_server.get_load_state(self.handle.clone_weak());
+    /// # Panics
+    ///
+    /// Manifests are required to run the game, so this panics if any of their files failed to load.
+    /// The reason is logged by the asset server just before.
+    fn load_state(&self, asset_server: &AssetServer) -> LoadState {
+        // Embedded manifests are added directly, so they are not tracked by the asset server
+        let load_state = asset_server.get_group_load_state(
+            self.handles
+                .iter()
+                .map(|handle| handle.id())
+                .filter(|handle_id| matches!(handle_id, HandleId::AssetPathId(_))),
+        );
 
         debug!("Load state: {load_state:?}");
 
+        if load_state == LoadState::Failed {
+            let failed_paths: Vec<String> = self
+                .handles
+                .iter()
+                .filter(|handle| asset_server.get_load_state(*handle) == LoadState::Failed)
+                .filter_map(|handle| asset_server.get_handle_path(handle))
+                .map(|asset_path| asset_path.path().display().to_string())
+                .collect();
+
+            panic!(
+                "Failed to load the manifest file {}. See the error above for the reason.",
+                failed_paths.join(", ")
+            );
+        }
+
         load_state
     }
 }
 
-/// Wait for the manifest to be fully loaded and then process it.
+/// Wait for the manifest to be fully loaded and then process it, once.
+///
+/// The result is recorded in the [`ManifestLoadState`], which decides when loading moves on to [`AssetState::LoadAssets`],
+/// or stops it with the recorded errors.
+/// If the [`ManifestLayers`] are strict and more than one layer defines the same entry, if the raw manifest is not available,
+/// or if the manifest cannot be processed, the error is logged and recorded there, and no manifest is inserted.
 pub fn detect_manifest_creation<M>(
     mut commands: Commands,
     raw_manifest_handle: Res<RawManifestHandle<M>>,
     raw_manifests: Res<Assets<M>>,
+    manifest_layers: Res<ManifestLayers>,
+    assets_to_load: Res<AssetsToLoad>,
+    mut manifest_load_state: ResMut<ManifestLoadState>,
 ) where
     M: IsRawManifest,
 {
-    let Some(raw_manifest) = raw_manifests.get(&raw_manifest_handle.handle) else {
+    let loading = assets_to_load.contains::<RawManifestHandle<M>>();
+    let settled =
+        manifest_load_state.is_processed::<M>() || manifest_load_state.error::<M>().is_some();
+    if loading || settled {
+        return;
+    }
+
+    if let Err(error) = raw_manifest_handle.check_strict(&raw_manifests, &manifest_layers) {
+        error!("Manifest {} is invalid. {error}", M::path().display());
+        manifest_load_state.mark_failed::<M>(error);
+        return;
+    }
+
+    let Some(raw_manifest) = raw_manifest_handle.merged(&raw_manifests) else {
         error!("Raw manifest for {} created, but asset not available!", M::path().display());
+        manifest_load_state.mark_failed::<M>(ManifestError::FileNotFound(M::path()));
         return;
     };
 
     info!("Manifest asset {} loaded!", M::path().display());
 
     // Create the manifest and insert it as a resource
-    commands.insert_resource(raw_manifest.process());
+    match raw_manifest.process() {
+        Ok(manifest) => {
+            log_load_summary::<M>(&manifest);
+            commands.insert_resource(manifest);
+            manifest_load_state.mark_processed::<M>();
+        }
+        Err(error) => {
+            error!("Manifest {} is invalid. {error}", M::path().display());
+            manifest_load_state.mark_failed::<M>(error);
+        }
+    }
+}
+
+/// Reports how many entries the processed `manifest` has, listing their names and its memory footprint at the debug level.
+///
+/// This makes it easy to spot manifests that are unexpectedly empty, or larger than expected.
+fn log_load_summary<M>(manifest: &Manifest<M::Marker, M::Data>)
+where
+    M: IsRawManifest,
+{
+    info!(
+        "Loaded {} entries from {}",
+        manifest.len(),
+        M::path().display()
+    );
+
+    let mut names: Vec<&str> = manifest.names().collect();
+    names.sort_unstable();
+    debug!("Entries of {}: {}", M::path().display(), names.join(", "));
+    debug!(
+        "{} takes up about {} bytes",
+        M::path().display(),
+        manifest.approx_memory_bytes()
+    );
 }
 
-/// Update the manifest after the asset has been changed.
+/// Check the raw manifest against the processed manifests, once they have all been created.
+///
+/// # Panics
+///
+/// Invalid manifests cannot be recovered from, so this panics with a description of every problem found.
+fn validate_manifest<M>(world: &World)
+where
+    M: IsRawManifest,
+{
+    let raw_manifest_handle = world.resource::<RawManifestHandle<M>>();
+    let raw_manifests = world.resource::<Assets<M>>();
+
+    let Some(raw_manifest) = raw_manifest_handle.merged(raw_manifests) else {
+        error!("Raw manifest for {} not available for validation!", M::path().display());
+        return;
+    };
+
+    if let Err(error) = raw_manifest.validate(world) {
+        panic!("Manifest {} is invalid. {error}", M::path().display());
+    }
+}
+
+/// Update the manifest after the asset of any of its layers has been changed.
+///
+/// The manifest is processed again from scratch:
+/// entries removed from the file are dropped, while the [`Id`](super::Id) of every remaining entry stays the same.
+/// The new manifest is then validated against the rest of the game data, just like when the game starts.
+/// If the modified manifest is invalid, the error is logged and the previous manifest is kept.
 fn detect_manifest_modification<M>(
-    mut ev_asset: EventReader<AssetEvent<M>>,
-    raw_manifests: Res<Assets<M>>,
-    mut manifest: ResMut<Manifest<M::Marker, M::Data>>,
+    world: &mut World,
+    mut asset_events: Local<ManualEventReader<AssetEvent<M>>>,
 ) where
     M: IsRawManifest,
 {
-    for ev in ev_asset.iter() {
-        if let AssetEvent::Modified { handle } = ev {
-            let Some(raw_manifest) = raw_manifests.get(handle) else {
-                warn!("Raw manifest modified, but asset not available!");
-                continue;
-            };
+    let raw_manifest_handle = world.resource::<RawManifestHandle<M>>();
+    let modified = asset_events
+        .iter(world.resource::<Events<AssetEvent<M>>>())
+        .filter(|ev| match ev {
+            AssetEvent::Modified { handle } => raw_manifest_handle.contains(handle),
+            _ => false,
+        })
+        .count()
+        > 0;
 
-            debug!("Manifest asset {} modified.", M::path().display());
+    if !modified {
+        return;
+    }
 
-            // Update the manifest resource
-            *manifest = raw_manifest.process();
+    let raw_manifests = world.resource::<Assets<M>>();
+    if let Err(error) =
+        raw_manifest_handle.check_strict(raw_manifests, world.resource::<ManifestLayers>())
+    {
+        error!(
+            "Modified manifest {} is invalid. {error}",
+            M::path().display()
+        );
+        return;
+    }
+
+    let Some(raw_manifest) = raw_manifest_handle.merged(raw_manifests) else {
+        warn!("Raw manifest modified, but asset not available!");
+        return;
+    };
+
+    debug!("Manifest asset {} modified.", M::path().display());
+
+    let processed = match raw_manifest.process() {
+        Ok(processed) => processed,
+        Err(error) => {
+            error!(
+                "Modified manifest {} is invalid. {error}",
+                M::path().display()
+            );
+            return;
+        }
+    };
+
+    // Validation reads the manifest from the world, so the new one is swapped in and reverted if it is invalid
+    let previous = world.remove_resource::<Manifest<M::Marker, M::Data>>();
+    world.insert_resource(processed);
+
+    if let Err(error) = raw_manifest.validate(world) {
+        error!(
+            "Modified manifest {} is invalid. {error}",
+            M::path().display()
+        );
+        if let Some(previous) = previous {
+            world.insert_resource(previous);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        asset_management::{
+            manifest::{errors::OutOfRangeValue, Id},
+            AssetManagementPlugin,
+        },
+        crafting::recipe::{RawRecipeInput, RawRecipeManifest, RecipeInput, RecipeManifest},
+        items::{
+            item_manifest::{Item, ItemManifest, RawItemData, RawItemManifest},
+            ItemCount,
+        },
+        organisms::energy::Energy,
+    };
+    use bevy::{
+        ecs::schedule::ExecutorKind,
+        log::Level,
+        reflect::TypeUuid,
+        utils::{tracing, HashMap},
+    };
+    use serde::Deserialize;
+
+    /// The marker type of [`FallibleManifest`] entries.
+    #[derive(Debug)]
+    struct Fallible;
+
+    /// A raw manifest that fails to process if any of its entries has a value of 0.
+    #[derive(Debug, Clone, Deserialize, TypeUuid)]
+    #[uuid = "5d0b8a59-4b0e-4a49-9bb4-3a8e0b2c7d61"]
+    struct FallibleManifest {
+        /// The value of each entry.
+        entries: HashMap<String, u32>,
+    }
+
+    impl IsRawManifest for FallibleManifest {
+        const EXTENSION: &'static str = "fallible_manifest";
+
+        type Marker = Fallible;
+        type Data = u32;
+        type RawData = u32;
+
+        fn process(&self) -> Result<Manifest<Self::Marker, Self::Data>, ManifestError> {
+            let out_of_range: Vec<OutOfRangeValue> = self
+                .entries
+                .iter()
+                .filter(|(_, &value)| value == 0)
+                .map(|(name, value)| OutOfRangeValue {
+                    entry: name.clone(),
+                    field: "value".to_string(),
+                    value: value.to_string(),
+                    expected: "at least 1",
+                })
+                .collect();
+            if !out_of_range.is_empty() {
+                return Err(ManifestError::OutOfRange(out_of_range));
+            }
+
+            let mut manifest = Manifest::new();
+            for (name, value) in &self.entries {
+                manifest.insert(name.clone(), *value);
+            }
+
+            Ok(manifest)
+        }
+
+        fn version(&self) -> u32 {
+            Self::CURRENT_VERSION
         }
+
+        fn entries(&self) -> &HashMap<String, Self::RawData> {
+            &self.entries
+        }
+
+        fn entries_mut(&mut self) -> &mut HashMap<String, Self::RawData> {
+            &mut self.entries
+        }
+    }
+
+    /// A raw item with the given `stack_size`.
+    fn raw_item(stack_size: u32) -> RawItemData {
+        RawItemData {
+            stack_size,
+            compostable: false,
+            fluid: false,
+            buoyant: false,
+            seed: None,
+            energy_content: Energy(0.),
+            tags: Vec::new(),
+            mass: 1.,
+            decay_time: None,
+            decays_into: None,
+            tier: 0,
+            name: None,
+            description: None,
+            icon: None,
+        }
+    }
+
+    #[test]
+    fn modified_raw_manifest_updates_manifest() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_asset::<RawItemManifest>()
+            .init_resource::<ManifestLayers>()
+            .add_system(detect_manifest_modification::<RawItemManifest>);
+
+        let raw_manifest = RawItemManifest {
+            version: RawItemManifest::CURRENT_VERSION,
+            items: HashMap::from_iter([
+                ("leaf".to_string(), raw_item(10)),
+                ("pollen".to_string(), raw_item(20)),
+            ]),
+        };
+        app.insert_resource(raw_manifest.process().unwrap());
+        let handle = app
+            .world
+            .resource_mut::<Assets<RawItemManifest>>()
+            .add(raw_manifest);
+        app.insert_resource(RawManifestHandle {
+            handles: vec![handle.clone()],
+        });
+        app.update();
+
+        let leaf_id = Id::<Item>::from_name("leaf".to_string());
+        let pollen_id = Id::<Item>::from_name("pollen".to_string());
+
+        // Edit the raw asset, as if the file had changed on disk
+        {
+            let mut raw_manifests = app.world.resource_mut::<Assets<RawItemManifest>>();
+            let raw_manifest = raw_manifests.get_mut(&handle).unwrap();
+            raw_manifest.items.remove("pollen");
+            raw_manifest.items.insert("leaf".to_string(), raw_item(5));
+        }
+
+        // Asset events are sent at the end of the frame, and read on the next one
+        app.update();
+        app.update();
+
+        let item_manifest = app.world.resource::<ItemManifest>();
+        assert_eq!(item_manifest.get_unchecked(leaf_id).stack_size, 5);
+        assert!(!item_manifest.data_map().contains_key(&pollen_id));
+    }
+
+    #[test]
+    fn modified_manifests_that_fail_validation_are_rejected() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_asset::<RawRecipeManifest>()
+            .init_resource::<ManifestLayers>()
+            .add_system(detect_manifest_modification::<RawRecipeManifest>);
+
+        let raw_item_manifest = RawItemManifest {
+            version: RawItemManifest::CURRENT_VERSION,
+            items: HashMap::from_iter([
+                ("leaf".to_string(), raw_item(10)),
+                ("compost".to_string(), raw_item(10)),
+            ]),
+        };
+        app.insert_resource(raw_item_manifest.process().unwrap());
+
+        let raw_manifest: RawRecipeManifest = serde_json::from_str(
+            r#"{
+                "version": 1,
+                "recipes": {
+                    "make_compost": {
+                        "inputs": { "Exact": { "leaf": 1 } },
+                        "outputs": { "compost": 1 },
+                        "craft_time": 1
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        app.insert_resource(raw_manifest.process().unwrap());
+        let handle = app
+            .world
+            .resource_mut::<Assets<RawRecipeManifest>>()
+            .add(raw_manifest);
+        app.insert_resource(RawManifestHandle {
+            handles: vec![handle.clone()],
+        });
+        app.update();
+
+        // Use an item that does not exist, which processing alone does not notice
+        app.world
+            .resource_mut::<Assets<RawRecipeManifest>>()
+            .get_mut(&handle)
+            .unwrap()
+            .recipes
+            .get_mut("make_compost")
+            .unwrap()
+            .inputs = RawRecipeInput::single("nectar", 1);
+        app.update();
+        app.update();
+
+        let recipe_manifest = app.world.resource::<RecipeManifest>();
+        let make_compost = recipe_manifest.get_unchecked(Id::from_name("make_compost".to_string()));
+        assert_eq!(
+            make_compost.inputs,
+            RecipeInput::Exact(vec![ItemCount::new(Id::from_name("leaf".to_string()), 1)])
+        );
+    }
+
+    /// Adds the raw manifest asset to the `app`, ready to be processed by [`detect_manifest_creation`].
+    fn add_raw_manifest<M>(app: &mut App, raw_manifest: M)
+    where
+        M: IsRawManifest,
+    {
+        app.add_asset::<M>().init_resource::<AssetsToLoad>();
+        let handle = app.world.resource_mut::<Assets<M>>().add(raw_manifest);
+        app.insert_resource(RawManifestHandle {
+            handles: vec![handle],
+        });
+        app.world
+            .resource_mut::<ManifestLoadState>()
+            .register::<M>();
+    }
+
+    /// Runs [`detect_manifest_creation`] for the raw manifest type `M` once.
+    fn process_manifest<M>(app: &mut App)
+    where
+        M: IsRawManifest,
+    {
+        let mut schedule = Schedule::new();
+        // Stay on the current thread, so that its log subscriber sees the events
+        schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+        schedule.add_system(detect_manifest_creation::<M>);
+        schedule.run(&mut app.world);
+    }
+
+    #[test]
+    fn load_progress_tracks_processed_manifests() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .init_resource::<ManifestLayers>()
+            .init_resource::<ManifestLoadState>();
+
+        add_raw_manifest(
+            &mut app,
+            RawItemManifest {
+                version: RawItemManifest::CURRENT_VERSION,
+                items: HashMap::from_iter([("leaf".to_string(), raw_item(10))]),
+            },
+        );
+        add_raw_manifest(
+            &mut app,
+            RawRecipeManifest {
+                version: RawRecipeManifest::CURRENT_VERSION,
+                recipes: HashMap::new(),
+            },
+        );
+
+        let load_state = app.world.resource::<ManifestLoadState>();
+        assert_eq!(load_state.progress(), 0.);
+        assert!(!load_state.is_complete());
+
+        process_manifest::<RawItemManifest>(&mut app);
+        let load_state = app.world.resource::<ManifestLoadState>();
+        assert_eq!(load_state.progress(), 0.5);
+        assert!(!load_state.is_complete());
+
+        process_manifest::<RawRecipeManifest>(&mut app);
+        let load_state = app.world.resource::<ManifestLoadState>();
+        assert_eq!(load_state.progress(), 1.);
+        assert!(load_state.is_complete());
+
+        assert!(app.world.contains_resource::<ItemManifest>());
+        assert!(app.world.contains_resource::<RecipeManifest>());
+    }
+
+    /// An app that loads manifests like the game does, without any manifest types registered yet.
+    fn loading_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_plugin(AssetManagementPlugin)
+            .init_resource::<ManifestLayers>()
+            // Stay on the current thread, so that panics reach the test unchanged
+            .edit_schedule(CoreSchedule::Main, |schedule| {
+                schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+            });
+        app
+    }
+
+    /// Processes the raw manifest type `M` while loading, like the [`ManifestPlugin`] does.
+    fn add_manifest_detection<M>(app: &mut App)
+    where
+        M: IsRawManifest,
+    {
+        app.add_system(
+            detect_manifest_creation::<M>
+                .in_set(DetectManifestCreationSet)
+                .run_if(in_state(AssetState::LoadManifests))
+                .run_if(resource_exists::<RawManifestHandle<M>>()),
+        );
+    }
+
+    #[test]
+    fn assets_load_once_all_manifests_are_processed() {
+        let mut app = loading_app();
+        add_raw_manifest(
+            &mut app,
+            RawItemManifest {
+                version: RawItemManifest::CURRENT_VERSION,
+                items: HashMap::from_iter([("leaf".to_string(), raw_item(10))]),
+            },
+        );
+        add_manifest_detection::<RawItemManifest>(&mut app);
+
+        // The manifest is processed on the first frame, and the state changes on the next one
+        app.update();
+        assert!(app.world.resource::<ManifestLoadState>().is_complete());
+        assert_eq!(
+            app.world.resource::<State<AssetState>>().0,
+            AssetState::LoadManifests
+        );
+        app.update();
+        assert_eq!(
+            app.world.resource::<State<AssetState>>().0,
+            AssetState::LoadAssets
+        );
+        assert!(app.world.contains_resource::<ItemManifest>());
+    }
+
+    #[test]
+    fn failed_manifests_never_reach_asset_loading() {
+        let mut app = loading_app();
+        add_raw_manifest(
+            &mut app,
+            RawItemManifest {
+                version: RawItemManifest::CURRENT_VERSION,
+                items: HashMap::from_iter([("leaf".to_string(), raw_item(10))]),
+            },
+        );
+        add_manifest_detection::<RawItemManifest>(&mut app);
+        add_raw_manifest(
+            &mut app,
+            FallibleManifest {
+                entries: HashMap::from_iter([("invalid".to_string(), 0)]),
+            },
+        );
+        add_manifest_detection::<FallibleManifest>(&mut app);
+
+        let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| app.update()))
+            .expect_err("Loading should stop when a manifest fails to process");
+        let message = panic.downcast_ref::<String>().unwrap();
+        assert!(message.contains(
+            "- fallible_manifest: Values out of range:\n- `invalid`: value is 0, but must be at least 1"
+        ));
+
+        assert_eq!(
+            app.world.resource::<State<AssetState>>().0,
+            AssetState::LoadManifests
+        );
+        assert!(!app.world.contains_resource::<Manifest<Fallible, u32>>());
+    }
+
+    #[test]
+    fn unavailable_raw_manifests_are_recorded_as_failed() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .init_resource::<ManifestLayers>()
+            .init_resource::<ManifestLoadState>();
+        add_raw_manifest(
+            &mut app,
+            RawItemManifest {
+                version: RawItemManifest::CURRENT_VERSION,
+                items: HashMap::new(),
+            },
+        );
+
+        // Drop the asset, so that the handle points at nothing
+        let handle = app
+            .world
+            .resource::<RawManifestHandle<RawItemManifest>>()
+            .handles[0]
+            .clone();
+        app.world
+            .resource_mut::<Assets<RawItemManifest>>()
+            .remove(&handle);
+        process_manifest::<RawItemManifest>(&mut app);
+
+        let load_state = app.world.resource::<ManifestLoadState>();
+        assert_eq!(
+            load_state.error::<RawItemManifest>(),
+            Some(&ManifestError::FileNotFound(RawItemManifest::path()))
+        );
+        assert!(!load_state.is_complete());
+    }
+
+    /// Records the messages of all log events, along with their level.
+    #[derive(Debug, Clone, Default)]
+    struct LogRecorder {
+        /// The recorded messages, shared with every clone of the recorder.
+        messages: std::sync::Arc<std::sync::Mutex<Vec<(Level, String)>>>,
+    }
+
+    impl LogRecorder {
+        /// The recorded messages with the given `level`.
+        fn messages(&self, level: Level) -> Vec<String> {
+            self.messages
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(message_level, _)| *message_level == level)
+                .map(|(_, message)| message.clone())
+                .collect()
+        }
+    }
+
+    /// Extracts the message of a log event.
+    struct MessageVisitor<'a>(&'a mut String);
+
+    impl tracing::field::Visit for MessageVisitor<'_> {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            if field.name() == "message" {
+                *self.0 = format!("{value:?}");
+            }
+        }
+    }
+
+    impl tracing::Subscriber for LogRecorder {
+        fn enabled(&self, _metadata: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            tracing::span::Id::from_u64(1)
+        }
+
+        fn record(&self, _span: &tracing::span::Id, _values: &tracing::span::Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &tracing::span::Id, _follows: &tracing::span::Id) {}
+
+        fn event(&self, event: &tracing::Event<'_>) {
+            let mut message = String::new();
+            event.record(&mut MessageVisitor(&mut message));
+            self.messages
+                .lock()
+                .unwrap()
+                .push((*event.metadata().level(), message));
+        }
+
+        fn enter(&self, _span: &tracing::span::Id) {}
+
+        fn exit(&self, _span: &tracing::span::Id) {}
+    }
+
+    #[test]
+    fn load_summary_reports_the_number_of_entries() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .init_resource::<ManifestLayers>()
+            .init_resource::<ManifestLoadState>();
+        add_raw_manifest(
+            &mut app,
+            RawItemManifest {
+                version: RawItemManifest::CURRENT_VERSION,
+                items: HashMap::from_iter([
+                    ("leaf".to_string(), raw_item(10)),
+                    ("pollen".to_string(), raw_item(20)),
+                    ("acorn".to_string(), raw_item(5)),
+                ]),
+            },
+        );
+
+        let log_recorder = LogRecorder::default();
+        tracing::subscriber::with_default(log_recorder.clone(), || {
+            process_manifest::<RawItemManifest>(&mut app);
+        });
+
+        let path = RawItemManifest::path().display().to_string();
+        assert_eq!(app.world.resource::<ItemManifest>().len(), 3);
+        assert!(log_recorder
+            .messages(Level::INFO)
+            .contains(&format!("Loaded 3 entries from {path}")));
+        assert!(log_recorder
+            .messages(Level::DEBUG)
+            .contains(&format!("Entries of {path}: acorn, leaf, pollen")));
+
+        let footprint = app.world.resource::<ItemManifest>().approx_memory_bytes();
+        assert!(log_recorder
+            .messages(Level::DEBUG)
+            .contains(&format!("{path} takes up about {footprint} bytes")));
+    }
+
+    #[test]
+    fn failed_processing_is_returned_to_the_caller() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .init_resource::<ManifestLayers>()
+            .init_resource::<ManifestLoadState>();
+
+        add_raw_manifest(
+            &mut app,
+            FallibleManifest {
+                entries: HashMap::from_iter([("invalid".to_string(), 0)]),
+            },
+        );
+        process_manifest::<FallibleManifest>(&mut app);
+
+        let load_state = app.world.resource::<ManifestLoadState>();
+        assert_eq!(
+            load_state.error::<FallibleManifest>(),
+            Some(&ManifestError::OutOfRange(vec![OutOfRangeValue {
+                entry: "invalid".to_string(),
+                field: "value".to_string(),
+                value: "0".to_string(),
+                expected: "at least 1",
+            }]))
+        );
+        assert!(load_state.has_failed());
+        assert!(!load_state.is_complete());
+        assert!(!app.world.contains_resource::<Manifest<Fallible, u32>>());
+    }
+
+    #[test]
+    fn failed_reprocessing_keeps_the_previous_manifest() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_asset::<FallibleManifest>()
+            .init_resource::<ManifestLayers>()
+            .add_system(detect_manifest_modification::<FallibleManifest>);
+
+        let raw_manifest = FallibleManifest {
+            entries: HashMap::from_iter([("valid".to_string(), 1)]),
+        };
+        app.insert_resource(raw_manifest.process().unwrap());
+        let handle = app
+            .world
+            .resource_mut::<Assets<FallibleManifest>>()
+            .add(raw_manifest);
+        app.insert_resource(RawManifestHandle {
+            handles: vec![handle.clone()],
+        });
+        app.update();
+
+        // Break the raw asset, as if an invalid file had been saved
+        app.world
+            .resource_mut::<Assets<FallibleManifest>>()
+            .get_mut(&handle)
+            .unwrap()
+            .entries
+            .insert("invalid".to_string(), 0);
+        app.update();
+        app.update();
+
+        let manifest = app.world.resource::<Manifest<Fallible, u32>>();
+        assert_eq!(manifest.names().collect::<Vec<_>>(), vec!["valid"]);
     }
 }
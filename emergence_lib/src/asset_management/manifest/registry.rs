@@ -0,0 +1,153 @@
+//! A central list of manifest types, so that all of them can be loaded and validated in one go.
+//!
+//! Each manifest type is registered once, and everything needed to load it is taken from its [`IsRawManifest`] implementation.
+
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::World;
+
+use crate::{
+    crafting::recipe::RawRecipeManifest,
+    environment::RawEnvironmentManifest,
+    items::{item_manifest::RawItemManifest, starter_manifest::RawStarterManifest},
+    structures::structure_manifest::RawStructureManifest,
+    terrain::terrain_manifest::RawTerrainManifest,
+    units::unit_manifest::RawUnitManifest,
+};
+
+use super::{
+    errors::ManifestError,
+    loader::IsRawManifest,
+    plugin::ManifestLayers,
+    validation::{read_raw_manifest, ManifestProblem},
+};
+
+/// Checks a raw manifest against all processed manifests.
+type Validation = Box<dyn FnOnce(&World) -> Result<(), ManifestError>>;
+
+/// A manifest type that was added to a [`ManifestRegistry`].
+struct RegisteredManifest {
+    /// The path to the base game manifest of this type, without the [`ManifestFormat`](super::loader::ManifestFormat) suffix.
+    path: PathBuf,
+    /// Reads, merges and processes all layers of the manifest, and stores the processed manifest in the world.
+    ///
+    /// Returns the validation of the raw manifest, to run once every manifest has been processed.
+    load: fn(&Path, &ManifestLayers, &mut World) -> Result<Validation, ManifestError>,
+}
+
+/// The manifest types that are loaded and validated together by [`ManifestRegistry::load_all`].
+#[derive(Default)]
+pub struct ManifestRegistry {
+    /// The registered manifest types, in the order in which they are loaded.
+    manifests: Vec<RegisteredManifest>,
+}
+
+impl ManifestRegistry {
+    /// Creates a registry without any manifest types.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry with every manifest type of the game.
+    pub fn base_game() -> Self {
+        ManifestRegistry::new()
+            .register::<RawEnvironmentManifest>()
+            .register::<RawItemManifest>()
+            .register::<RawRecipeManifest>()
+            .register::<RawStarterManifest>()
+            .register::<RawStructureManifest>()
+            .register::<RawTerrainManifest>()
+            .register::<RawUnitManifest>()
+    }
+
+    /// Adds the manifest type `M`, to be loaded after all types registered so far.
+    ///
+    /// Registering the same type again has no effect.
+    /// Manifests that are validated against other manifest types, such as recipes against items, need those types to be registered as well.
+    pub fn register<M>(mut self) -> Self
+    where
+        M: IsRawManifest,
+    {
+        if !self.is_registered::<M>() {
+            self.manifests.push(RegisteredManifest {
+                path: M::path(),
+                load: load_manifest::<M>,
+            });
+        }
+        self
+    }
+
+    /// Has the manifest type `M` been registered?
+    pub fn is_registered<M>(&self) -> bool
+    where
+        M: IsRawManifest,
+    {
+        let path = M::path();
+        self.manifests.iter().any(|manifest| manifest.path == path)
+    }
+
+    /// The paths to the base game manifests of all registered types, in the order in which they are loaded.
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.manifests
+            .iter()
+            .map(|manifest| manifest.path.as_path())
+    }
+
+    /// Loads, processes and validates every registered manifest type in the `asset_folder`, using the provided `manifest_layers`.
+    ///
+    /// Returns a world that holds every processed manifest as a resource,
+    /// or every problem found, in the order in which the manifests are registered.
+    /// Cross-references between manifests are only checked once all of them have been read successfully,
+    /// as the checks would otherwise report spurious errors.
+    pub fn load_all(
+        &self,
+        asset_folder: &Path,
+        manifest_layers: &ManifestLayers,
+    ) -> Result<World, Vec<ManifestProblem>> {
+        let mut world = World::new();
+        let mut validations = Vec::new();
+        let mut problems = Vec::new();
+
+        for manifest in &self.manifests {
+            match (manifest.load)(asset_folder, manifest_layers, &mut world) {
+                Ok(validation) => validations.push((&manifest.path, validation)),
+                Err(error) => problems.push(ManifestProblem {
+                    manifest: manifest.path.clone(),
+                    error,
+                }),
+            }
+        }
+
+        if problems.is_empty() {
+            for (path, validation) in validations {
+                if let Err(error) = validation(&world) {
+                    problems.push(ManifestProblem {
+                        manifest: path.clone(),
+                        error,
+                    });
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(world)
+        } else {
+            Err(problems)
+        }
+    }
+}
+
+/// Reads, merges and processes all layers of the manifest type `M`, and stores the processed manifest in the `world`.
+fn load_manifest<M>(
+    asset_folder: &Path,
+    manifest_layers: &ManifestLayers,
+    world: &mut World,
+) -> Result<Validation, ManifestError>
+where
+    M: IsRawManifest,
+{
+    let raw_manifest = read_raw_manifest::<M>(asset_folder, manifest_layers)?;
+    world.insert_resource(raw_manifest.process()?);
+
+    Ok(Box::new(move |world| raw_manifest.validate(world)))
+}
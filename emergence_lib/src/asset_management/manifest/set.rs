@@ -0,0 +1,175 @@
+//! Access to several manifests at once, for systems that need to follow references between them.
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    crafting::recipe::{Recipe, RecipeData, RecipeInput, RecipeManifest},
+    items::item_manifest::{Item, ItemData, ItemManifest},
+};
+
+use super::{errors::ManifestError, Id};
+
+/// The loaded manifests, bundled together so that cross-references between them can be resolved in one place.
+///
+/// This borrows the manifest resources rather than copying them,
+/// so it always reflects the latest hot-reloaded data.
+#[derive(SystemParam)]
+pub struct ManifestSet<'w> {
+    /// The items that exist in the game.
+    items: Res<'w, ItemManifest>,
+    /// The recipes that can be crafted.
+    recipes: Res<'w, RecipeManifest>,
+}
+
+impl<'w> ManifestSet<'w> {
+    /// The manifest of all items.
+    pub fn items(&self) -> &ItemManifest {
+        &self.items
+    }
+
+    /// The manifest of all recipes.
+    pub fn recipes(&self) -> &RecipeManifest {
+        &self.recipes
+    }
+
+    /// The data of the recipe with the given `recipe_id`.
+    pub fn recipe(&self, recipe_id: Id<Recipe>) -> Result<&RecipeData, ManifestError> {
+        self.recipes.get(recipe_id)
+    }
+
+    /// The data of every item that can be used as an input of the recipe with the given `recipe_id`.
+    ///
+    /// Exact inputs and alternatives are listed in the order they are stored in,
+    /// while flexible and category inputs list every matching item, sorted by [`Id`].
+    /// Fails if the recipe or any of its input items is not in the manifests.
+    pub fn input_items(
+        &self,
+        recipe_id: Id<Recipe>,
+    ) -> Result<Vec<(Id<Item>, &ItemData)>, ManifestError> {
+        let item_ids: Vec<Id<Item>> = match &self.recipe(recipe_id)?.inputs {
+            RecipeInput::Exact(inputs) | RecipeInput::AnyOf(inputs) => {
+                inputs.iter().map(|input| input.item_id).collect()
+            }
+            RecipeInput::Flexible { tag, .. } => {
+                let mut item_ids: Vec<Id<Item>> = self
+                    .items
+                    .variants()
+                    .into_iter()
+                    .filter(|&item_id| self.items.has_tag(item_id, *tag))
+                    .collect();
+                item_ids.sort();
+                item_ids
+            }
+            RecipeInput::Category { category, .. } => self.items.items_with_tag(*category),
+        };
+
+        self.resolve_items(item_ids)
+    }
+
+    /// The data of every item that the recipe with the given `recipe_id` can produce.
+    ///
+    /// The items are listed in the same order as [`RecipeData::output_item_ids`].
+    /// Fails if the recipe or any of its output items is not in the manifests.
+    pub fn output_items(
+        &self,
+        recipe_id: Id<Recipe>,
+    ) -> Result<Vec<(Id<Item>, &ItemData)>, ManifestError> {
+        self.resolve_items(self.recipe(recipe_id)?.output_item_ids())
+    }
+
+    /// Looks up the data of each of the `item_ids`.
+    fn resolve_items(
+        &self,
+        item_ids: Vec<Id<Item>>,
+    ) -> Result<Vec<(Id<Item>, &ItemData)>, ManifestError> {
+        item_ids
+            .into_iter()
+            .map(|item_id| Ok((item_id, self.items.get(item_id)?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+
+    use super::*;
+    use crate::{
+        asset_management::manifest::loader::IsRawManifest, crafting::recipe::RawRecipeManifest,
+        items::item_manifest::RawItemManifest,
+    };
+
+    /// A world holding manifests that turn pollen into honey.
+    fn world_with_manifests() -> World {
+        let raw_items: RawItemManifest = serde_json::from_str(
+            r#"{
+                "version": 1,
+                "items": {
+                    "pollen": { "stack_size": 10, "compostable": true, "fluid": false, "buoyant": true, "mass": 0.5 },
+                    "honey": { "stack_size": 5, "compostable": true, "fluid": false, "buoyant": false }
+                }
+            }"#,
+        )
+        .unwrap();
+        let raw_recipes: RawRecipeManifest = serde_json::from_str(
+            r#"{
+                "version": 1,
+                "recipes": {
+                    "make_honey": {
+                        "inputs": { "Exact": { "pollen": 2 } },
+                        "outputs": { "honey": 1 },
+                        "craft_time": 1
+                    },
+                    "make_mead": {
+                        "inputs": { "Exact": { "water": 1 } },
+                        "outputs": { "honey": 1 },
+                        "craft_time": 1
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let mut world = World::new();
+        world.insert_resource(raw_items.process().unwrap());
+        world.insert_resource(raw_recipes.process().unwrap());
+        world
+    }
+
+    #[test]
+    fn recipe_inputs_resolve_to_item_data() {
+        let mut world = world_with_manifests();
+        let mut system_state = SystemState::<ManifestSet>::new(&mut world);
+        let manifest_set = system_state.get(&world);
+
+        let make_honey = Id::from_name("make_honey".to_string());
+        let pollen = Id::from_name("pollen".to_string());
+
+        let input_items = manifest_set.input_items(make_honey).unwrap();
+        let (item_id, item_data) = input_items[0];
+        assert_eq!(item_id, pollen);
+        assert_eq!(item_data, manifest_set.items().get_unchecked(pollen));
+        assert_eq!(item_data.stack_size, 10);
+        assert_eq!(item_data.mass, 0.5);
+
+        let output_items = manifest_set.output_items(make_honey).unwrap();
+        assert_eq!(output_items.len(), 1);
+        assert_eq!(output_items[0].1.stack_size, 5);
+    }
+
+    #[test]
+    fn unknown_references_fail_to_resolve() {
+        let mut world = world_with_manifests();
+        let mut system_state = SystemState::<ManifestSet>::new(&mut world);
+        let manifest_set = system_state.get(&world);
+
+        assert!(matches!(
+            manifest_set.input_items(Id::from_name("make_mead".to_string())),
+            Err(ManifestError::UnknownId { .. })
+        ));
+        assert!(matches!(
+            manifest_set.input_items(Id::from_name("make_wax".to_string())),
+            Err(ManifestError::UnknownId { .. })
+        ));
+    }
+}
@@ -0,0 +1,142 @@
+//! Headless validation of manifest files, without starting the game.
+//!
+//! This reads the manifests straight from disk, rather than through the asset server,
+//! so that content authors can check their changes quickly.
+
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    errors::ManifestError,
+    loader::{merge_layers, names_defined_by_multiple_layers, IsRawManifest, ManifestFormat},
+    plugin::ManifestLayers,
+    registry::ManifestRegistry,
+};
+
+/// A problem found in one of the manifests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestProblem {
+    /// The path to the base game manifest of the affected type, without the [`ManifestFormat`] suffix.
+    pub manifest: PathBuf,
+    /// What is wrong with the manifest.
+    pub error: ManifestError,
+}
+
+impl Display for ManifestProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.manifest.display(), self.error)
+    }
+}
+
+// The error is already part of the message, so it is not reported as the source as well
+impl std::error::Error for ManifestProblem {}
+
+/// Loads, processes and validates every manifest type in the `asset_folder`, using the provided `manifest_layers`.
+///
+/// The manifest types are those of [`ManifestRegistry::base_game`].
+/// Returns every problem found, in the order in which the manifests are loaded.
+/// Cross-references between manifests are only checked once all of them have been read successfully,
+/// as the checks would otherwise report spurious errors.
+pub fn validate_manifests(
+    asset_folder: &Path,
+    manifest_layers: &ManifestLayers,
+) -> Vec<ManifestProblem> {
+    match ManifestRegistry::base_game().load_all(asset_folder, manifest_layers) {
+        Ok(_) => Vec::new(),
+        Err(problems) => problems,
+    }
+}
+
+/// The contents of the file of a single manifest layer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerFile {
+    /// The path to the file.
+    pub path: PathBuf,
+    /// The format that the file is written in.
+    pub format: ManifestFormat,
+    /// The raw contents of the file.
+    pub bytes: Vec<u8>,
+}
+
+/// Reads the files of all layers of the manifest type `M` from the `asset_folder`, in order.
+///
+/// Like the asset loader, this uses the first format that has a file on disk for each layer,
+/// and skips layers without any file.
+/// Layers are also discovered in the [`ManifestLayers::directories`].
+pub fn read_layer_files<M>(
+    asset_folder: &Path,
+    manifest_layers: &ManifestLayers,
+) -> Result<Vec<LayerFile>, ManifestError>
+where
+    M: IsRawManifest,
+{
+    let layers = manifest_layers.layers_for::<M>(|directory| {
+        let entries = std::fs::read_dir(asset_folder.join(directory)).ok()?;
+        let paths = entries
+            .filter_map(|entry| Some(directory.join(entry.ok()?.file_name())))
+            .collect();
+        Some(paths)
+    });
+    let mut layer_files = Vec::new();
+
+    for layer in &layers {
+        let Some((format, path)) = ManifestFormat::ALL.into_iter().find_map(|format| {
+            let path = asset_folder.join(M::layer_path_with_format(layer, format));
+            path.is_file().then_some((format, path))
+        }) else {
+            continue;
+        };
+
+        let bytes = std::fs::read(&path).map_err(|_| ManifestError::FileNotFound(path.clone()))?;
+        layer_files.push(LayerFile {
+            path,
+            format,
+            bytes,
+        });
+    }
+
+    Ok(layer_files)
+}
+
+/// Parses and merges the `layer_files` of the manifest type `M`, in the same way as the asset loader.
+///
+/// Fails with [`ManifestError::FileNotFound`] if there are no layer files at all.
+pub fn parse_layer_files<M>(
+    layer_files: &[LayerFile],
+    manifest_layers: &ManifestLayers,
+) -> Result<M, ManifestError>
+where
+    M: IsRawManifest,
+{
+    let layers = layer_files
+        .iter()
+        .map(|layer_file| {
+            layer_file
+                .format
+                .deserialize::<M>(&layer_file.path, &layer_file.bytes)
+        })
+        .collect::<Result<Vec<M>, ManifestError>>()?;
+
+    if manifest_layers.strict {
+        let duplicates = names_defined_by_multiple_layers(&layers);
+        if !duplicates.is_empty() {
+            return Err(ManifestError::DuplicateId(duplicates));
+        }
+    }
+
+    merge_layers(&layers).ok_or_else(|| ManifestError::FileNotFound(M::path()))
+}
+
+/// Reads, parses and merges all layers of the manifest type `M` from the `asset_folder`.
+pub fn read_raw_manifest<M>(
+    asset_folder: &Path,
+    manifest_layers: &ManifestLayers,
+) -> Result<M, ManifestError>
+where
+    M: IsRawManifest,
+{
+    let layer_files = read_layer_files::<M>(asset_folder, manifest_layers)?;
+    parse_layer_files(&layer_files, manifest_layers)
+}
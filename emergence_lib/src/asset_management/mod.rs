@@ -15,7 +15,7 @@ use std::{
     fmt::{Display, Formatter},
 };
 
-use self::manifest::plugin::DetectManifestCreationSet;
+use self::manifest::plugin::{DetectManifestCreationSet, ManifestLoadState};
 use bevy::{
     asset::LoadState,
     prelude::*,
@@ -31,15 +31,13 @@ impl Plugin for AssetManagementPlugin {
     fn build(&self, app: &mut App) {
         app.add_state::<AssetState>()
             .init_resource::<AssetsToLoad>()
-            .add_system(check_manifests_loaded.run_if(in_state(AssetState::LoadManifests)))
-            .add_system(check_assets_loaded.run_if(in_state(AssetState::LoadAssets)))
-            // This is needed to ensure that the manifest resources are actually created in time for AssetState::Loading
-            // BLOCKED: this can be removed in Bevy 0.11, as schedules will automatically flush the commands.
+            .init_resource::<ManifestLoadState>()
             .add_system(
-                apply_system_buffers
+                check_manifests_loaded
                     .after(DetectManifestCreationSet)
-                    .in_schedule(OnExit(AssetState::LoadManifests)),
-            );
+                    .run_if(in_state(AssetState::LoadManifests)),
+            )
+            .add_system(check_assets_loaded.run_if(in_state(AssetState::LoadAssets)));
     }
 }
 
@@ -94,12 +92,29 @@ impl AssetsToLoad {
     }
 }
 
-/// A system that checks if all manifests are loaded.
+/// A system that checks if all manifests are loaded and processed.
+///
+/// The processed manifests are only inserted at the end of the frame, so they are available once the state has changed.
+///
+/// # Panics
+///
+/// The game cannot run without its manifests, so this panics with the errors of every manifest that could not be processed.
 fn check_manifests_loaded(
     assets_to_load: Res<AssetsToLoad>,
+    manifest_load_state: Res<ManifestLoadState>,
     mut next_state: ResMut<NextState<AssetState>>,
 ) {
-    if assets_to_load.remaining.is_empty() {
+    if manifest_load_state.has_failed() {
+        let errors: Vec<String> = manifest_load_state
+            .errors()
+            .into_iter()
+            .map(|(extension, error)| format!("- {extension}: {error}"))
+            .collect();
+
+        panic!("Manifests could not be processed:\n{}", errors.join("\n"));
+    }
+
+    if assets_to_load.remaining.is_empty() && manifest_load_state.is_complete() {
         info!("All manifests loaded: transitioning to AssetState::LoadAssets");
 
         next_state.set(AssetState::LoadAssets);
@@ -438,15 +438,16 @@ pub(super) fn ghost_structure_lifecycle(
                 }
             }
             CraftingState::RecipeComplete => {
-                let structure_data = structure_manifest.get(structure_id);
+                let structure_data = structure_manifest.get_unchecked(structure_id);
 
                 for &voxel_pos in structure_data.footprint.normalized(facing, center).iter() {
                     commands.despawn_ghost_structure(voxel_pos);
                 }
 
                 // Spawn the seedling form of a structure if any
-                if let ConstructionStrategy::Seedling(seedling) =
-                    structure_manifest.get(structure_id).construction_strategy
+                if let ConstructionStrategy::Seedling(seedling) = structure_manifest
+                    .get_unchecked(structure_id)
+                    .construction_strategy
                 {
                     commands.spawn_structure(
                         center,
@@ -487,7 +488,7 @@ pub(super) fn validate_ghost_structures(
     }
 
     for (&voxel_pos, &structure_id, &facing) in ghost_query.iter() {
-        let structure_details = structure_manifest.get(structure_id);
+        let structure_details = structure_manifest.get_unchecked(structure_id);
 
         if map_geometry
             .is_space_available(voxel_pos, &structure_details.footprint, facing)
@@ -69,12 +69,10 @@ impl TerraformingAction {
 
         match self {
             Self::Raise => OutputInventory::NULL,
-            Self::Lower => OutputInventory {
-                inventory: Inventory::full_from_item(soil_id, Self::N_ITEMS),
-            },
-            Self::Change(_terrain) => OutputInventory {
-                inventory: Inventory::full_from_item(soil_id, Self::N_ITEMS),
-            },
+            Self::Lower => OutputInventory::new(Inventory::full_from_item(soil_id, Self::N_ITEMS)),
+            Self::Change(_terrain) => {
+                OutputInventory::new(Inventory::full_from_item(soil_id, Self::N_ITEMS))
+            }
         }
     }
 
@@ -0,0 +1,370 @@
+//! Tracks which recipes have been discovered, and which recipes are available as a result.
+
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::{
+    asset_management::manifest::Id,
+    items::{errors::RemoveManyItemsError, inventory::Inventory, ItemCount},
+};
+
+use super::{
+    inventories::StorageInventory,
+    recipe::{Recipe, RecipeManifest},
+};
+
+/// The recipes that have been discovered so far.
+///
+/// A recipe is available once all of its [prerequisites](super::recipe::RecipeData::requires) have been discovered.
+/// Only available recipes should be offered to the player.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiscoveredRecipes {
+    /// The discovered recipes.
+    discovered: HashSet<Id<Recipe>>,
+}
+
+impl DiscoveredRecipes {
+    /// Marks the recipe as discovered.
+    ///
+    /// Returns `true` if it had not been discovered before.
+    pub fn discover(&mut self, recipe_id: Id<Recipe>) -> bool {
+        self.discovered.insert(recipe_id)
+    }
+
+    /// Has the recipe been discovered?
+    pub fn is_discovered(&self, recipe_id: Id<Recipe>) -> bool {
+        self.discovered.contains(&recipe_id)
+    }
+
+    /// Have all prerequisites of the recipe been discovered?
+    ///
+    /// Recipes that are not in the `recipe_manifest` are never available.
+    pub fn is_available(&self, recipe_id: Id<Recipe>, recipe_manifest: &RecipeManifest) -> bool {
+        recipe_manifest.get(recipe_id).is_ok_and(|recipe_data| {
+            recipe_data
+                .requires
+                .iter()
+                .all(|required| self.is_discovered(*required))
+        })
+    }
+
+    /// Discovers the recipe by spending its [research cost](super::recipe::RecipeData::research_cost) from the `inventory`.
+    ///
+    /// The items are only spent if all of them are available, and if the recipe has not been discovered yet.
+    /// Otherwise, the `inventory` is left untouched and the recipe stays as it was.
+    pub fn research(
+        &mut self,
+        recipe_id: Id<Recipe>,
+        recipe_manifest: &RecipeManifest,
+        inventory: &mut Inventory,
+    ) -> Result<(), ResearchError> {
+        let recipe_data = recipe_manifest
+            .get(recipe_id)
+            .map_err(|_| ResearchError::UnknownRecipe)?;
+
+        if self.is_discovered(recipe_id) {
+            return Err(ResearchError::AlreadyDiscovered);
+        }
+
+        inventory
+            .remove_items_all_or_nothing(&recipe_data.research_cost)
+            .map_err(|RemoveManyItemsError { missing_counts }| {
+                ResearchError::MissingItems(missing_counts)
+            })?;
+
+        self.discover(recipe_id);
+        Ok(())
+    }
+
+    /// The recipes in the `recipe_manifest` whose prerequisites have all been discovered, in an arbitrary order.
+    pub fn available_recipes<'a>(
+        &'a self,
+        recipe_manifest: &'a RecipeManifest,
+    ) -> impl Iterator<Item = Id<Recipe>> + 'a {
+        recipe_manifest
+            .variants()
+            .into_iter()
+            .filter(|recipe_id| self.is_available(*recipe_id, recipe_manifest))
+    }
+}
+
+/// Failed to [research](DiscoveredRecipes::research) a recipe.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResearchError {
+    /// The recipe is not in the [`RecipeManifest`].
+    UnknownRecipe,
+    /// The recipe has already been discovered, so its research cost is not spent again.
+    AlreadyDiscovered,
+    /// The inventory lacks some of the research items, by the given amounts.
+    MissingItems(Vec<ItemCount>),
+}
+
+/// An event sent when the player spends the items stored in the `storage` entity to research the recipe.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResearchRecipe {
+    /// The recipe to research.
+    pub recipe_id: Id<Recipe>,
+    /// The entity whose storage inventory the research items are taken from.
+    pub storage: Entity,
+}
+
+/// Researches the recipes requested by [`ResearchRecipe`] events, consuming the research items.
+pub(super) fn research_recipes(
+    mut research_events: EventReader<ResearchRecipe>,
+    mut storage_query: Query<&mut StorageInventory>,
+    recipe_manifest: Res<RecipeManifest>,
+    mut discovered_recipes: ResMut<DiscoveredRecipes>,
+) {
+    for event in research_events.iter() {
+        let Ok(mut storage_inventory) = storage_query.get_mut(event.storage) else {
+            warn!("Cannot research a recipe from {:?}, as it has no storage.", event.storage);
+            continue;
+        };
+
+        if let Err(error) = discovered_recipes.research(
+            event.recipe_id,
+            &recipe_manifest,
+            &mut storage_inventory.inventory,
+        ) {
+            debug!(
+                "Could not research {}: {error:?}",
+                recipe_manifest.named(event.recipe_id)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        asset_management::manifest::{loader::IsRawManifest, Manifest},
+        crafting::recipe::{
+            ConsumptionMode, OverflowPolicy, QualityRule, RawOutputCount, RawRecipeData,
+            RawRecipeInput, RawRecipeManifest, TimePhase,
+        },
+        items::item_manifest::{ItemData, ItemManifest},
+        organisms::energy::Energy,
+    };
+    use bevy::utils::HashMap;
+
+    /// A recipe that can only be crafted once the `required` recipes have been discovered.
+    fn raw_recipe(required: &[&str]) -> RawRecipeData {
+        RawRecipeData {
+            inputs: RawRecipeInput::single("leaf", 1),
+            catalysts: HashMap::new(),
+            byproducts: HashMap::new(),
+            output_one_of: Vec::new(),
+            outputs: HashMap::from_iter([("compost".to_string(), RawOutputCount::Count(1.))]),
+            craft_time: 1.,
+            work_units: None,
+            conditions: None,
+            energy: None,
+            allow_mass_gain: false,
+            allow_self_production: false,
+            requires: required.iter().map(|name| name.to_string()).collect(),
+            research_cost: HashMap::new(),
+            fluid_inputs: HashMap::new(),
+            fluid_outputs: HashMap::new(),
+            consumption: ConsumptionMode::AtStart,
+            overflow: OverflowPolicy::Block,
+            output_slots: HashMap::new(),
+            max_batch: 1,
+            next_recipe: None,
+            output_tier: None,
+            output_quality: QualityRule::default(),
+            category: RawRecipeData::DEFAULT_CATEGORY.to_string(),
+            active_phase: TimePhase::Always,
+            requires_environment: None,
+            enabled: true,
+        }
+    }
+
+    /// Shorthand for the ID of the recipe `name`.
+    fn id(name: &str) -> Id<Recipe> {
+        Id::from_name(name.to_string())
+    }
+
+    #[test]
+    fn recipes_unlock_along_a_chain() {
+        let recipe_manifest = RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([
+                ("gather".to_string(), raw_recipe(&[])),
+                ("dry".to_string(), raw_recipe(&["gather"])),
+                ("press".to_string(), raw_recipe(&["dry"])),
+            ]),
+        }
+        .process()
+        .unwrap();
+        let mut discovered_recipes = DiscoveredRecipes::default();
+
+        let available = |discovered_recipes: &DiscoveredRecipes| {
+            let mut available: Vec<_> = discovered_recipes
+                .available_recipes(&recipe_manifest)
+                .collect();
+            available.sort();
+            available
+        };
+
+        assert_eq!(available(&discovered_recipes), vec![id("gather")]);
+
+        assert!(discovered_recipes.discover(id("gather")));
+        let mut expected = vec![id("gather"), id("dry")];
+        expected.sort();
+        assert_eq!(available(&discovered_recipes), expected);
+        assert!(!discovered_recipes.is_available(id("press"), &recipe_manifest));
+
+        discovered_recipes.discover(id("dry"));
+        assert!(discovered_recipes.is_available(id("press"), &recipe_manifest));
+        assert_eq!(available(&discovered_recipes).len(), 3);
+    }
+
+    /// A recipe manifest with a `blueprint` recipe that costs two pollen and a feather to research.
+    fn blueprint_manifest() -> RecipeManifest {
+        RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([(
+                "blueprint".to_string(),
+                RawRecipeData {
+                    research_cost: HashMap::from_iter([
+                        ("pollen".to_string(), 2),
+                        ("feather".to_string(), 1),
+                    ]),
+                    ..raw_recipe(&[])
+                },
+            )]),
+        }
+        .process()
+        .unwrap()
+    }
+
+    /// An item manifest with pollen and feathers.
+    fn item_manifest() -> ItemManifest {
+        let mut manifest = Manifest::new();
+        for name in ["pollen", "feather"] {
+            manifest.insert(
+                name.to_string(),
+                ItemData {
+                    stack_size: 10,
+                    compostable: false,
+                    fluid: false,
+                    buoyant: false,
+                    seed: None,
+                    energy_content: Energy(0.),
+                    tags: HashSet::new(),
+                    mass: 1.,
+                    decay_time: None,
+                    decays_into: None,
+                    tier: 0,
+                    name: None,
+                    description: None,
+                    icon: None,
+                },
+            );
+        }
+        manifest
+    }
+
+    /// An inventory with the given number of pollen and feathers.
+    fn inventory_with(pollen: u32, feathers: u32) -> Inventory {
+        let item_manifest = item_manifest();
+        let mut inventory = Inventory::new(2, None);
+        for (name, count) in [("pollen", pollen), ("feather", feathers)] {
+            inventory
+                .try_add_item(
+                    &ItemCount::new(Id::from_name(name.to_string()), count),
+                    &item_manifest,
+                )
+                .unwrap();
+        }
+        inventory
+    }
+
+    #[test]
+    fn research_consumes_the_items_once() {
+        let recipe_manifest = blueprint_manifest();
+        let mut discovered_recipes = DiscoveredRecipes::default();
+        let mut inventory = inventory_with(5, 1);
+
+        assert_eq!(
+            discovered_recipes.research(id("blueprint"), &recipe_manifest, &mut inventory),
+            Ok(())
+        );
+        assert!(discovered_recipes.is_discovered(id("blueprint")));
+        assert_eq!(inventory.item_count(Id::from_name("pollen".to_string())), 3);
+        assert_eq!(
+            inventory.item_count(Id::from_name("feather".to_string())),
+            0
+        );
+
+        // The unlock persists, without spending anything again
+        assert_eq!(
+            discovered_recipes.research(id("blueprint"), &recipe_manifest, &mut inventory),
+            Err(ResearchError::AlreadyDiscovered)
+        );
+        assert!(discovered_recipes.is_discovered(id("blueprint")));
+        assert_eq!(inventory.item_count(Id::from_name("pollen".to_string())), 3);
+    }
+
+    #[test]
+    fn failed_research_leaves_the_inventory_untouched() {
+        let recipe_manifest = blueprint_manifest();
+        let mut discovered_recipes = DiscoveredRecipes::default();
+        let mut inventory = inventory_with(5, 0);
+        let original_inventory = inventory.clone();
+
+        assert_eq!(
+            discovered_recipes.research(id("blueprint"), &recipe_manifest, &mut inventory),
+            Err(ResearchError::MissingItems(vec![ItemCount::new(
+                Id::from_name("feather".to_string()),
+                1
+            )]))
+        );
+        assert!(!discovered_recipes.is_discovered(id("blueprint")));
+        assert_eq!(inventory, original_inventory);
+    }
+
+    #[test]
+    fn research_events_spend_items_from_storage() {
+        let mut app = App::new();
+        app.add_event::<ResearchRecipe>()
+            .insert_resource(blueprint_manifest())
+            .init_resource::<DiscoveredRecipes>()
+            .add_system(research_recipes);
+
+        let storage = app
+            .world
+            .spawn(StorageInventory {
+                inventory: inventory_with(2, 3),
+                ..default()
+            })
+            .id();
+        app.world.send_event(ResearchRecipe {
+            recipe_id: id("blueprint"),
+            storage,
+        });
+        app.update();
+
+        assert!(app
+            .world
+            .resource::<DiscoveredRecipes>()
+            .is_discovered(id("blueprint")));
+        let inventory = &app
+            .world
+            .get::<StorageInventory>(storage)
+            .unwrap()
+            .inventory;
+        assert_eq!(inventory.item_count(Id::from_name("pollen".to_string())), 0);
+        assert_eq!(
+            inventory.item_count(Id::from_name("feather".to_string())),
+            2
+        );
+    }
+
+    #[test]
+    fn unknown_recipes_are_never_available() {
+        let discovered_recipes = DiscoveredRecipes::default();
+
+        assert!(!discovered_recipes.is_available(id("gather"), &RecipeManifest::default()));
+    }
+}
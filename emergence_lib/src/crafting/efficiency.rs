@@ -0,0 +1,184 @@
+//! Multipliers on how much structures produce, based on their condition.
+
+use bevy::{prelude::*, utils::HashMap};
+
+use std::fmt::Display;
+
+use crate::{
+    asset_management::manifest::Id,
+    items::{item_manifest::Item, ItemCount},
+};
+
+/// How much a structure produces per craft, relative to the outputs of its recipes.
+///
+/// An efficiency of 0.5 halves the items produced, as happens for damaged or poorly-maintained structures.
+/// Outputs are rounded down, but the fractions lost to rounding are carried over to the next craft,
+/// so that no items are lost over time.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct CraftingEfficiency {
+    /// The multiplier applied to the number of items produced.
+    multiplier: f32,
+    /// The fractional items of each kind that were not produced by previous crafts.
+    ///
+    /// Every value is in the range `[0, 1)`.
+    carryover: HashMap<Id<Item>, f32>,
+}
+
+impl CraftingEfficiency {
+    /// Creates a new [`CraftingEfficiency`] with the provided multiplier.
+    ///
+    /// Negative or non-finite multipliers are treated as 0.0, producing nothing.
+    pub fn new(multiplier: f32) -> Self {
+        CraftingEfficiency {
+            multiplier: Self::sanitize(multiplier),
+            carryover: HashMap::default(),
+        }
+    }
+
+    /// Clamps invalid multipliers to 0.0.
+    fn sanitize(multiplier: f32) -> f32 {
+        if multiplier.is_finite() {
+            multiplier.max(0.)
+        } else {
+            0.
+        }
+    }
+
+    /// The multiplier applied to the number of items produced.
+    pub fn multiplier(&self) -> f32 {
+        self.multiplier
+    }
+
+    /// Sets the multiplier applied to the number of items produced.
+    ///
+    /// The same rules as [`CraftingEfficiency::new`] apply.
+    /// Fractional items carried over from previous crafts are kept.
+    pub fn set_multiplier(&mut self, multiplier: f32) {
+        self.multiplier = Self::sanitize(multiplier);
+    }
+
+    /// The fraction of an item of kind `item_id` carried over to the next craft.
+    pub fn carryover(&self, item_id: Id<Item>) -> f32 {
+        self.carryover.get(&item_id).copied().unwrap_or_default()
+    }
+
+    /// Scales the items `produced` by a single craft by the multiplier.
+    ///
+    /// For each kind of item, the exact amount is `count * multiplier` plus the fraction carried over for that item.
+    /// The whole part of this amount is produced, and the fractional part is carried over to the next craft.
+    /// As a result, the total produced over any number of crafts is always within one item of the exact total.
+    pub fn apply(&mut self, produced: Vec<ItemCount>) -> Vec<ItemCount> {
+        // Skip the bookkeeping in the common case, so that perfect structures carry nothing over
+        if self.multiplier == 1. && self.carryover.is_empty() {
+            return produced;
+        }
+
+        produced
+            .into_iter()
+            .map(|item_count| {
+                let carried = self.carryover.remove(&item_count.item_id);
+                let exact = item_count.count as f32 * self.multiplier + carried.unwrap_or(0.);
+                let whole = exact.floor();
+
+                let fraction = exact - whole;
+                if fraction > 0. {
+                    self.carryover.insert(item_count.item_id, fraction);
+                }
+
+                ItemCount::new(item_count.item_id, whole as u32)
+            })
+            .collect()
+    }
+}
+
+impl Default for CraftingEfficiency {
+    fn default() -> Self {
+        CraftingEfficiency::new(1.)
+    }
+}
+
+impl Display for CraftingEfficiency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.0}%", self.multiplier * 100.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Shorthand for the [`Id`] of the item `name`.
+    fn id(name: &str) -> Id<Item> {
+        Id::from_name(name.to_string())
+    }
+
+    /// The total number of items produced over `crafts` crafts that each nominally produce `count` leaves.
+    fn total_over_crafts(efficiency: &mut CraftingEfficiency, count: u32, crafts: u32) -> u32 {
+        (0..crafts)
+            .flat_map(|_| efficiency.apply(vec![ItemCount::new(id("leaf"), count)]))
+            .map(|item_count| item_count.count)
+            .sum()
+    }
+
+    #[test]
+    fn default_efficiency_leaves_outputs_unchanged() {
+        let mut efficiency = CraftingEfficiency::default();
+        let produced = vec![ItemCount::new(id("leaf"), 3), ItemCount::new(id("log"), 1)];
+
+        assert_eq!(efficiency.apply(produced.clone()), produced);
+        assert_eq!(efficiency.carryover(id("leaf")), 0.);
+    }
+
+    #[test]
+    fn outputs_are_rounded_down_and_carried_over() {
+        let mut efficiency = CraftingEfficiency::new(0.5);
+
+        let first = efficiency.apply(vec![ItemCount::new(id("leaf"), 3)]);
+        assert_eq!(first, vec![ItemCount::new(id("leaf"), 1)]);
+        assert_eq!(efficiency.carryover(id("leaf")), 0.5);
+
+        let second = efficiency.apply(vec![ItemCount::new(id("leaf"), 3)]);
+        assert_eq!(second, vec![ItemCount::new(id("leaf"), 2)]);
+        assert_eq!(efficiency.carryover(id("leaf")), 0.);
+    }
+
+    #[test]
+    fn total_output_matches_efficiency_within_one_item() {
+        let crafts = 1000;
+
+        for multiplier in [0.1, 0.33, 0.5, 0.75, 0.9, 1., 1.25] {
+            for count in [1, 2, 7] {
+                let mut efficiency = CraftingEfficiency::new(multiplier);
+                let total = total_over_crafts(&mut efficiency, count, crafts);
+                let expected = multiplier * (count * crafts) as f32;
+
+                assert!(
+                    (total as f32 - expected).abs() <= 1.,
+                    "An efficiency of {multiplier} produced {total} items, but {expected} were expected"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn each_item_carries_over_separately() {
+        let mut efficiency = CraftingEfficiency::new(0.5);
+
+        efficiency.apply(vec![ItemCount::new(id("leaf"), 1)]);
+        let produced = efficiency.apply(vec![ItemCount::new(id("log"), 1)]);
+
+        assert_eq!(produced, vec![ItemCount::new(id("log"), 0)]);
+        assert_eq!(efficiency.carryover(id("leaf")), 0.5);
+        assert_eq!(efficiency.carryover(id("log")), 0.5);
+    }
+
+    #[test]
+    fn invalid_efficiencies_produce_nothing() {
+        for multiplier in [-1., f32::NAN, f32::INFINITY] {
+            let mut efficiency = CraftingEfficiency::new(multiplier);
+
+            assert_eq!(efficiency.multiplier(), 0.);
+            assert_eq!(total_over_crafts(&mut efficiency, 5, 10), 0);
+        }
+    }
+}
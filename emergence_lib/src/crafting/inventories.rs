@@ -1,14 +1,16 @@
 //! Various inventory types used in crafting.
 
 use super::{
-    item_tags::ItemTag,
-    recipe::{RecipeData, RecipeInput, RecipeOutput},
+    efficiency::CraftingEfficiency,
+    item_tags::{ItemKind, ItemTag},
+    recipe::{ConsumptionMode, OutputSlot, OverflowPolicy, Recipe, RecipeData, RecipeInput},
 };
 
 use crate::{
     asset_management::manifest::Id,
     items::{
-        errors::{AddManyItemsError, AddOneItemError},
+        errors::{AddOneItemError, RemoveOneItemError},
+        filter::ItemFilter,
         inventory::Inventory,
         item_manifest::{Item, ItemManifest},
         slot::ItemSlot,
@@ -16,10 +18,15 @@ use crate::{
     },
 };
 
-use std::{fmt::Display, time::Duration};
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    ops::{Deref, DerefMut},
+    time::Duration,
+};
 
-use bevy::prelude::*;
-use rand::{distributions::Uniform, prelude::Distribution, rngs::ThreadRng};
+use bevy::{prelude::*, utils::HashMap};
+use rand::{distributions::Uniform, prelude::Distribution, rngs::ThreadRng, Rng};
 use serde::{Deserialize, Serialize};
 
 /// The current state in the crafting progress.
@@ -36,7 +43,12 @@ pub(crate) enum CraftingState {
         required: Duration,
     },
     /// Resources need to be claimed before more crafting can continue.
-    FullAndBlocked,
+    ///
+    /// Under [`OverflowPolicy::Block`], the outputs of the finished cycle wait here until they all fit.
+    FullAndBlocked {
+        /// The outputs that have been produced, but not yet stored.
+        pending: Vec<ItemCount>,
+    },
     /// The recipe is complete.
     RecipeComplete,
     /// The output is full but production is continuing.
@@ -69,7 +81,7 @@ impl Display for CraftingState {
                 format!("In progress ({progress_in_seconds:.1} / {required_in_seconds:.1})")
             }
             CraftingState::RecipeComplete => "Recipe complete".to_string(),
-            CraftingState::FullAndBlocked => "Blocked".to_string(),
+            CraftingState::FullAndBlocked { .. } => "Blocked".to_string(),
             CraftingState::Overproduction => "Overproduction".to_string(),
             CraftingState::NoRecipe => "No recipe set".to_string(),
         };
@@ -78,6 +90,178 @@ impl Display for CraftingState {
     }
 }
 
+/// The time spent crafting so far, accumulated from the elapsed time of each tick.
+///
+/// Time beyond the end of a cycle is kept for the next one,
+/// so the number of completed cycles matches the total time spent crafting, no matter how the time is split up.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CraftingProgress {
+    /// The time accumulated towards the current cycle, and any cycles after it.
+    elapsed: Duration,
+}
+
+impl CraftingProgress {
+    /// Creates progress that has already accumulated the `elapsed` time.
+    pub fn new(elapsed: Duration) -> Self {
+        CraftingProgress { elapsed }
+    }
+
+    /// The time accumulated so far.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Accumulates the `delta` time.
+    pub fn add(&mut self, delta: Duration) {
+        self.elapsed += delta;
+    }
+
+    /// Completes a single cycle taking `craft_time`, if enough time has accumulated.
+    ///
+    /// The remaining time is kept for the following cycles.
+    pub fn try_complete(&mut self, craft_time: Duration) -> bool {
+        match self.elapsed.checked_sub(craft_time) {
+            Some(remaining) => {
+                self.elapsed = remaining;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Accumulates the `delta` time, and completes as many cycles taking `craft_time` as possible.
+    ///
+    /// Returns the number of completed cycles.
+    /// Recipes that take no time at all never complete, as they would complete infinitely often.
+    pub fn advance(&mut self, delta: Duration, craft_time: Duration) -> u32 {
+        self.add(delta);
+
+        if craft_time.is_zero() {
+            return 0;
+        }
+
+        // Count in whole nanoseconds, so that no time is lost to rounding
+        let elapsed = self.elapsed.as_nanos();
+        let craft_time = craft_time.as_nanos();
+        // The remainder is shorter than the craft time, so it always fits
+        self.elapsed = Duration::from_nanos((elapsed % craft_time) as u64);
+        (elapsed / craft_time).try_into().unwrap_or(u32::MAX)
+    }
+
+    /// Discards all accumulated time.
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+    }
+}
+
+/// The [quality](ItemSlot::quality) of the items produced by the cycle currently being crafted.
+///
+/// This is set from the inputs when crafting starts, following the [`QualityRule`](super::recipe::QualityRule) of the recipe,
+/// and kept until the outputs have been stored.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct CraftedQuality(pub f32);
+
+impl Default for CraftedQuality {
+    fn default() -> Self {
+        CraftedQuality(ItemSlot::FULL_QUALITY)
+    }
+}
+
+/// The recipe that the current crafting cycle belongs to, and the inputs that were taken when it started.
+///
+/// This is compared against the [`ActiveRecipe`](super::recipe::ActiveRecipe) of the crafter to notice when its recipe is swapped,
+/// so that the cycle can be abandoned rather than carried over into an unrelated recipe.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct CraftingCycle {
+    /// The recipe being crafted, if any.
+    pub recipe_id: Option<Id<Recipe>>,
+    /// The inputs that were taken when the current cycle started, for recipes consumed [`ConsumptionMode::AtStart`].
+    ///
+    /// This is empty between cycles.
+    pub taken_at_start: Vec<ItemCount>,
+    /// The number of units of the recipe crafted in the current cycle, up to its [`RecipeData::max_batch`].
+    ///
+    /// This is 1 between cycles.
+    pub batch_size: u32,
+}
+
+impl Default for CraftingCycle {
+    fn default() -> Self {
+        CraftingCycle::new(None)
+    }
+}
+
+impl CraftingCycle {
+    /// Creates a cycle of the recipe with the given `recipe_id`, which has not started yet.
+    pub fn new(recipe_id: Option<Id<Recipe>>) -> Self {
+        CraftingCycle {
+            recipe_id,
+            taken_at_start: Vec::new(),
+            batch_size: 1,
+        }
+    }
+
+    /// Abandons the current cycle of the `previous` recipe, and replaces the `input` and `output` inventories with those of the `next` recipe.
+    ///
+    /// Whether the inputs of the abandoned cycle are refunded depends on the [`ConsumptionMode`] of the previous recipe:
+    /// - [`ConsumptionMode::AtStart`]: the inputs taken when the cycle started are refunded in full, as the cycle never finished.
+    /// - [`ConsumptionMode::AtEnd`]: the inputs have not been taken yet, so they are kept.
+    /// - [`ConsumptionMode::Continuous`]: the inputs consumed so far have been used up, and are forfeited.
+    ///
+    /// Catalysts are never used up, so they are always kept.
+    /// The kept and refunded items, along with any outputs that were already produced,
+    /// are moved into the new inventories, preferring the input inventory.
+    /// The items that do not fit are returned, so that they can be dropped rather than lost.
+    pub(crate) fn abandon(
+        &mut self,
+        previous: Option<&RecipeData>,
+        next: Option<&RecipeData>,
+        input: &mut InputInventory,
+        output: &mut OutputInventory,
+        item_manifest: &ItemManifest,
+    ) -> Vec<ItemCount> {
+        let taken_at_start = std::mem::take(&mut self.taken_at_start);
+        self.batch_size = 1;
+        let refunded = match previous.map(|recipe| recipe.consumption) {
+            Some(ConsumptionMode::AtStart) => taken_at_start,
+            Some(ConsumptionMode::AtEnd | ConsumptionMode::Continuous) | None => Vec::new(),
+        };
+
+        let (mut next_input, mut next_output) = match next {
+            Some(next) => (
+                next.input_inventory(item_manifest),
+                next.output_inventory(item_manifest),
+            ),
+            None => (InputInventory::NULL, OutputInventory::NULL),
+        };
+
+        let items = input
+            .iter()
+            .chain(output.iter())
+            .map(|item_slot| item_slot.item_count())
+            .chain(refunded)
+            .filter(|item_count| item_count.count > 0);
+
+        let mut leftovers = Vec::new();
+        for item_count in items {
+            let mut remaining = item_count;
+            if next_input.currently_accepts(remaining.item_id, item_manifest) {
+                remaining = next_input.inventory_mut().deposit(remaining, item_manifest);
+            }
+            if remaining.count > 0 {
+                remaining = next_output.deposit(remaining, item_manifest);
+            }
+            if remaining.count > 0 {
+                leftovers.push(remaining);
+            }
+        }
+
+        *input = next_input;
+        *output = next_output;
+        leftovers
+    }
+}
+
 /// The input inventory for a structure.
 #[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum InputInventory {
@@ -189,13 +373,34 @@ impl InputInventory {
         recipe_input: &RecipeInput,
         item_manifest: &ItemManifest,
     ) -> Result<(), ConsumeInputError> {
-        let inventory = self.inventory_mut();
+        let items_to_consume = self.items_to_consume(recipe_input, item_manifest)?;
+
+        match self
+            .inventory_mut()
+            .remove_items_all_or_nothing(&items_to_consume)
+        {
+            Ok(()) => Ok(()),
+            Err(_) => panic!("Inventory should have had enough items to remove"),
+        }
+    }
+
+    /// The items that [`InputInventory::consume_items`] would remove for the `recipe_input`, without removing them.
+    pub fn items_to_consume(
+        &self,
+        recipe_input: &RecipeInput,
+        item_manifest: &ItemManifest,
+    ) -> Result<Vec<ItemCount>, ConsumeInputError> {
+        let inventory = self.inventory();
 
         match recipe_input {
             RecipeInput::Exact(item_counts) => {
-                match inventory.remove_items_all_or_nothing(item_counts) {
-                    Ok(()) => Ok(()),
-                    Err(_) => Err(ConsumeInputError::NotEnoughItems),
+                if item_counts
+                    .iter()
+                    .all(|item_count| inventory.has_count_of_item(item_count))
+                {
+                    Ok(item_counts.clone())
+                } else {
+                    Err(ConsumeInputError::NotEnoughItems)
                 }
             }
             RecipeInput::Flexible { tag, count } => {
@@ -226,11 +431,132 @@ impl InputInventory {
                     return Err(ConsumeInputError::NotEnoughItems);
                 }
 
-                match inventory.remove_items_all_or_nothing(&proposed_removal) {
-                    Ok(()) => Ok(()),
-                    Err(_) => panic!("Inventory should have had enough items to remove"),
+                Ok(proposed_removal)
+            }
+            RecipeInput::Category { category, count } => {
+                let mut remaining_to_remove = *count;
+                let mut proposed_removal: Vec<ItemCount> = Vec::new();
+
+                // Matching items are sorted by their ID, so the lowest ID is used up first
+                for item_id in item_manifest.items_with_tag(*category) {
+                    let removed_of_this_item =
+                        std::cmp::min(inventory.item_count(item_id), remaining_to_remove);
+                    if removed_of_this_item > 0 {
+                        proposed_removal.push(ItemCount::new(item_id, removed_of_this_item));
+                        remaining_to_remove -= removed_of_this_item;
+                    }
+
+                    if remaining_to_remove == 0 {
+                        break;
+                    }
                 }
+
+                if remaining_to_remove > 0 {
+                    return Err(ConsumeInputError::NotEnoughItems);
+                }
+
+                Ok(proposed_removal)
             }
+            RecipeInput::AnyOf(alternatives) => {
+                match RecipeInput::first_available_alternative(alternatives, |item_id| {
+                    inventory.item_count(item_id)
+                }) {
+                    Some(alternative) => Ok(vec![alternative.clone()]),
+                    None => Err(ConsumeInputError::NotEnoughItems),
+                }
+            }
+        }
+    }
+
+    /// Does this inventory contain all of the `catalysts`?
+    pub fn has_catalysts(&self, catalysts: &[ItemCount]) -> bool {
+        catalysts
+            .iter()
+            .all(|catalyst| self.inventory().has_count_of_item(catalyst))
+    }
+
+    /// The quality of the items that crafting the `recipe` from the current contents would produce.
+    ///
+    /// Catalysts are not used up, and so do not affect the quality of the outputs.
+    pub fn output_quality(&self, recipe: &RecipeData) -> f32 {
+        recipe.output_quality.resolve(
+            self.iter()
+                .filter(|slot| {
+                    !recipe
+                        .catalysts
+                        .iter()
+                        .any(|catalyst| slot.is_for_item(catalyst.item_id))
+                })
+                .map(|slot| (slot.quality(), slot.count())),
+        )
+    }
+
+    /// Try to start crafting the `recipe`, removing its inputs from the inventory if they are consumed [`ConsumptionMode::AtStart`].
+    ///
+    /// Crafting can only start if all of the recipe's catalysts are present.
+    /// Unlike the inputs, the catalysts are left in the inventory.
+    pub fn start_crafting(
+        &mut self,
+        recipe: &RecipeData,
+        item_manifest: &ItemManifest,
+    ) -> Result<(), ConsumeInputError> {
+        self.start_batch(recipe, 1, item_manifest)
+    }
+
+    /// Try to start crafting a `batch` of the `recipe`, as [`InputInventory::start_crafting`] does for a single unit.
+    ///
+    /// The inputs of every unit in the batch are needed, while the catalysts are shared by the whole batch.
+    pub fn start_batch(
+        &mut self,
+        recipe: &RecipeData,
+        batch: u32,
+        item_manifest: &ItemManifest,
+    ) -> Result<(), ConsumeInputError> {
+        if !self.has_catalysts(&recipe.catalysts) {
+            return Err(ConsumeInputError::MissingCatalysts);
+        }
+
+        let Some(inputs) = recipe.inputs.times(batch) else {
+            return Err(ConsumeInputError::NotEnoughItems);
+        };
+        match recipe.consumption {
+            ConsumptionMode::AtStart => self.consume_items(&inputs, item_manifest),
+            // Check that the inputs are there, without taking them yet
+            ConsumptionMode::AtEnd => self.clone().consume_items(&inputs, item_manifest),
+            // The inputs are taken as progress is made
+            ConsumptionMode::Continuous => Ok(()),
+        }
+    }
+
+    /// Try to pay for crafting the `recipe` from `from` until `to`.
+    ///
+    /// This only removes items for recipes that consume their inputs [`ConsumptionMode::Continuous`]ly.
+    /// If any of the needed items are missing, nothing is removed, and no progress should be made.
+    pub fn continue_crafting(
+        &mut self,
+        recipe: &RecipeData,
+        from: Duration,
+        to: Duration,
+        item_manifest: &ItemManifest,
+    ) -> Result<(), ConsumeInputError> {
+        match recipe.consumption {
+            ConsumptionMode::Continuous => self.consume_items(
+                &recipe.inputs.consumed_between(from, to, recipe.craft_time),
+                item_manifest,
+            ),
+            ConsumptionMode::AtStart | ConsumptionMode::AtEnd => Ok(()),
+        }
+    }
+
+    /// Try to finish crafting the `recipe`, removing its inputs from the inventory if they are consumed [`ConsumptionMode::AtEnd`].
+    pub fn finish_crafting(
+        &mut self,
+        recipe: &RecipeData,
+        item_manifest: &ItemManifest,
+    ) -> Result<(), ConsumeInputError> {
+        match recipe.consumption {
+            ConsumptionMode::AtEnd => self.consume_items(&recipe.inputs, item_manifest),
+            ConsumptionMode::AtStart | ConsumptionMode::Continuous => Ok(()),
         }
     }
 
@@ -239,6 +565,30 @@ impl InputInventory {
         self.inventory_mut().clear_empty_slots();
     }
 
+    /// The input inventory of the `next` recipe, holding every item left in this inventory and in the `output` inventory.
+    ///
+    /// This hands the items produced by one stage of a [chain of recipes](RecipeData::next_recipe) over to the next stage,
+    /// together with any catalysts that remain.
+    /// Returns [`None`] if the next recipe does not accept all of these items, so that none of them are lost.
+    pub(super) fn for_next_recipe(
+        &self,
+        output: &OutputInventory,
+        next: &RecipeData,
+        item_manifest: &ItemManifest,
+    ) -> Option<InputInventory> {
+        let mut next_input = next.input_inventory(item_manifest);
+
+        for item_slot in self.iter().chain(output.iter()) {
+            if item_slot.count() > 0 {
+                next_input
+                    .fill_with_items(&item_slot.item_count(), item_manifest)
+                    .ok()?;
+            }
+        }
+
+        Some(next_input)
+    }
+
     /// Randomizes the contents of this inventory so that each slot is somewhere between empty and full.
     ///
     /// Note that this only works for [`InputInventory::Exact`].
@@ -268,6 +618,8 @@ pub enum ConsumeInputError {
     NotEnoughItems,
     /// The items in the inventory did not match the provided recipe.
     IncorrectItemTags,
+    /// The catalysts required by the recipe are not present.
+    MissingCatalysts,
 }
 
 /// An error that can occur when trying to add items to an [`InputInventory`].
@@ -283,18 +635,125 @@ pub enum AddToInputError {
 }
 
 /// The output inventory for a structure.
-#[derive(Component, Clone, Debug, Default, Deref, DerefMut)]
+///
+/// The items are split into partitions by their [`OutputSlot`], such as a separate partition for waste.
+/// The [`OutputSlot::Product`] partition is the inner inventory, which is what the [`Deref`] implementation exposes.
+/// The methods defined here that look at or take items cover every partition.
+#[derive(Component, Clone, Debug, Default)]
 pub(crate) struct OutputInventory {
-    /// Inner storage
+    /// Inner storage, holding the products
     pub(crate) inventory: Inventory,
+    /// The storage for every other output slot that this structure keeps apart from its products
+    pub(crate) partitions: BTreeMap<OutputSlot, Inventory>,
+}
+
+impl Deref for OutputInventory {
+    type Target = Inventory;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inventory
+    }
+}
+
+impl DerefMut for OutputInventory {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inventory
+    }
 }
 
 impl OutputInventory {
     /// An output inventory with no slots.
     pub(crate) const NULL: Self = Self {
         inventory: Inventory::NULL,
+        partitions: BTreeMap::new(),
     };
 
+    /// An output inventory that stores all of its items, whatever their [`OutputSlot`], in the `inventory`.
+    pub(crate) fn new(inventory: Inventory) -> Self {
+        OutputInventory {
+            inventory,
+            partitions: BTreeMap::new(),
+        }
+    }
+
+    /// The partition that items routed to `output_slot` are stored in.
+    ///
+    /// Output slots without a partition of their own share the partition of the products.
+    #[cfg(test)]
+    pub(crate) fn partition(&self, output_slot: OutputSlot) -> &Inventory {
+        self.partitions.get(&output_slot).unwrap_or(&self.inventory)
+    }
+
+    /// The partition that items routed to `output_slot` are stored in, mutably.
+    ///
+    /// Output slots without a partition of their own share the partition of the products.
+    pub(crate) fn partition_mut(&mut self, output_slot: OutputSlot) -> &mut Inventory {
+        self.partitions
+            .get_mut(&output_slot)
+            .unwrap_or(&mut self.inventory)
+    }
+
+    /// Returns an iterator over the item slots of every partition.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &ItemSlot> {
+        self.inventory
+            .iter()
+            .chain(self.partitions.values().flat_map(Inventory::iter))
+    }
+
+    /// Returns a mutable iterator over the item slots of every partition.
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = &mut ItemSlot> {
+        self.inventory
+            .iter_mut()
+            .chain(self.partitions.values_mut().flat_map(Inventory::iter_mut))
+    }
+
+    /// Does any partition contain at least one matching item?
+    pub(crate) fn contains_kind(&self, item_kind: ItemKind, item_manifest: &ItemManifest) -> bool {
+        self.inventory.contains_kind(item_kind, item_manifest)
+            || self
+                .partitions
+                .values()
+                .any(|partition| partition.contains_kind(item_kind, item_manifest))
+    }
+
+    /// Returns the first [`Id<Item>`] in any partition that matches the given [`ItemKind`], if any.
+    ///
+    /// Empty slots will be skipped.
+    pub(crate) fn matching_item_id(
+        &self,
+        item_kind: ItemKind,
+        item_manifest: &ItemManifest,
+    ) -> Option<Id<Item>> {
+        self.inventory
+            .matching_item_id(item_kind, item_manifest)
+            .or_else(|| {
+                self.partitions
+                    .values()
+                    .find_map(|partition| partition.matching_item_id(item_kind, item_manifest))
+            })
+    }
+
+    /// Try to remove the given count of items, together, from a single partition.
+    ///
+    /// The products are checked first, followed by the other partitions.
+    /// If no partition holds enough of the items, _no_ item is removed and `Err` is returned.
+    pub(crate) fn remove_item_all_or_nothing(
+        &mut self,
+        item_count: &ItemCount,
+    ) -> Result<(), RemoveOneItemError> {
+        if !self.inventory.has_count_of_item(item_count) {
+            if let Some(partition) = self
+                .partitions
+                .values_mut()
+                .find(|partition| partition.has_count_of_item(item_count))
+            {
+                return partition.remove_item_all_or_nothing(item_count);
+            }
+        }
+
+        self.inventory.remove_item_all_or_nothing(item_count)
+    }
+
     /// Randomizes the contents of this inventory so that each slot is somewhere between empty and full.
     pub(crate) fn randomize(&mut self, rng: &mut ThreadRng) {
         for item_slot in self.iter_mut() {
@@ -302,61 +761,156 @@ impl OutputInventory {
         }
     }
 
-    /// Produces the items specified by `recipe` and adds them to the inventory.
+    /// Produces the items specified by `recipe`, scaled by the `efficiency` of the crafter, and adds them to the inventory.
+    ///
+    /// The items are of the given `quality`, and each is stored in the partition of its [`OutputSlot`].
+    /// Items that do not fit are handled according to the [`OverflowPolicy`] of the recipe.
+    #[cfg(test)]
     pub(super) fn craft(
         &mut self,
         recipe: &RecipeData,
+        efficiency: &mut CraftingEfficiency,
+        quality: f32,
         item_manifest: &ItemManifest,
-        rng: &mut ThreadRng,
-    ) -> Result<(), AddManyItemsError> {
-        let mut overflow: Vec<ItemCount> = Vec::new();
-
-        match &recipe.outputs {
-            RecipeOutput::Deterministic(outputs) => {
-                for output in outputs {
-                    let result = self.try_add_item(output, item_manifest);
-                    if let Err(AddOneItemError { excess_count }) = result {
-                        overflow.push(excess_count);
-                    }
-                }
-            }
-            RecipeOutput::Stochastic(outputs) => {
-                let distribution = Uniform::new(0.0, 1.0);
-                for (item_id, number) in outputs {
-                    // Always produce items equal to quotient,
-                    // and then produce one extra items with probability remainder.
-                    let (quotient, remainder) = (number / 1.0, number % 1.0);
-                    let count = if remainder == 0. || distribution.sample(rng) > remainder {
-                        quotient as u32
-                    } else {
-                        quotient as u32 + 1
-                    };
-
-                    let output = ItemCount::new(*item_id, count);
-
-                    let result = self.try_add_item(&output, item_manifest);
-                    if let Err(AddOneItemError { excess_count }) = result {
-                        overflow.push(excess_count);
-                    }
-                }
+        rng: &mut impl Rng,
+    ) -> StoredOutputs {
+        self.craft_batch(recipe, 1, efficiency, quality, item_manifest, rng)
+    }
+
+    /// Produces the items of a `batch` of the `recipe`, as [`OutputInventory::craft`] does for a single unit.
+    ///
+    /// The outputs of each unit are rolled for separately, and the whole batch is stored at once.
+    pub(super) fn craft_batch(
+        &mut self,
+        recipe: &RecipeData,
+        batch: u32,
+        efficiency: &mut CraftingEfficiency,
+        quality: f32,
+        item_manifest: &ItemManifest,
+        rng: &mut impl Rng,
+    ) -> StoredOutputs {
+        let rolled = (0..batch).flat_map(|_| recipe.roll_outputs(rng)).collect();
+        let produced = efficiency.apply(rolled);
+        self.store(
+            produced,
+            &recipe.output_slots,
+            recipe.overflow,
+            quality,
+            item_manifest,
+        )
+    }
+
+    /// Adds the `produced` items of the given `quality` to the inventory,
+    /// handling the items that do not fit according to the `overflow` policy.
+    ///
+    /// Each item is stored in the partition of its slot in `output_slots`, or with the products if it has none.
+    /// Whether an item fits only depends on the room in its own partition,
+    /// so a full waste partition only holds up the products under [`OverflowPolicy::Block`].
+    pub(super) fn store(
+        &mut self,
+        produced: Vec<ItemCount>,
+        output_slots: &HashMap<Id<Item>, OutputSlot>,
+        overflow: OverflowPolicy,
+        quality: f32,
+        item_manifest: &ItemManifest,
+    ) -> StoredOutputs {
+        // Several outputs may produce the same item, which must be added together
+        let mut merged: Vec<ItemCount> = Vec::new();
+        for item_count in produced
+            .into_iter()
+            .filter(|item_count| item_count.count > 0)
+        {
+            match merged
+                .iter_mut()
+                .find(|merged_count| merged_count.item_id == item_count.item_id)
+            {
+                Some(merged_count) => merged_count.count += item_count.count,
+                None => merged.push(item_count),
             }
+        }
+
+        let output_slot = |item_count: &ItemCount| {
+            output_slots
+                .get(&item_count.item_id)
+                .copied()
+                .unwrap_or_default()
         };
 
-        if overflow.is_empty() {
-            Ok(())
-        } else {
-            Err(AddManyItemsError {
-                excess_counts: overflow,
+        if overflow == OverflowPolicy::Block {
+            // Add the items to a copy first, so that nothing is stored unless everything fits
+            let mut trial = self.clone();
+            let fits = merged.iter().all(|item_count| {
+                trial
+                    .partition_mut(output_slot(item_count))
+                    .try_add_item_with_quality(item_count, quality, item_manifest)
+                    .is_ok()
+            });
+
+            return if fits {
+                *self = trial;
+                StoredOutputs::Stored
+            } else {
+                StoredOutputs::Blocked(merged)
+            };
+        }
+
+        let excess: Vec<ItemCount> = merged
+            .iter()
+            .filter_map(|item_count| {
+                self.partition_mut(output_slot(item_count))
+                    .try_add_item_with_quality(item_count, quality, item_manifest)
+                    .err()
+                    .map(|AddOneItemError { excess_count }| excess_count)
             })
+            .collect();
+
+        match (excess.is_empty(), overflow) {
+            (true, _) => StoredOutputs::Stored,
+            (false, OverflowPolicy::Discard) => StoredOutputs::Discarded(excess),
+            (false, _) => StoredOutputs::Voided(excess),
         }
     }
 }
 
+/// What happened to the items produced by a recipe, as returned by [`OutputInventory::store`].
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum StoredOutputs {
+    /// All of the items were added to the output inventory.
+    Stored,
+    /// None of the items were added, as there was no room for all of them.
+    ///
+    /// The items are returned, so that they can be stored once there is room.
+    Blocked(Vec<ItemCount>),
+    /// The items that fit were added, and these remaining items should be dropped as litter.
+    Discarded(Vec<ItemCount>),
+    /// The items that fit were added, and these remaining items were lost.
+    Voided(Vec<ItemCount>),
+}
+
 /// An inventory that simply stores items
-#[derive(Component, Clone, Debug, Default, Deref, DerefMut)]
+#[derive(Component, Clone, Debug, Default)]
 pub(crate) struct StorageInventory {
     /// Inner storage
     pub(crate) inventory: Inventory,
+    /// The items that may be stored here
+    ///
+    /// This is only checked by [`StorageInventory::currently_accepts`] and [`StorageInventory::store_item_all_or_nothing`];
+    /// the inner inventory can still be modified directly.
+    pub(crate) filter: ItemFilter,
+}
+
+impl Deref for StorageInventory {
+    type Target = Inventory;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inventory
+    }
+}
+
+impl DerefMut for StorageInventory {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inventory
+    }
 }
 
 impl StorageInventory {
@@ -366,12 +920,1232 @@ impl StorageInventory {
     pub(crate) fn new(max_slot_count: usize, reserved_for: Option<Id<Item>>) -> Self {
         StorageInventory {
             inventory: Inventory::new(max_slot_count, reserved_for),
+            filter: ItemFilter::default(),
         }
     }
 
-    /// Does this inventory have space for at least one item of the given kind?
+    /// Does this inventory have space for at least one item of the given kind, and does its filter allow the item?
     pub fn currently_accepts(&self, item_id: Id<Item>, item_manifest: &ItemManifest) -> bool {
         // Check that we can fit at least one item of this type
-        self.remaining_space_for_item(item_id, item_manifest) > 0
+        self.filter.accepts(item_id) && self.remaining_space_for_item(item_id, item_manifest) > 0
+    }
+
+    /// Tries to store all of the items, failing if there is not enough space or if the [`ItemFilter`] rejects them.
+    ///
+    /// Items that are rejected by the filter all count as excess.
+    pub fn store_item_all_or_nothing(
+        &mut self,
+        item_count: &ItemCount,
+        item_manifest: &ItemManifest,
+    ) -> Result<(), AddOneItemError> {
+        if !self.filter.accepts(item_count.item_id) {
+            return Err(AddOneItemError {
+                excess_count: item_count.clone(),
+            });
+        }
+
+        self.inventory
+            .add_item_all_or_nothing(item_count, item_manifest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        asset_management::manifest::Manifest,
+        crafting::recipe::{
+            ConsumptionMode, ProbabilisticOutput, QualityRule, RawInputAlternative, RawOutputCount,
+            RawRecipeData, RawRecipeInput, TimePhase, WeightedOutput,
+        },
+        items::item_manifest::ItemData,
+        organisms::energy::Energy,
+        simulation::rng::GlobalRng,
+    };
+    use bevy::utils::{HashMap, HashSet};
+
+    /// An item manifest containing all items used by [`fermentation`] and [`composting`].
+    ///
+    /// Leaves and fungi are tagged as `"organic"`.
+    fn item_manifest() -> ItemManifest {
+        let mut manifest = Manifest::new();
+        for name in ["leaf", "fungus", "compost", "spores"] {
+            manifest.insert(
+                name.to_string(),
+                ItemData {
+                    stack_size: 10,
+                    compostable: true,
+                    fluid: false,
+                    buoyant: false,
+                    seed: None,
+                    energy_content: Energy(0.),
+                    tags: match name {
+                        "leaf" | "fungus" => {
+                            HashSet::from_iter([Id::from_name("organic".to_string())])
+                        }
+                        _ => HashSet::new(),
+                    },
+                    mass: 1.,
+                    decay_time: None,
+                    decays_into: None,
+                    tier: 0,
+                    name: None,
+                    description: None,
+                    icon: None,
+                },
+            );
+        }
+        manifest
+    }
+
+    /// A recipe that turns a leaf into compost, but only in the presence of a fungus.
+    fn fermentation() -> RecipeData {
+        RecipeData::try_from(fermentation_raw()).unwrap()
+    }
+
+    /// The unprocessed form of [`fermentation`].
+    fn fermentation_raw() -> RawRecipeData {
+        RawRecipeData {
+            inputs: RawRecipeInput::single("leaf", 1),
+            catalysts: HashMap::from_iter([("fungus".to_string(), 1)]),
+            byproducts: HashMap::new(),
+            output_one_of: Vec::new(),
+            outputs: HashMap::from_iter([("compost".to_string(), RawOutputCount::Count(1.))]),
+            craft_time: 1.,
+            work_units: None,
+            conditions: None,
+            energy: None,
+            allow_mass_gain: false,
+            allow_self_production: false,
+            requires: Vec::new(),
+            research_cost: HashMap::new(),
+            fluid_inputs: HashMap::new(),
+            fluid_outputs: HashMap::new(),
+            consumption: ConsumptionMode::AtStart,
+            overflow: OverflowPolicy::Block,
+            output_slots: HashMap::new(),
+            max_batch: 1,
+            next_recipe: None,
+            output_tier: None,
+            output_quality: QualityRule::default(),
+            category: RawRecipeData::DEFAULT_CATEGORY.to_string(),
+            active_phase: TimePhase::Always,
+            requires_environment: None,
+            enabled: true,
+        }
+    }
+
+    /// A recipe that turns any three organic items into compost.
+    fn composting() -> RecipeData {
+        RecipeData::try_from(RawRecipeData {
+            inputs: RawRecipeInput::Category {
+                tag: "organic".to_string(),
+                count: 3,
+            },
+            catalysts: HashMap::new(),
+            byproducts: HashMap::new(),
+            output_one_of: Vec::new(),
+            outputs: HashMap::from_iter([("compost".to_string(), RawOutputCount::Count(1.))]),
+            craft_time: 1.,
+            work_units: None,
+            conditions: None,
+            energy: None,
+            allow_mass_gain: false,
+            allow_self_production: false,
+            requires: Vec::new(),
+            research_cost: HashMap::new(),
+            fluid_inputs: HashMap::new(),
+            fluid_outputs: HashMap::new(),
+            consumption: ConsumptionMode::AtStart,
+            overflow: OverflowPolicy::Block,
+            output_slots: HashMap::new(),
+            max_batch: 1,
+            next_recipe: None,
+            output_tier: None,
+            output_quality: QualityRule::default(),
+            category: RawRecipeData::DEFAULT_CATEGORY.to_string(),
+            active_phase: TimePhase::Always,
+            requires_environment: None,
+            enabled: true,
+        })
+        .unwrap()
+    }
+
+    /// Shorthand for an [`ItemCount`] of the item `name`.
+    fn item_count(name: &str, count: u32) -> ItemCount {
+        ItemCount::new(Id::from_name(name.to_string()), count)
+    }
+
+    /// A recipe that turns four leaves into compost over four seconds, consuming the leaves as specified.
+    fn mulching(consumption: ConsumptionMode) -> RecipeData {
+        RecipeData::try_from(RawRecipeData {
+            inputs: RawRecipeInput::single("leaf", 4),
+            catalysts: HashMap::new(),
+            craft_time: 4.,
+            consumption,
+            ..fermentation_raw()
+        })
+        .unwrap()
+    }
+
+    /// A recipe that turns either three leaves or one spore into compost.
+    fn sprouting() -> RecipeData {
+        RecipeData::try_from(RawRecipeData {
+            inputs: RawRecipeInput::AnyOf(vec![
+                RawInputAlternative {
+                    item: "leaf".to_string(),
+                    count: 3,
+                },
+                RawInputAlternative {
+                    item: "spores".to_string(),
+                    count: 1,
+                },
+            ]),
+            catalysts: HashMap::new(),
+            ..fermentation_raw()
+        })
+        .unwrap()
+    }
+
+    /// An input inventory for [`sprouting`], holding the given number of leaves and spores.
+    fn sprouting_input(leaves: u32, spores: u32) -> InputInventory {
+        let item_manifest = item_manifest();
+        let mut input = sprouting().input_inventory(&item_manifest);
+        for item_count in [item_count("leaf", leaves), item_count("spores", spores)] {
+            if item_count.count > 0 {
+                input.fill_with_items(&item_count, &item_manifest).unwrap();
+            }
+        }
+        input
+    }
+
+    /// An input inventory for the `recipe`, holding the given number of leaves.
+    fn input_with_leaves(recipe: &RecipeData, leaves: u32) -> InputInventory {
+        let item_manifest = item_manifest();
+        let mut input = recipe.input_inventory(&item_manifest);
+        input
+            .fill_with_items(&item_count("leaf", leaves), &item_manifest)
+            .unwrap();
+        input
+    }
+
+    /// The number of leaves in the input inventory.
+    fn leaves(input: &InputInventory) -> u32 {
+        input
+            .inventory()
+            .item_count(Id::from_name("leaf".to_string()))
+    }
+
+    #[test]
+    fn inputs_are_consumed_at_the_start_by_default() {
+        let item_manifest = item_manifest();
+        let recipe = mulching(ConsumptionMode::default());
+        let mut input = input_with_leaves(&recipe, 4);
+
+        assert_eq!(input.start_crafting(&recipe, &item_manifest), Ok(()));
+        assert_eq!(leaves(&input), 0);
+        assert_eq!(input.finish_crafting(&recipe, &item_manifest), Ok(()));
+    }
+
+    #[test]
+    fn inputs_consumed_at_the_end_must_be_present_from_the_start() {
+        let item_manifest = item_manifest();
+        let recipe = mulching(ConsumptionMode::AtEnd);
+
+        let mut too_few = input_with_leaves(&recipe, 3);
+        assert_eq!(
+            too_few.start_crafting(&recipe, &item_manifest),
+            Err(ConsumeInputError::NotEnoughItems)
+        );
+
+        let mut input = input_with_leaves(&recipe, 4);
+        assert_eq!(input.start_crafting(&recipe, &item_manifest), Ok(()));
+        assert_eq!(leaves(&input), 4);
+        assert_eq!(input.finish_crafting(&recipe, &item_manifest), Ok(()));
+        assert_eq!(leaves(&input), 0);
+    }
+
+    #[test]
+    fn continuous_inputs_are_consumed_in_proportion_to_progress() {
+        let item_manifest = item_manifest();
+        let recipe = mulching(ConsumptionMode::Continuous);
+        let mut input = input_with_leaves(&recipe, 4);
+
+        assert_eq!(input.start_crafting(&recipe, &item_manifest), Ok(()));
+        assert_eq!(leaves(&input), 4);
+
+        // Every started second of crafting costs one leaf
+        let steps = [(0., 0.5, 3), (0.5, 1., 3), (1., 2.5, 1), (2.5, 4., 0)];
+        for (from, to, remaining) in steps {
+            let result = input.continue_crafting(
+                &recipe,
+                Duration::from_secs_f32(from),
+                Duration::from_secs_f32(to),
+                &item_manifest,
+            );
+            assert_eq!(result, Ok(()));
+            assert_eq!(leaves(&input), remaining);
+        }
+
+        assert_eq!(input.finish_crafting(&recipe, &item_manifest), Ok(()));
+    }
+
+    #[test]
+    fn continuous_crafting_stalls_when_inputs_run_out() {
+        let item_manifest = item_manifest();
+        let recipe = mulching(ConsumptionMode::Continuous);
+        let mut input = input_with_leaves(&recipe, 2);
+        let second = |seconds| Duration::from_secs(seconds);
+
+        assert_eq!(
+            input.continue_crafting(&recipe, second(0), second(2), &item_manifest),
+            Ok(())
+        );
+        assert_eq!(leaves(&input), 0);
+
+        // Halfway through, the leaves have run out
+        assert_eq!(
+            input.continue_crafting(&recipe, second(2), second(3), &item_manifest),
+            Err(ConsumeInputError::NotEnoughItems)
+        );
+
+        // Once more leaves arrive, crafting can pick up where it stopped
+        input
+            .fill_with_items(&item_count("leaf", 2), &item_manifest)
+            .unwrap();
+        assert_eq!(
+            input.continue_crafting(&recipe, second(2), second(4), &item_manifest),
+            Ok(())
+        );
+        assert_eq!(leaves(&input), 0);
+    }
+
+    #[test]
+    fn storage_rejects_filtered_items() {
+        let item_manifest = item_manifest();
+        let mut storage = StorageInventory::new(2, None);
+        storage.filter = ItemFilter::Allow(HashSet::from_iter([Id::from_name("leaf".to_string())]));
+
+        assert!(storage.currently_accepts(Id::from_name("leaf".to_string()), &item_manifest));
+        assert!(!storage.currently_accepts(Id::from_name("fungus".to_string()), &item_manifest));
+
+        assert_eq!(
+            storage.store_item_all_or_nothing(&item_count("fungus", 2), &item_manifest),
+            Err(AddOneItemError {
+                excess_count: item_count("fungus", 2)
+            })
+        );
+        assert!(storage.is_empty());
+        assert_eq!(
+            storage.store_item_all_or_nothing(&item_count("leaf", 2), &item_manifest),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn recipe_with_unmet_catalyst_cannot_start() {
+        let item_manifest = item_manifest();
+        let recipe = fermentation();
+        let mut input = recipe.input_inventory(&item_manifest);
+        input
+            .fill_with_items(&item_count("leaf", 1), &item_manifest)
+            .unwrap();
+
+        assert_eq!(
+            input.start_crafting(&recipe, &item_manifest),
+            Err(ConsumeInputError::MissingCatalysts)
+        );
+        // Nothing was consumed
+        assert!(input.inventory().has_count_of_item(&item_count("leaf", 1)));
+    }
+
+    #[test]
+    fn catalysts_remain_after_crafting() {
+        let item_manifest = item_manifest();
+        let recipe = fermentation();
+        let mut input = recipe.input_inventory(&item_manifest);
+        let mut output = recipe.output_inventory(&item_manifest);
+        input
+            .fill_with_items(&item_count("leaf", 1), &item_manifest)
+            .unwrap();
+        input
+            .fill_with_items(&item_count("fungus", 1), &item_manifest)
+            .unwrap();
+
+        assert_eq!(input.start_crafting(&recipe, &item_manifest), Ok(()));
+        assert_eq!(
+            output.craft(
+                &recipe,
+                &mut CraftingEfficiency::default(),
+                ItemSlot::FULL_QUALITY,
+                &item_manifest,
+                &mut rand::thread_rng()
+            ),
+            StoredOutputs::Stored
+        );
+
+        let leaf_id = Id::from_name("leaf".to_string());
+        assert_eq!(input.inventory().item_count(leaf_id), 0);
+        assert!(input.has_catalysts(&recipe.catalysts));
+        assert!(output.has_count_of_item(&item_count("compost", 1)));
+    }
+
+    #[test]
+    fn chained_recipes_hand_their_outputs_to_the_next_stage() {
+        let item_manifest = item_manifest();
+        let fermentation = RecipeData::try_from(RawRecipeData {
+            next_recipe: Some("sporulation".to_string()),
+            ..fermentation_raw()
+        })
+        .unwrap();
+        // Turns compost into spores, with the same fungus
+        let sporulation = RecipeData::try_from(RawRecipeData {
+            inputs: RawRecipeInput::single("compost", 1),
+            outputs: HashMap::from_iter([("spores".to_string(), RawOutputCount::Count(2.))]),
+            ..fermentation_raw()
+        })
+        .unwrap();
+        let rng = &mut GlobalRng::new(0);
+
+        let mut input = fermentation.input_inventory(&item_manifest);
+        let mut output = fermentation.output_inventory(&item_manifest);
+        input
+            .fill_with_items(&item_count("leaf", 1), &item_manifest)
+            .unwrap();
+        input
+            .fill_with_items(&item_count("fungus", 1), &item_manifest)
+            .unwrap();
+        assert_eq!(input.start_crafting(&fermentation, &item_manifest), Ok(()));
+        output.craft(
+            &fermentation,
+            &mut CraftingEfficiency::default(),
+            ItemSlot::FULL_QUALITY,
+            &item_manifest,
+            rng.get_mut(),
+        );
+
+        // The compost and the fungus both move on to the second stage
+        let mut input = input
+            .for_next_recipe(&output, &sporulation, &item_manifest)
+            .unwrap();
+        let mut output = sporulation.output_inventory(&item_manifest);
+        assert!(input
+            .inventory()
+            .has_count_of_item(&item_count("compost", 1)));
+        assert!(input.has_catalysts(&sporulation.catalysts));
+
+        assert_eq!(input.start_crafting(&sporulation, &item_manifest), Ok(()));
+        output.craft(
+            &sporulation,
+            &mut CraftingEfficiency::default(),
+            ItemSlot::FULL_QUALITY,
+            &item_manifest,
+            rng.get_mut(),
+        );
+
+        assert!(output.has_count_of_item(&item_count("spores", 2)));
+    }
+
+    #[test]
+    fn chains_do_not_continue_with_unaccepted_items() {
+        let item_manifest = item_manifest();
+        let recipe = fermentation();
+        let mut input = recipe.input_inventory(&item_manifest);
+        let mut output = recipe.output_inventory(&item_manifest);
+        input
+            .fill_with_items(&item_count("fungus", 1), &item_manifest)
+            .unwrap();
+        output
+            .try_add_item(&item_count("compost", 1), &item_manifest)
+            .unwrap();
+
+        // Composting accepts the fungus, but not the compost
+        assert_eq!(
+            input.for_next_recipe(&output, &composting(), &item_manifest),
+            None
+        );
+    }
+
+    /// Starts crafting the `recipe` from the `input` inventory, as the crafting system does.
+    fn start_cycle(
+        recipe: &RecipeData,
+        input: &mut InputInventory,
+        item_manifest: &ItemManifest,
+    ) -> CraftingCycle {
+        let mut cycle = CraftingCycle::new(Some(Id::from_name("previous".to_string())));
+        if recipe.consumption == ConsumptionMode::AtStart {
+            cycle.taken_at_start = input
+                .items_to_consume(&recipe.inputs, item_manifest)
+                .unwrap();
+        }
+        input.start_crafting(recipe, item_manifest).unwrap();
+        cycle
+    }
+
+    #[test]
+    fn swapping_recipes_refunds_inputs_taken_at_the_start() {
+        let item_manifest = item_manifest();
+        let recipe = mulching(ConsumptionMode::AtStart);
+        let mut input = recipe.input_inventory(&item_manifest);
+        let mut output = recipe.output_inventory(&item_manifest);
+        input
+            .fill_with_items(&item_count("leaf", 4), &item_manifest)
+            .unwrap();
+
+        let mut cycle = start_cycle(&recipe, &mut input, &item_manifest);
+        assert_eq!(cycle.taken_at_start, vec![item_count("leaf", 4)]);
+        assert!(!input
+            .inventory()
+            .contains(Id::from_name("leaf".to_string())));
+
+        let leftovers = cycle.abandon(
+            Some(&recipe),
+            Some(&composting()),
+            &mut input,
+            &mut output,
+            &item_manifest,
+        );
+
+        assert!(leftovers.is_empty());
+        assert!(cycle.taken_at_start.is_empty());
+        assert!(input.inventory().has_count_of_item(&item_count("leaf", 4)));
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn swapping_recipes_keeps_inputs_taken_at_the_end() {
+        let item_manifest = item_manifest();
+        let recipe = mulching(ConsumptionMode::AtEnd);
+        let mut input = recipe.input_inventory(&item_manifest);
+        let mut output = recipe.output_inventory(&item_manifest);
+        input
+            .fill_with_items(&item_count("leaf", 4), &item_manifest)
+            .unwrap();
+
+        let mut cycle = start_cycle(&recipe, &mut input, &item_manifest);
+        assert!(cycle.taken_at_start.is_empty());
+
+        let leftovers = cycle.abandon(
+            Some(&recipe),
+            Some(&composting()),
+            &mut input,
+            &mut output,
+            &item_manifest,
+        );
+
+        // The leaves were never taken, so they are neither lost nor refunded twice
+        assert!(leftovers.is_empty());
+        assert_eq!(
+            input
+                .inventory()
+                .item_count(Id::from_name("leaf".to_string())),
+            4
+        );
+    }
+
+    #[test]
+    fn swapping_recipes_forfeits_inputs_consumed_continuously() {
+        let item_manifest = item_manifest();
+        let recipe = mulching(ConsumptionMode::Continuous);
+        let mut input = recipe.input_inventory(&item_manifest);
+        let mut output = recipe.output_inventory(&item_manifest);
+        input
+            .fill_with_items(&item_count("leaf", 4), &item_manifest)
+            .unwrap();
+
+        let mut cycle = start_cycle(&recipe, &mut input, &item_manifest);
+        // Half of the craft time uses up half of the leaves
+        input
+            .continue_crafting(
+                &recipe,
+                Duration::ZERO,
+                Duration::from_secs(2),
+                &item_manifest,
+            )
+            .unwrap();
+
+        let leftovers = cycle.abandon(
+            Some(&recipe),
+            Some(&composting()),
+            &mut input,
+            &mut output,
+            &item_manifest,
+        );
+
+        assert!(leftovers.is_empty());
+        assert_eq!(
+            input
+                .inventory()
+                .item_count(Id::from_name("leaf".to_string())),
+            2
+        );
+    }
+
+    #[test]
+    fn items_that_the_new_recipe_cannot_hold_are_returned() {
+        let item_manifest = item_manifest();
+        let recipe = fermentation();
+        let mut input = recipe.input_inventory(&item_manifest);
+        let mut output = recipe.output_inventory(&item_manifest);
+        input
+            .fill_with_items(&item_count("fungus", 1), &item_manifest)
+            .unwrap();
+        output
+            .try_add_item(&item_count("compost", 2), &item_manifest)
+            .unwrap();
+
+        let leftovers = CraftingCycle::default().abandon(
+            Some(&recipe),
+            Some(&sprouting()),
+            &mut input,
+            &mut output,
+            &item_manifest,
+        );
+
+        // Sprouting makes compost, but has no use for fungi
+        assert_eq!(leftovers, vec![item_count("fungus", 1)]);
+        assert!(output.has_count_of_item(&item_count("compost", 2)));
+    }
+
+    #[test]
+    fn output_quality_follows_the_inputs() {
+        let item_manifest = item_manifest();
+        let recipe = RecipeData::try_from(RawRecipeData {
+            output_quality: QualityRule::Average,
+            ..fermentation_raw()
+        })
+        .unwrap();
+        let mut input = recipe.input_inventory(&item_manifest);
+        let mut output = recipe.output_inventory(&item_manifest);
+        input
+            .fill_with_items(&item_count("leaf", 1), &item_manifest)
+            .unwrap();
+        input
+            .fill_with_items(&item_count("fungus", 1), &item_manifest)
+            .unwrap();
+
+        // Age the leaf halfway to decaying, leaving the fungus fresh
+        let leaf_id = Id::from_name("leaf".to_string());
+        for slot in input.inventory_mut().iter_mut() {
+            if slot.is_for_item(leaf_id) {
+                slot.advance_age(Duration::from_secs(1), Duration::from_secs(2));
+            }
+        }
+
+        // The fungus is only a catalyst, so it does not improve the outputs
+        let quality = input.output_quality(&recipe);
+        assert_eq!(quality, 0.5);
+
+        assert_eq!(input.start_crafting(&recipe, &item_manifest), Ok(()));
+        output.craft(
+            &recipe,
+            &mut CraftingEfficiency::default(),
+            quality,
+            &item_manifest,
+            &mut rand::thread_rng(),
+        );
+        let compost_slot = output.iter().find(|slot| !slot.is_empty()).unwrap();
+        assert_eq!(compost_slot.quality(), 0.5);
+
+        // Fresh compost is mixed into the stack
+        output.craft(
+            &recipe,
+            &mut CraftingEfficiency::default(),
+            ItemSlot::FULL_QUALITY,
+            &item_manifest,
+            &mut rand::thread_rng(),
+        );
+        let compost_slot = output.iter().find(|slot| !slot.is_empty()).unwrap();
+        assert_eq!(compost_slot.quality(), 0.75);
+    }
+
+    #[test]
+    fn byproducts_are_produced_alongside_outputs() {
+        let item_manifest = item_manifest();
+        let mut recipe = fermentation();
+        recipe.byproducts = vec![item_count("spores", 2)];
+        let mut output = recipe.output_inventory(&item_manifest);
+
+        assert_eq!(
+            output.craft(
+                &recipe,
+                &mut CraftingEfficiency::default(),
+                ItemSlot::FULL_QUALITY,
+                &item_manifest,
+                &mut rand::thread_rng()
+            ),
+            StoredOutputs::Stored
+        );
+
+        assert!(output.has_count_of_item(&item_count("compost", 1)));
+        assert!(output.has_count_of_item(&item_count("spores", 2)));
+    }
+
+    #[test]
+    fn the_only_available_alternative_is_used() {
+        let item_manifest = item_manifest();
+        let recipe = sprouting();
+        let mut input = sprouting_input(2, 1);
+
+        assert!(recipe.can_craft_with(input.inventory(), &item_manifest));
+        assert_eq!(input.start_crafting(&recipe, &item_manifest), Ok(()));
+        assert_eq!(leaves(&input), 2);
+        assert_eq!(
+            input
+                .inventory()
+                .item_count(Id::from_name("spores".to_string())),
+            0
+        );
+    }
+
+    #[test]
+    fn the_first_listed_alternative_is_preferred() {
+        let item_manifest = item_manifest();
+        let mut input = sprouting_input(3, 1);
+
+        assert_eq!(input.start_crafting(&sprouting(), &item_manifest), Ok(()));
+        assert_eq!(leaves(&input), 0);
+        assert_eq!(
+            input
+                .inventory()
+                .item_count(Id::from_name("spores".to_string())),
+            1
+        );
+    }
+
+    #[test]
+    fn crafting_is_blocked_without_any_alternative() {
+        let item_manifest = item_manifest();
+        let recipe = sprouting();
+        let mut input = sprouting_input(2, 0);
+
+        assert!(!recipe.can_craft_with(input.inventory(), &item_manifest));
+        assert_eq!(
+            input.start_crafting(&recipe, &item_manifest),
+            Err(ConsumeInputError::NotEnoughItems)
+        );
+        assert_eq!(leaves(&input), 2);
+    }
+
+    #[test]
+    fn category_inputs_accept_only_items_in_the_category() {
+        let item_manifest = item_manifest();
+        let input = composting().input_inventory(&item_manifest);
+
+        assert!(input.currently_accepts(Id::from_name("leaf".to_string()), &item_manifest));
+        assert!(input.currently_accepts(Id::from_name("fungus".to_string()), &item_manifest));
+        assert!(!input.currently_accepts(Id::from_name("spores".to_string()), &item_manifest));
+    }
+
+    #[test]
+    fn category_inputs_consume_the_lowest_id_first() {
+        let item_manifest = item_manifest();
+        let recipe = composting();
+        let mut input = recipe.input_inventory(&item_manifest);
+        input
+            .fill_with_items(&item_count("leaf", 2), &item_manifest)
+            .unwrap();
+        input
+            .fill_with_items(&item_count("fungus", 2), &item_manifest)
+            .unwrap();
+
+        assert_eq!(input.start_crafting(&recipe, &item_manifest), Ok(()));
+
+        let leaf_id = Id::from_name("leaf".to_string());
+        let fungus_id = Id::from_name("fungus".to_string());
+        let (lowest_id, highest_id) = if leaf_id < fungus_id {
+            (leaf_id, fungus_id)
+        } else {
+            (fungus_id, leaf_id)
+        };
+        assert_eq!(input.inventory().item_count(lowest_id), 0);
+        assert_eq!(input.inventory().item_count(highest_id), 1);
+    }
+
+    #[test]
+    fn category_inputs_need_enough_matching_items() {
+        let item_manifest = item_manifest();
+        let recipe = composting();
+        let mut input = recipe.input_inventory(&item_manifest);
+        input
+            .fill_with_items(&item_count("leaf", 2), &item_manifest)
+            .unwrap();
+
+        assert_eq!(
+            input.start_crafting(&recipe, &item_manifest),
+            Err(ConsumeInputError::NotEnoughItems)
+        );
+        // Nothing was consumed
+        assert!(input.inventory().has_count_of_item(&item_count("leaf", 2)));
+    }
+
+    #[test]
+    fn one_weighted_output_is_picked_alongside_fixed_outputs() {
+        let item_manifest = item_manifest();
+        let mut recipe = fermentation();
+        recipe.output_one_of = vec![
+            WeightedOutput {
+                item_count: item_count("spores", 1),
+                weight: 1.,
+            },
+            WeightedOutput {
+                item_count: item_count("fungus", 1),
+                weight: 1.,
+            },
+        ];
+        let mut output = recipe.output_inventory(&item_manifest);
+
+        assert_eq!(
+            output.craft(
+                &recipe,
+                &mut CraftingEfficiency::default(),
+                ItemSlot::FULL_QUALITY,
+                &item_manifest,
+                &mut rand::thread_rng()
+            ),
+            StoredOutputs::Stored
+        );
+
+        assert!(output.has_count_of_item(&item_count("compost", 1)));
+        let spores = output.item_count(Id::from_name("spores".to_string()));
+        let fungi = output.item_count(Id::from_name("fungus".to_string()));
+        assert_eq!(spores + fungi, 1);
+    }
+
+    /// The spores and fungi produced by crafting a random fermentation recipe `completions` times.
+    fn random_yields(rng: &mut GlobalRng, completions: usize) -> Vec<(u32, u32)> {
+        let item_manifest = item_manifest();
+        let mut recipe = fermentation();
+        recipe.probabilistic_outputs = vec![ProbabilisticOutput {
+            item_count: item_count("spores", 3),
+            chance: 0.5,
+        }];
+        recipe.output_one_of = vec![
+            WeightedOutput {
+                item_count: item_count("spores", 1),
+                weight: 1.,
+            },
+            WeightedOutput {
+                item_count: item_count("fungus", 1),
+                weight: 2.,
+            },
+        ];
+
+        (0..completions)
+            .map(|_| {
+                let mut output = recipe.output_inventory(&item_manifest);
+                assert_eq!(
+                    output.craft(
+                        &recipe,
+                        &mut CraftingEfficiency::default(),
+                        ItemSlot::FULL_QUALITY,
+                        &item_manifest,
+                        rng.get_mut()
+                    ),
+                    StoredOutputs::Stored
+                );
+
+                (
+                    output.item_count(Id::from_name("spores".to_string())),
+                    output.item_count(Id::from_name("fungus".to_string())),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn equal_seeds_produce_identical_yields() {
+        let first_run = random_yields(&mut GlobalRng::new(7), 100);
+        let second_run = random_yields(&mut GlobalRng::new(7), 100);
+
+        assert_eq!(first_run, second_run);
+    }
+
+    #[test]
+    fn completions_match_the_total_time_regardless_of_frame_deltas() {
+        let craft_time = Duration::from_millis(1500);
+        // Uneven frame times, with a single huge hitch in the middle
+        let deltas = [
+            Duration::from_millis(16),
+            Duration::from_millis(33),
+            Duration::from_millis(7),
+            Duration::from_secs(47),
+            Duration::from_millis(250),
+            Duration::from_micros(16_667),
+        ];
+
+        let mut crafting_progress = CraftingProgress::default();
+        let mut completions = 0;
+        let mut total_time = Duration::ZERO;
+        for _ in 0..100 {
+            for delta in deltas {
+                completions += crafting_progress.advance(delta, craft_time);
+                total_time += delta;
+            }
+        }
+
+        let expected = total_time.as_nanos() / craft_time.as_nanos();
+        assert_eq!(u128::from(completions), expected);
+        assert_eq!(
+            crafting_progress.elapsed(),
+            total_time - craft_time * completions
+        );
+    }
+
+    #[test]
+    fn overflow_carries_into_the_next_cycle() {
+        let craft_time = Duration::from_secs(2);
+        let mut crafting_progress = CraftingProgress::new(Duration::from_secs(5));
+
+        assert!(crafting_progress.try_complete(craft_time));
+        assert!(crafting_progress.try_complete(craft_time));
+        assert!(!crafting_progress.try_complete(craft_time));
+        assert_eq!(crafting_progress.elapsed(), Duration::from_secs(1));
+    }
+
+    /// The [`fermentation`] recipe with the given overflow policy, and an output inventory for it that is already full.
+    fn full_fermentation(overflow: OverflowPolicy) -> (RecipeData, OutputInventory) {
+        let item_manifest = item_manifest();
+        let recipe = RecipeData::try_from(RawRecipeData {
+            overflow,
+            ..fermentation_raw()
+        })
+        .unwrap();
+
+        let mut output = recipe.output_inventory(&item_manifest);
+        output
+            .add_item_all_or_nothing(&item_count("compost", 10), &item_manifest)
+            .unwrap();
+
+        (recipe, output)
+    }
+
+    /// The amount of compost in the output inventory.
+    fn compost(output: &OutputInventory) -> u32 {
+        output.item_count(Id::from_name("compost".to_string()))
+    }
+
+    #[test]
+    fn blocked_outputs_are_held_until_there_is_room() {
+        let item_manifest = item_manifest();
+        let (recipe, mut output) = full_fermentation(OverflowPolicy::Block);
+        let mut rng = GlobalRng::new(0);
+
+        let pending = match output.craft(
+            &recipe,
+            &mut CraftingEfficiency::default(),
+            ItemSlot::FULL_QUALITY,
+            &item_manifest,
+            rng.get_mut(),
+        ) {
+            StoredOutputs::Blocked(pending) => pending,
+            stored => panic!("Expected the outputs to be blocked, got {stored:?}"),
+        };
+        assert_eq!(pending, vec![item_count("compost", 1)]);
+        assert_eq!(compost(&output), 10);
+
+        // Still no room, so the outputs keep waiting
+        assert_eq!(
+            output.store(
+                pending.clone(),
+                &HashMap::new(),
+                OverflowPolicy::Block,
+                ItemSlot::FULL_QUALITY,
+                &item_manifest
+            ),
+            StoredOutputs::Blocked(pending.clone())
+        );
+
+        output.try_remove_item(&item_count("compost", 1)).unwrap();
+        assert_eq!(
+            output.store(
+                pending,
+                &HashMap::new(),
+                OverflowPolicy::Block,
+                ItemSlot::FULL_QUALITY,
+                &item_manifest
+            ),
+            StoredOutputs::Stored
+        );
+        assert_eq!(compost(&output), 10);
+    }
+
+    #[test]
+    fn blocked_outputs_are_not_partially_stored() {
+        let item_manifest = item_manifest();
+        let (_, mut output) = full_fermentation(OverflowPolicy::Block);
+        output.try_remove_item(&item_count("compost", 1)).unwrap();
+
+        assert_eq!(
+            output.store(
+                vec![item_count("compost", 1), item_count("compost", 1)],
+                &HashMap::new(),
+                OverflowPolicy::Block,
+                ItemSlot::FULL_QUALITY,
+                &item_manifest
+            ),
+            StoredOutputs::Blocked(vec![item_count("compost", 2)])
+        );
+        assert_eq!(compost(&output), 9);
+    }
+
+    #[test]
+    fn discarded_outputs_are_returned_to_be_dropped() {
+        let item_manifest = item_manifest();
+        let (recipe, mut output) = full_fermentation(OverflowPolicy::Discard);
+        let mut rng = GlobalRng::new(0);
+
+        assert_eq!(
+            output.craft(
+                &recipe,
+                &mut CraftingEfficiency::default(),
+                ItemSlot::FULL_QUALITY,
+                &item_manifest,
+                rng.get_mut()
+            ),
+            StoredOutputs::Discarded(vec![item_count("compost", 1)])
+        );
+        assert_eq!(compost(&output), 10);
+    }
+
+    #[test]
+    fn voided_outputs_are_lost() {
+        let item_manifest = item_manifest();
+        let (recipe, mut output) = full_fermentation(OverflowPolicy::Void);
+        let mut rng = GlobalRng::new(0);
+
+        assert_eq!(
+            output.craft(
+                &recipe,
+                &mut CraftingEfficiency::default(),
+                ItemSlot::FULL_QUALITY,
+                &item_manifest,
+                rng.get_mut()
+            ),
+            StoredOutputs::Voided(vec![item_count("compost", 1)])
+        );
+        assert_eq!(compost(&output), 10);
+    }
+
+    #[test]
+    fn outputs_that_fit_are_stored_under_any_policy() {
+        let item_manifest = item_manifest();
+        for overflow in [
+            OverflowPolicy::Block,
+            OverflowPolicy::Discard,
+            OverflowPolicy::Void,
+        ] {
+            let (recipe, mut output) = full_fermentation(overflow);
+            output.try_remove_item(&item_count("compost", 1)).unwrap();
+            let mut rng = GlobalRng::new(0);
+
+            assert_eq!(
+                output.craft(
+                    &recipe,
+                    &mut CraftingEfficiency::default(),
+                    ItemSlot::FULL_QUALITY,
+                    &item_manifest,
+                    rng.get_mut()
+                ),
+                StoredOutputs::Stored
+            );
+            assert_eq!(compost(&output), 10);
+        }
+    }
+
+    /// The [`fermentation`] recipe with the given overflow policy, which also produces two spores as waste.
+    fn fermentation_with_waste(overflow: OverflowPolicy) -> RecipeData {
+        RecipeData::try_from(RawRecipeData {
+            byproducts: HashMap::from_iter([("spores".to_string(), 2)]),
+            output_slots: HashMap::from_iter([("spores".to_string(), OutputSlot::Waste)]),
+            overflow,
+            ..fermentation_raw()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn products_and_waste_are_stored_in_separate_slots() {
+        let item_manifest = item_manifest();
+        let recipe = fermentation_with_waste(OverflowPolicy::Block);
+        let mut output = recipe.output_inventory(&item_manifest);
+        let mut rng = GlobalRng::new(0);
+        let spores = Id::from_name("spores".to_string());
+
+        assert_eq!(
+            output.craft(
+                &recipe,
+                &mut CraftingEfficiency::default(),
+                ItemSlot::FULL_QUALITY,
+                &item_manifest,
+                rng.get_mut()
+            ),
+            StoredOutputs::Stored
+        );
+        assert_eq!(compost(&output), 1);
+        assert_eq!(output.partition(OutputSlot::Product).item_count(spores), 0);
+        assert_eq!(output.partition(OutputSlot::Waste).item_count(spores), 2);
+
+        // The waste can be collected like any other output
+        assert!(output.contains_kind(ItemKind::Single(spores), &item_manifest));
+        output
+            .remove_item_all_or_nothing(&item_count("spores", 2))
+            .unwrap();
+        assert_eq!(output.partition(OutputSlot::Waste).item_count(spores), 0);
+    }
+
+    #[test]
+    fn full_waste_slots_are_handled_by_the_overflow_policy() {
+        let item_manifest = item_manifest();
+        let mut rng = GlobalRng::new(0);
+
+        for (overflow, expected) in [
+            (
+                OverflowPolicy::Block,
+                StoredOutputs::Blocked(vec![item_count("compost", 1), item_count("spores", 2)]),
+            ),
+            (
+                OverflowPolicy::Discard,
+                StoredOutputs::Discarded(vec![item_count("spores", 2)]),
+            ),
+            (
+                OverflowPolicy::Void,
+                StoredOutputs::Voided(vec![item_count("spores", 2)]),
+            ),
+        ] {
+            let recipe = fermentation_with_waste(overflow);
+            let mut output = recipe.output_inventory(&item_manifest);
+            output
+                .partition_mut(OutputSlot::Waste)
+                .add_item_all_or_nothing(&item_count("spores", 10), &item_manifest)
+                .unwrap();
+
+            let blocked = overflow == OverflowPolicy::Block;
+            assert_eq!(
+                output.craft(
+                    &recipe,
+                    &mut CraftingEfficiency::default(),
+                    ItemSlot::FULL_QUALITY,
+                    &item_manifest,
+                    rng.get_mut()
+                ),
+                expected
+            );
+            // The products have room of their own, so they are only held up by blocking
+            assert_eq!(compost(&output), if blocked { 0 } else { 1 });
+        }
+    }
+
+    #[test]
+    fn output_slots_without_a_partition_share_the_products() {
+        let item_manifest = item_manifest();
+        let mut output = OutputInventory::new(Inventory::new(2, None));
+        let output_slots =
+            HashMap::from_iter([(Id::from_name("spores".to_string()), OutputSlot::Waste)]);
+
+        assert_eq!(
+            output.store(
+                vec![item_count("spores", 2)],
+                &output_slots,
+                OverflowPolicy::Void,
+                ItemSlot::FULL_QUALITY,
+                &item_manifest
+            ),
+            StoredOutputs::Stored
+        );
+        assert_eq!(output.item_count(Id::from_name("spores".to_string())), 2);
+    }
+
+    /// A [`fermentation`] recipe that may craft up to `max_batch` units at once, and the inventories to craft it with.
+    ///
+    /// The input inventory holds the fungus and the given number of `leaves`,
+    /// while the output inventory already holds the given amount of `compost`.
+    fn batched_fermentation(
+        max_batch: u32,
+        leaves: u32,
+        compost: u32,
+    ) -> (RecipeData, InputInventory, OutputInventory) {
+        let item_manifest = item_manifest();
+        let recipe = RecipeData::try_from(RawRecipeData {
+            max_batch,
+            ..fermentation_raw()
+        })
+        .unwrap();
+
+        let mut input = recipe.input_inventory(&item_manifest);
+        for item_count in [item_count("fungus", 1), item_count("leaf", leaves)] {
+            input.fill_with_items(&item_count, &item_manifest).unwrap();
+        }
+        let mut output = recipe.output_inventory(&item_manifest);
+        output
+            .try_add_item(&item_count("compost", compost), &item_manifest)
+            .unwrap();
+
+        (recipe, input, output)
+    }
+
+    #[test]
+    fn full_batches_multiply_the_inputs_and_outputs() {
+        let item_manifest = item_manifest();
+        let (recipe, mut input, mut output) = batched_fermentation(3, 5, 0);
+
+        let batch = recipe.batch_size(&input, &output, &item_manifest);
+        assert_eq!(batch, 3);
+        assert_eq!(input.start_batch(&recipe, batch, &item_manifest), Ok(()));
+        assert_eq!(leaves(&input), 2);
+        // The catalyst is shared by the whole batch
+        assert!(input
+            .inventory()
+            .has_count_of_item(&item_count("fungus", 1)));
+
+        assert_eq!(
+            output.craft_batch(
+                &recipe,
+                batch,
+                &mut CraftingEfficiency::default(),
+                ItemSlot::FULL_QUALITY,
+                &item_manifest,
+                &mut rand::thread_rng()
+            ),
+            StoredOutputs::Stored
+        );
+        assert_eq!(output.item_count(Id::from_name("compost".to_string())), 3);
+    }
+
+    #[test]
+    fn partial_batches_are_limited_by_inputs_and_output_space() {
+        let item_manifest = item_manifest();
+
+        // Only two leaves are available
+        let (recipe, input, output) = batched_fermentation(3, 2, 0);
+        assert_eq!(recipe.batch_size(&input, &output, &item_manifest), 2);
+
+        // There is only room for two more compost
+        let (recipe, input, output) = batched_fermentation(3, 5, 8);
+        assert_eq!(recipe.batch_size(&input, &output, &item_manifest), 2);
+
+        // Large batch limits are bounded by the inputs before the outputs are checked
+        let (recipe, input, output) = batched_fermentation(RawRecipeData::MAX_BATCH, 5, 0);
+        assert_eq!(recipe.batch_size(&input, &output, &item_manifest), 5);
+
+        // Without room for even two, a single unit is crafted as usual
+        let (recipe, input, output) = batched_fermentation(3, 5, 10);
+        assert_eq!(recipe.batch_size(&input, &output, &item_manifest), 1);
+    }
+
+    #[test]
+    fn recipes_craft_single_units_unless_batches_are_allowed() {
+        let item_manifest = item_manifest();
+
+        let (recipe, input, output) = batched_fermentation(1, 5, 0);
+        assert_eq!(recipe.batch_size(&input, &output, &item_manifest), 1);
+
+        // Inputs that are not paid for up front are never batched
+        let (recipe, input, output) = batched_fermentation(3, 5, 0);
+        let recipe = RecipeData {
+            consumption: ConsumptionMode::AtEnd,
+            ..recipe
+        };
+        assert_eq!(recipe.batch_size(&input, &output, &item_manifest), 1);
     }
 }
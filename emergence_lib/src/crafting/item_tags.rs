@@ -2,6 +2,7 @@
 //!
 //! Items can belong to multiple tags, and correspond to fields on [`ItemData`](crate::items::item_manifest::ItemData).
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 
@@ -11,7 +12,9 @@ use crate::{
 };
 
 /// A category of items.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
 pub enum ItemTag {
     /// Items that can be composted.
     Compostable,
@@ -41,6 +44,13 @@ impl Display for ItemTag {
     }
 }
 
+/// The marker type for [`Id<ItemCategory>`](super::Id).
+///
+/// Unlike an [`ItemTag`], which is derived from the properties of an item,
+/// categories are free-form labels given to items in the item manifest, such as `"construction_material"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ItemCategory;
+
 /// An item or collection of items that shares a property.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize)]
 pub enum ItemKind {
@@ -1,38 +1,55 @@
 //! Crafting and recipes.
 
 use leafwing_abilities::prelude::Pool;
-use recipe::{RawRecipeManifest, RecipeManifest};
+use recipe::{RawRecipeManifest, Recipe, RecipeManifest};
 
 use crate::{
     asset_management::manifest::{plugin::ManifestPlugin, Id},
     construction::{demolition::MarkedForDemolition, ghosts::WorkplaceId},
+    environment::{EnvironmentManifest, EnvironmentSample},
     geometry::{MapGeometry, VoxelPos},
     items::{
         inventory::Inventory,
         item_manifest::{ItemManifest, RawItemManifest},
+        starter_manifest::RawStarterManifest,
+        ItemCount,
     },
     light::shade::ReceivedLight,
-    organisms::{energy::EnergyPool, lifecycle::Lifecycle, Organism},
+    litter::LitterCommandsExt,
+    organisms::{
+        energy::{Energy, EnergyLedger, EnergyPool},
+        lifecycle::Lifecycle,
+    },
     player_interaction::InteractionSystem,
     signals::{Emitter, SignalStrength, SignalType},
-    simulation::SimulationSet,
+    simulation::{rng::GlobalRng, time::InGameTime, SimulationSet},
     structures::structure_manifest::{Structure, StructureManifest},
 };
 
 use std::time::Duration;
 
-use bevy::{ecs::query::WorldQuery, prelude::*};
+use bevy::{ecs::query::WorldQuery, prelude::*, utils::HashMap};
 
 use self::{
-    inventories::{CraftingState, InputInventory, OutputInventory, StorageInventory},
+    discovery::{research_recipes, DiscoveredRecipes, ResearchRecipe},
+    efficiency::CraftingEfficiency,
+    inventories::{
+        CraftedQuality, CraftingCycle, CraftingProgress, CraftingState, InputInventory,
+        OutputInventory, StorageInventory, StoredOutputs,
+    },
     item_tags::{ItemKind, ItemTag},
-    recipe::{ActiveRecipe, RecipeInput},
+    recipe::{ActiveRecipe, ConsumptionMode, OverflowPolicy, RecipeData, RecipeInput, WorkKind},
+    speed::CraftingSpeed,
     workers::WorkersPresent,
 };
 
+pub mod discovery;
+pub mod efficiency;
 pub mod inventories;
 pub mod item_tags;
 pub mod recipe;
+pub mod speed;
+pub mod throughput;
 pub mod workers;
 
 /// Add crafting capabilities to structures.
@@ -42,6 +59,10 @@ impl Plugin for CraftingPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(ManifestPlugin::<RawItemManifest>::new())
             .add_plugin(ManifestPlugin::<RawRecipeManifest>::new())
+            .add_plugin(ManifestPlugin::<RawStarterManifest>::new())
+            .init_resource::<DiscoveredRecipes>()
+            .init_resource::<EnergyLedger>()
+            .add_event::<ResearchRecipe>()
             .add_systems(
                 (
                     progress_crafting,
@@ -51,7 +72,9 @@ impl Plugin for CraftingPlugin {
                         // This must run before zoning, to avoid wiping out the destruction signal
                         .before(InteractionSystem::ApplyZoning),
                     set_storage_emitter.before(InteractionSystem::ApplyZoning),
+                    decay_stored_items.before(clear_empty_storage_slots),
                     clear_empty_storage_slots,
+                    research_recipes,
                 )
                     .in_set(SimulationSet)
                     .in_schedule(CoreSchedule::FixedUpdate),
@@ -74,11 +97,26 @@ pub(crate) struct CraftingBundle {
     /// The current state for the crafting process.
     craft_state: CraftingState,
 
+    /// The time spent crafting, including any time left over from the previous cycle.
+    craft_progress: CraftingProgress,
+
     /// Emits signals, drawing units towards this structure to ensure crafting flows smoothly
     emitter: Emitter,
 
     /// The number of workers present / allowed at this structure
     workers_present: WorkersPresent,
+
+    /// How quickly this structure crafts, relative to the craft time of its recipes
+    crafting_speed: CraftingSpeed,
+
+    /// How much this structure produces, relative to the outputs of its recipes
+    crafting_efficiency: CraftingEfficiency,
+
+    /// The quality of the items produced by the current cycle
+    crafted_quality: CraftedQuality,
+
+    /// The recipe of the current cycle, to notice when the active recipe is swapped
+    crafting_cycle: CraftingCycle,
 }
 
 impl CraftingBundle {
@@ -90,31 +128,48 @@ impl CraftingBundle {
         item_manifest: &ItemManifest,
         structure_manifest: &StructureManifest,
     ) -> Self {
-        let max_workers = structure_manifest.get(structure_id).max_workers;
-
-        if let Some(recipe_id) = starting_recipe.0 {
-            let recipe = recipe_manifest.get(recipe_id);
+        let max_workers = structure_manifest.get_unchecked(structure_id).max_workers;
+
+        let starting_recipe =
+            starting_recipe
+                .0
+                .and_then(|recipe_id| match recipe_manifest.get(recipe_id) {
+                    Ok(recipe) => Some((recipe_id, recipe)),
+                    Err(error) => {
+                        error!("Cannot start crafting. {error}");
+                        None
+                    }
+                });
 
+        if let Some((recipe_id, recipe)) = starting_recipe {
             Self {
                 input_inventory: recipe.input_inventory(item_manifest),
                 output_inventory: recipe.output_inventory(item_manifest),
                 active_recipe: ActiveRecipe(Some(recipe_id)),
                 craft_state: CraftingState::NeedsInput,
+                craft_progress: CraftingProgress::default(),
                 emitter: Emitter::default(),
                 workers_present: WorkersPresent::new(max_workers),
+                crafting_speed: CraftingSpeed::default(),
+                crafting_efficiency: CraftingEfficiency::default(),
+                crafted_quality: CraftedQuality::default(),
+                crafting_cycle: CraftingCycle::new(Some(recipe_id)),
             }
         } else {
             Self {
                 input_inventory: InputInventory::Exact {
                     inventory: Inventory::new(0, None),
                 },
-                output_inventory: OutputInventory {
-                    inventory: Inventory::new(1, None),
-                },
+                output_inventory: OutputInventory::new(Inventory::new(1, None)),
                 active_recipe: ActiveRecipe(None),
                 craft_state: CraftingState::NeedsInput,
+                craft_progress: CraftingProgress::default(),
                 emitter: Emitter::default(),
                 workers_present: WorkersPresent::new(max_workers),
+                crafting_speed: CraftingSpeed::default(),
+                crafting_efficiency: CraftingEfficiency::default(),
+                crafted_quality: CraftedQuality::default(),
+                crafting_cycle: CraftingCycle::new(None),
             }
         }
     }
@@ -124,52 +179,145 @@ impl CraftingBundle {
 #[derive(WorldQuery)]
 #[world_query(mutable)]
 struct CraftingQuery {
-    /// The recipe of the crafter
-    active_recipe: &'static ActiveRecipe,
+    /// The recipe of the crafter, which changes when a chain of recipes moves on to its next stage
+    active_recipe: &'static mut ActiveRecipe,
     /// The status of crafting
     state: &'static mut CraftingState,
+    /// The time spent crafting
+    progress: &'static mut CraftingProgress,
     /// The inputs
     input: &'static mut InputInventory,
     /// The outputs
     output: &'static mut OutputInventory,
     /// The number of workers present
     workers_present: &'static WorkersPresent,
+    /// How quickly the crafter works
+    crafting_speed: &'static CraftingSpeed,
+    /// How much the crafter produces, including the fractional items carried over between crafts
+    crafting_efficiency: &'static mut CraftingEfficiency,
+    /// The quality of the items being crafted
+    crafted_quality: &'static mut CraftedQuality,
+    /// The recipe of the cycle being crafted, and the inputs it took
+    cycle: &'static mut CraftingCycle,
     /// The current position of the crafter
     voxel_pos: &'static VoxelPos,
-    /// Is the structure an organism?
-    maybe_organism: Option<&'static Organism>,
+    /// The energy available to pay for recipes, if the structure is alive.
+    maybe_energy_pool: Option<&'static EnergyPool>,
 }
 
 /// Progress the state of recipes that are being crafted.
 fn progress_crafting(
     time: Res<FixedTime>,
+    in_game_time: Res<InGameTime>,
     recipe_manifest: Res<RecipeManifest>,
     item_manifest: Res<ItemManifest>,
-    terrain_query: Query<&ReceivedLight>,
+    environment_manifest: Res<EnvironmentManifest>,
+    terrain_query: Query<(&ReceivedLight, &EnvironmentSample)>,
     mut crafting_query: Query<CraftingQuery>,
     map_geometry: Res<MapGeometry>,
+    mut rng: ResMut<GlobalRng>,
+    mut commands: Commands,
 ) {
-    let rng = &mut rand::thread_rng();
+    // Draw from the seeded source, so that the same world produces the same yields
+    let rng = rng.get_mut();
+    let time_of_day = in_game_time.time_of_day();
 
     for mut crafter in crafting_query.iter_mut() {
+        let recipe = match crafter.active_recipe.recipe_id() {
+            Some(recipe_id) => match recipe_manifest.get(*recipe_id) {
+                Ok(recipe) => Some(recipe),
+                Err(error) => {
+                    // Only report the problem once, when the crafter stops
+                    if *crafter.state != CraftingState::NoRecipe {
+                        error!("Cannot craft the active recipe. {error}");
+                    }
+                    None
+                }
+            },
+            None => None,
+        };
+
+        // Swapping the recipe abandons the current cycle, but a finished cycle produces its outputs first
+        if crafter.cycle.recipe_id != *crafter.active_recipe.recipe_id()
+            && *crafter.state != CraftingState::RecipeComplete
+        {
+            switch_recipe(
+                &mut crafter,
+                recipe,
+                &recipe_manifest,
+                &item_manifest,
+                &mut commands,
+            );
+        }
+
+        // Time spent on a recipe does not count towards any other recipe
+        if recipe.is_none() {
+            crafter.progress.reset();
+        }
+
         *crafter.state = match *crafter.state {
-            CraftingState::NoRecipe => match crafter.active_recipe.recipe_id() {
+            CraftingState::NoRecipe => match recipe {
                 Some(_) => CraftingState::NeedsInput,
                 None => CraftingState::NoRecipe,
             },
+            // Recipes outside of their active phase do not start, so their inputs are not consumed
+            ref state @ (CraftingState::NeedsInput | CraftingState::Overproduction)
+                if recipe.is_some_and(|recipe| !recipe.active_phase.is_active(time_of_day)) =>
+            {
+                state.clone()
+            }
             CraftingState::NeedsInput | CraftingState::Overproduction => {
-                if let Some(recipe_id) = crafter.active_recipe.recipe_id() {
-                    let recipe = recipe_manifest.get(*recipe_id);
-                    // Check if we have enough items, and if so, start crafting
-                    match crafter.input.consume_items(&recipe.inputs, &item_manifest) {
+                if let Some(recipe) = recipe {
+                    // The quality of the outputs depends on the inputs, which may be consumed right away
+                    let quality = crafter.input.output_quality(recipe);
+                    // Craft as many units at once as the inputs and the room for the outputs allow
+                    let batch_size =
+                        recipe.batch_size(&crafter.input, &crafter.output, &item_manifest);
+                    // Remember the inputs taken at the start, so that they can be refunded if the recipe is swapped
+                    let taken_at_start = match recipe.consumption {
+                        ConsumptionMode::AtStart => recipe
+                            .inputs
+                            .times(batch_size)
+                            .and_then(|inputs| {
+                                crafter.input.items_to_consume(&inputs, &item_manifest).ok()
+                            })
+                            .unwrap_or_default(),
+                        ConsumptionMode::AtEnd | ConsumptionMode::Continuous => Vec::new(),
+                    };
+
+                    // Check if we have enough items and catalysts, and if so, start crafting
+                    match crafter
+                        .input
+                        .start_batch(recipe, batch_size, &item_manifest)
+                    {
                         Ok(()) => {
+                            *crafter.crafted_quality = CraftedQuality(quality);
+                            crafter.cycle.taken_at_start = taken_at_start;
+                            crafter.cycle.batch_size = batch_size;
+
                             // If this is crafting with flexible inputs, clear the input slots
                             if matches!(recipe.inputs, RecipeInput::Flexible { .. }) {
                                 crafter.input.clear_empty_slots();
                             }
 
+                            // Continue with any time left over from the previous cycle
+                            let carried_over = crafter.progress.elapsed().min(recipe.craft_time);
+                            // Recipes that consume their inputs continuously must pay for that time too
+                            if crafter
+                                .input
+                                .continue_crafting(
+                                    recipe,
+                                    Duration::ZERO,
+                                    carried_over,
+                                    &item_manifest,
+                                )
+                                .is_err()
+                            {
+                                crafter.progress.reset();
+                            }
+
                             CraftingState::InProgress {
-                                progress: Duration::ZERO,
+                                progress: crafter.progress.elapsed().min(recipe.craft_time),
                                 required: recipe.craft_time,
                             }
                         }
@@ -180,31 +328,58 @@ fn progress_crafting(
                 }
             }
             CraftingState::InProgress { progress, required } => {
-                let mut updated_progress = progress;
-                if let Some(recipe_id) = crafter.active_recipe.recipe_id() {
-                    let recipe = recipe_manifest.get(*recipe_id);
+                if let Some(recipe) = recipe {
                     let terrain_entity = map_geometry.get_terrain(crafter.voxel_pos.hex).unwrap();
 
-                    let received_light = terrain_query.get(terrain_entity).unwrap();
+                    let (received_light, environment_sample) =
+                        terrain_query.get(terrain_entity).unwrap();
 
                     // Check if we can make progress
-                    if recipe.satisfied(crafter.workers_present.current(), received_light) {
-                        // Many hands make light work!
-                        if recipe.workers_required() > 0 {
-                            updated_progress += Duration::from_secs_f32(
-                                time.period.as_secs_f32()
-                                    * crafter.workers_present.effective_workers()
-                                    / recipe.workers_required() as f32,
-                            );
-                        } else {
-                            updated_progress += time.period;
+                    if recipe.satisfied(
+                        crafter.workers_present.current(),
+                        received_light,
+                        time_of_day,
+                    ) && recipe.environment_satisfied(environment_sample, &environment_manifest)
+                        && !crafter.crafting_speed.is_stalled()
+                    {
+                        // Finished cycles wait for energy without accumulating more time
+                        if crafter.progress.elapsed() < required {
+                            // Many hands make light work, up to the number of workers required unless the work is fixed
+                            let delta = crafter.crafting_speed.scale(time.period.mul_f32(
+                                recipe.work_rate(crafter.workers_present.effective_workers()),
+                            ));
+                            let reached = (crafter.progress.elapsed() + delta).min(required);
+
+                            // Without the inputs for this stretch, continuous recipes hold their progress
+                            if crafter
+                                .input
+                                .continue_crafting(recipe, progress, reached, &item_manifest)
+                                .is_ok()
+                            {
+                                crafter.progress.add(delta);
+                            }
                         }
 
-                        if updated_progress >= required {
-                            CraftingState::RecipeComplete
+                        if crafter.progress.elapsed() >= required {
+                            // Wait until the organism can pay for the recipe, rather than draining it below zero,
+                            // and until the inputs of recipes that consume them at the end are available
+                            if recipe.can_afford_energy(crafter.maybe_energy_pool)
+                                && crafter
+                                    .input
+                                    .finish_crafting(recipe, &item_manifest)
+                                    .is_ok()
+                            {
+                                crafter.progress.try_complete(required);
+                                CraftingState::RecipeComplete
+                            } else {
+                                CraftingState::InProgress {
+                                    progress: required,
+                                    required,
+                                }
+                            }
                         } else {
                             CraftingState::InProgress {
-                                progress: updated_progress,
+                                progress: crafter.progress.elapsed(),
                                 required,
                             }
                         }
@@ -216,39 +391,156 @@ fn progress_crafting(
                 }
             }
             CraftingState::RecipeComplete => {
-                if let Some(recipe_id) = crafter.active_recipe.recipe_id() {
-                    let recipe = recipe_manifest.get(*recipe_id);
-                    // Actually produce the items
-                    match crafter.maybe_organism {
-                        Some(_) => {
-                            match crafter.output.craft(recipe, &item_manifest, rng) {
-                                Ok(_) => CraftingState::NeedsInput,
-                                // TODO: handle the waste products somehow
-                                Err(_) => CraftingState::Overproduction,
-                            }
-                        }
-                        None => match crafter.output.craft(recipe, &item_manifest, rng) {
-                            Ok(()) => CraftingState::NeedsInput,
-                            Err(_) => CraftingState::FullAndBlocked,
-                        },
+                if let Some(recipe) = recipe {
+                    // Actually produce the items, as many as the condition of the crafter allows
+                    let stored = crafter.output.craft_batch(
+                        recipe,
+                        crafter.cycle.batch_size,
+                        &mut crafter.crafting_efficiency,
+                        crafter.crafted_quality.0,
+                        &item_manifest,
+                        rng,
+                    );
+                    // The cycle is over, so there is nothing left to refund
+                    crafter.cycle.taken_at_start.clear();
+                    crafter.cycle.batch_size = 1;
+                    let state = state_after_storing(stored, *crafter.voxel_pos, &mut commands);
+
+                    // Multi-stage processing carries on with the items that were just produced
+                    if let (CraftingState::NeedsInput, Some(next_recipe_id)) =
+                        (&state, recipe.next_recipe)
+                    {
+                        start_next_recipe(
+                            &mut crafter,
+                            next_recipe_id,
+                            &recipe_manifest,
+                            &item_manifest,
+                        );
                     }
+
+                    state
                 } else {
                     CraftingState::NoRecipe
                 }
             }
-            CraftingState::FullAndBlocked => {
-                let mut item_slots = crafter.output.iter();
-                match item_slots.any(|slot| slot.is_full()) {
-                    true => CraftingState::FullAndBlocked,
-                    false => CraftingState::NeedsInput,
-                }
+            CraftingState::FullAndBlocked { ref pending } => {
+                // The outputs of the finished cycle are stored as soon as there is room for all of them
+                let no_output_slots = HashMap::new();
+                let stored = crafter.output.store(
+                    pending.clone(),
+                    recipe.map_or(&no_output_slots, |recipe| &recipe.output_slots),
+                    OverflowPolicy::Block,
+                    crafter.crafted_quality.0,
+                    &item_manifest,
+                );
+                state_after_storing(stored, *crafter.voxel_pos, &mut commands)
             }
         };
     }
 }
 
+/// Switches the `crafter` over to the recipe with the `next_recipe_id`, handing its items over as inputs.
+///
+/// The crafter keeps its current recipe if the next recipe does not accept all of the items,
+/// such as when the outputs did not match its inputs.
+fn start_next_recipe(
+    crafter: &mut CraftingQueryItem,
+    next_recipe_id: Id<Recipe>,
+    recipe_manifest: &RecipeManifest,
+    item_manifest: &ItemManifest,
+) {
+    let next = match recipe_manifest.get(next_recipe_id) {
+        Ok(next) => next,
+        Err(error) => {
+            error!("Cannot continue with the next recipe. {error}");
+            return;
+        }
+    };
+
+    if let Some(next_input) = crafter
+        .input
+        .for_next_recipe(&crafter.output, next, item_manifest)
+    {
+        *crafter.input = next_input;
+        *crafter.output = next.output_inventory(item_manifest);
+        *crafter.active_recipe = ActiveRecipe::new(next_recipe_id);
+        *crafter.cycle = CraftingCycle::new(Some(next_recipe_id));
+        // Time spent on a recipe does not count towards any other recipe
+        crafter.progress.reset();
+    }
+}
+
+/// Moves a `crafter` whose [`ActiveRecipe`] was changed over to its new `recipe`, abandoning the current cycle.
+///
+/// Accumulated progress never carries over to another recipe.
+/// See [`CraftingCycle::abandon`] for which inputs are refunded.
+/// Items that do not fit in the new inventories are dropped as litter, along with any outputs that were blocked.
+fn switch_recipe(
+    crafter: &mut CraftingQueryItem,
+    recipe: Option<&RecipeData>,
+    recipe_manifest: &RecipeManifest,
+    item_manifest: &ItemManifest,
+    commands: &mut Commands,
+) {
+    let previous = crafter
+        .cycle
+        .recipe_id
+        .and_then(|recipe_id| recipe_manifest.get(recipe_id).ok());
+
+    let mut leftovers = crafter.cycle.abandon(
+        previous,
+        recipe,
+        &mut crafter.input,
+        &mut crafter.output,
+        item_manifest,
+    );
+    if let CraftingState::FullAndBlocked { pending } = &*crafter.state {
+        leftovers.extend(pending.iter().cloned());
+    }
+    drop_as_litter(leftovers, *crafter.voxel_pos, commands);
+
+    *crafter.cycle = CraftingCycle::new(*crafter.active_recipe.recipe_id());
+    crafter.progress.reset();
+    *crafter.state = match recipe {
+        Some(_) => CraftingState::NeedsInput,
+        None => CraftingState::NoRecipe,
+    };
+}
+
+/// Drops the `items` as litter at the `voxel_pos`.
+fn drop_as_litter(items: Vec<ItemCount>, voxel_pos: VoxelPos, commands: &mut Commands) {
+    for item_count in items {
+        for _ in 0..item_count.count {
+            commands.spawn_litter(voxel_pos, item_count.item_id);
+        }
+    }
+}
+
+/// The state that crafting continues in, once the outputs of a recipe have been `stored`.
+///
+/// Discarded items are dropped as litter at the `voxel_pos` of the crafter.
+fn state_after_storing(
+    stored: StoredOutputs,
+    voxel_pos: VoxelPos,
+    commands: &mut Commands,
+) -> CraftingState {
+    match stored {
+        StoredOutputs::Stored => CraftingState::NeedsInput,
+        StoredOutputs::Blocked(pending) => CraftingState::FullAndBlocked { pending },
+        StoredOutputs::Discarded(excess) => {
+            drop_as_litter(excess, voxel_pos, commands);
+            CraftingState::Overproduction
+        }
+        StoredOutputs::Voided(_) => CraftingState::Overproduction,
+    }
+}
+
 /// Sessile organisms gain energy when they finish crafting recipes.
+///
+/// The energy of every completed recipe is also recorded in the [`EnergyLedger`].
 fn gain_energy_when_crafting_completes(
+    time: Res<FixedTime>,
+    mut energy_ledger: ResMut<EnergyLedger>,
     mut sessile_query: Query<(
         &mut EnergyPool,
         &mut Lifecycle,
@@ -261,15 +553,25 @@ fn gain_energy_when_crafting_completes(
     {
         if matches!(crafting_state, CraftingState::RecipeComplete) {
             if let Some(recipe_id) = active_recipe.recipe_id() {
-                let recipe = recipe_manifest.get(*recipe_id);
+                let Ok(recipe) = recipe_manifest.get(*recipe_id) else {
+                    continue;
+                };
+
                 if let Some(energy) = recipe.energy {
                     let proposed = energy_pool.current() + energy;
                     energy_pool.set_current(proposed);
-                    lifecycle.record_energy_gained(energy);
+
+                    // Energy spent on crafting does not count against the organism's growth
+                    if energy > Energy(0.) {
+                        lifecycle.record_energy_gained(energy);
+                    }
+                    energy_ledger.record(energy);
                 }
             }
         }
     }
+
+    energy_ledger.finish_tick(time.period);
 }
 
 /// Causes crafting structures to emit signals based on the items they have and need.
@@ -344,8 +646,11 @@ pub(crate) fn set_crafting_emitter(
         // Work signals
         if let CraftingState::InProgress { .. } = crafting_state {
             if let Some(recipe_id) = active_recipe.recipe_id() {
-                let recipe = recipe_manifest.get(*recipe_id);
-                if workers_present.needs_more() && recipe.needs_workers() {
+                let Ok(recipe) = recipe_manifest.get(*recipe_id) else {
+                    continue;
+                };
+
+                if workers_present.needs_more() && recipe.work_kind() == WorkKind::ManualWork {
                     let signal_strength = SignalStrength::new(100.);
                     emitter.signals.push((
                         SignalType::Work(WorkplaceId::structure(structure_id)),
@@ -370,7 +675,7 @@ pub(crate) fn set_storage_emitter(
             // Item-specific storage
             Some(item_id) => {
                 // If there's space, signal that
-                if storage_inventory.remaining_space_for_item(item_id, &item_manifest) > 0 {
+                if storage_inventory.currently_accepts(item_id, &item_manifest) {
                     let signal_type = SignalType::Stores(ItemKind::Single(item_id));
                     let signal_strength = SignalStrength::new(10.);
                     emitter.signals.push((signal_type, signal_strength));
@@ -387,8 +692,8 @@ pub(crate) fn set_storage_emitter(
             None => {
                 // You could put anything in here!
                 for item_id in item_manifest.variants() {
-                    // If there's space, signal that
-                    if storage_inventory.remaining_space_for_item(item_id, &item_manifest) > 0 {
+                    // If there's space, and the item is allowed, signal that
+                    if storage_inventory.currently_accepts(item_id, &item_manifest) {
                         let signal_type = SignalType::Stores(ItemKind::Single(item_id));
                         let signal_strength = SignalStrength::new(10.);
                         emitter.signals.push((signal_type, signal_strength));
@@ -406,6 +711,17 @@ pub(crate) fn set_storage_emitter(
     }
 }
 
+/// Ages the items in storage, turning them into their decay products over time.
+fn decay_stored_items(
+    time: Res<FixedTime>,
+    item_manifest: Res<ItemManifest>,
+    mut query: Query<&mut StorageInventory>,
+) {
+    for mut storage_inventory in query.iter_mut() {
+        storage_inventory.decay(time.period, &item_manifest);
+    }
+}
+
 /// The space in storage inventories is not reserved
 fn clear_empty_storage_slots(mut query: Query<&mut StorageInventory>) {
     for mut storage_inventory in query.iter_mut() {
@@ -1,23 +1,49 @@
 //! Instructions to craft items.
 
-use crate::asset_management::manifest::loader::IsRawManifest;
-use crate::asset_management::manifest::{Id, Manifest};
+use crate as emergence_lib;
+use crate::asset_management::manifest::errors::{
+    DanglingReference, EnergyImbalance, InvalidRecipe, ManifestError, OutOfRangeValue,
+    RecipeProblem, TierMismatch,
+};
+use crate::asset_management::manifest::{
+    diff::{same_elements, Diffable},
+    duration::{deserialize_seconds, RawDuration},
+    export::{NameLookup, ToRaw},
+    loader::IsRawManifest,
+    Id, Manifest,
+};
+use crate::environment::{
+    EnvironmentManifest, EnvironmentRequirement, EnvironmentSample, RawEnvironmentRequirement,
+};
 use crate::items::item_manifest::{Item, ItemManifest};
-use crate::items::{inventory::Inventory, ItemCount};
+use crate::items::{
+    fluid::{FluidAmount, FluidCount},
+    inventory::Inventory,
+    slot::ItemSlot,
+    ItemCount,
+};
 use crate::light::shade::ReceivedLight;
 use crate::light::Illuminance;
+use crate::simulation::time::TimeOfDay;
 use crate::{
-    crafting::inventories::{InputInventory, OutputInventory},
-    organisms::energy::Energy,
+    crafting::inventories::{InputInventory, OutputInventory, StoredOutputs},
+    organisms::energy::{Energy, EnergyPool},
 };
 use bevy::prelude::*;
 use bevy::reflect::{FromReflect, Reflect, TypeUuid};
-use bevy::utils::HashMap;
+use bevy::utils::{HashMap, HashSet};
+use emergence_macros::RawManifest;
 use itertools::Itertools;
+use leafwing_abilities::prelude::Pool;
+use rand::{
+    distributions::{Distribution, Uniform, WeightedIndex},
+    Rng,
+};
+use schemars::{schema::RootSchema, schema_for, JsonSchema};
 use serde::{Deserialize, Serialize};
 use std::{fmt::Display, time::Duration};
 
-use super::item_tags::ItemTag;
+use super::item_tags::{ItemCategory, ItemTag};
 
 /// The marker type for [`Id<Recipe>`](super::Id).
 #[derive(Reflect, FromReflect, Clone, Copy, PartialEq, Eq)]
@@ -32,19 +58,452 @@ pub struct RecipeData {
     /// The inputs needed to craft the recipe.
     pub inputs: RecipeInput,
 
+    /// The items that must be present to craft the recipe, but are not consumed by it.
+    ///
+    /// These are stored in the input inventory, alongside the inputs.
+    pub catalysts: Vec<ItemCount>,
+
     /// The outputs generated by crafting.
     pub outputs: RecipeOutput,
 
+    /// The outputs that are only generated some of the time.
+    ///
+    /// Each of these is rolled for separately whenever the recipe completes.
+    pub probabilistic_outputs: Vec<ProbabilisticOutput>,
+
+    /// The outputs that are produced in a random amount within a range.
+    ///
+    /// Each of these is rolled for separately whenever the recipe completes.
+    pub ranged_outputs: Vec<RangedOutput>,
+
+    /// Mutually exclusive outputs, of which exactly one is picked whenever the recipe completes.
+    ///
+    /// The chance of each entry being picked is proportional to its weight.
+    /// The pick is made in addition to the regular outputs, so a recipe can have a few fixed outputs plus one weighted pick.
+    /// If this is empty, nothing extra is produced.
+    pub output_one_of: Vec<WeightedOutput>,
+
+    /// The side products that are always generated alongside the outputs.
+    ///
+    /// These are kept apart from the outputs so that they can be presented differently.
+    pub byproducts: Vec<ItemCount>,
+
+    /// The items measured in fractional amounts that are consumed by the recipe, such as fluids.
+    ///
+    /// These are needed in addition to the [`inputs`](Self::inputs), which are always counted in whole items.
+    /// An item may be listed in both, in which case both amounts are needed, each in its own form.
+    pub fluid_inputs: Vec<FluidCount>,
+
+    /// The items measured in fractional amounts that are produced by the recipe, in addition to the [`outputs`](Self::outputs).
+    pub fluid_outputs: Vec<FluidCount>,
+
     /// The time needed to craft the recipe.
+    ///
+    /// For recipes that need workers and have [`work_units`](Self::work_units) set,
+    /// this only sets the scale of the progress tracked, as the actual time depends on the number of workers.
     pub craft_time: Duration,
 
+    /// The total amount of work needed to craft the recipe, in worker-seconds, if it is fixed.
+    ///
+    /// Each worker present contributes [`RecipeData::WORK_PER_WORKER`] per second,
+    /// so doubling the number of workers halves the time needed, without limit.
+    /// Without any workers, no progress is made.
+    /// This is ignored for [passive](WorkKind::Passive) recipes, which always take their `craft_time`.
+    pub work_units: Option<f32>,
+
     /// The conditions that must be met to craft the recipe.
     pub conditions: RecipeConditions,
 
     /// The amount of [`Energy`] produced by making this recipe, if any.
     ///
+    /// Positive values are gained by the organism whenever the recipe completes,
+    /// while negative values are the energy it must spend to complete the recipe.
+    /// If the organism does not have enough energy to pay for the recipe, crafting stalls until it does.
+    ///
     /// This is only relevant to living structures.
     pub energy: Option<Energy>,
+
+    /// May this recipe produce more mass than it consumes, such as when an organism grows?
+    ///
+    /// Other recipes are expected to [conserve mass](Self::is_balanced_mass).
+    pub allow_mass_gain: bool,
+
+    /// May this recipe produce more of one of its inputs than it consumes, such as when seeds multiply?
+    ///
+    /// Other recipes must not [produce their own inputs](Self::self_produced_items).
+    pub allow_self_production: bool,
+
+    /// The recipes that must be [discovered](crate::crafting::discovery::DiscoveredRecipes) before this recipe is available.
+    pub requires: Vec<Id<Recipe>>,
+
+    /// The items that are spent once to [research](crate::crafting::discovery::DiscoveredRecipes::research) this recipe.
+    pub research_cost: Vec<ItemCount>,
+
+    /// When the inputs are taken from the input inventory.
+    pub consumption: ConsumptionMode,
+
+    /// What happens to the outputs that do not fit in the output inventory.
+    pub overflow: OverflowPolicy,
+
+    /// The output slots that items are routed to, for items that are not stored in [`OutputSlot::Product`].
+    pub output_slots: HashMap<Id<Item>, OutputSlot>,
+
+    /// The largest number of times that the inputs and outputs may be multiplied in a single crafting cycle.
+    ///
+    /// Crafters craft as large a batch as their inputs and the room in their output inventory allow.
+    pub max_batch: u32,
+
+    /// The recipe that the crafter switches to once this one is complete, handing its outputs over as inputs.
+    ///
+    /// This chains the stages of multi-stage processing together, such as threshing grain and then baking it.
+    pub next_recipe: Option<Id<Recipe>>,
+
+    /// How the [tier](crate::items::item_manifest::ItemData::tier) of the outputs follows from the inputs, if at all.
+    pub output_tier: Option<TierRule>,
+
+    /// How the [quality](crate::items::slot::ItemSlot::quality) of the outputs follows from the inputs.
+    pub output_quality: QualityRule,
+
+    /// The category that this recipe is listed under in crafting menus, such as "Food".
+    pub category: String,
+
+    /// The time of day during which this recipe can be crafted.
+    pub active_phase: TimePhase,
+
+    /// The range that an environmental value at the tile of the crafter must be in for work to be done, if any.
+    pub requires_environment: Option<EnvironmentRequirement>,
+}
+
+/// A change to a [`RecipeData`], as found by [`Manifest::diff`].
+///
+/// Changes to individual items are listed in the order of their [`Id`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecipeChange {
+    /// The number of an item needed as an exact input changed.
+    ///
+    /// Items that were added or removed as inputs have a count of zero in the other version.
+    Input {
+        /// The item that is consumed.
+        item_id: Id<Item>,
+        /// The old number of the item.
+        before: u32,
+        /// The new number of the item.
+        after: u32,
+    },
+    /// The number of an item produced as a regular output changed.
+    ///
+    /// Items that were added or removed as outputs have a count of zero in the other version.
+    Output {
+        /// The item that is produced.
+        item_id: Id<Item>,
+        /// The old average number of the item.
+        before: f32,
+        /// The new average number of the item.
+        after: f32,
+    },
+    /// The time needed to craft the recipe changed.
+    CraftTime {
+        /// The old craft time.
+        before: Duration,
+        /// The new craft time.
+        after: Duration,
+    },
+    /// The field with this name changed.
+    ///
+    /// This is also used for the inputs, when either version does not use exact inputs.
+    Other(&'static str),
+}
+
+impl Diffable for RecipeData {
+    type Change = RecipeChange;
+
+    fn changes_to(&self, other: &Self) -> Vec<RecipeChange> {
+        // Destructured, so that new fields cannot be forgotten here
+        let RecipeData {
+            inputs,
+            catalysts,
+            outputs,
+            probabilistic_outputs,
+            ranged_outputs,
+            output_one_of,
+            byproducts,
+            fluid_inputs,
+            fluid_outputs,
+            craft_time,
+            work_units,
+            conditions,
+            energy,
+            allow_mass_gain,
+            allow_self_production,
+            requires,
+            research_cost,
+            consumption,
+            overflow,
+            output_slots,
+            max_batch,
+            next_recipe,
+            output_tier,
+            output_quality,
+            category,
+            active_phase,
+            requires_environment,
+        } = self;
+
+        let mut changes = Vec::new();
+
+        match (inputs, &other.inputs) {
+            (RecipeInput::Exact(before), RecipeInput::Exact(after)) => {
+                let count = |item_counts: &[ItemCount], item_id| {
+                    item_counts
+                        .iter()
+                        .find(|item_count| item_count.item_id == item_id)
+                        .map_or(0, |item_count| item_count.count)
+                };
+                let item_ids = before
+                    .iter()
+                    .chain(after)
+                    .map(|item_count| item_count.item_id)
+                    .sorted()
+                    .dedup();
+
+                changes.extend(item_ids.filter_map(|item_id| {
+                    let (before, after) = (count(before, item_id), count(after, item_id));
+                    (before != after).then_some(RecipeChange::Input {
+                        item_id,
+                        before,
+                        after,
+                    })
+                }));
+            }
+            (before, after) if before != after => changes.push(RecipeChange::Other("inputs")),
+            _ => (),
+        }
+
+        let (before, after) = (outputs.average_counts(), other.outputs.average_counts());
+        let count = |counts: &[(Id<Item>, f32)], item_id| {
+            counts
+                .iter()
+                .find(|(output_id, _)| *output_id == item_id)
+                .map_or(0., |(_, count)| *count)
+        };
+        let item_ids = before
+            .iter()
+            .chain(&after)
+            .map(|(item_id, _)| *item_id)
+            .sorted()
+            .dedup();
+        changes.extend(item_ids.filter_map(|item_id| {
+            let (before, after) = (count(&before, item_id), count(&after, item_id));
+            (before != after).then_some(RecipeChange::Output {
+                item_id,
+                before,
+                after,
+            })
+        }));
+
+        if *craft_time != other.craft_time {
+            changes.push(RecipeChange::CraftTime {
+                before: *craft_time,
+                after: other.craft_time,
+            });
+        }
+
+        let other_fields = [
+            ("catalysts", !same_elements(catalysts, &other.catalysts)),
+            (
+                "probabilistic_outputs",
+                !same_elements(probabilistic_outputs, &other.probabilistic_outputs),
+            ),
+            (
+                "ranged_outputs",
+                !same_elements(ranged_outputs, &other.ranged_outputs),
+            ),
+            (
+                "output_one_of",
+                !same_elements(output_one_of, &other.output_one_of),
+            ),
+            ("byproducts", !same_elements(byproducts, &other.byproducts)),
+            (
+                "fluid_inputs",
+                !same_elements(fluid_inputs, &other.fluid_inputs),
+            ),
+            (
+                "fluid_outputs",
+                !same_elements(fluid_outputs, &other.fluid_outputs),
+            ),
+            ("work_units", *work_units != other.work_units),
+            ("conditions", *conditions != other.conditions),
+            ("energy", *energy != other.energy),
+            ("allow_mass_gain", *allow_mass_gain != other.allow_mass_gain),
+            (
+                "allow_self_production",
+                *allow_self_production != other.allow_self_production,
+            ),
+            ("requires", !same_elements(requires, &other.requires)),
+            (
+                "research_cost",
+                !same_elements(research_cost, &other.research_cost),
+            ),
+            ("consumption", *consumption != other.consumption),
+            ("overflow", *overflow != other.overflow),
+            ("output_slots", *output_slots != other.output_slots),
+            ("max_batch", *max_batch != other.max_batch),
+            ("next_recipe", *next_recipe != other.next_recipe),
+            ("output_tier", *output_tier != other.output_tier),
+            ("output_quality", *output_quality != other.output_quality),
+            ("category", *category != other.category),
+            ("active_phase", *active_phase != other.active_phase),
+            (
+                "requires_environment",
+                *requires_environment != other.requires_environment,
+            ),
+        ];
+        changes.extend(
+            other_fields
+                .into_iter()
+                .filter(|(_, changed)| *changed)
+                .map(|(field, _)| RecipeChange::Other(field)),
+        );
+
+        changes
+    }
+}
+
+/// How the [tier](crate::items::item_manifest::ItemData::tier) of the items produced by a recipe follows from its inputs.
+///
+/// When the inputs are of mixed tiers, the lowest of them counts,
+/// so that a single low-tier ingredient cannot be upgraded by mixing it with better ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum TierRule {
+    /// The outputs are always of the given tier.
+    Fixed(u8),
+    /// The outputs are of the same tier as the inputs.
+    MatchInput,
+    /// The outputs are one tier above the inputs.
+    InputPlusOne,
+}
+
+impl TierRule {
+    /// The tier of the outputs, for inputs of the given `input_tiers`.
+    ///
+    /// Recipes without any inputs count as using inputs of tier 0.
+    pub fn resolve(&self, input_tiers: impl IntoIterator<Item = u8>) -> u8 {
+        let input_tier = input_tiers.into_iter().min().unwrap_or(0);
+
+        match self {
+            TierRule::Fixed(tier) => *tier,
+            TierRule::MatchInput => input_tier,
+            TierRule::InputPlusOne => input_tier.saturating_add(1),
+        }
+    }
+}
+
+/// How the [quality](crate::items::slot::ItemSlot::quality) of the items produced by a recipe follows from its inputs.
+///
+/// Qualities range from 0.0 for spoiled items to [`ItemSlot::FULL_QUALITY`](crate::items::slot::ItemSlot::FULL_QUALITY) for fresh ones.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum QualityRule {
+    /// The outputs are of the average quality of the inputs, weighted by the number of each item.
+    Average,
+    /// The outputs are of the quality of the worst input.
+    Min,
+    /// The outputs are always of the given quality.
+    Fixed(f32),
+}
+
+impl QualityRule {
+    /// The quality of the outputs, for inputs of the given `(quality, count)` pairs.
+    ///
+    /// Recipes without any inputs produce items of full quality.
+    pub fn resolve(&self, inputs: impl IntoIterator<Item = (f32, u32)>) -> f32 {
+        let inputs = inputs.into_iter().filter(|(_, count)| *count > 0);
+
+        let quality = match self {
+            QualityRule::Fixed(quality) => *quality,
+            QualityRule::Min => inputs
+                .map(|(quality, _)| quality)
+                .reduce(f32::min)
+                .unwrap_or(ItemSlot::FULL_QUALITY),
+            QualityRule::Average => {
+                let (total, count) = inputs.fold((0., 0), |(total, count), (quality, n)| {
+                    (total + quality * n as f32, count + n)
+                });
+
+                if count == 0 {
+                    ItemSlot::FULL_QUALITY
+                } else {
+                    total / count as f32
+                }
+            }
+        };
+
+        quality.clamp(0., ItemSlot::FULL_QUALITY)
+    }
+}
+
+impl Default for QualityRule {
+    fn default() -> Self {
+        QualityRule::Fixed(ItemSlot::FULL_QUALITY)
+    }
+}
+
+/// What happens to the outputs of a recipe that do not fit in the output inventory of the structure crafting it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum OverflowPolicy {
+    /// Crafting pauses until there is room for all of the outputs, which are then stored together.
+    ///
+    /// No outputs are lost, but the structure stops working until its outputs are collected.
+    #[default]
+    Block,
+    /// The outputs that fit are stored, and the rest are dropped on the ground as litter.
+    Discard,
+    /// The outputs that fit are stored, and the rest are lost.
+    Void,
+}
+
+/// The part of an output inventory that an output of a recipe is stored in.
+///
+/// Structures with several outputs can use these to keep their waste apart from their products,
+/// so that a full waste slot does not stop the products from being stored, and vice versa.
+/// Structures without a separate partition for a slot store its items with the products.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+)]
+pub enum OutputSlot {
+    /// The main products of the recipe.
+    #[default]
+    Product,
+    /// The waste left over from crafting.
+    Waste,
+}
+
+/// When the inputs of a recipe are consumed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum ConsumptionMode {
+    /// All inputs are consumed when crafting starts.
+    #[default]
+    AtStart,
+    /// All inputs must be present to start crafting, but are only consumed when the recipe completes.
+    ///
+    /// If they are missing by then, the recipe waits until they are available again.
+    AtEnd,
+    /// The inputs are consumed bit by bit, in proportion to the progress made.
+    ///
+    /// Each input is paid for ahead of the progress that it allows:
+    /// after a fraction of the craft time, that fraction of each input, rounded up, has been consumed.
+    /// If an input is missing, crafting stalls and holds its progress until it is delivered.
+    Continuous,
 }
 
 /// The items needed to craft a recipe.
@@ -59,17 +518,35 @@ pub enum RecipeInput {
         /// The number of inputs that must meet the tag.
         count: u32,
     },
+    /// The recipe requires a fixed number of inputs that belong to the provided free-form category.
+    ///
+    /// Any mix of matching items can be used.
+    /// They are consumed greedily, starting with the item with the lowest [`Id`].
+    Category {
+        /// The category that inputs must belong to.
+        category: Id<ItemCategory>,
+        /// The number of inputs that must belong to the category.
+        count: u32,
+    },
+    /// The recipe requires any one of the provided alternatives, such as either 3 pollen or 1 nectar.
+    ///
+    /// The alternatives are checked in the order they are listed,
+    /// and the first one that there are enough items for is consumed.
+    /// The other alternatives are left untouched, even if they are also available.
+    AnyOf(Vec<ItemCount>),
 }
 
 impl RecipeInput {
     /// No inputs are needed.
     pub const EMPTY: RecipeInput = RecipeInput::Exact(Vec::new());
 
-    /// The number of slots needed to craft this recipe.
+    /// The number of distinct inputs needed to craft this recipe.
+    ///
+    /// Flexible, category and alternative inputs count as a single input, no matter how many items can be used.
     pub fn len(&self) -> usize {
         match self {
             Self::Exact(inputs) => inputs.len(),
-            Self::Flexible { .. } => 1,
+            Self::Flexible { .. } | Self::Category { .. } | Self::AnyOf(_) => 1,
         }
     }
 
@@ -78,15 +555,98 @@ impl RecipeInput {
         match self {
             Self::Exact(inputs) => inputs.is_empty(),
             Self::Flexible { .. } => false,
+            Self::Category { count, .. } => *count == 0,
+            Self::AnyOf(alternatives) => alternatives
+                .iter()
+                .any(|alternative| alternative.count == 0),
+        }
+    }
+
+    /// The first of the `alternatives` of a [`RecipeInput::AnyOf`] that `count_of` reports enough items for.
+    pub fn first_available_alternative(
+        alternatives: &[ItemCount],
+        count_of: impl Fn(Id<Item>) -> u32,
+    ) -> Option<&ItemCount> {
+        alternatives
+            .iter()
+            .find(|alternative| count_of(alternative.item_id) >= alternative.count)
+    }
+
+    /// These inputs, with every count multiplied by `batch`, as needed to craft a batch of that many units.
+    ///
+    /// Each alternative of a [`RecipeInput::AnyOf`] is multiplied on its own,
+    /// so the whole batch is paid for with a single alternative.
+    /// Returns [`None`] if any of the counts would overflow.
+    pub fn times(&self, batch: u32) -> Option<Self> {
+        Some(match self {
+            Self::Exact(inputs) => Self::Exact(scaled_item_counts(inputs, batch)?),
+            Self::Flexible { tag, count } => Self::Flexible {
+                tag: *tag,
+                count: count.checked_mul(batch)?,
+            },
+            Self::Category { category, count } => Self::Category {
+                category: *category,
+                count: count.checked_mul(batch)?,
+            },
+            Self::AnyOf(alternatives) => Self::AnyOf(scaled_item_counts(alternatives, batch)?),
+        })
+    }
+
+    /// The share of these inputs that pays for crafting from `from` until `to`, for a recipe that takes `craft_time`.
+    ///
+    /// By the time `t`, the fraction `t / craft_time` of each input, rounded up, has been paid for.
+    /// This is used by [`ConsumptionMode::Continuous`].
+    ///
+    /// Each alternative of a [`RecipeInput::AnyOf`] is shared out on its own,
+    /// so every payment uses whichever alternative is available at the time.
+    pub fn consumed_between(&self, from: Duration, to: Duration, craft_time: Duration) -> Self {
+        /// How much of the `count` has been paid for by the time `elapsed`.
+        fn consumed_by(count: u32, elapsed: Duration, craft_time: Duration) -> u32 {
+            if elapsed >= craft_time {
+                return count;
+            }
+
+            // Round up, so that each item is paid for before the progress it allows
+            let paid = (u128::from(count) * elapsed.as_nanos()).div_ceil(craft_time.as_nanos());
+            // This is less than the count, as the recipe is not yet complete
+            paid as u32
+        }
+
+        let share = |count: u32| {
+            consumed_by(count, to, craft_time).saturating_sub(consumed_by(count, from, craft_time))
+        };
+
+        match self {
+            Self::Exact(inputs) => Self::Exact(
+                inputs
+                    .iter()
+                    .map(|item_count| ItemCount::new(item_count.item_id, share(item_count.count)))
+                    .filter(|item_count| item_count.count > 0)
+                    .collect(),
+            ),
+            Self::Flexible { tag, count } => Self::Flexible {
+                tag: *tag,
+                count: share(*count),
+            },
+            Self::Category { category, count } => Self::Category {
+                category: *category,
+                count: share(*count),
+            },
+            Self::AnyOf(alternatives) => Self::AnyOf(
+                alternatives
+                    .iter()
+                    .map(|item_count| ItemCount::new(item_count.item_id, share(item_count.count)))
+                    .collect(),
+            ),
         }
     }
 }
 
 /// The unprocessed equivalent of [`RecipeInput`].
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum RawRecipeInput {
     /// The recipe requires exactly the provided number of each input.
-    Exact(HashMap<String, u32>),
+    Exact(#[schemars(with = "std::collections::HashMap<String, u32>")] HashMap<String, u32>),
     /// The recipe requires a fixed number of inputs that meet the provided conditions.
     Flexible {
         /// The conditions that inputs must meet.
@@ -94,6 +654,27 @@ pub enum RawRecipeInput {
         /// The number of inputs that must meet the tag.
         count: u32,
     },
+    /// The recipe requires a fixed number of inputs that are labeled with the provided tag in the item manifest.
+    Category {
+        /// The name of the tag that inputs must be labeled with.
+        tag: String,
+        /// The number of inputs that must be labeled with the tag.
+        count: u32,
+    },
+    /// The recipe requires any one of the listed alternatives.
+    ///
+    /// The first alternative that there are enough items for is used; see [`RecipeInput::AnyOf`].
+    AnyOf(Vec<RawInputAlternative>),
+}
+
+/// One of the alternatives of a [`RawRecipeInput::AnyOf`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "strict_manifests", serde(deny_unknown_fields))]
+pub struct RawInputAlternative {
+    /// The name of the item.
+    pub item: String,
+    /// The number of items needed.
+    pub count: u32,
 }
 
 impl RawRecipeInput {
@@ -113,16 +694,20 @@ impl RawRecipeInput {
 impl From<RawRecipeInput> for RecipeInput {
     fn from(raw_input: RawRecipeInput) -> Self {
         match raw_input {
-            RawRecipeInput::Exact(raw_data) => Self::Exact(
-                raw_data
+            RawRecipeInput::Exact(raw_data) => Self::Exact(item_counts(raw_data)),
+            RawRecipeInput::Flexible { tag, count } => Self::Flexible { tag, count },
+            RawRecipeInput::Category { tag, count } => Self::Category {
+                category: Id::from_name(tag),
+                count,
+            },
+            RawRecipeInput::AnyOf(alternatives) => Self::AnyOf(
+                alternatives
                     .into_iter()
-                    .map(|(item_name, count)| ItemCount {
-                        item_id: Id::from_name(item_name),
-                        count,
+                    .map(|alternative| {
+                        ItemCount::new(Id::from_name(alternative.item), alternative.count)
                     })
                     .collect(),
             ),
-            RawRecipeInput::Flexible { tag, count } => Self::Flexible { tag, count },
         }
     }
 }
@@ -140,7 +725,7 @@ impl RecipeOutput {
     /// Nothing is produced.
     pub const EMPTY: RecipeOutput = RecipeOutput::Deterministic(Vec::new());
 
-    /// Convert the raw data into a [`RecipeOutput`].
+    /// Convert the plain counts of the raw data into a [`RecipeOutput`].
     ///
     /// If all the counts are integers, then the recipe is deterministic.
     /// Otherwise, it is stochastic.
@@ -155,12 +740,14 @@ impl RecipeOutput {
                         item_id: Id::from_name(item_name),
                         count: count as u32,
                     })
+                    .sorted_by_key(|item_count| item_count.item_id)
                     .collect(),
             ),
             false => Self::Stochastic(
                 raw_data
                     .into_iter()
                     .map(|(item_name, count)| (Id::from_name(item_name), count))
+                    .sorted_by_key(|(item_id, _)| *item_id)
                     .collect(),
             ),
         }
@@ -186,259 +773,4295 @@ impl RecipeOutput {
             Self::Stochastic(outputs) => outputs.iter().map(|(item_id, _)| *item_id).collect(),
         }
     }
+
+    /// The average number of each item produced by this recipe.
+    pub fn average_counts(&self) -> Vec<(Id<Item>, f32)> {
+        match self {
+            RecipeOutput::Deterministic(outputs) => outputs
+                .iter()
+                .map(|output| (output.item_id, output.count as f32))
+                .collect(),
+            RecipeOutput::Stochastic(outputs) => outputs.clone(),
+        }
+    }
 }
 
-/// The unprocessed equivalent of [`RecipeData`].
+/// An output of a recipe that is only produced with some probability.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProbabilisticOutput {
+    /// The items produced when the roll succeeds.
+    pub item_count: ItemCount,
+    /// The probability of producing the items each time the recipe completes, between 0 and 1.
+    pub chance: f32,
+}
+
+impl ProbabilisticOutput {
+    /// Rolls for this output, returning the produced items if the roll succeeds.
+    pub fn roll(&self, rng: &mut impl Rng) -> Option<ItemCount> {
+        if rng.gen_bool(self.chance.clamp(0., 1.) as f64) {
+            Some(self.item_count.clone())
+        } else {
+            None
+        }
+    }
+}
+
+/// An output of a recipe that is produced in a random amount, see [`RecipeData::ranged_outputs`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RangedOutput {
+    /// The item produced.
+    pub item_id: Id<Item>,
+    /// The smallest number of items produced.
+    pub min: u32,
+    /// The largest number of items produced.
+    pub max: u32,
+}
+
+impl RangedOutput {
+    /// Rolls for the number of items produced, each number between `min` and `max` being equally likely.
+    pub fn roll(&self, rng: &mut impl Rng) -> ItemCount {
+        let count = if self.max > self.min {
+            rng.gen_range(self.min..=self.max)
+        } else {
+            self.min
+        };
+
+        ItemCount::new(self.item_id, count)
+    }
+
+    /// The average number of items produced.
+    pub fn average(&self) -> f32 {
+        (self.min + self.max) as f32 / 2.
+    }
+}
+
+/// One of several mutually exclusive outputs of a recipe, see [`RecipeData::output_one_of`].
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeightedOutput {
+    /// The items produced when this entry is picked.
+    pub item_count: ItemCount,
+    /// How likely this entry is to be picked, relative to the other entries.
+    pub weight: f32,
+}
+
+impl WeightedOutput {
+    /// Picks exactly one of the `outputs`, with a chance proportional to its weight.
+    ///
+    /// Returns [`None`] if there are no outputs, or if none of them has a positive weight.
+    pub fn pick(outputs: &[WeightedOutput], rng: &mut impl Rng) -> Option<ItemCount> {
+        let distribution =
+            WeightedIndex::new(outputs.iter().map(|output| output.weight.max(0.))).ok()?;
+
+        Some(outputs[distribution.sample(rng)].item_count.clone())
+    }
+}
+
+/// The unprocessed equivalent of [`WeightedOutput`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "strict_manifests", serde(deny_unknown_fields))]
+pub struct RawWeightedOutput {
+    /// The name of the item produced when this entry is picked.
+    pub item: String,
+    /// The number of items produced when this entry is picked.
+    pub count: u32,
+    /// How likely this entry is to be picked, relative to the other entries.
+    pub weight: f32,
+}
+
+impl From<RawWeightedOutput> for WeightedOutput {
+    fn from(raw: RawWeightedOutput) -> Self {
+        Self {
+            item_count: ItemCount::new(Id::from_name(raw.item), raw.count),
+            weight: raw.weight,
+        }
+    }
+}
+
+/// How many of an item a recipe produces, as seen in the manifest file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum RawOutputCount {
+    /// The number of items produced each time.
+    ///
+    /// Fractional counts are rounded up with a probability equal to their fractional part.
+    Count(f32),
+    /// A number of items that is only produced some of the time.
+    Chance {
+        /// The number of items produced when the roll succeeds.
+        count: u32,
+        /// The probability of producing the items, between 0 and 1.
+        chance: f32,
+    },
+    /// A random number of items between `min` and `max`, inclusive, rolled each time.
+    ///
+    /// If both are equal, exactly that many items are produced.
+    Range {
+        /// The smallest number of items produced.
+        min: u32,
+        /// The largest number of items produced.
+        max: u32,
+    },
+}
+
+/// The unprocessed equivalent of [`RecipeData`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "strict_manifests", serde(deny_unknown_fields))]
 pub struct RawRecipeData {
     /// The inputs needed to craft the recipe.
     pub inputs: RawRecipeInput,
 
+    /// The items that must be present to craft the recipe, but are not consumed by it.
+    #[serde(default)]
+    #[schemars(with = "std::collections::HashMap<String, u32>")]
+    pub catalysts: HashMap<String, u32>,
+
     /// The outputs generated by crafting.
-    pub outputs: HashMap<String, f32>,
+    #[schemars(with = "std::collections::HashMap<String, RawOutputCount>")]
+    pub outputs: HashMap<String, RawOutputCount>,
 
-    /// The time needed to craft the recipe.
+    /// Mutually exclusive outputs, of which exactly one is picked by weight, in addition to the `outputs`.
+    #[serde(default)]
+    pub output_one_of: Vec<RawWeightedOutput>,
+
+    /// The side products that are always generated alongside the outputs.
+    #[serde(default)]
+    #[schemars(with = "std::collections::HashMap<String, u32>")]
+    pub byproducts: HashMap<String, u32>,
+
+    /// The fractional amounts of items, such as fluids, that are consumed in addition to the `inputs`.
+    ///
+    /// Amounts are rounded to the nearest thousandth.
+    #[serde(default)]
+    #[schemars(with = "std::collections::HashMap<String, f64>")]
+    pub fluid_inputs: HashMap<String, f64>,
+
+    /// The fractional amounts of items, such as fluids, that are produced in addition to the `outputs`.
+    ///
+    /// Amounts are rounded to the nearest thousandth.
+    #[serde(default)]
+    #[schemars(with = "std::collections::HashMap<String, f64>")]
+    pub fluid_outputs: HashMap<String, f64>,
+
+    /// The time needed to craft the recipe, in seconds.
+    ///
+    /// This may also be written as a humanized string, such as `"1m30s"`.
+    /// If `work_units` is set on a recipe that needs workers, this only sets the scale of the progress bar,
+    /// and must be greater than 0.
+    #[serde(deserialize_with = "deserialize_seconds")]
+    #[schemars(with = "RawDuration")]
     pub craft_time: f32,
 
+    /// The total amount of work needed to craft the recipe, in worker-seconds.
+    ///
+    /// Each worker contributes one unit per second, so the time needed shrinks as more workers help out.
+    /// Recipes that do not need workers ignore this, and always take their `craft_time`.
+    #[serde(default)]
+    pub work_units: Option<f32>,
+
     /// The conditions that must be met to craft the recipe.
     pub conditions: Option<RecipeConditions>,
 
     /// The amount of [`Energy`] produced by making this recipe, if any.
     ///
+    /// Negative values are consumed instead.
     /// This is only relevant to living structures.
     pub energy: Option<Energy>,
-}
 
-impl From<RawRecipeData> for RecipeData {
-    fn from(raw: RawRecipeData) -> Self {
-        Self {
-            inputs: raw.inputs.into(),
-            outputs: RecipeOutput::from_raw(raw.outputs),
-            craft_time: Duration::from_secs_f32(raw.craft_time),
-            conditions: raw.conditions.unwrap_or_default(),
-            energy: raw.energy,
-        }
-    }
-}
+    /// May this recipe produce more mass than it consumes, such as when an organism grows?
+    ///
+    /// Defaults to `false`, so that recipes that create matter by accident can be found.
+    #[serde(default)]
+    pub allow_mass_gain: bool,
 
-impl RecipeData {
-    /// Are the conditions to craft this recipe met?
-    pub(crate) fn satisfied(&self, workers: u8, received_light: &ReceivedLight) -> bool {
-        self.conditions.satisfied(workers, received_light)
-    }
+    /// May this recipe produce more of one of its inputs than it consumes, such as when seeds multiply?
+    ///
+    /// Defaults to `false`, as such recipes are usually accidental sources of infinite items.
+    #[serde(default)]
+    pub allow_self_production: bool,
 
-    /// An inventory with empty slots for all of the inputs of this recipe.
-    pub(crate) fn input_inventory(&self, item_manifest: &ItemManifest) -> InputInventory {
-        match self.inputs {
-            RecipeInput::Exact(ref inputs) => {
-                let mut inventory = Inventory::new(self.inputs.len(), None);
+    /// The names of the recipes that must be discovered before this recipe becomes available.
+    ///
+    /// These must be enabled recipes, and may not require this recipe in turn.
+    #[serde(default)]
+    pub requires: Vec<String>,
 
-                for item_count in inputs.iter() {
-                    inventory.add_empty_slot(item_count.item_id, item_manifest);
-                }
+    /// The items that can be spent to discover this recipe, once.
+    #[serde(default)]
+    #[schemars(with = "std::collections::HashMap<String, u32>")]
+    pub research_cost: HashMap<String, u32>,
 
-                InputInventory::Exact { inventory }
-            }
-            RecipeInput::Flexible { tag, .. } => InputInventory::Tagged {
-                tag,
-                inventory: Inventory::new(1, None),
-            },
-        }
-    }
+    /// When the inputs are consumed.
+    ///
+    /// Defaults to consuming all of them when crafting starts.
+    #[serde(default)]
+    pub consumption: ConsumptionMode,
 
-    /// An inventory with empty slots for all of the outputs of this recipe.
-    pub(crate) fn output_inventory(&self, item_manifest: &ItemManifest) -> OutputInventory {
-        let mut inventory = Inventory::new(self.outputs.len(), None);
-        for item_id in self.outputs.item_ids() {
-            inventory.add_empty_slot(item_id, item_manifest);
-        }
-        OutputInventory { inventory }
-    }
+    /// What happens to the outputs that do not fit in the output inventory.
+    ///
+    /// Defaults to blocking until there is room for them.
+    #[serde(default)]
+    pub overflow: OverflowPolicy,
 
-    /// The number of workers this recipe needs to be crafted at all.
-    pub(crate) fn workers_required(&self) -> u8 {
-        self.conditions.workers_required
-    }
+    /// The output slots that the named items are stored in, such as a separate slot for waste.
+    ///
+    /// Each item must be one of the `outputs`, `output_one_of` or `byproducts` of this recipe.
+    /// Items that are not listed are stored as products.
+    #[serde(default)]
+    #[schemars(with = "std::collections::HashMap<String, OutputSlot>")]
+    pub output_slots: HashMap<String, OutputSlot>,
 
-    /// Does this recipe need workers to produce?
-    pub(crate) fn needs_workers(&self) -> bool {
-        self.conditions.workers_required > 0
-    }
+    /// The largest number of times that the inputs and outputs may be multiplied in a single crafting cycle.
+    ///
+    /// Crafters craft as large a batch as their inputs and the room in their output inventory allow, in the same craft time.
+    /// Defaults to 1, which crafts a single unit at a time, and may be at most [`RawRecipeData::MAX_BATCH`].
+    #[serde(default = "RawRecipeData::default_max_batch")]
+    pub max_batch: u32,
 
-    /// The pretty formatting of this type
-    pub(crate) fn display(&self, item_manifest: &ItemManifest) -> String {
-        let input_str: String = match self.inputs {
-            RecipeInput::Exact(ref inputs) => inputs
-                .iter()
+    /// The name of the recipe that the crafter switches to once this one is complete.
+    ///
+    /// The outputs are handed over as the inputs of the next recipe, if they all fit.
+    /// This must be an enabled recipe, and following the chain of next recipes must not lead back to this one.
+    #[serde(default)]
+    pub next_recipe: Option<String>,
+
+    /// How the tier of the outputs follows from the tier of the inputs.
+    ///
+    /// If this is not set, the outputs may be of any tier.
+    #[serde(default)]
+    pub output_tier: Option<TierRule>,
+
+    /// How the quality of the outputs follows from the quality of the inputs.
+    ///
+    /// Defaults to producing items of full quality, regardless of the inputs.
+    #[serde(default)]
+    pub output_quality: QualityRule,
+
+    /// The category that this recipe is listed under in crafting menus, such as "Food".
+    ///
+    /// Defaults to [`RawRecipeData::DEFAULT_CATEGORY`] when left out.
+    #[serde(default = "RawRecipeData::default_category")]
+    pub category: String,
+
+    /// The time of day during which this recipe can be crafted.
+    ///
+    /// Defaults to any time of day.
+    #[serde(default)]
+    pub active_phase: TimePhase,
+
+    /// The range that an environmental value, such as the temperature, must be in at the tile of the crafter.
+    ///
+    /// The key must be defined in the environment manifest.
+    /// Outside of this range, recipes in progress hold their progress.
+    #[serde(default)]
+    pub requires_environment: Option<RawEnvironmentRequirement>,
+
+    /// Should this recipe be part of the game?
+    ///
+    /// Disabled recipes are left out of the processed [`RecipeManifest`] entirely, and are not validated,
+    /// so that experimental recipes can be kept in the file while switched off.
+    /// Structures that are set to craft a disabled recipe behave as if they had no recipe.
+    #[serde(default = "RawRecipeData::default_enabled")]
+    pub enabled: bool,
+}
+
+impl RawRecipeData {
+    /// The longest craft time that a recipe may take, in seconds.
+    ///
+    /// Longer craft times are almost certainly mistakes, such as times written in milliseconds.
+    pub const MAX_CRAFT_TIME: f32 = 3600.;
+
+    /// The largest batch that a recipe may be crafted in.
+    ///
+    /// This keeps the counts of a whole batch far away from overflowing.
+    pub const MAX_BATCH: u32 = 1000;
+
+    /// The category of recipes that do not declare one.
+    pub const DEFAULT_CATEGORY: &'static str = "Uncategorized";
+
+    /// Returns `true`, as recipes are enabled unless stated otherwise.
+    fn default_enabled() -> bool {
+        true
+    }
+
+    /// Returns 1, as recipes craft a single unit at a time unless stated otherwise.
+    fn default_max_batch() -> u32 {
+        1
+    }
+
+    /// Returns [`RawRecipeData::DEFAULT_CATEGORY`], for use as a serde default.
+    fn default_category() -> String {
+        Self::DEFAULT_CATEGORY.to_string()
+    }
+
+    /// Does this recipe produce no items, fluids or energy at all?
+    pub fn produces_nothing(&self) -> bool {
+        self.outputs.is_empty()
+            && self.output_one_of.is_empty()
+            && self.byproducts.is_empty()
+            && self.fluid_outputs.is_empty()
+            && self.energy.is_none_or(|energy| energy <= Energy(0.))
+    }
+
+    /// Finds the numeric fields of this recipe, named `recipe_name`, that hold nonsensical values.
+    ///
+    /// Item counts must be at least 1, fractional amounts must be positive, chances must lie between 0 and 1,
+    /// the craft time must lie between 0 and [`RawRecipeData::MAX_CRAFT_TIME`],
+    /// and the maximum batch must lie between 1 and [`RawRecipeData::MAX_BATCH`].
+    pub fn out_of_range_values(&self, recipe_name: &str) -> Vec<OutOfRangeValue> {
+        let mut out_of_range_values = Vec::new();
+        let mut check = |in_range: bool, field: String, value: &dyn Display, expected| {
+            if !in_range {
+                out_of_range_values.push(OutOfRangeValue {
+                    entry: recipe_name.to_string(),
+                    field,
+                    value: value.to_string(),
+                    expected,
+                });
+            }
+        };
+
+        match &self.inputs {
+            RawRecipeInput::Exact(inputs) => {
+                for (item_name, count) in inputs {
+                    check(
+                        *count >= 1,
+                        format!("inputs.{item_name}"),
+                        count,
+                        "at least 1",
+                    );
+                }
+            }
+            RawRecipeInput::Flexible { count, .. } | RawRecipeInput::Category { count, .. } => {
+                check(*count >= 1, "inputs.count".to_string(), count, "at least 1");
+            }
+            RawRecipeInput::AnyOf(alternatives) => {
+                for alternative in alternatives {
+                    check(
+                        alternative.count >= 1,
+                        format!("inputs.{}", alternative.item),
+                        &alternative.count,
+                        "at least 1",
+                    );
+                }
+            }
+        }
+
+        for (field, item_counts) in [
+            ("catalysts", &self.catalysts),
+            ("byproducts", &self.byproducts),
+            ("research_cost", &self.research_cost),
+        ] {
+            for (item_name, count) in item_counts {
+                check(
+                    *count >= 1,
+                    format!("{field}.{item_name}"),
+                    count,
+                    "at least 1",
+                );
+            }
+        }
+
+        for (item_name, output_count) in &self.outputs {
+            match output_count {
+                RawOutputCount::Count(count) => check(
+                    count.is_finite() && *count > 0.,
+                    format!("outputs.{item_name}"),
+                    count,
+                    "greater than 0",
+                ),
+                RawOutputCount::Chance { count, chance } => {
+                    check(
+                        *count >= 1,
+                        format!("outputs.{item_name}.count"),
+                        count,
+                        "at least 1",
+                    );
+                    check(
+                        (0. ..=1.).contains(chance),
+                        format!("outputs.{item_name}.chance"),
+                        chance,
+                        "between 0 and 1",
+                    );
+                }
+                RawOutputCount::Range { min, max } => check(
+                    *max >= 1 && min <= max,
+                    format!("outputs.{item_name}.max"),
+                    max,
+                    "at least 1 and at least `min`",
+                ),
+            }
+        }
+
+        for (i, weighted_output) in self.output_one_of.iter().enumerate() {
+            check(
+                weighted_output.count >= 1,
+                format!("output_one_of.{i}.count"),
+                &weighted_output.count,
+                "at least 1",
+            );
+            check(
+                weighted_output.weight.is_finite() && weighted_output.weight >= 0.,
+                format!("output_one_of.{i}.weight"),
+                &weighted_output.weight,
+                "at least 0",
+            );
+        }
+
+        for (field, fluid_amounts) in [
+            ("fluid_inputs", &self.fluid_inputs),
+            ("fluid_outputs", &self.fluid_outputs),
+        ] {
+            for (item_name, amount) in fluid_amounts {
+                check(
+                    amount.is_finite() && *amount > 0.,
+                    format!("{field}.{item_name}"),
+                    amount,
+                    "greater than 0",
+                );
+            }
+        }
+
+        check(
+            (0. ..=Self::MAX_CRAFT_TIME).contains(&self.craft_time),
+            "craft_time".to_string(),
+            &self.craft_time,
+            "between 0 and 3600 seconds",
+        );
+        check(
+            (1..=Self::MAX_BATCH).contains(&self.max_batch),
+            "max_batch".to_string(),
+            &self.max_batch,
+            "between 1 and 1000",
+        );
+        if let Some(work_units) = self.work_units {
+            check(
+                work_units.is_finite() && work_units > 0.,
+                "work_units".to_string(),
+                &work_units,
+                "greater than 0",
+            );
+        }
+        if let Some(requirement) = &self.requires_environment {
+            check(
+                requirement.min <= requirement.max,
+                "requires_environment.max".to_string(),
+                &requirement.max,
+                "at least `min`",
+            );
+        }
+
+        out_of_range_values
+    }
+}
+
+/// Converts the named, fractional amounts of a [`RawRecipeData`] into [`FluidCount`]s.
+fn fluid_counts(raw_amounts: HashMap<String, f64>) -> Vec<FluidCount> {
+    raw_amounts
+        .into_iter()
+        .map(|(item_name, amount)| {
+            FluidCount::new(Id::from_name(item_name), FluidAmount::from_f64(amount))
+        })
+        .sorted_by_key(|fluid_count| fluid_count.item_id)
+        .collect()
+}
+
+/// The `item_counts`, with every count multiplied by `batch`, or [`None`] if any of the counts would overflow.
+fn scaled_item_counts(item_counts: &[ItemCount], batch: u32) -> Option<Vec<ItemCount>> {
+    item_counts
+        .iter()
+        .map(|item_count| {
+            let count = item_count.count.checked_mul(batch)?;
+            Some(ItemCount::new(item_count.item_id, count))
+        })
+        .collect()
+}
+
+/// The largest batch between 1 and `upper` that `fits`, or 1 if none of them do.
+///
+/// Batches are searched by bisection, so `fits` must hold for every batch smaller than one that it holds for.
+fn largest_batch(upper: u32, fits: impl Fn(u32) -> bool) -> u32 {
+    let (mut lower, mut upper) = (1, upper.max(1));
+    while lower < upper {
+        let middle = lower + (upper - lower).div_ceil(2);
+        if fits(middle) {
+            lower = middle;
+        } else {
+            upper = middle - 1;
+        }
+    }
+    lower
+}
+
+/// Converts the raw counts of each item into a list of [`ItemCount`]s, sorted by [`Id`].
+///
+/// Sorting makes the result independent of the order of the map, so that processing the same recipe always gives the same data.
+fn item_counts(raw_counts: HashMap<String, u32>) -> Vec<ItemCount> {
+    raw_counts
+        .into_iter()
+        .map(|(item_name, count)| ItemCount::new(Id::from_name(item_name), count))
+        .sorted_by_key(|item_count| item_count.item_id)
+        .collect()
+}
+
+impl TryFrom<RawRecipeData> for RecipeData {
+    type Error = RecipeProblem;
+
+    /// Converts the raw recipe, rejecting recipes that produce nothing, cannot be timed or can never be started.
+    ///
+    /// Recipes that only produce energy are allowed, as they feed the organism crafting them.
+    fn try_from(raw: RawRecipeData) -> Result<Self, RecipeProblem> {
+        if raw.produces_nothing() {
+            return Err(RecipeProblem::NoOutputs);
+        }
+        if matches!(&raw.inputs, RawRecipeInput::AnyOf(alternatives) if alternatives.is_empty()) {
+            return Err(RecipeProblem::NoAlternatives);
+        }
+        let craft_time = Duration::try_from_secs_f32(raw.craft_time)
+            .map_err(|_| RecipeProblem::InvalidCraftTime)?;
+        let produces = |item_name: &String| {
+            raw.outputs.contains_key(item_name)
+                || raw.byproducts.contains_key(item_name)
+                || raw
+                    .output_one_of
+                    .iter()
+                    .any(|output| output.item == *item_name)
+        };
+        if !raw.output_slots.keys().all(produces) {
+            return Err(RecipeProblem::OutputSlotOfMissingItem);
+        }
+
+        let mut outputs = HashMap::new();
+        let mut probabilistic_outputs = Vec::new();
+        let mut ranged_outputs = Vec::new();
+
+        for (item_name, output_count) in raw.outputs {
+            match output_count {
+                RawOutputCount::Count(count) => {
+                    outputs.insert(item_name, count);
+                }
+                RawOutputCount::Chance { count, chance } => {
+                    probabilistic_outputs.push(ProbabilisticOutput {
+                        item_count: ItemCount::new(Id::from_name(item_name), count),
+                        chance,
+                    });
+                }
+                RawOutputCount::Range { min, max } if min == max => {
+                    outputs.insert(item_name, min as f32);
+                }
+                RawOutputCount::Range { min, max } => {
+                    ranged_outputs.push(RangedOutput {
+                        item_id: Id::from_name(item_name),
+                        min,
+                        max,
+                    });
+                }
+            }
+        }
+        // The outputs were visited in the order of the map, which differs between runs
+        probabilistic_outputs.sort_by_key(|output| output.item_count.item_id);
+        ranged_outputs.sort_by_key(|output| output.item_id);
+
+        Ok(Self {
+            inputs: raw.inputs.into(),
+            catalysts: item_counts(raw.catalysts),
+            outputs: RecipeOutput::from_raw(outputs),
+            probabilistic_outputs,
+            ranged_outputs,
+            output_one_of: raw.output_one_of.into_iter().map(Into::into).collect(),
+            byproducts: item_counts(raw.byproducts),
+            fluid_inputs: fluid_counts(raw.fluid_inputs),
+            fluid_outputs: fluid_counts(raw.fluid_outputs),
+            craft_time,
+            work_units: raw.work_units,
+            conditions: raw.conditions.unwrap_or_default(),
+            energy: raw.energy,
+            allow_mass_gain: raw.allow_mass_gain,
+            allow_self_production: raw.allow_self_production,
+            requires: raw.requires.into_iter().map(Id::from_name).collect(),
+            research_cost: item_counts(raw.research_cost),
+            consumption: raw.consumption,
+            overflow: raw.overflow,
+            output_slots: raw
+                .output_slots
+                .into_iter()
+                .map(|(item_name, slot)| (Id::from_name(item_name), slot))
+                .collect(),
+            max_batch: raw.max_batch,
+            next_recipe: raw.next_recipe.map(Id::from_name),
+            output_tier: raw.output_tier,
+            output_quality: raw.output_quality,
+            category: raw.category,
+            active_phase: raw.active_phase,
+            requires_environment: raw.requires_environment.map(|requirement| {
+                EnvironmentRequirement {
+                    key: Id::from_name(requirement.key),
+                    min: requirement.min,
+                    max: requirement.max,
+                }
+            }),
+        })
+    }
+}
+
+impl ToRaw for RecipeData {
+    type RawManifest = RawRecipeManifest;
+
+    fn to_raw(&self, names: &NameLookup) -> Result<RawRecipeData, ManifestError> {
+        let item_counts = |item_counts: &[ItemCount]| {
+            item_counts
+                .iter()
+                .map(|item_count| Ok((names.name(item_count.item_id)?, item_count.count)))
+                .collect::<Result<HashMap<String, u32>, ManifestError>>()
+        };
+        let fluid_amounts = |fluid_counts: &[FluidCount]| {
+            fluid_counts
+                .iter()
+                .map(|fluid_count| {
+                    Ok((
+                        names.name(fluid_count.item_id)?,
+                        fluid_count.amount.as_f64(),
+                    ))
+                })
+                .collect::<Result<HashMap<String, f64>, ManifestError>>()
+        };
+
+        let inputs = match &self.inputs {
+            RecipeInput::Exact(inputs) => RawRecipeInput::Exact(item_counts(inputs)?),
+            RecipeInput::Flexible { tag, count } => RawRecipeInput::Flexible {
+                tag: *tag,
+                count: *count,
+            },
+            RecipeInput::Category { category, count } => RawRecipeInput::Category {
+                tag: names.name(*category)?,
+                count: *count,
+            },
+            RecipeInput::AnyOf(alternatives) => RawRecipeInput::AnyOf(
+                alternatives
+                    .iter()
+                    .map(|alternative| {
+                        Ok(RawInputAlternative {
+                            item: names.name(alternative.item_id)?,
+                            count: alternative.count,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, ManifestError>>()?,
+            ),
+        };
+
+        let mut outputs = HashMap::new();
+        for (item_id, count) in self.outputs.average_counts() {
+            outputs.insert(names.name(item_id)?, RawOutputCount::Count(count));
+        }
+        for output in &self.probabilistic_outputs {
+            outputs.insert(
+                names.name(output.item_count.item_id)?,
+                RawOutputCount::Chance {
+                    count: output.item_count.count,
+                    chance: output.chance,
+                },
+            );
+        }
+        for output in &self.ranged_outputs {
+            outputs.insert(
+                names.name(output.item_id)?,
+                RawOutputCount::Range {
+                    min: output.min,
+                    max: output.max,
+                },
+            );
+        }
+
+        Ok(RawRecipeData {
+            inputs,
+            catalysts: item_counts(&self.catalysts)?,
+            outputs,
+            output_one_of: self
+                .output_one_of
+                .iter()
+                .map(|output| {
+                    Ok(RawWeightedOutput {
+                        item: names.name(output.item_count.item_id)?,
+                        count: output.item_count.count,
+                        weight: output.weight,
+                    })
+                })
+                .collect::<Result<Vec<_>, ManifestError>>()?,
+            byproducts: item_counts(&self.byproducts)?,
+            fluid_inputs: fluid_amounts(&self.fluid_inputs)?,
+            fluid_outputs: fluid_amounts(&self.fluid_outputs)?,
+            craft_time: self.craft_time.as_secs_f32(),
+            work_units: self.work_units,
+            conditions: (self.conditions != RecipeConditions::default())
+                .then(|| self.conditions.clone()),
+            energy: self.energy,
+            allow_mass_gain: self.allow_mass_gain,
+            allow_self_production: self.allow_self_production,
+            requires: self
+                .requires
+                .iter()
+                .map(|&recipe_id| names.name(recipe_id))
+                .collect::<Result<Vec<_>, ManifestError>>()?,
+            research_cost: item_counts(&self.research_cost)?,
+            consumption: self.consumption,
+            overflow: self.overflow,
+            output_slots: self
+                .output_slots
+                .iter()
+                .map(|(&item_id, &slot)| Ok((names.name(item_id)?, slot)))
+                .collect::<Result<HashMap<_, _>, ManifestError>>()?,
+            max_batch: self.max_batch,
+            next_recipe: self
+                .next_recipe
+                .map(|recipe_id| names.name(recipe_id))
+                .transpose()?,
+            output_tier: self.output_tier,
+            output_quality: self.output_quality,
+            category: self.category.clone(),
+            active_phase: self.active_phase,
+            requires_environment: self
+                .requires_environment
+                .map(|requirement| {
+                    Ok(RawEnvironmentRequirement {
+                        key: names.name(requirement.key)?,
+                        min: requirement.min,
+                        max: requirement.max,
+                    })
+                })
+                .transpose()?,
+            // Only enabled recipes are processed
+            enabled: true,
+        })
+    }
+
+    fn raw_manifest(recipes: HashMap<String, RawRecipeData>) -> RawRecipeManifest {
+        RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes,
+        }
+    }
+}
+
+impl RecipeManifest {
+    /// Groups the recipes by their [`RecipeData::category`], for organizing crafting menus.
+    ///
+    /// The recipes of each category are sorted by their [`Id`].
+    pub fn recipes_by_category(&self) -> HashMap<String, Vec<Id<Recipe>>> {
+        let mut categories: HashMap<String, Vec<Id<Recipe>>> = HashMap::new();
+        for (recipe_id, recipe_data) in self.data_map() {
+            categories
+                .entry(recipe_data.category.clone())
+                .or_default()
+                .push(*recipe_id);
+        }
+
+        for recipe_ids in categories.values_mut() {
+            recipe_ids.sort();
+        }
+        categories
+    }
+
+    /// The recipes that can produce the item `item_id`, sorted by their [`Id`].
+    ///
+    /// Every kind of output counts, including probabilistic outputs, byproducts and fluids.
+    pub fn recipes_producing(&self, item_id: Id<Item>) -> Vec<Id<Recipe>> {
+        self.recipes_where(|recipe_data| {
+            recipe_data.output_item_ids().contains(&item_id)
+                || recipe_data
+                    .fluid_outputs
+                    .iter()
+                    .any(|fluid_count| fluid_count.item_id == item_id)
+        })
+    }
+
+    /// The recipes that consume the item `item_id`, sorted by their [`Id`].
+    ///
+    /// Exact inputs, alternative inputs and fluid inputs count.
+    /// Flexible and category inputs do not, as matching them to items needs the [`ItemManifest`],
+    /// and neither do catalysts, as they are not consumed.
+    pub fn recipes_consuming(&self, item_id: Id<Item>) -> Vec<Id<Recipe>> {
+        self.recipes_where(|recipe_data| {
+            let inputs: &[ItemCount] = match &recipe_data.inputs {
+                RecipeInput::Exact(inputs) | RecipeInput::AnyOf(inputs) => inputs,
+                RecipeInput::Flexible { .. } | RecipeInput::Category { .. } => &[],
+            };
+
+            inputs.iter().any(|input| input.item_id == item_id)
+                || recipe_data
+                    .fluid_inputs
+                    .iter()
+                    .any(|fluid_count| fluid_count.item_id == item_id)
+        })
+    }
+
+    /// The recipes whose data matches the `predicate`, sorted by their [`Id`].
+    fn recipes_where(&self, predicate: impl Fn(&RecipeData) -> bool) -> Vec<Id<Recipe>> {
+        let mut recipe_ids: Vec<Id<Recipe>> = self
+            .data_map()
+            .iter()
+            .filter(|(_, recipe_data)| predicate(recipe_data))
+            .map(|(&recipe_id, _)| recipe_id)
+            .collect();
+
+        recipe_ids.sort();
+        recipe_ids
+    }
+
+    /// The names of the recipes that produce more mass than they consume, without being [allowed](RecipeData::allow_mass_gain) to.
+    ///
+    /// See [`RecipeData::is_balanced_mass`] for how the mass is counted.
+    /// The names are returned in sorted order.
+    pub fn mass_gaining_recipes(&self, item_manifest: &ItemManifest) -> Vec<String> {
+        let mut recipe_names: Vec<String> = self
+            .iter()
+            .filter(|(_, recipe_data)| !recipe_data.is_balanced_mass(item_manifest))
+            .map(|(recipe_id, _)| self.name(recipe_id).to_string())
+            .collect();
+
+        recipe_names.sort();
+        recipe_names
+    }
+
+    /// The base items needed to craft a single `target` item, expanding every intermediate item recursively.
+    ///
+    /// Each item is crafted with the recipe with the lowest [`Id`] that lists it among its outputs,
+    /// counting the average yield of stochastic outputs.
+    /// Items that no recipe produces are base items, and count towards the cost themselves,
+    /// while items produced from nothing cost nothing at all.
+    ///
+    /// Only exact inputs and the first of several alternatives are counted,
+    /// as flexible and category inputs cannot be attributed to a single item.
+    /// Catalysts are not consumed, and byproducts are not credited.
+    ///
+    /// Fails with the [`CraftingCycle`] that was found if an item is needed to craft itself.
+    pub fn total_craft_cost(
+        &self,
+        target: Id<Item>,
+    ) -> Result<HashMap<Id<Item>, f64>, CraftingCycle> {
+        let mut cost = HashMap::new();
+        self.add_craft_cost(target, 1., &mut Vec::new(), &mut cost)?;
+        Ok(cost)
+    }
+
+    /// Adds the base items needed to craft `amount` of `item_id` to the `cost`.
+    ///
+    /// The `path` holds the items that are currently being expanded, to detect cycles.
+    fn add_craft_cost(
+        &self,
+        item_id: Id<Item>,
+        amount: f64,
+        path: &mut Vec<Id<Item>>,
+        cost: &mut HashMap<Id<Item>, f64>,
+    ) -> Result<(), CraftingCycle> {
+        if let Some(start) = path.iter().position(|&visited| visited == item_id) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(item_id);
+            return Err(CraftingCycle(cycle));
+        }
+
+        let producer = self
+            .data_map()
+            .iter()
+            .filter_map(|(&recipe_id, recipe_data)| {
+                let (_, count) = recipe_data
+                    .outputs
+                    .average_counts()
+                    .into_iter()
+                    .find(|&(output_id, count)| output_id == item_id && count > 0.)?;
+                Some((recipe_id, recipe_data, count as f64))
+            })
+            .min_by_key(|&(recipe_id, ..)| recipe_id);
+
+        let Some((_, recipe_data, count)) = producer else {
+            *cost.entry(item_id).or_default() += amount;
+            return Ok(());
+        };
+
+        let inputs: &[ItemCount] = match &recipe_data.inputs {
+            RecipeInput::Exact(inputs) => inputs,
+            RecipeInput::AnyOf(alternatives) => alternatives.get(..1).unwrap_or_default(),
+            RecipeInput::Flexible { .. } | RecipeInput::Category { .. } => &[],
+        };
+
+        let crafts = amount / count;
+        path.push(item_id);
+        for input in inputs {
+            self.add_craft_cost(input.item_id, crafts * input.count as f64, path, cost)?;
+        }
+        path.pop();
+
+        Ok(())
+    }
+}
+
+/// A chain of items that are each crafted from the next, as found by [`RecipeManifest::total_craft_cost`].
+///
+/// The first and last item are the same.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CraftingCycle(pub Vec<Id<Item>>);
+
+impl RecipeData {
+    /// The amount of work that a single worker contributes to recipes with [`work_units`](Self::work_units) each second.
+    pub const WORK_PER_WORKER: f32 = 1.;
+
+    /// Are the conditions to craft this recipe met at the current `time_of_day`?
+    pub(crate) fn satisfied(
+        &self,
+        workers: u8,
+        received_light: &ReceivedLight,
+        time_of_day: TimeOfDay,
+    ) -> bool {
+        self.active_phase.is_active(time_of_day)
+            && self.conditions.satisfied(workers, received_light)
+    }
+
+    /// Is the environment of the crafter within the range that this recipe [requires](Self::requires_environment)?
+    ///
+    /// Recipes without an environmental requirement can be crafted anywhere.
+    pub(crate) fn environment_satisfied(
+        &self,
+        environment_sample: &EnvironmentSample,
+        environment_manifest: &EnvironmentManifest,
+    ) -> bool {
+        self.requires_environment.is_none_or(|requirement| {
+            requirement.satisfied_by(environment_sample, environment_manifest)
+        })
+    }
+
+    /// The items that are still needed to craft this recipe from the `inventory`, and how many of each are missing.
+    ///
+    /// This covers the exact inputs and the catalysts.
+    /// Flexible, category and alternative inputs can be satisfied by many different items,
+    /// so their shortfall cannot be attributed to a single item and is not listed here.
+    /// Use [`RecipeData::can_craft_with`] to check those as well.
+    pub fn missing_inputs(&self, inventory: &Inventory) -> Vec<ItemCount> {
+        let exact_inputs = match &self.inputs {
+            RecipeInput::Exact(inputs) => inputs.as_slice(),
+            RecipeInput::Flexible { .. } | RecipeInput::Category { .. } | RecipeInput::AnyOf(_) => {
+                &[]
+            }
+        };
+
+        exact_inputs
+            .iter()
+            .chain(self.catalysts.iter())
+            .filter_map(|required| {
+                let shortfall = required
+                    .count
+                    .saturating_sub(inventory.item_count(required.item_id));
+                (shortfall > 0).then(|| ItemCount::new(required.item_id, shortfall))
+            })
+            .collect()
+    }
+
+    /// Does the `inventory` contain everything needed to craft this recipe?
+    ///
+    /// This includes the catalysts, and any items that match flexible, category or alternative inputs.
+    pub fn can_craft_with(&self, inventory: &Inventory, item_manifest: &ItemManifest) -> bool {
+        if !self.missing_inputs(inventory).is_empty() {
+            return false;
+        }
+
+        match self.inputs {
+            RecipeInput::Exact(_) => true,
+            RecipeInput::Flexible { tag, count } => {
+                let available: u32 = inventory
+                    .iter()
+                    .filter(|item_slot| item_manifest.has_tag(item_slot.item_id(), tag))
+                    .map(|item_slot| item_slot.count())
+                    .sum();
+                available >= count
+            }
+            RecipeInput::Category { category, count } => {
+                let available: u32 = item_manifest
+                    .items_with_tag(category)
+                    .into_iter()
+                    .map(|item_id| inventory.item_count(item_id))
+                    .sum();
+                available >= count
+            }
+            RecipeInput::AnyOf(ref alternatives) => {
+                RecipeInput::first_available_alternative(alternatives, |item_id| {
+                    inventory.item_count(item_id)
+                })
+                .is_some()
+            }
+        }
+    }
+
+    /// An inventory with empty slots for all of the inputs of this recipe.
+    pub(crate) fn input_inventory(&self, item_manifest: &ItemManifest) -> InputInventory {
+        match self.inputs {
+            RecipeInput::Exact(ref inputs) => {
+                let mut inventory = Inventory::new(self.inputs.len() + self.catalysts.len(), None);
+
+                for item_count in inputs.iter().chain(self.catalysts.iter()) {
+                    inventory.add_empty_slot(item_count.item_id, item_manifest);
+                }
+
+                InputInventory::Exact { inventory }
+            }
+            RecipeInput::Flexible { tag, .. } => InputInventory::Tagged {
+                tag,
+                inventory: Inventory::new(1, None),
+            },
+            // Reserve a slot for every matching item, so that units know which items to deliver
+            RecipeInput::Category { category, .. } => {
+                let item_ids = item_manifest.items_with_tag(category);
+                let mut inventory = Inventory::new(item_ids.len() + self.catalysts.len(), None);
+
+                for item_id in item_ids
+                    .into_iter()
+                    .chain(self.catalysts.iter().map(|catalyst| catalyst.item_id))
+                {
+                    inventory.add_empty_slot(item_id, item_manifest);
+                }
+
+                InputInventory::Exact { inventory }
+            }
+            // Reserve a slot for every alternative, as any of them may be delivered
+            RecipeInput::AnyOf(ref alternatives) => {
+                let mut inventory = Inventory::new(alternatives.len() + self.catalysts.len(), None);
+
+                for item_count in alternatives.iter().chain(self.catalysts.iter()) {
+                    inventory.add_empty_slot(item_count.item_id, item_manifest);
+                }
+
+                InputInventory::Exact { inventory }
+            }
+        }
+    }
+
+    /// The [`Id<Item>`]s of all items that this recipe can produce, including probabilistic ones and byproducts.
+    pub fn output_item_ids(&self) -> Vec<Id<Item>> {
+        let mut item_ids = self.outputs.item_ids();
+        item_ids.extend(
+            self.probabilistic_outputs
+                .iter()
+                .map(|output| output.item_count.item_id),
+        );
+        item_ids.extend(self.ranged_outputs.iter().map(|output| output.item_id));
+        // Several weighted entries may produce different amounts of the same item
+        for output in &self.output_one_of {
+            if !item_ids.contains(&output.item_count.item_id) {
+                item_ids.push(output.item_count.item_id);
+            }
+        }
+        item_ids.extend(self.byproducts.iter().map(|byproduct| byproduct.item_id));
+        item_ids
+    }
+
+    /// Rolls for the items produced by completing this recipe once.
+    ///
+    /// Stochastic outputs produce one extra item with a chance equal to the fractional part of their count.
+    /// Byproducts are always produced, probabilistic and ranged outputs are rolled for separately,
+    /// and one of the weighted outputs is picked.
+    pub fn roll_outputs(&self, rng: &mut impl Rng) -> Vec<ItemCount> {
+        let mut produced = match &self.outputs {
+            RecipeOutput::Deterministic(outputs) => outputs.clone(),
+            RecipeOutput::Stochastic(outputs) => {
+                let distribution = Uniform::new(0.0, 1.0);
+                outputs
+                    .iter()
+                    .map(|(item_id, number)| {
+                        // Always produce items equal to quotient,
+                        // and then produce one extra items with probability remainder.
+                        let (quotient, remainder) = (number / 1.0, number % 1.0);
+                        let count = if remainder == 0. || distribution.sample(rng) > remainder {
+                            quotient as u32
+                        } else {
+                            quotient as u32 + 1
+                        };
+
+                        ItemCount::new(*item_id, count)
+                    })
+                    .collect()
+            }
+        };
+
+        produced.extend(self.byproducts.iter().cloned());
+        produced.extend(
+            self.probabilistic_outputs
+                .iter()
+                .filter_map(|probabilistic_output| probabilistic_output.roll(rng)),
+        );
+        produced.extend(
+            self.ranged_outputs
+                .iter()
+                .map(|ranged_output| ranged_output.roll(rng)),
+        );
+        produced.extend(WeightedOutput::pick(&self.output_one_of, rng));
+
+        produced
+    }
+
+    /// The output slot that items of kind `item_id` produced by this recipe are stored in.
+    pub fn output_slot(&self, item_id: Id<Item>) -> OutputSlot {
+        self.output_slots.get(&item_id).copied().unwrap_or_default()
+    }
+
+    /// An inventory with empty slots for all of the outputs of this recipe.
+    ///
+    /// Each [`OutputSlot`] that the outputs are routed to gets its own partition.
+    pub(crate) fn output_inventory(&self, item_manifest: &ItemManifest) -> OutputInventory {
+        let item_ids = self.output_item_ids();
+        let partition = |output_slot: OutputSlot| {
+            let item_ids: Vec<Id<Item>> = item_ids
+                .iter()
+                .copied()
+                .filter(|&item_id| self.output_slot(item_id) == output_slot)
+                .collect();
+            let mut inventory = Inventory::new(item_ids.len(), None);
+            for item_id in item_ids {
+                inventory.add_empty_slot(item_id, item_manifest);
+            }
+            inventory
+        };
+
+        OutputInventory {
+            inventory: partition(OutputSlot::Product),
+            partitions: self
+                .output_slots
+                .values()
+                .filter(|&&output_slot| output_slot != OutputSlot::Product)
+                .map(|&output_slot| (output_slot, partition(output_slot)))
+                .collect(),
+        }
+    }
+
+    /// The most items that completing this recipe once could produce, if every roll produced as much as it can.
+    ///
+    /// Every weighted output is included, even though only one of them is picked,
+    /// so this may overestimate the outputs of recipes with several weighted outputs.
+    fn max_outputs(&self) -> Vec<ItemCount> {
+        let mut outputs = match &self.outputs {
+            RecipeOutput::Deterministic(outputs) => outputs.clone(),
+            RecipeOutput::Stochastic(outputs) => outputs
+                .iter()
+                .map(|(item_id, number)| ItemCount::new(*item_id, number.ceil() as u32))
+                .collect(),
+        };
+
+        outputs.extend(self.byproducts.iter().cloned());
+        outputs.extend(
+            self.probabilistic_outputs
+                .iter()
+                .map(|probabilistic_output| probabilistic_output.item_count.clone()),
+        );
+        outputs.extend(
+            self.ranged_outputs
+                .iter()
+                .map(|ranged_output| ItemCount::new(ranged_output.item_id, ranged_output.max)),
+        );
+        outputs.extend(
+            self.output_one_of
+                .iter()
+                .map(|weighted_output| weighted_output.item_count.clone()),
+        );
+
+        outputs
+    }
+
+    /// The number of units of this recipe to craft in the next crafting cycle, between 1 and [`RecipeData::max_batch`].
+    ///
+    /// This is the largest batch whose inputs are all in the `input` inventory,
+    /// and whose outputs are sure to fit in the `output` inventory.
+    /// If no larger batch is possible, a single unit is crafted, whose outputs are handled by the usual [`OverflowPolicy`].
+    ///
+    /// Only recipes that consume their inputs [`ConsumptionMode::AtStart`] and do not involve [`energy`](Self::energy) are crafted in batches,
+    /// as their whole cost can be paid up front.
+    pub(crate) fn batch_size(
+        &self,
+        input: &InputInventory,
+        output: &OutputInventory,
+        item_manifest: &ItemManifest,
+    ) -> u32 {
+        if self.consumption != ConsumptionMode::AtStart || self.energy.is_some() {
+            return 1;
+        }
+
+        // Checking the inputs is cheap, while checking the outputs needs a copy of the output inventory
+        let affordable = largest_batch(self.max_batch, |batch| {
+            self.inputs
+                .times(batch)
+                .is_some_and(|inputs| input.items_to_consume(&inputs, item_manifest).is_ok())
+        });
+
+        let max_outputs = self.max_outputs();
+        let quality = input.output_quality(self);
+        largest_batch(affordable, |batch| {
+            scaled_item_counts(&max_outputs, batch).is_some_and(|outputs| {
+                output.clone().store(
+                    outputs,
+                    &self.output_slots,
+                    OverflowPolicy::Block,
+                    quality,
+                    item_manifest,
+                ) == StoredOutputs::Stored
+            })
+        })
+    }
+
+    /// The number of workers this recipe needs to be crafted at full speed.
+    pub(crate) fn workers_required(&self) -> u8 {
+        self.conditions.workers_required
+    }
+
+    /// Can the recipe be completed by a structure with the provided `energy_pool`?
+    ///
+    /// Only recipes that consume energy can be unaffordable, and only for living structures.
+    /// Completing the recipe never brings the energy pool below zero.
+    pub(crate) fn can_afford_energy(&self, energy_pool: Option<&EnergyPool>) -> bool {
+        match (self.energy, energy_pool) {
+            (Some(energy), Some(energy_pool)) if energy < Energy(0.) => {
+                energy_pool.current().0 >= -energy.0
+            }
+            _ => true,
+        }
+    }
+
+    /// The largest amount of mass, in kilograms, that a recipe may gain while still counting as balanced.
+    ///
+    /// This absorbs rounding errors in the masses of items.
+    pub const MASS_TOLERANCE: f32 = 1e-3;
+
+    /// Does this recipe produce no more mass than it consumes, or is it [allowed](Self::allow_mass_gain) to?
+    ///
+    /// Both sides count the average mass of the items involved, including fluids, probabilistic and ranged outputs,
+    /// byproducts and the weighted average of the [`output_one_of`](Self::output_one_of).
+    /// Inputs that can be fulfilled by several items count as the lightest of them,
+    /// as that is the least mass the recipe may consume.
+    /// Items that are not in the `item_manifest` count as weightless.
+    pub fn is_balanced_mass(&self, item_manifest: &ItemManifest) -> bool {
+        if self.allow_mass_gain {
+            return true;
+        }
+
+        let mass = |item_id: Id<Item>| {
+            item_manifest
+                .data_map()
+                .get(&item_id)
+                .map_or(0., |item_data| item_data.mass)
+        };
+        let lightest = |item_ids: Vec<Id<Item>>| {
+            item_ids
+                .into_iter()
+                .map(mass)
+                .reduce(f32::min)
+                .unwrap_or(0.)
+        };
+        let fluid_mass = |fluid_counts: &[FluidCount]| -> f32 {
+            fluid_counts
+                .iter()
+                .map(|fluid_count| fluid_count.amount.as_f64() as f32 * mass(fluid_count.item_id))
+                .sum()
+        };
+
+        let input_mass = match &self.inputs {
+            RecipeInput::Exact(inputs) => inputs
+                .iter()
+                .map(|input| input.count as f32 * mass(input.item_id))
+                .sum::<f32>(),
+            RecipeInput::AnyOf(alternatives) => alternatives
+                .iter()
+                .map(|alternative| alternative.count as f32 * mass(alternative.item_id))
+                .reduce(f32::min)
+                .unwrap_or(0.),
+            RecipeInput::Flexible { tag, count } => {
+                let matching = item_manifest
+                    .variants()
+                    .into_iter()
+                    .filter(|&item_id| item_manifest.has_tag(item_id, *tag))
+                    .collect();
+                *count as f32 * lightest(matching)
+            }
+            RecipeInput::Category { category, count } => {
+                *count as f32 * lightest(item_manifest.items_with_tag(*category))
+            }
+        } + fluid_mass(&self.fluid_inputs);
+
+        let total_weight: f32 = self.output_one_of.iter().map(|output| output.weight).sum();
+        let weighted_mass: f32 = if total_weight > 0. {
+            self.output_one_of
+                .iter()
+                .map(|output| {
+                    output.weight / total_weight
+                        * output.item_count.count as f32
+                        * mass(output.item_count.item_id)
+                })
+                .sum()
+        } else {
+            0.
+        };
+
+        let output_mass = self
+            .outputs
+            .average_counts()
+            .into_iter()
+            .map(|(item_id, count)| count * mass(item_id))
+            .sum::<f32>()
+            + self
+                .probabilistic_outputs
+                .iter()
+                .map(|output| {
+                    output.chance * output.item_count.count as f32 * mass(output.item_count.item_id)
+                })
+                .sum::<f32>()
+            + self
+                .ranged_outputs
+                .iter()
+                .map(|output| output.average() * mass(output.item_id))
+                .sum::<f32>()
+            + self
+                .byproducts
+                .iter()
+                .map(|byproduct| byproduct.count as f32 * mass(byproduct.item_id))
+                .sum::<f32>()
+            + weighted_mass
+            + fluid_mass(&self.fluid_outputs);
+
+        output_mass <= input_mass + Self::MASS_TOLERANCE
+    }
+
+    /// The fraction of the full crafting speed reached with the given number of `effective_workers`.
+    ///
+    /// Recipes that need no workers always progress at full speed.
+    /// Otherwise, each worker contributes an equal share of the speed, up to the number of workers required.
+    /// Additional workers do not speed up crafting any further.
+    ///
+    /// As progress is tracked as a [`Duration`], partial crews accumulate it more slowly, but never lose it:
+    /// two out of four workers take twice as long to complete the recipe.
+    ///
+    /// Recipes with fixed [`work_units`](Self::work_units) are instead sped up by every worker present,
+    /// so that the recipe completes once the workers have done that much work together.
+    pub(crate) fn work_rate(&self, effective_workers: f32) -> f32 {
+        match (self.workers_required(), self.work_units) {
+            (0, _) => 1.,
+            (_, Some(work_units)) => {
+                effective_workers.max(0.) * Self::WORK_PER_WORKER * self.craft_time.as_secs_f32()
+                    / work_units
+            }
+            (workers_required, None) => (effective_workers / workers_required as f32).clamp(0., 1.),
+        }
+    }
+
+    /// Does this recipe need workers to produce?
+    pub fn work_kind(&self) -> WorkKind {
+        self.conditions.work_kind()
+    }
+
+    /// The net change in the number of each item caused by crafting this recipe once.
+    ///
+    /// Consumed inputs count as negative, while outputs and byproducts count as positive.
+    /// Items that are both consumed and produced are reported as the difference.
+    ///
+    /// Only the guaranteed yield is counted: stochastic outputs are rounded down, ranged outputs count their minimum,
+    /// and probabilistic and weighted outputs are ignored.
+    /// Catalysts are not consumed, and flexible, category or alternative inputs cannot be attributed to a single item,
+    /// so none of them show up here.
+    pub fn net_item_flow(&self) -> HashMap<Id<Item>, i64> {
+        let mut flow = HashMap::new();
+
+        if let RecipeInput::Exact(inputs) = &self.inputs {
+            for input in inputs {
+                *flow.entry(input.item_id).or_default() -= input.count as i64;
+            }
+        }
+
+        let outputs = match &self.outputs {
+            RecipeOutput::Deterministic(outputs) => outputs.clone(),
+            RecipeOutput::Stochastic(outputs) => outputs
+                .iter()
+                .map(|&(item_id, count)| ItemCount::new(item_id, count as u32))
+                .collect(),
+        };
+
+        for output in outputs.iter().chain(self.byproducts.iter()) {
+            *flow.entry(output.item_id).or_default() += output.count as i64;
+        }
+
+        for output in &self.ranged_outputs {
+            *flow.entry(output.item_id).or_default() += output.min as i64;
+        }
+
+        flow
+    }
+
+    /// The average net change in the number of each item per second, when this recipe is crafted continuously.
+    ///
+    /// Unlike [`RecipeData::net_item_flow`], this uses the expected yield of all random outputs.
+    /// Time spent waiting for inputs or workers is not taken into account.
+    /// Recipes that take no time to craft have no meaningful rate, and return an empty map.
+    pub fn items_per_second(&self) -> HashMap<Id<Item>, f64> {
+        let craft_time = self.craft_time.as_secs_f64();
+        if craft_time == 0. {
+            return HashMap::new();
+        }
+
+        self.expected_item_flow()
+            .into_iter()
+            .map(|(item_id, count)| (item_id, count / craft_time))
+            .collect()
+    }
+
+    /// The average net change in the number of each item caused by crafting this recipe once.
+    ///
+    /// Unlike [`RecipeData::net_item_flow`], this uses the expected yield of all random outputs.
+    /// As there, only exact inputs are counted.
+    pub fn expected_item_flow(&self) -> HashMap<Id<Item>, f64> {
+        let mut flow: HashMap<Id<Item>, f64> = HashMap::new();
+
+        if let RecipeInput::Exact(inputs) = &self.inputs {
+            for input in inputs {
+                *flow.entry(input.item_id).or_default() -= input.count as f64;
+            }
+        }
+
+        match &self.outputs {
+            RecipeOutput::Deterministic(outputs) => {
+                for output in outputs {
+                    *flow.entry(output.item_id).or_default() += output.count as f64;
+                }
+            }
+            RecipeOutput::Stochastic(outputs) => {
+                for &(item_id, count) in outputs {
+                    *flow.entry(item_id).or_default() += count as f64;
+                }
+            }
+        }
+
+        for output in &self.probabilistic_outputs {
+            *flow.entry(output.item_count.item_id).or_default() +=
+                output.item_count.count as f64 * output.chance.clamp(0., 1.) as f64;
+        }
+
+        for output in &self.ranged_outputs {
+            *flow.entry(output.item_id).or_default() += output.average() as f64;
+        }
+
+        let total_weight: f64 = self
+            .output_one_of
+            .iter()
+            .map(|output| output.weight.max(0.) as f64)
+            .sum();
+        if total_weight > 0. {
+            for output in &self.output_one_of {
+                *flow.entry(output.item_count.item_id).or_default() +=
+                    output.item_count.count as f64 * output.weight.max(0.) as f64 / total_weight;
+            }
+        }
+
+        for byproduct in &self.byproducts {
+            *flow.entry(byproduct.item_id).or_default() += byproduct.count as f64;
+        }
+
+        flow
+    }
+
+    /// The inputs of this recipe that it produces more of than it consumes, sorted by [`Id`].
+    ///
+    /// Crafting such a recipe over and over yields an endless supply of these items,
+    /// so this is empty for recipes that are [allowed](Self::allow_self_production) to do so.
+    pub fn self_produced_items(&self) -> Vec<Id<Item>> {
+        let RecipeInput::Exact(inputs) = &self.inputs else {
+            return Vec::new();
+        };
+        if self.allow_self_production {
+            return Vec::new();
+        }
+
+        let flow = self.expected_item_flow();
+        let mut item_ids: Vec<Id<Item>> = inputs
+            .iter()
+            .map(|input| input.item_id)
+            .filter(|item_id| flow.get(item_id).is_some_and(|&net| net > 0.))
+            .collect();
+
+        item_ids.sort();
+        item_ids.dedup();
+        item_ids
+    }
+
+    /// The pretty formatting of the weighted pick, if this recipe has one.
+    fn display_output_one_of(&self, item_manifest: &ItemManifest) -> Option<String> {
+        if self.output_one_of.is_empty() {
+            return None;
+        }
+
+        let total_weight: f32 = self
+            .output_one_of
+            .iter()
+            .map(|output| output.weight.max(0.))
+            .sum();
+
+        let entries = self
+            .output_one_of
+            .iter()
+            .map(|output| {
+                format!(
+                    "{} ({:.0}%)",
+                    output.item_count.display(item_manifest),
+                    output.weight.max(0.) / total_weight * 100.
+                )
+            })
+            .join(" or ");
+
+        Some(format!("one of {entries}"))
+    }
+
+    /// The pretty formatting of this type
+    pub(crate) fn display(&self, item_manifest: &ItemManifest) -> String {
+        let input_str: String = match self.inputs {
+            RecipeInput::Exact(ref inputs) => inputs
+                .iter()
                 .map(|input| input.display(item_manifest))
                 .join(", "),
             RecipeInput::Flexible { tag, count } => format!("{count}x {tag}"),
+            RecipeInput::Category { category, count } => format!(
+                "{count}x any of {}",
+                item_manifest
+                    .items_with_tag(category)
+                    .into_iter()
+                    .map(|item_id| item_manifest.name(item_id))
+                    .join(", ")
+            ),
+            RecipeInput::AnyOf(ref alternatives) => alternatives
+                .iter()
+                .map(|alternative| alternative.display(item_manifest))
+                .join(" or "),
+        };
+
+        let output_strings: Vec<String> = self
+            .outputs
+            .item_ids()
+            .iter()
+            .map(|output_id| item_manifest.name(*output_id).to_string())
+            .chain(self.probabilistic_outputs.iter().map(|output| {
+                format!(
+                    "{} ({:.0}%)",
+                    item_manifest.name(output.item_count.item_id),
+                    output.chance * 100.
+                )
+            }))
+            .chain(self.ranged_outputs.iter().map(|output| {
+                format!(
+                    "{} ({}-{})",
+                    item_manifest.name(output.item_id),
+                    output.min,
+                    output.max
+                )
+            }))
+            .chain(self.display_output_one_of(item_manifest))
+            .collect();
+        let mut output_str = output_strings.join(", ");
+        if !self.byproducts.is_empty() {
+            let byproduct_str = self
+                .byproducts
+                .iter()
+                .map(|byproduct| item_manifest.name(byproduct.item_id))
+                .join(", ");
+            output_str = format!("{output_str} + byproducts: {byproduct_str}");
+        }
+
+        let duration_str = format!("{:.2}", self.craft_time.as_secs_f32());
+
+        let condition_str = if self.conditions == RecipeConditions::default() {
+            String::new()
+        } else {
+            format!("\nwhen {}", self.conditions)
+        };
+
+        format!("[{input_str}] -> [{output_str}] | {duration_str} s{condition_str}")
+    }
+}
+
+/// The environmental conditions needed for work to be done on a recipe.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default, JsonSchema)]
+#[cfg_attr(feature = "strict_manifests", serde(deny_unknown_fields))]
+pub struct RecipeConditions {
+    /// The number of workers required to advance this recipe at full speed.
+    ///
+    /// If this is more than zero, at least one worker must be present to make any progress.
+    /// Fewer workers than this make proportionally slower progress.
+    pub workers_required: u8,
+    /// The range of light levels that are acceptable for this recipe.
+    pub allowable_light_range: Option<Threshold<Illuminance>>,
+}
+
+/// Whether or not a recipe needs workers to make progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkKind {
+    /// The recipe progresses on its own.
+    Passive,
+    /// The recipe only progresses while workers are present.
+    ManualWork,
+}
+
+impl Display for RecipeConditions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.workers_required > 0 {
+            write!(f, "Workers: {}", self.workers_required)?;
+        }
+        if let Some(range) = &self.allowable_light_range {
+            write!(f, "Light: {}", *range)?;
+        }
+        Ok(())
+    }
+}
+
+impl RecipeConditions {
+    /// No conditions are required to craft this recipe.
+    pub const NONE: RecipeConditions = RecipeConditions {
+        workers_required: 0,
+        allowable_light_range: None,
+    };
+
+    /// Creates a new [`RecipeConditions`].
+    pub const fn new(workers_required: u8, allowable_light_range: Threshold<Illuminance>) -> Self {
+        Self {
+            workers_required,
+            allowable_light_range: Some(allowable_light_range),
+        }
+    }
+
+    /// Does work need to be done to craft this recipe?
+    ///
+    /// Any recipe that requires at least one worker needs manual work.
+    pub fn work_kind(&self) -> WorkKind {
+        match self.workers_required {
+            0 => WorkKind::Passive,
+            _ => WorkKind::ManualWork,
+        }
+    }
+
+    /// Are the conditions to craft this recipe met?
+    fn satisfied(&self, workers: u8, received_light: &ReceivedLight) -> bool {
+        let work_satisfied = self.workers_required == 0 || workers > 0;
+        let light_satisfied = self
+            .allowable_light_range
+            .as_ref()
+            .map_or(true, |range| range.contains(received_light.0));
+
+        work_satisfied && light_satisfied
+    }
+}
+
+/// The time of day during which a recipe can be crafted.
+///
+/// Outside of this phase, crafting does not start, and recipes in progress hold their progress without consuming any inputs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum TimePhase {
+    /// The recipe can be crafted at any time.
+    #[default]
+    Always,
+    /// The recipe can only be crafted while the sun is out.
+    Day,
+    /// The recipe can only be crafted while the sun is down.
+    Night,
+}
+
+impl TimePhase {
+    /// Can recipes with this phase be crafted at the given `time_of_day`?
+    pub fn is_active(&self, time_of_day: TimeOfDay) -> bool {
+        match self {
+            TimePhase::Always => true,
+            TimePhase::Day => time_of_day == TimeOfDay::Day,
+            TimePhase::Night => time_of_day == TimeOfDay::Night,
+        }
+    }
+}
+
+/// A viable range of a value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Threshold<T: PartialOrd> {
+    /// The minimum value of the range.
+    min: T,
+    /// The maximum value of the range.
+    max: T,
+}
+
+impl<T: PartialOrd> Threshold<T> {
+    /// Creates a new [`Threshold`].
+    pub fn new(min: T, max: T) -> Self {
+        assert!(min <= max);
+
+        Self { min, max }
+    }
+
+    /// Returns true if the value is within the threshold.
+    fn contains(&self, value: T) -> bool {
+        self.min <= value && value <= self.max
+    }
+}
+
+impl<T: Display + PartialOrd> Display for Threshold<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} - {}", self.min, self.max)
+    }
+}
+
+/// The [`RecipeManifest`] as seen in the manifest file.
+#[derive(Debug, Clone, Serialize, Deserialize, TypeUuid, PartialEq, JsonSchema, RawManifest)]
+#[uuid = "c711b30c-c3ff-4b86-92d0-f1aff2ec7818"]
+#[raw_manifest(
+    extension = "recipe_manifest",
+    marker = Recipe,
+    data = RecipeData,
+    process = Self::process_recipes,
+    validate = Self::validate_recipes
+)]
+pub struct RawRecipeManifest {
+    /// The version of the manifest format that this file is written in.
+    ///
+    /// This must match [`IsRawManifest::CURRENT_VERSION`].
+    pub version: u32,
+    /// The data for each item.
+    #[schemars(with = "std::collections::HashMap<String, RawRecipeData>")]
+    pub recipes: HashMap<String, RawRecipeData>,
+}
+
+impl RawRecipeManifest {
+    /// A JSON Schema describing the recipe manifest files, for use by editors.
+    pub fn json_schema() -> RootSchema {
+        schema_for!(RawRecipeManifest)
+    }
+
+    /// Finds the numeric fields of all recipes that hold nonsensical values, sorted by recipe and field.
+    ///
+    /// See [`RawRecipeData::out_of_range_values`] for the ranges that are checked.
+    pub fn out_of_range_values(&self) -> Vec<OutOfRangeValue> {
+        let mut out_of_range_values: Vec<OutOfRangeValue> = self
+            .recipes
+            .iter()
+            .flat_map(|(recipe_name, raw_data)| raw_data.out_of_range_values(recipe_name))
+            .collect();
+
+        out_of_range_values.sort();
+        out_of_range_values
+    }
+
+    /// The recipes that are [enabled](RawRecipeData::enabled), and should be part of the game.
+    pub fn enabled_recipes(&self) -> impl Iterator<Item = (&String, &RawRecipeData)> {
+        self.recipes.iter().filter(|(_, raw_data)| raw_data.enabled)
+    }
+
+    /// Checks that every item used as an input, catalyst, output, byproduct, fluid or research cost of a recipe exists in the `item_manifest`.
+    ///
+    /// Category inputs must name a tag that at least one item is labeled with.
+    /// All unknown items and tags are reported at once, together with the recipe that references them.
+    /// If there are recipes but no items at all, a single [`ManifestError::NoItems`] is reported instead,
+    /// as listing every item of every recipe would hide the actual problem.
+    pub fn validate_items(&self, item_manifest: &ItemManifest) -> Result<(), ManifestError> {
+        if item_manifest.is_empty() && self.enabled_recipes().next().is_some() {
+            return Err(ManifestError::NoItems);
+        }
+
+        let mut dangling_references = Vec::new();
+
+        for (recipe_name, raw_data) in self.enabled_recipes() {
+            let input_names: Vec<&String> = match &raw_data.inputs {
+                RawRecipeInput::Exact(inputs) => inputs.keys().collect(),
+                RawRecipeInput::Flexible { .. } => Vec::new(),
+                RawRecipeInput::AnyOf(alternatives) => alternatives
+                    .iter()
+                    .map(|alternative| &alternative.item)
+                    .collect(),
+                RawRecipeInput::Category { tag, .. } => {
+                    if item_manifest
+                        .items_with_tag(Id::from_name(tag.clone()))
+                        .is_empty()
+                    {
+                        dangling_references.push(DanglingReference {
+                            entry: recipe_name.clone(),
+                            name: tag.clone(),
+                        });
+                    }
+
+                    Vec::new()
+                }
+            };
+
+            for item_name in input_names
+                .into_iter()
+                .chain(raw_data.catalysts.keys())
+                .chain(raw_data.outputs.keys())
+                .chain(raw_data.byproducts.keys())
+                .chain(raw_data.output_one_of.iter().map(|output| &output.item))
+                .chain(raw_data.research_cost.keys())
+                .chain(raw_data.fluid_inputs.keys())
+                .chain(raw_data.fluid_outputs.keys())
+            {
+                let item_id = Id::<Item>::from_name(item_name.clone());
+
+                if !item_manifest.data_map().contains_key(&item_id) {
+                    dangling_references.push(DanglingReference {
+                        entry: recipe_name.clone(),
+                        name: item_name.clone(),
+                    });
+                }
+            }
+        }
+
+        if dangling_references.is_empty() {
+            Ok(())
+        } else {
+            dangling_references.sort();
+            Err(ManifestError::UnknownReference(dangling_references))
+        }
+    }
+
+    /// Finds the prerequisites that do not name an enabled recipe.
+    ///
+    /// Disabled recipes are left out of the game, so they can never be discovered.
+    pub fn unknown_prerequisites(&self) -> Vec<DanglingReference> {
+        let mut dangling_references: Vec<DanglingReference> = self
+            .enabled_recipes()
+            .flat_map(|(recipe_name, raw_data)| {
+                raw_data
+                    .requires
+                    .iter()
+                    .filter(|required| !self.recipes.get(*required).is_some_and(|raw| raw.enabled))
+                    .map(|required| DanglingReference {
+                        entry: recipe_name.clone(),
+                        name: required.clone(),
+                    })
+            })
+            .collect();
+
+        dangling_references.sort();
+        dangling_references
+    }
+
+    /// Finds a set of recipes that require each other, so that none of them could ever be discovered.
+    ///
+    /// The recipes are returned in the order in which they require each other,
+    /// with each one requiring the next, and the last one requiring the first.
+    /// Only one cycle is reported, and the search is deterministic.
+    pub fn prerequisite_cycle(&self) -> Option<Vec<String>> {
+        /// How far the search has progressed for a recipe.
+        #[derive(Clone, Copy, PartialEq, Eq)]
+        enum Visit {
+            /// The recipe is on the current path.
+            InProgress,
+            /// All prerequisites of the recipe have been searched, without finding a cycle.
+            Done,
+        }
+
+        /// Searches the prerequisites of `recipe_name`, returning the first cycle found.
+        fn visit<'a>(
+            recipe_name: &'a String,
+            raw_manifest: &'a RawRecipeManifest,
+            visits: &mut HashMap<&'a String, Visit>,
+            path: &mut Vec<&'a String>,
+        ) -> Option<Vec<String>> {
+            match visits.get(recipe_name) {
+                Some(Visit::Done) => return None,
+                Some(Visit::InProgress) => {
+                    let start = path.iter().position(|name| *name == recipe_name)?;
+                    return Some(path[start..].iter().map(|name| name.to_string()).collect());
+                }
+                None => (),
+            }
+
+            let raw_data = raw_manifest.recipes.get(recipe_name)?;
+            visits.insert(recipe_name, Visit::InProgress);
+            path.push(recipe_name);
+
+            for required in &raw_data.requires {
+                if let Some(cycle) = visit(required, raw_manifest, visits, path) {
+                    return Some(cycle);
+                }
+            }
+
+            path.pop();
+            visits.insert(recipe_name, Visit::Done);
+            None
+        }
+
+        let mut recipe_names: Vec<&String> = self.enabled_recipes().map(|(name, _)| name).collect();
+        recipe_names.sort();
+
+        let mut visits = HashMap::new();
+        recipe_names
+            .into_iter()
+            .find_map(|recipe_name| visit(recipe_name, self, &mut visits, &mut Vec::new()))
+    }
+
+    /// Finds the environmental requirements whose key is not in the `environment_manifest`.
+    pub fn unknown_environment_keys(
+        &self,
+        environment_manifest: &EnvironmentManifest,
+    ) -> Vec<DanglingReference> {
+        let mut dangling_references: Vec<DanglingReference> = self
+            .enabled_recipes()
+            .filter_map(|(recipe_name, raw_data)| {
+                let requirement = raw_data.requires_environment.as_ref()?;
+                (!environment_manifest.contains_name(&requirement.key)).then(|| DanglingReference {
+                    entry: recipe_name.clone(),
+                    name: requirement.key.clone(),
+                })
+            })
+            .collect();
+
+        dangling_references.sort();
+        dangling_references
+    }
+
+    /// Finds the next recipes that do not name an enabled recipe.
+    ///
+    /// Crafters cannot switch to recipes that are not part of the game.
+    pub fn unknown_next_recipes(&self) -> Vec<DanglingReference> {
+        let mut dangling_references: Vec<DanglingReference> = self
+            .enabled_recipes()
+            .filter_map(|(recipe_name, raw_data)| {
+                let next_recipe = raw_data.next_recipe.as_ref()?;
+                (!self.recipes.get(next_recipe).is_some_and(|raw| raw.enabled)).then(|| {
+                    DanglingReference {
+                        entry: recipe_name.clone(),
+                        name: next_recipe.clone(),
+                    }
+                })
+            })
+            .collect();
+
+        dangling_references.sort();
+        dangling_references
+    }
+
+    /// Finds a set of recipes that lead into each other as their [next recipe](RawRecipeData::next_recipe).
+    ///
+    /// Crafters would follow such a chain forever, without ever returning to the recipe they were set to.
+    /// The recipes are returned in the order in which they lead into each other,
+    /// with the last one leading into the first.
+    /// Only one cycle is reported, and the search is deterministic.
+    pub fn recipe_chain_cycle(&self) -> Option<Vec<String>> {
+        let mut recipe_names: Vec<&String> = self.enabled_recipes().map(|(name, _)| name).collect();
+        recipe_names.sort();
+
+        // Recipes whose chain has been followed to its end
+        let mut finished: HashSet<&String> = HashSet::new();
+
+        for recipe_name in recipe_names {
+            let mut path: Vec<&String> = Vec::new();
+            let mut current = Some(recipe_name);
+
+            while let Some(name) = current {
+                if finished.contains(name) {
+                    break;
+                }
+
+                if let Some(start) = path.iter().position(|visited| *visited == name) {
+                    return Some(path[start..].iter().map(|name| name.to_string()).collect());
+                }
+
+                path.push(name);
+                current = self
+                    .recipes
+                    .get(name)
+                    .and_then(|raw_data| raw_data.next_recipe.as_ref());
+            }
+
+            finished.extend(path);
+        }
+
+        None
+    }
+
+    /// Finds the recipes that need no workers, but also take no time to craft.
+    ///
+    /// These would complete every frame, which is almost certainly a mistake.
+    /// Recipes that need workers may take no time, as they are gated by the work instead,
+    /// unless they have fixed `work_units`: their progress is tracked relative to the craft time.
+    /// The names are returned in sorted order.
+    pub fn zero_craft_time_recipes(&self) -> Vec<String> {
+        let mut recipe_names: Vec<String> = self
+            .enabled_recipes()
+            .filter(|(_, raw_data)| {
+                let workers_required = raw_data
+                    .conditions
+                    .as_ref()
+                    .map_or(0, |conditions| conditions.workers_required);
+
+                (workers_required == 0 || raw_data.work_units.is_some())
+                    && raw_data.craft_time <= 0.
+            })
+            .map(|(recipe_name, _)| recipe_name.clone())
+            .collect();
+
+        recipe_names.sort();
+        recipe_names
+    }
+
+    /// Finds the recipes that produce more of one of their inputs than they consume, as listed by [`RecipeData::self_produced_items`].
+    ///
+    /// Recipes that cannot be processed are skipped, as they are reported by [`IsRawManifest::process`] instead.
+    /// The names are returned in sorted order.
+    pub fn self_producing_recipes(&self) -> Vec<String> {
+        let mut recipe_names: Vec<String> = self
+            .enabled_recipes()
+            .filter(|(_, raw_data)| {
+                RecipeData::try_from((*raw_data).clone())
+                    .is_ok_and(|recipe_data| !recipe_data.self_produced_items().is_empty())
+            })
+            .map(|(recipe_name, _)| recipe_name.clone())
+            .collect();
+
+        recipe_names.sort();
+        recipe_names
+    }
+
+    /// Finds the recipes that produce more than `max_ratio` times the energy contained in their inputs.
+    ///
+    /// Only recipes with exact inputs that produce energy are checked.
+    /// Inputs that are unknown count as containing no energy.
+    pub fn energy_imbalances(
+        &self,
+        item_manifest: &ItemManifest,
+        max_ratio: f32,
+    ) -> Vec<EnergyImbalance> {
+        let mut imbalances = Vec::new();
+
+        for (recipe_name, raw_data) in self.enabled_recipes() {
+            let (Some(output_energy), RawRecipeInput::Exact(inputs)) =
+                (raw_data.energy, &raw_data.inputs) else {
+                continue;
+            };
+
+            let input_energy: f32 = inputs
+                .iter()
+                .filter_map(|(item_name, count)| {
+                    let item_id = Id::<Item>::from_name(item_name.clone());
+                    let item_energy = item_manifest.data_map().get(&item_id)?.energy_content;
+                    Some(item_energy.0 * *count as f32)
+                })
+                .sum();
+
+            if output_energy.0 > input_energy * max_ratio {
+                imbalances.push(EnergyImbalance {
+                    recipe: recipe_name.clone(),
+                    input_energy,
+                    output_energy: output_energy.0,
+                });
+            }
+        }
+
+        imbalances.sort_by(|a, b| a.recipe.cmp(&b.recipe));
+        imbalances
+    }
+
+    /// Finds the outputs of recipes that are not of the tier required by their [`TierRule`].
+    ///
+    /// The tier of the inputs is the lowest tier of any item that may be consumed,
+    /// including all alternatives and all items in an input category.
+    /// Recipes with flexible inputs are only checked if their rule does not depend on the inputs.
+    /// Byproducts are not checked, and unknown items are skipped, as they are reported by [`Self::validate_items`].
+    pub fn tier_mismatches(&self, item_manifest: &ItemManifest) -> Vec<TierMismatch> {
+        let tier = |item_name: &String| {
+            let item_id = Id::<Item>::from_name(item_name.clone());
+            item_manifest.data_map().get(&item_id).map(|data| data.tier)
+        };
+        let mut mismatches = Vec::new();
+
+        for (recipe_name, raw_data) in self.enabled_recipes() {
+            let Some(output_tier) = raw_data.output_tier else {
+                continue;
+            };
+
+            let input_tiers: Vec<u8> = match &raw_data.inputs {
+                RawRecipeInput::Exact(inputs) => inputs.keys().filter_map(tier).collect(),
+                RawRecipeInput::AnyOf(alternatives) => alternatives
+                    .iter()
+                    .filter_map(|alternative| tier(&alternative.item))
+                    .collect(),
+                RawRecipeInput::Category { tag, .. } => item_manifest
+                    .items_with_tag(Id::from_name(tag.clone()))
+                    .into_iter()
+                    .map(|item_id| item_manifest.get_unchecked(item_id).tier)
+                    .collect(),
+                RawRecipeInput::Flexible { .. } => {
+                    if !matches!(output_tier, TierRule::Fixed(_)) {
+                        continue;
+                    }
+                    Vec::new()
+                }
+            };
+            let expected = output_tier.resolve(input_tiers);
+
+            for item_name in raw_data
+                .outputs
+                .keys()
+                .chain(raw_data.output_one_of.iter().map(|output| &output.item))
+            {
+                if let Some(found) = tier(item_name).filter(|found| *found != expected) {
+                    mismatches.push(TierMismatch {
+                        recipe: recipe_name.clone(),
+                        item: item_name.clone(),
+                        expected,
+                        found,
+                    });
+                }
+            }
+        }
+
+        mismatches.sort();
+        mismatches.dedup();
+        mismatches
+    }
+}
+
+impl RawRecipeManifest {
+    /// Checks the recipes on their own, and processes only the [enabled](RawRecipeData::enabled) ones.
+    fn process_recipes(&self) -> Result<RecipeManifest, ManifestError> {
+        let out_of_range_values = self.out_of_range_values();
+        if !out_of_range_values.is_empty() {
+            return Err(ManifestError::OutOfRange(out_of_range_values));
+        }
+
+        let unknown_prerequisites = self.unknown_prerequisites();
+        if !unknown_prerequisites.is_empty() {
+            return Err(ManifestError::UnknownReference(unknown_prerequisites));
+        }
+
+        if let Some(cycle) = self.prerequisite_cycle() {
+            return Err(ManifestError::CyclicPrerequisites(cycle));
+        }
+
+        let unknown_next_recipes = self.unknown_next_recipes();
+        if !unknown_next_recipes.is_empty() {
+            return Err(ManifestError::UnknownReference(unknown_next_recipes));
+        }
+
+        if let Some(cycle) = self.recipe_chain_cycle() {
+            return Err(ManifestError::CyclicRecipeChain(cycle));
+        }
+
+        let mut manifest = Manifest::new();
+        let mut invalid_recipes = Vec::new();
+
+        // Insert the recipes in order of their name, so that the result does not depend on the order of the map
+        for (raw_id, raw_data) in self
+            .enabled_recipes()
+            .sorted_unstable_by(|(a, _), (b, _)| a.cmp(b))
+        {
+            match RecipeData::try_from(raw_data.clone()) {
+                Ok(data) => manifest.insert(raw_id.clone(), data),
+                Err(problem) => invalid_recipes.push(InvalidRecipe {
+                    recipe: raw_id.clone(),
+                    problem,
+                }),
+            }
+        }
+
+        if !invalid_recipes.is_empty() {
+            invalid_recipes.sort();
+            return Err(ManifestError::InvalidRecipe(invalid_recipes));
+        }
+
+        Ok(manifest)
+    }
+
+    /// Checks the recipes against the [`ItemManifest`] and [`EnvironmentManifest`],
+    /// and the [`RecipeEnergyBalance`] and [`RecipeMassBalance`] if they are present.
+    fn validate_recipes(&self, world: &World) -> Result<(), ManifestError> {
+        let item_manifest = world.resource::<ItemManifest>();
+        self.validate_items(item_manifest)?;
+
+        // Without an environment manifest, no environmental value is known
+        let no_environment = EnvironmentManifest::default();
+        let environment_manifest = world
+            .get_resource::<EnvironmentManifest>()
+            .unwrap_or(&no_environment);
+        let unknown_environment_keys = self.unknown_environment_keys(environment_manifest);
+        if !unknown_environment_keys.is_empty() {
+            return Err(ManifestError::UnknownReference(unknown_environment_keys));
+        }
+
+        let tier_mismatches = self.tier_mismatches(item_manifest);
+        if !tier_mismatches.is_empty() {
+            return Err(ManifestError::MismatchedTier(tier_mismatches));
+        }
+
+        let zero_craft_time_recipes = self.zero_craft_time_recipes();
+        if !zero_craft_time_recipes.is_empty() {
+            return Err(ManifestError::ZeroCraftTime(zero_craft_time_recipes));
+        }
+
+        let self_producing_recipes = self.self_producing_recipes();
+        if !self_producing_recipes.is_empty() {
+            return Err(ManifestError::SelfProducing(self_producing_recipes));
+        }
+
+        if let Some(energy_balance) = world.get_resource::<RecipeEnergyBalance>() {
+            let imbalances = self.energy_imbalances(item_manifest, energy_balance.max_ratio);
+
+            if energy_balance.deny && !imbalances.is_empty() {
+                return Err(ManifestError::UnbalancedEnergy(imbalances));
+            }
+
+            for imbalance in imbalances {
+                warn!("Unbalanced recipe energy: {imbalance}");
+            }
+        }
+
+        if world.contains_resource::<RecipeMassBalance>() {
+            // The recipes are processed before any manifest is validated
+            if let Some(recipe_manifest) = world.get_resource::<RecipeManifest>() {
+                for recipe_name in recipe_manifest.mass_gaining_recipes(item_manifest) {
+                    warn!("Recipe `{recipe_name}` produces more mass than it consumes");
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Enables checking that recipes do not produce much more [`Energy`] than their inputs contain.
+///
+/// Insert this resource before the manifests are loaded to run the check.
+/// Inputs that are not in the item manifest count as containing no energy,
+/// and recipes with flexible inputs are not checked.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct RecipeEnergyBalance {
+    /// The largest allowed ratio of the energy produced to the energy contained in the inputs.
+    pub max_ratio: f32,
+    /// Should unbalanced recipes fail validation, rather than only being warned about?
+    pub deny: bool,
+}
+
+impl Default for RecipeEnergyBalance {
+    fn default() -> Self {
+        RecipeEnergyBalance {
+            max_ratio: 1.,
+            deny: false,
+        }
+    }
+}
+
+/// Enables warning about recipes that produce more mass than they consume, as checked by [`RecipeData::is_balanced_mass`].
+///
+/// Insert this resource before the manifests are loaded to run the check.
+/// Recipes that are meant to gain mass, such as growth, can opt out with [`RecipeData::allow_mass_gain`].
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecipeMassBalance;
+
+/// The recipe that is currently being crafted, if any.
+#[derive(Component, Debug, Default, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct ActiveRecipe(pub(super) Option<Id<Recipe>>);
+
+/// The raw version of [`ActiveRecipe`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RawActiveRecipe(Option<String>);
+
+impl RawActiveRecipe {
+    /// Creates a new [`RawActiveRecipe`], set to `recipe_name`.
+    pub fn new(recipe_name: &str) -> Self {
+        RawActiveRecipe(Some(recipe_name.to_string()))
+    }
+}
+
+impl From<RawActiveRecipe> for ActiveRecipe {
+    fn from(raw: RawActiveRecipe) -> Self {
+        ActiveRecipe(raw.0.map(Id::from_name))
+    }
+}
+
+impl ActiveRecipe {
+    /// The un-set [`ActiveRecipe`].
+    pub const NONE: ActiveRecipe = ActiveRecipe(None);
+
+    /// Creates a new [`ActiveRecipe`], set to `recipe_id`
+    pub fn new(recipe_id: Id<Recipe>) -> Self {
+        ActiveRecipe(Some(recipe_id))
+    }
+
+    /// The ID of the currently active recipe, if one has been selected.
+    pub fn recipe_id(&self) -> &Option<Id<Recipe>> {
+        &self.0
+    }
+
+    /// The pretty formatting for this type
+    pub(crate) fn display(&self, recipe_manifest: &RecipeManifest) -> String {
+        match self.0 {
+            Some(recipe_id) => recipe_manifest.name(recipe_id).to_string(),
+            None => "None".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset_management::manifest::diff::ModifiedEntry;
+    use crate::crafting::inventories::CraftingProgress;
+    use crate::environment::EnvironmentData;
+    use crate::items::item_manifest::ItemData;
+    use bevy::utils::HashSet;
+
+    /// Create a simple item manifest for testing purposes.
+    fn item_manifest() -> ItemManifest {
+        let mut manifest = Manifest::new();
+        manifest.insert(
+            "pollen".to_string(),
+            ItemData {
+                stack_size: 10,
+                compostable: true,
+                fluid: false,
+                buoyant: true,
+                seed: None,
+                energy_content: Energy(0.),
+                tags: HashSet::new(),
+                mass: 1.,
+                decay_time: None,
+                decays_into: None,
+                tier: 0,
+                name: None,
+                description: None,
+                icon: None,
+            },
+        );
+        manifest.insert(
+            "honey".to_string(),
+            ItemData {
+                stack_size: 5,
+                compostable: false,
+                fluid: true,
+                buoyant: false,
+                seed: None,
+                energy_content: Energy(0.),
+                tags: HashSet::new(),
+                mass: 1.,
+                decay_time: None,
+                decays_into: None,
+                tier: 0,
+                name: None,
+                description: None,
+                icon: None,
+            },
+        );
+        manifest
+    }
+
+    /// A recipe that turns `input` into `output`.
+    fn raw_recipe(input: &str, output: &str) -> RawRecipeData {
+        RawRecipeData {
+            inputs: RawRecipeInput::single(input, 1),
+            catalysts: HashMap::new(),
+            byproducts: HashMap::new(),
+            output_one_of: Vec::new(),
+            outputs: HashMap::from_iter([(output.to_string(), RawOutputCount::Count(1.))]),
+            craft_time: 1.,
+            work_units: None,
+            conditions: None,
+            energy: None,
+            allow_mass_gain: false,
+            allow_self_production: false,
+            requires: Vec::new(),
+            research_cost: HashMap::new(),
+            fluid_inputs: HashMap::new(),
+            fluid_outputs: HashMap::new(),
+            consumption: ConsumptionMode::AtStart,
+            overflow: OverflowPolicy::Block,
+            output_slots: HashMap::new(),
+            max_batch: 1,
+            next_recipe: None,
+            output_tier: None,
+            output_quality: QualityRule::default(),
+            category: RawRecipeData::DEFAULT_CATEGORY.to_string(),
+            active_phase: TimePhase::Always,
+            requires_environment: None,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn known_items_pass_validation() {
+        let raw_recipe_manifest = RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([(
+                "make_honey".to_string(),
+                raw_recipe("pollen", "honey"),
+            )]),
+        };
+
+        assert_eq!(raw_recipe_manifest.validate_items(&item_manifest()), Ok(()));
+    }
+
+    #[test]
+    fn unknown_items_are_reported_with_their_recipe() {
+        let raw_recipe_manifest = RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([
+                ("make_honey".to_string(), raw_recipe("pollne", "honey")),
+                ("make_wax".to_string(), raw_recipe("pollen", "wax")),
+            ]),
+        };
+
+        let error = raw_recipe_manifest
+            .validate_items(&item_manifest())
+            .unwrap_err();
+
+        assert_eq!(
+            error,
+            ManifestError::UnknownReference(vec![
+                DanglingReference {
+                    entry: "make_honey".to_string(),
+                    name: "pollne".to_string(),
+                },
+                DanglingReference {
+                    entry: "make_wax".to_string(),
+                    name: "wax".to_string(),
+                },
+            ])
+        );
+
+        let message = error.to_string();
+        assert!(message.contains("make_honey"));
+        assert!(message.contains("pollne"));
+    }
+
+    #[test]
+    fn fluid_amounts_are_read_alongside_whole_items() {
+        let recipe_data = RecipeData::try_from(RawRecipeData {
+            fluid_inputs: HashMap::from_iter([("honey".to_string(), 0.25)]),
+            fluid_outputs: HashMap::from_iter([("honey".to_string(), 1.0004)]),
+            ..raw_recipe("pollen", "honey")
+        })
+        .unwrap();
+
+        let honey = Id::from_name("honey".to_string());
+        assert_eq!(
+            recipe_data.fluid_inputs,
+            vec![FluidCount::new(honey, FluidAmount::from_f64(0.25))]
+        );
+        assert_eq!(
+            recipe_data.fluid_outputs,
+            vec![FluidCount::new(honey, FluidAmount::from_f64(1.))]
+        );
+    }
+
+    #[test]
+    fn unknown_fluids_are_reported() {
+        let raw_recipe_manifest = RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([(
+                "make_honey".to_string(),
+                RawRecipeData {
+                    fluid_inputs: HashMap::from_iter([("nectar".to_string(), 0.5)]),
+                    ..raw_recipe("pollen", "honey")
+                },
+            )]),
+        };
+
+        assert_eq!(
+            raw_recipe_manifest.validate_items(&item_manifest()),
+            Err(ManifestError::UnknownReference(vec![DanglingReference {
+                entry: "make_honey".to_string(),
+                name: "nectar".to_string(),
+            }]))
+        );
+    }
+
+    #[test]
+    fn zero_count_inputs_are_rejected() {
+        let raw_recipe_manifest = RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([(
+                "make_honey".to_string(),
+                RawRecipeData {
+                    inputs: RawRecipeInput::single("pollen", 0),
+                    ..raw_recipe("pollen", "honey")
+                },
+            )]),
+        };
+
+        let error = raw_recipe_manifest.process().err().unwrap();
+        assert_eq!(
+            error,
+            ManifestError::OutOfRange(vec![OutOfRangeValue {
+                entry: "make_honey".to_string(),
+                field: "inputs.pollen".to_string(),
+                value: "0".to_string(),
+                expected: "at least 1",
+            }])
+        );
+        assert!(error
+            .to_string()
+            .contains("`make_honey`: inputs.pollen is 0, but must be at least 1"));
+    }
+
+    #[test]
+    fn oversized_batches_are_rejected() {
+        let raw_recipe_manifest = RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([(
+                "make_honey".to_string(),
+                RawRecipeData {
+                    max_batch: RawRecipeData::MAX_BATCH + 1,
+                    ..raw_recipe("pollen", "honey")
+                },
+            )]),
+        };
+
+        assert_eq!(
+            raw_recipe_manifest.process().err(),
+            Some(ManifestError::OutOfRange(vec![OutOfRangeValue {
+                entry: "make_honey".to_string(),
+                field: "max_batch".to_string(),
+                value: "1001".to_string(),
+                expected: "between 1 and 1000",
+            }]))
+        );
+    }
+
+    #[test]
+    fn overflowing_batches_have_no_inputs() {
+        let inputs =
+            RecipeInput::Exact(vec![ItemCount::new(Id::from_name("pollen".to_string()), 2)]);
+
+        assert_eq!(
+            inputs.times(3),
+            Some(RecipeInput::Exact(vec![ItemCount::new(
+                Id::from_name("pollen".to_string()),
+                6
+            )]))
+        );
+        assert_eq!(inputs.times(u32::MAX), None);
+    }
+
+    #[test]
+    fn recipes_are_grouped_by_category() {
+        let raw_recipe_manifest = RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([
+                (
+                    "make_honey".to_string(),
+                    RawRecipeData {
+                        category: "Food".to_string(),
+                        ..raw_recipe("pollen", "honey")
+                    },
+                ),
+                (
+                    "make_jam".to_string(),
+                    RawRecipeData {
+                        category: "Food".to_string(),
+                        ..raw_recipe("honey", "jam")
+                    },
+                ),
+                (
+                    "make_wax".to_string(),
+                    RawRecipeData {
+                        category: "Processing".to_string(),
+                        ..raw_recipe("honey", "wax")
+                    },
+                ),
+            ]),
+        };
+
+        let categories = raw_recipe_manifest.process().unwrap().recipes_by_category();
+
+        let mut food = vec![
+            Id::from_name("make_honey".to_string()),
+            Id::from_name("make_jam".to_string()),
+        ];
+        food.sort();
+        assert_eq!(categories.len(), 2);
+        assert_eq!(categories["Food"], food);
+        assert_eq!(
+            categories["Processing"],
+            vec![Id::from_name("make_wax".to_string())]
+        );
+    }
+
+    #[test]
+    fn recipes_without_a_category_are_uncategorized() {
+        let raw_data: RawRecipeData = serde_json::from_str(
+            r#"{
+                "inputs": { "Exact": { "pollen": 1 } },
+                "outputs": { "honey": 1 },
+                "craft_time": 1
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(raw_data.category, RawRecipeData::DEFAULT_CATEGORY);
+
+        let mut recipe_manifest = RecipeManifest::new();
+        recipe_manifest.insert(
+            "make_honey".to_string(),
+            RecipeData::try_from(raw_data).unwrap(),
+        );
+        assert_eq!(
+            recipe_manifest.recipes_by_category()["Uncategorized"],
+            vec![Id::from_name("make_honey".to_string())]
+        );
+    }
+
+    #[test]
+    fn craft_times_can_be_humanized() {
+        let craft_time = |craft_time: &str| {
+            let raw_data: RawRecipeData = serde_json::from_str(&format!(
+                r#"{{
+                    "inputs": {{ "Exact": {{ "pollen": 1 }} }},
+                    "outputs": {{ "honey": 1 }},
+                    "craft_time": {craft_time}
+                }}"#
+            ))
+            .unwrap();
+            RecipeData::try_from(raw_data).unwrap().craft_time
+        };
+
+        assert_eq!(craft_time(r#""90s""#), Duration::from_secs(90));
+        assert_eq!(craft_time("90"), Duration::from_secs(90));
+        assert_eq!(craft_time(r#""1m30s""#), Duration::from_secs(90));
+    }
+
+    #[test]
+    fn processing_the_same_recipes_twice_gives_equal_manifests() {
+        // Every list in the processed recipe is built from a map in the raw recipe
+        let json = r#"{
+            "version": 1,
+            "recipes": {
+                "brew_mead": {
+                    "inputs": { "Exact": { "honey": 2, "water": 1, "yeast": 1, "pollen": 3 } },
+                    "catalysts": { "barrel": 1, "ladle": 1, "funnel": 1 },
+                    "outputs": {
+                        "mead": 1.5,
+                        "foam": 0.5,
+                        "dregs": 2.25,
+                        "wax": { "count": 1, "chance": 0.5 },
+                        "grit": { "count": 2, "chance": 0.1 },
+                        "husks": { "min": 1, "max": 3 },
+                        "spores": { "min": 0, "max": 2 }
+                    },
+                    "byproducts": { "heat": 1, "steam": 2, "ash": 1 },
+                    "fluid_inputs": { "nectar": 1.5, "sap": 0.5, "dew": 2.0 },
+                    "fluid_outputs": { "vinegar": 0.5, "syrup": 1.0, "brine": 0.25 },
+                    "research_cost": { "leaf": 5, "acorn": 2, "petal": 1 },
+                    "craft_time": 1
+                }
+            }
+        }"#;
+
+        let process = || {
+            serde_json::from_str::<RawRecipeManifest>(json)
+                .unwrap()
+                .process()
+                .unwrap()
+        };
+
+        for _ in 0..10 {
+            assert!(process() == process());
+        }
+    }
+
+    /// A recipe that turns `input_count` of `input` into `output_count` of `output`.
+    fn conversion(input: &str, input_count: u32, output: &str, output_count: f32) -> RecipeData {
+        RecipeData::try_from(RawRecipeData {
+            inputs: RawRecipeInput::single(input, input_count),
+            outputs: HashMap::from_iter([(
+                output.to_string(),
+                RawOutputCount::Count(output_count),
+            )]),
+            ..raw_recipe(input, output)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn recipe_manifests_can_be_collected_from_entries() {
+        let recipe_manifest: RecipeManifest = [
+            ("make_honey", conversion("pollen", 2, "honey", 1.)),
+            ("make_wax", conversion("honey", 3, "wax", 2.)),
+        ]
+        .into_iter()
+        .map(|(name, data)| (name.to_string(), data))
+        .collect();
+
+        assert_eq!(recipe_manifest.len(), 2);
+        for (name, data) in [
+            ("make_honey", conversion("pollen", 2, "honey", 1.)),
+            ("make_wax", conversion("honey", 3, "wax", 2.)),
+        ] {
+            let id = Id::from_name(name.to_string());
+            assert_eq!(recipe_manifest.name(id), name);
+            assert_eq!(recipe_manifest.get(id), Ok(&data));
+        }
+    }
+
+    #[test]
+    fn craft_costs_are_expanded_down_to_base_items() {
+        let mut recipe_manifest = RecipeManifest::new();
+        recipe_manifest.insert(
+            "make_honey".to_string(),
+            conversion("pollen", 2, "honey", 1.),
+        );
+        recipe_manifest.insert("make_wax".to_string(), conversion("honey", 3, "wax", 2.));
+
+        let cost = recipe_manifest
+            .total_craft_cost(Id::from_name("wax".to_string()))
+            .unwrap();
+
+        // A single wax takes one and a half honey, which takes three pollen
+        assert_eq!(
+            cost,
+            HashMap::from_iter([(Id::from_name("pollen".to_string()), 3.)])
+        );
+    }
+
+    #[test]
+    fn producers_and_consumers_of_items_can_be_looked_up() {
+        let mut recipe_manifest = RecipeManifest::new();
+        recipe_manifest.insert(
+            "make_honey".to_string(),
+            conversion("pollen", 2, "honey", 1.),
+        );
+        recipe_manifest.insert("melt_wax".to_string(), conversion("wax", 1, "honey", 1.));
+        recipe_manifest.insert("make_wax".to_string(), conversion("honey", 3, "wax", 2.));
+        let item = |name: &str| Id::<Item>::from_name(name.to_string());
+        let recipe = |name: &str| Id::<Recipe>::from_name(name.to_string());
+
+        let mut honey_producers = vec![recipe("make_honey"), recipe("melt_wax")];
+        honey_producers.sort();
+        assert_eq!(
+            recipe_manifest.recipes_producing(item("honey")),
+            honey_producers
+        );
+        assert_eq!(
+            recipe_manifest.recipes_consuming(item("honey")),
+            vec![recipe("make_wax")]
+        );
+
+        assert_eq!(
+            recipe_manifest.recipes_producing(item("pollen")),
+            Vec::new()
+        );
+        assert_eq!(
+            recipe_manifest.recipes_consuming(item("pollen")),
+            vec![recipe("make_honey")]
+        );
+    }
+
+    #[test]
+    fn base_items_cost_themselves() {
+        let recipe_manifest = RecipeManifest::new();
+        let pollen = Id::from_name("pollen".to_string());
+
+        assert_eq!(
+            recipe_manifest.total_craft_cost(pollen),
+            Ok(HashMap::from_iter([(pollen, 1.)]))
+        );
+    }
+
+    #[test]
+    fn craft_cost_cycles_are_reported() {
+        let mut recipe_manifest = RecipeManifest::new();
+        recipe_manifest.insert("make_honey".to_string(), conversion("wax", 1, "honey", 1.));
+        recipe_manifest.insert("make_wax".to_string(), conversion("honey", 1, "wax", 1.));
+        let honey = Id::from_name("honey".to_string());
+        let wax = Id::from_name("wax".to_string());
+
+        assert_eq!(
+            recipe_manifest.total_craft_cost(honey),
+            Err(CraftingCycle(vec![honey, wax, honey]))
+        );
+    }
+
+    #[test]
+    fn diffs_list_removed_recipes_and_changed_craft_times() {
+        let mut old = RecipeManifest::new();
+        old.insert(
+            "make_honey".to_string(),
+            RecipeData::try_from(raw_recipe("pollen", "honey")).unwrap(),
+        );
+        old.insert(
+            "make_wax".to_string(),
+            RecipeData::try_from(raw_recipe("honey", "wax")).unwrap(),
+        );
+        let mut new = RecipeManifest::new();
+        new.insert(
+            "make_honey".to_string(),
+            RecipeData::try_from(RawRecipeData {
+                inputs: RawRecipeInput::single("pollen", 3),
+                craft_time: 2.5,
+                ..raw_recipe("pollen", "honey")
+            })
+            .unwrap(),
+        );
+
+        let diff = old.diff(&new);
+
+        assert!(diff.added.is_empty());
+        assert_eq!(diff.removed, vec!["make_wax".to_string()]);
+        assert_eq!(
+            diff.modified,
+            vec![ModifiedEntry {
+                name: "make_honey".to_string(),
+                changes: vec![
+                    RecipeChange::Input {
+                        item_id: Id::from_name("pollen".to_string()),
+                        before: 1,
+                        after: 3,
+                    },
+                    RecipeChange::CraftTime {
+                        before: Duration::from_secs(1),
+                        after: Duration::from_secs_f32(2.5),
+                    },
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn output_changes_are_listed_per_item() {
+        let old = RecipeData::try_from(raw_recipe("pollen", "honey")).unwrap();
+        let new = RecipeData::try_from(raw_recipe("pollen", "wax")).unwrap();
+
+        let mut expected = vec![
+            RecipeChange::Output {
+                item_id: Id::from_name("honey".to_string()),
+                before: 1.,
+                after: 0.,
+            },
+            RecipeChange::Output {
+                item_id: Id::from_name("wax".to_string()),
+                before: 0.,
+                after: 1.,
+            },
+        ];
+        if Id::<Item>::from_name("wax".to_string()) < Id::from_name("honey".to_string()) {
+            expected.reverse();
+        }
+
+        assert_eq!(old.changes_to(&new), expected);
+        assert!(old.changes_to(&old).is_empty());
+    }
+
+    #[test]
+    fn recipes_without_outputs_are_rejected() {
+        let no_outputs = RawRecipeData {
+            outputs: HashMap::new(),
+            ..raw_recipe("pollen", "honey")
+        };
+        assert_eq!(
+            RecipeData::try_from(no_outputs.clone()),
+            Err(RecipeProblem::NoOutputs)
+        );
+
+        let raw_recipe_manifest = RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([
+                ("make_honey".to_string(), raw_recipe("pollen", "honey")),
+                ("waste_pollen".to_string(), no_outputs),
+            ]),
+        };
+
+        let error = raw_recipe_manifest.process().err().unwrap();
+        assert_eq!(
+            error,
+            ManifestError::InvalidRecipe(vec![InvalidRecipe {
+                recipe: "waste_pollen".to_string(),
+                problem: RecipeProblem::NoOutputs,
+            }])
+        );
+        assert!(error
+            .to_string()
+            .contains("`waste_pollen` produces nothing"));
+    }
+
+    #[test]
+    fn recipes_that_only_produce_energy_are_converted() {
+        let raw_recipe_data = RawRecipeData {
+            outputs: HashMap::new(),
+            energy: Some(Energy(10.)),
+            ..raw_recipe("pollen", "honey")
+        };
+
+        let recipe_data = RecipeData::try_from(raw_recipe_data).unwrap();
+        assert_eq!(recipe_data.energy, Some(Energy(10.)));
+    }
+
+    #[test]
+    fn negative_craft_times_fail_to_convert() {
+        let raw_recipe_data = RawRecipeData {
+            craft_time: -1.,
+            ..raw_recipe("pollen", "honey")
+        };
+
+        assert_eq!(
+            RecipeData::try_from(raw_recipe_data),
+            Err(RecipeProblem::InvalidCraftTime)
+        );
+    }
+
+    #[test]
+    fn every_out_of_range_value_is_reported() {
+        let raw_recipe_data = RawRecipeData {
+            outputs: HashMap::from_iter([(
+                "honey".to_string(),
+                RawOutputCount::Chance {
+                    count: 1,
+                    chance: 1.5,
+                },
+            )]),
+            byproducts: HashMap::from_iter([("wax".to_string(), 0)]),
+            craft_time: RawRecipeData::MAX_CRAFT_TIME * 1000.,
+            ..raw_recipe("pollen", "honey")
+        };
+
+        let fields: Vec<String> = raw_recipe_data
+            .out_of_range_values("make_honey")
+            .into_iter()
+            .map(|out_of_range_value| out_of_range_value.field)
+            .collect();
+        assert_eq!(
+            fields,
+            vec!["byproducts.wax", "outputs.honey.chance", "craft_time"]
+        );
+        assert!(raw_recipe("pollen", "honey")
+            .out_of_range_values("make_honey")
+            .is_empty());
+    }
+
+    #[test]
+    fn prerequisites_are_read_as_recipe_ids() {
+        let raw_recipe_manifest = RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([
+                ("make_honey".to_string(), raw_recipe("pollen", "honey")),
+                (
+                    "make_mead".to_string(),
+                    RawRecipeData {
+                        requires: vec!["make_honey".to_string()],
+                        ..raw_recipe("honey", "mead")
+                    },
+                ),
+            ]),
+        };
+
+        let recipe_manifest = raw_recipe_manifest.process().unwrap();
+        let make_mead = recipe_manifest
+            .get(Id::from_name("make_mead".to_string()))
+            .unwrap();
+
+        assert_eq!(
+            make_mead.requires,
+            vec![Id::from_name("make_honey".to_string())]
+        );
+    }
+
+    #[test]
+    fn prerequisite_cycles_are_rejected() {
+        let requiring = |required: &str| RawRecipeData {
+            requires: vec![required.to_string()],
+            ..raw_recipe("pollen", "honey")
+        };
+        let raw_recipe_manifest = RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([
+                ("make_honey".to_string(), requiring("make_wax")),
+                ("make_wax".to_string(), requiring("make_mead")),
+                ("make_mead".to_string(), requiring("make_honey")),
+                ("make_pollen".to_string(), requiring("make_honey")),
+            ]),
+        };
+
+        let error = raw_recipe_manifest.process().err().unwrap();
+
+        assert_eq!(
+            error,
+            ManifestError::CyclicPrerequisites(vec![
+                "make_honey".to_string(),
+                "make_wax".to_string(),
+                "make_mead".to_string(),
+            ])
+        );
+        assert!(error
+            .to_string()
+            .contains("`make_honey` -> `make_wax` -> `make_mead` -> `make_honey`"));
+    }
+
+    #[test]
+    fn prerequisites_must_be_enabled_recipes() {
+        let raw_recipe_manifest = RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([
+                (
+                    "make_honey".to_string(),
+                    RawRecipeData {
+                        enabled: false,
+                        ..raw_recipe("pollen", "honey")
+                    },
+                ),
+                (
+                    "make_mead".to_string(),
+                    RawRecipeData {
+                        requires: vec!["make_honey".to_string(), "make_wax".to_string()],
+                        ..raw_recipe("honey", "mead")
+                    },
+                ),
+            ]),
+        };
+
+        assert_eq!(
+            raw_recipe_manifest.process().err(),
+            Some(ManifestError::UnknownReference(vec![
+                DanglingReference {
+                    entry: "make_mead".to_string(),
+                    name: "make_honey".to_string(),
+                },
+                DanglingReference {
+                    entry: "make_mead".to_string(),
+                    name: "make_wax".to_string(),
+                },
+            ]))
+        );
+    }
+
+    #[test]
+    fn recipe_chain_cycles_are_rejected() {
+        let leading_into = |next: &str| RawRecipeData {
+            next_recipe: Some(next.to_string()),
+            ..raw_recipe("pollen", "honey")
+        };
+        let raw_recipe_manifest = RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([
+                ("gather_pollen".to_string(), leading_into("make_honey")),
+                ("make_honey".to_string(), leading_into("make_wax")),
+                ("make_wax".to_string(), leading_into("make_honey")),
+            ]),
+        };
+
+        let error = raw_recipe_manifest.process().err().unwrap();
+
+        assert_eq!(
+            error,
+            ManifestError::CyclicRecipeChain(vec![
+                "make_honey".to_string(),
+                "make_wax".to_string(),
+            ])
+        );
+        assert!(error
+            .to_string()
+            .contains("`make_honey` -> `make_wax` -> `make_honey`"));
+    }
+
+    #[test]
+    fn recipe_chains_that_end_are_accepted() {
+        let raw_recipe_manifest = RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([
+                (
+                    "make_honey".to_string(),
+                    RawRecipeData {
+                        next_recipe: Some("make_mead".to_string()),
+                        ..raw_recipe("pollen", "honey")
+                    },
+                ),
+                ("make_mead".to_string(), raw_recipe("honey", "mead")),
+            ]),
+        };
+
+        assert_eq!(raw_recipe_manifest.recipe_chain_cycle(), None);
+        let recipe_manifest = raw_recipe_manifest.process().unwrap();
+        let make_honey = recipe_manifest
+            .get(Id::from_name("make_honey".to_string()))
+            .unwrap();
+
+        assert_eq!(
+            make_honey.next_recipe,
+            Some(Id::from_name("make_mead".to_string()))
+        );
+    }
+
+    #[test]
+    fn next_recipes_must_be_enabled_recipes() {
+        let raw_recipe_manifest = RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([(
+                "make_honey".to_string(),
+                RawRecipeData {
+                    next_recipe: Some("make_mead".to_string()),
+                    ..raw_recipe("pollen", "honey")
+                },
+            )]),
+        };
+
+        assert_eq!(
+            raw_recipe_manifest.process().err(),
+            Some(ManifestError::UnknownReference(vec![DanglingReference {
+                entry: "make_honey".to_string(),
+                name: "make_mead".to_string(),
+            }]))
+        );
+    }
+
+    #[test]
+    fn category_inputs_are_read_from_their_tag_name() {
+        let raw_input: RawRecipeInput =
+            serde_json::from_str(r#"{ "Category": { "tag": "seed", "count": 3 } }"#).unwrap();
+
+        assert_eq!(
+            RecipeInput::from(raw_input),
+            RecipeInput::Category {
+                category: Id::from_name("seed".to_string()),
+                count: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn alternative_inputs_keep_their_order() {
+        let raw_input: RawRecipeInput = serde_json::from_str(
+            r#"{ "AnyOf": [{ "item": "pollen", "count": 3 }, { "item": "nectar", "count": 1 }] }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            RecipeInput::from(raw_input),
+            RecipeInput::AnyOf(vec![
+                ItemCount::new(Id::from_name("pollen".to_string()), 3),
+                ItemCount::new(Id::from_name("nectar".to_string()), 1),
+            ])
+        );
+    }
+
+    #[test]
+    fn recipes_without_any_alternatives_are_rejected() {
+        let raw_recipe_data = RawRecipeData {
+            inputs: RawRecipeInput::AnyOf(Vec::new()),
+            ..raw_recipe("pollen", "honey")
+        };
+
+        assert_eq!(
+            RecipeData::try_from(raw_recipe_data),
+            Err(RecipeProblem::NoAlternatives)
+        );
+    }
+
+    #[test]
+    fn output_slots_must_refer_to_produced_items() {
+        let routed = |item_name: &str| RawRecipeData {
+            byproducts: HashMap::from_iter([("wax".to_string(), 1)]),
+            output_slots: HashMap::from_iter([(item_name.to_string(), OutputSlot::Waste)]),
+            ..raw_recipe("pollen", "honey")
+        };
+
+        let recipe_data = RecipeData::try_from(routed("wax")).unwrap();
+        assert_eq!(
+            recipe_data.output_slot(Id::from_name("wax".to_string())),
+            OutputSlot::Waste
+        );
+        assert_eq!(
+            recipe_data.output_slot(Id::from_name("honey".to_string())),
+            OutputSlot::Product
+        );
+
+        assert_eq!(
+            RecipeData::try_from(routed("pollen")),
+            Err(RecipeProblem::OutputSlotOfMissingItem)
+        );
+    }
+
+    #[test]
+    fn category_inputs_without_matching_items_are_reported() {
+        let raw_recipe_manifest = RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([(
+                "make_honey".to_string(),
+                RawRecipeData {
+                    inputs: RawRecipeInput::Category {
+                        tag: "flower".to_string(),
+                        count: 1,
+                    },
+                    ..raw_recipe("pollen", "honey")
+                },
+            )]),
+        };
+
+        assert_eq!(
+            raw_recipe_manifest.validate_items(&item_manifest()),
+            Err(ManifestError::UnknownReference(vec![DanglingReference {
+                entry: "make_honey".to_string(),
+                name: "flower".to_string(),
+            }]))
+        );
+    }
+
+    #[test]
+    fn chance_outputs_are_kept_apart_from_plain_counts() {
+        let mut raw_data = raw_recipe("pollen", "honey");
+        raw_data.outputs.insert(
+            "pollen".to_string(),
+            RawOutputCount::Chance {
+                count: 2,
+                chance: 0.5,
+            },
+        );
+
+        let recipe_data = RecipeData::try_from(raw_data).unwrap();
+        let honey_id = Id::from_name("honey".to_string());
+        let pollen_id = Id::from_name("pollen".to_string());
+
+        assert_eq!(
+            recipe_data.outputs,
+            RecipeOutput::Deterministic(vec![ItemCount::new(honey_id, 1)])
+        );
+        assert_eq!(
+            recipe_data.probabilistic_outputs,
+            vec![ProbabilisticOutput {
+                item_count: ItemCount::new(pollen_id, 2),
+                chance: 0.5,
+            }]
+        );
+        assert_eq!(recipe_data.output_item_ids(), vec![honey_id, pollen_id]);
+    }
+
+    #[test]
+    fn weighted_outputs_are_read_from_the_manifest() {
+        let raw_data: RawRecipeData = serde_json::from_str(
+            r#"{
+                "inputs": { "Exact": { "pollen": 1 } },
+                "outputs": { "honey": 1 },
+                "output_one_of": [
+                    { "item": "pollen", "count": 2, "weight": 3.0 },
+                    { "item": "honey", "count": 1, "weight": 1.0 }
+                ],
+                "craft_time": 1.0,
+                "conditions": null,
+                "energy": null
+            }"#,
+        )
+        .unwrap();
+
+        let recipe_data = RecipeData::try_from(raw_data).unwrap();
+        let honey_id = Id::from_name("honey".to_string());
+        let pollen_id = Id::from_name("pollen".to_string());
+
+        assert_eq!(
+            recipe_data.output_one_of,
+            vec![
+                WeightedOutput {
+                    item_count: ItemCount::new(pollen_id, 2),
+                    weight: 3.,
+                },
+                WeightedOutput {
+                    item_count: ItemCount::new(honey_id, 1),
+                    weight: 1.,
+                },
+            ]
+        );
+        // Each item only needs a single output slot
+        assert_eq!(recipe_data.output_item_ids(), vec![honey_id, pollen_id]);
+    }
+
+    #[test]
+    fn weighted_picks_follow_the_weights() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        let mut rng = SmallRng::seed_from_u64(42);
+        let pollen_id = Id::from_name("pollen".to_string());
+        let honey_id = Id::from_name("honey".to_string());
+        let outputs = vec![
+            WeightedOutput {
+                item_count: ItemCount::new(pollen_id, 1),
+                weight: 3.,
+            },
+            WeightedOutput {
+                item_count: ItemCount::new(honey_id, 1),
+                weight: 1.,
+            },
+        ];
+
+        let completions = 10_000;
+        let pollen_picks = (0..completions)
+            .filter_map(|_| WeightedOutput::pick(&outputs, &mut rng))
+            .filter(|item_count| item_count.item_id == pollen_id)
+            .count();
+
+        let pollen_share = pollen_picks as f32 / completions as f32;
+        assert!(
+            (pollen_share - 0.75).abs() < 0.02,
+            "Pollen was picked {pollen_share} of the time"
+        );
+    }
+
+    #[test]
+    fn nothing_is_picked_without_positive_weights() {
+        let outputs = vec![WeightedOutput {
+            item_count: ItemCount::new(Id::from_name("pollen".to_string()), 1),
+            weight: 0.,
+        }];
+
+        assert_eq!(WeightedOutput::pick(&[], &mut rand::thread_rng()), None);
+        assert_eq!(
+            WeightedOutput::pick(&outputs, &mut rand::thread_rng()),
+            None
+        );
+    }
+
+    #[test]
+    fn probabilistic_yield_converges_to_expected_value() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        let mut rng = SmallRng::seed_from_u64(42);
+        let output = ProbabilisticOutput {
+            item_count: ItemCount::new(Id::from_name("pollen".to_string()), 3),
+            chance: 0.2,
+        };
+
+        let completions = 10_000;
+        let total: u32 = (0..completions)
+            .filter_map(|_| output.roll(&mut rng))
+            .map(|item_count| item_count.count)
+            .sum();
+
+        let mean_yield = total as f32 / completions as f32;
+        let expected_yield = 3. * 0.2;
+        assert!(
+            (mean_yield - expected_yield).abs() < 0.05,
+            "Mean yield {mean_yield} is too far from {expected_yield}"
+        );
+    }
+
+    #[test]
+    fn ranged_yields_stay_in_range_and_average_the_midpoint() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        let mut rng = SmallRng::seed_from_u64(42);
+        let output = RangedOutput {
+            item_id: Id::from_name("leaf".to_string()),
+            min: 2,
+            max: 5,
+        };
+
+        let completions = 10_000;
+        let counts: Vec<u32> = (0..completions)
+            .map(|_| output.roll(&mut rng).count)
+            .collect();
+
+        assert!(counts.iter().all(|count| (2..=5).contains(count)));
+        for count in 2..=5 {
+            assert!(counts.contains(&count), "{count} leaves were never rolled");
+        }
+
+        let mean_yield = counts.iter().sum::<u32>() as f32 / completions as f32;
+        assert!(
+            (mean_yield - output.average()).abs() < 0.05,
+            "Mean yield {mean_yield} is too far from {}",
+            output.average()
+        );
+    }
+
+    #[test]
+    fn ranges_are_read_from_outputs() {
+        let raw_data: RawRecipeData = serde_json::from_str(
+            r#"{
+                "inputs": { "Exact": { "acacia_leaf": 1 } },
+                "outputs": { "leaf": { "min": 2, "max": 5 }, "seed": { "min": 1, "max": 1 } },
+                "craft_time": 1
+            }"#,
+        )
+        .unwrap();
+        let recipe_data = RecipeData::try_from(raw_data).unwrap();
+
+        assert_eq!(
+            recipe_data.ranged_outputs,
+            vec![RangedOutput {
+                item_id: Id::from_name("leaf".to_string()),
+                min: 2,
+                max: 5,
+            }]
+        );
+        // Equal bounds are just a fixed count
+        assert_eq!(
+            recipe_data.outputs,
+            RecipeOutput::Deterministic(vec![ItemCount::new(Id::from_name("seed".to_string()), 1)])
+        );
+    }
+
+    #[test]
+    fn inverted_ranges_are_out_of_range() {
+        let raw_data = RawRecipeData {
+            outputs: HashMap::from_iter([(
+                "leaf".to_string(),
+                RawOutputCount::Range { min: 5, max: 2 },
+            )]),
+            ..raw_recipe("pollen", "leaf")
+        };
+
+        assert_eq!(
+            raw_data.out_of_range_values("grow_leaves"),
+            vec![OutOfRangeValue {
+                entry: "grow_leaves".to_string(),
+                field: "outputs.leaf.max".to_string(),
+                value: "2".to_string(),
+                expected: "at least 1 and at least `min`",
+            }]
+        );
+    }
+
+    #[test]
+    fn certain_and_impossible_outputs_are_deterministic() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        let mut rng = SmallRng::seed_from_u64(42);
+        let item_count = ItemCount::new(Id::from_name("pollen".to_string()), 1);
+        let always = ProbabilisticOutput {
+            item_count: item_count.clone(),
+            chance: 1.,
+        };
+        let never = ProbabilisticOutput {
+            item_count: item_count.clone(),
+            chance: 0.,
+        };
+
+        for _ in 0..100 {
+            assert_eq!(always.roll(&mut rng), Some(item_count.clone()));
+            assert_eq!(never.roll(&mut rng), None);
+        }
+    }
+
+    #[test]
+    fn fixed_tiers_ignore_the_inputs() {
+        assert_eq!(TierRule::Fixed(3).resolve([0, 5]), 3);
+        assert_eq!(TierRule::Fixed(3).resolve([]), 3);
+    }
+
+    #[test]
+    fn matching_tiers_take_the_lowest_input() {
+        assert_eq!(TierRule::MatchInput.resolve([2]), 2);
+        assert_eq!(TierRule::MatchInput.resolve([4, 1, 3]), 1);
+        assert_eq!(TierRule::MatchInput.resolve([]), 0);
+    }
+
+    #[test]
+    fn upgraded_tiers_are_one_above_the_lowest_input() {
+        assert_eq!(TierRule::InputPlusOne.resolve([2]), 3);
+        assert_eq!(TierRule::InputPlusOne.resolve([4, 1, 3]), 2);
+        assert_eq!(TierRule::InputPlusOne.resolve([u8::MAX]), u8::MAX);
+    }
+
+    #[test]
+    fn average_quality_is_weighted_by_count() {
+        assert_eq!(QualityRule::Average.resolve([(0.5, 2), (1., 2)]), 0.75);
+        assert_eq!(QualityRule::Average.resolve([(0.25, 3), (1., 1)]), 0.4375);
+        assert_eq!(QualityRule::Average.resolve([(0., 0), (0.5, 1)]), 0.5);
+    }
+
+    #[test]
+    fn minimum_quality_takes_the_worst_input() {
+        assert_eq!(QualityRule::Min.resolve([(0.9, 5), (0.3, 1)]), 0.3);
+        assert_eq!(QualityRule::Min.resolve([(0.1, 0), (0.6, 1)]), 0.6);
+    }
+
+    #[test]
+    fn quality_without_inputs_is_full() {
+        for rule in [QualityRule::Average, QualityRule::Min] {
+            assert_eq!(rule.resolve([]), ItemSlot::FULL_QUALITY);
+        }
+        assert_eq!(
+            QualityRule::default().resolve([(0.2, 4)]),
+            ItemSlot::FULL_QUALITY
+        );
+        assert_eq!(QualityRule::Fixed(2.).resolve([]), ItemSlot::FULL_QUALITY);
+    }
+
+    /// An item manifest where `ore` is of tier 1, and `metal` of tier 2.
+    fn tiered_item_manifest() -> ItemManifest {
+        let mut manifest = item_manifest();
+        for (name, tier) in [("ore", 1), ("metal", 2)] {
+            manifest.insert(
+                name.to_string(),
+                ItemData {
+                    stack_size: 10,
+                    compostable: false,
+                    fluid: false,
+                    buoyant: false,
+                    seed: None,
+                    energy_content: Energy(0.),
+                    tags: HashSet::new(),
+                    mass: 1.,
+                    decay_time: None,
+                    decays_into: None,
+                    tier,
+                    name: None,
+                    description: None,
+                    icon: None,
+                },
+            );
+        }
+        manifest
+    }
+
+    /// A recipe manifest that smelts ore into metal, following the `output_tier` rule.
+    fn smelting_with(output_tier: TierRule) -> RawRecipeManifest {
+        let mut raw_data = raw_recipe("ore", "metal");
+        raw_data.output_tier = Some(output_tier);
+
+        RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([("smelting".to_string(), raw_data)]),
+        }
+    }
+
+    #[test]
+    fn outputs_of_the_required_tier_pass_validation() {
+        for output_tier in [TierRule::Fixed(2), TierRule::InputPlusOne] {
+            assert!(smelting_with(output_tier)
+                .tier_mismatches(&tiered_item_manifest())
+                .is_empty());
+        }
+    }
+
+    #[test]
+    fn outputs_of_the_wrong_tier_fail_validation() {
+        let raw_recipe_manifest = smelting_with(TierRule::MatchInput);
+        let mut world = World::new();
+        world.insert_resource(tiered_item_manifest());
+
+        assert_eq!(
+            raw_recipe_manifest.validate(&world),
+            Err(ManifestError::MismatchedTier(vec![TierMismatch {
+                recipe: "smelting".to_string(),
+                item: "metal".to_string(),
+                expected: 1,
+                found: 2,
+            }]))
+        );
+    }
+
+    /// An item manifest where pollen contains 10 energy.
+    fn energetic_item_manifest() -> ItemManifest {
+        let mut manifest = item_manifest();
+        manifest.insert(
+            "pollen".to_string(),
+            ItemData {
+                stack_size: 10,
+                compostable: true,
+                fluid: false,
+                buoyant: true,
+                seed: None,
+                energy_content: Energy(10.),
+                tags: HashSet::new(),
+                mass: 1.,
+                decay_time: None,
+                decays_into: None,
+                tier: 0,
+                name: None,
+                description: None,
+                icon: None,
+            },
+        );
+        manifest
+    }
+
+    /// A recipe manifest that makes honey from two pollen, producing `energy`.
+    fn honey_recipe_producing(energy: f32) -> RawRecipeManifest {
+        let mut raw_data = raw_recipe("pollen", "honey");
+        raw_data.inputs = RawRecipeInput::single("pollen", 2);
+        raw_data.energy = Some(Energy(energy));
+
+        RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([("make_honey".to_string(), raw_data)]),
+        }
+    }
+
+    #[test]
+    fn over_unity_recipes_are_unbalanced() {
+        let raw_recipe_manifest = honey_recipe_producing(50.);
+
+        assert_eq!(
+            raw_recipe_manifest.energy_imbalances(&energetic_item_manifest(), 2.),
+            vec![EnergyImbalance {
+                recipe: "make_honey".to_string(),
+                input_energy: 20.,
+                output_energy: 50.,
+            }]
+        );
+    }
+
+    #[test]
+    fn recipes_within_the_ratio_are_balanced() {
+        let raw_recipe_manifest = honey_recipe_producing(30.);
+
+        assert!(raw_recipe_manifest
+            .energy_imbalances(&energetic_item_manifest(), 2.)
+            .is_empty());
+    }
+
+    #[test]
+    fn unbalanced_energy_only_fails_validation_when_denied() {
+        let raw_recipe_manifest = honey_recipe_producing(50.);
+        let mut world = World::new();
+        world.insert_resource(energetic_item_manifest());
+
+        // Without the resource, energy is not checked at all
+        assert_eq!(raw_recipe_manifest.validate(&world), Ok(()));
+
+        // Unbalanced recipes are only warned about by default
+        world.insert_resource(RecipeEnergyBalance::default());
+        assert_eq!(raw_recipe_manifest.validate(&world), Ok(()));
+
+        world.insert_resource(RecipeEnergyBalance {
+            max_ratio: 1.,
+            deny: true,
+        });
+        assert!(matches!(
+            raw_recipe_manifest.validate(&world),
+            Err(ManifestError::UnbalancedEnergy(_))
+        ));
+    }
+
+    /// A recipe that turns one pollen into `honey_count` honey, both weighing 1 kg.
+    fn pollen_to_honey(honey_count: f32) -> RawRecipeData {
+        let mut raw_data = raw_recipe("pollen", "honey");
+        raw_data.outputs =
+            HashMap::from_iter([("honey".to_string(), RawOutputCount::Count(honey_count))]);
+        raw_data
+    }
+
+    #[test]
+    fn recipes_that_conserve_mass_are_balanced() {
+        let recipe_data = RecipeData::try_from(pollen_to_honey(1.)).unwrap();
+
+        assert!(recipe_data.is_balanced_mass(&item_manifest()));
+    }
+
+    #[test]
+    fn recipes_that_create_mass_are_unbalanced() {
+        let raw_recipe_manifest = RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([
+                ("make_honey".to_string(), pollen_to_honey(1.)),
+                ("multiply_honey".to_string(), pollen_to_honey(3.)),
+            ]),
         };
+        let recipe_manifest = raw_recipe_manifest.process().unwrap();
+        let multiply_honey =
+            recipe_manifest.get_unchecked(Id::from_name("multiply_honey".to_string()));
 
-        let output_strings: Vec<String> = self
-            .outputs
-            .item_ids()
-            .iter()
-            .map(|output_id| item_manifest.name(*output_id).to_string())
-            .collect();
-        let output_str = output_strings.join(", ");
+        assert!(!multiply_honey.is_balanced_mass(&item_manifest()));
+        assert_eq!(
+            recipe_manifest.mass_gaining_recipes(&item_manifest()),
+            vec!["multiply_honey".to_string()]
+        );
+    }
 
-        let duration_str = format!("{:.2}", self.craft_time.as_secs_f32());
+    #[test]
+    fn recipes_may_be_allowed_to_gain_mass() {
+        let mut raw_data = pollen_to_honey(3.);
+        raw_data.allow_mass_gain = true;
+        let recipe_data = RecipeData::try_from(raw_data).unwrap();
 
-        let condition_str = if self.conditions == RecipeConditions::default() {
-            String::new()
-        } else {
-            format!("\nwhen {}", self.conditions)
+        assert!(recipe_data.is_balanced_mass(&item_manifest()));
+    }
+
+    /// A recipe manifest holding only the `recipe_name` recipe, which turns pollen into the given `outputs`.
+    fn pollen_recipe(recipe_name: &str, outputs: &[(&str, f32)]) -> RawRecipeManifest {
+        let raw_data = RawRecipeData {
+            outputs: outputs
+                .iter()
+                .map(|&(item, count)| (item.to_string(), RawOutputCount::Count(count)))
+                .collect(),
+            ..raw_recipe("pollen", "honey")
         };
 
-        format!("[{input_str}] -> [{output_str}] | {duration_str} s{condition_str}")
+        RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([(recipe_name.to_string(), raw_data)]),
+        }
     }
-}
 
-/// The environmental conditions needed for work to be done on a recipe.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
-pub struct RecipeConditions {
-    /// The number of workers required to advance this recipe.
-    pub workers_required: u8,
-    /// The range of light levels that are acceptable for this recipe.
-    pub allowable_light_range: Option<Threshold<Illuminance>>,
-}
+    #[test]
+    fn duplicating_recipes_fail_validation() {
+        let raw_recipe_manifest = pollen_recipe("duplicate_pollen", &[("pollen", 2.)]);
+        let mut world = World::new();
+        world.insert_resource(item_manifest());
 
-impl Display for RecipeConditions {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.workers_required > 0 {
-            write!(f, "Workers: {}", self.workers_required)?;
+        assert_eq!(
+            raw_recipe_manifest.validate(&world),
+            Err(ManifestError::SelfProducing(vec![
+                "duplicate_pollen".to_string()
+            ]))
+        );
+    }
+
+    #[test]
+    fn expected_yields_count_towards_self_production() {
+        let raw_recipe_manifest = pollen_recipe("duplicate_pollen", &[("pollen", 1.5)]);
+
+        assert_eq!(
+            raw_recipe_manifest.self_producing_recipes(),
+            vec!["duplicate_pollen".to_string()]
+        );
+    }
+
+    #[test]
+    fn net_producing_recipes_without_shared_inputs_pass_validation() {
+        let mut world = World::new();
+        world.insert_resource(item_manifest());
+
+        for raw_recipe_manifest in [
+            pollen_recipe("make_honey", &[("honey", 3.)]),
+            // Some of the pollen is returned, but less than was consumed
+            pollen_recipe("sift_pollen", &[("pollen", 1.), ("honey", 1.)]),
+        ] {
+            assert_eq!(raw_recipe_manifest.validate(&world), Ok(()));
         }
-        if let Some(range) = &self.allowable_light_range {
-            write!(f, "Light: {}", *range)?;
+    }
+
+    #[test]
+    fn recipes_may_be_allowed_to_produce_their_inputs() {
+        let mut raw_recipe_manifest = pollen_recipe("duplicate_pollen", &[("pollen", 2.)]);
+        for raw_data in raw_recipe_manifest.recipes.values_mut() {
+            raw_data.allow_self_production = true;
         }
-        Ok(())
+        let mut world = World::new();
+        world.insert_resource(item_manifest());
+
+        assert_eq!(raw_recipe_manifest.validate(&world), Ok(()));
     }
-}
 
-impl RecipeConditions {
-    /// No conditions are required to craft this recipe.
-    pub const NONE: RecipeConditions = RecipeConditions {
-        workers_required: 0,
-        allowable_light_range: None,
-    };
+    #[test]
+    fn passive_recipes_without_craft_time_fail_validation() {
+        let mut raw_data = raw_recipe("pollen", "honey");
+        raw_data.craft_time = 0.;
+        let raw_recipe_manifest = RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([("make_honey".to_string(), raw_data)]),
+        };
+        let mut world = World::new();
+        world.insert_resource(item_manifest());
 
-    /// Creates a new [`RecipeConditions`].
-    pub const fn new(workers_required: u8, allowable_light_range: Threshold<Illuminance>) -> Self {
-        Self {
+        assert_eq!(
+            raw_recipe_manifest.validate(&world),
+            Err(ManifestError::ZeroCraftTime(vec!["make_honey".to_string()]))
+        );
+    }
+
+    #[test]
+    fn worked_recipes_without_craft_time_pass_validation() {
+        let mut raw_data = raw_recipe("pollen", "honey");
+        raw_data.craft_time = 0.;
+        raw_data.conditions = Some(RecipeConditions {
+            workers_required: 1,
+            allowable_light_range: None,
+        });
+        let raw_recipe_manifest = RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([("make_honey".to_string(), raw_data)]),
+        };
+        let mut world = World::new();
+        world.insert_resource(item_manifest());
+
+        assert_eq!(raw_recipe_manifest.validate(&world), Ok(()));
+    }
+
+    /// A recipe that turns one leaf and two dirt into three dirt over two seconds.
+    fn dirt_recycling() -> RecipeData {
+        RecipeData {
+            inputs: RecipeInput::Exact(vec![
+                ItemCount::new(Id::from_name("leaf".to_string()), 1),
+                ItemCount::new(Id::from_name("dirt".to_string()), 2),
+            ]),
+            catalysts: Vec::new(),
+            outputs: RecipeOutput::Deterministic(vec![ItemCount::new(
+                Id::from_name("dirt".to_string()),
+                3,
+            )]),
+            probabilistic_outputs: Vec::new(),
+            ranged_outputs: Vec::new(),
+            output_one_of: Vec::new(),
+            byproducts: Vec::new(),
+            craft_time: Duration::from_secs(2),
+            work_units: None,
+            conditions: RecipeConditions::NONE,
+            energy: None,
+            allow_mass_gain: false,
+            allow_self_production: false,
+            requires: Vec::new(),
+            research_cost: Vec::new(),
+            fluid_inputs: Vec::new(),
+            fluid_outputs: Vec::new(),
+            consumption: ConsumptionMode::AtStart,
+            overflow: OverflowPolicy::Block,
+            output_slots: HashMap::new(),
+            max_batch: 1,
+            next_recipe: None,
+            output_tier: None,
+            output_quality: QualityRule::default(),
+            category: RawRecipeData::DEFAULT_CATEGORY.to_string(),
+            active_phase: TimePhase::Always,
+            requires_environment: None,
+        }
+    }
+
+    #[test]
+    fn net_item_flow_nets_items_that_are_consumed_and_produced() {
+        let flow = dirt_recycling().net_item_flow();
+
+        assert_eq!(flow.len(), 2);
+        assert_eq!(flow[&Id::from_name("leaf".to_string())], -1);
+        assert_eq!(flow[&Id::from_name("dirt".to_string())], 1);
+    }
+
+    #[test]
+    fn items_per_second_divides_the_flow_by_the_craft_time() {
+        let mut recipe = dirt_recycling();
+        recipe.probabilistic_outputs = vec![ProbabilisticOutput {
+            item_count: ItemCount::new(Id::from_name("spores".to_string()), 4),
+            chance: 0.5,
+        }];
+
+        let rates = recipe.items_per_second();
+
+        assert_eq!(rates[&Id::from_name("leaf".to_string())], -0.5);
+        assert_eq!(rates[&Id::from_name("dirt".to_string())], 0.5);
+        assert_eq!(rates[&Id::from_name("spores".to_string())], 1.);
+        // Random outputs are not guaranteed
+        assert!(!recipe
+            .net_item_flow()
+            .contains_key(&Id::from_name("spores".to_string())));
+    }
+
+    #[test]
+    fn instant_recipes_have_no_rate() {
+        let mut recipe = dirt_recycling();
+        recipe.craft_time = Duration::ZERO;
+
+        assert!(recipe.items_per_second().is_empty());
+    }
+
+    /// A recipe that needs `workers_required` workers to be crafted at full speed.
+    fn worked_recipe(workers_required: u8) -> RecipeData {
+        let mut recipe = dirt_recycling();
+        recipe.conditions = RecipeConditions {
             workers_required,
-            allowable_light_range: Some(allowable_light_range),
+            allowable_light_range: None,
+        };
+        recipe
+    }
+
+    #[test]
+    fn work_rate_scales_with_workers_up_to_the_required_count() {
+        let recipe = worked_recipe(4);
+
+        assert_eq!(recipe.work_rate(0.), 0.);
+        assert_eq!(recipe.work_rate(2.), 0.5);
+        assert_eq!(recipe.work_rate(4.), 1.);
+        assert_eq!(recipe.work_rate(6.), 1.);
+    }
+
+    #[test]
+    fn recipes_without_workers_progress_at_full_speed() {
+        assert_eq!(dirt_recycling().work_rate(0.), 1.);
+    }
+
+    /// The number of fixed ticks of `period` needed to complete `recipe` with the given number of `workers`.
+    fn ticks_to_complete(recipe: &RecipeData, workers: f32, period: Duration) -> Option<u32> {
+        let mut crafting_progress = CraftingProgress::default();
+        for tick in 1..=10_000 {
+            crafting_progress.add(period.mul_f32(recipe.work_rate(workers)));
+            if crafting_progress.try_complete(recipe.craft_time) {
+                return Some(tick);
+            }
         }
+
+        None
     }
 
-    /// Are the conditions to craft this recipe met?
-    fn satisfied(&self, workers: u8, received_light: &ReceivedLight) -> bool {
-        let work_satisfied = self.workers_required == 0 || workers >= self.workers_required;
-        let light_satisfied = self
-            .allowable_light_range
-            .as_ref()
-            .map_or(true, |range| range.contains(received_light.0));
+    #[test]
+    fn doubling_workers_halves_the_time_needed_for_fixed_work() {
+        let mut recipe = worked_recipe(1);
+        recipe.work_units = Some(4.);
+        // Exactly representable as a float, so that no ticks are lost to rounding
+        let period = Duration::from_millis(125);
 
-        work_satisfied && light_satisfied
+        // Four worker-seconds of work take a single worker four seconds
+        assert_eq!(ticks_to_complete(&recipe, 1., period), Some(32));
+        // Workers beyond the number required still help out
+        assert_eq!(ticks_to_complete(&recipe, 2., period), Some(16));
+        assert_eq!(ticks_to_complete(&recipe, 4., period), Some(8));
     }
-}
 
-/// A viable range of a value.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct Threshold<T: PartialOrd> {
-    /// The minimum value of the range.
-    min: T,
-    /// The maximum value of the range.
-    max: T,
-}
+    #[test]
+    fn fixed_work_makes_no_progress_without_workers() {
+        let mut recipe = worked_recipe(1);
+        recipe.work_units = Some(4.);
 
-impl<T: PartialOrd> Threshold<T> {
-    /// Creates a new [`Threshold`].
-    pub fn new(min: T, max: T) -> Self {
-        assert!(min <= max);
+        assert_eq!(recipe.work_rate(0.), 0.);
+        assert_eq!(
+            ticks_to_complete(&recipe, 0., Duration::from_millis(125)),
+            None
+        );
+    }
 
-        Self { min, max }
+    #[test]
+    fn passive_recipes_ignore_work_units() {
+        let mut recipe = dirt_recycling();
+        recipe.work_units = Some(100.);
+
+        assert_eq!(recipe.work_rate(0.), 1.);
+        assert_eq!(recipe.work_rate(3.), 1.);
     }
 
-    /// Returns true if the value is within the threshold.
-    fn contains(&self, value: T) -> bool {
-        self.min <= value && value <= self.max
+    #[test]
+    fn work_units_must_be_positive() {
+        let raw_data = RawRecipeData {
+            work_units: Some(0.),
+            ..raw_recipe("pollen", "honey")
+        };
+
+        let fields: Vec<String> = raw_data
+            .out_of_range_values("make_honey")
+            .into_iter()
+            .map(|value| value.field)
+            .collect();
+        assert_eq!(fields, vec!["work_units"]);
     }
-}
 
-impl<T: Display + PartialOrd> Display for Threshold<T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} - {}", self.min, self.max)
+    #[test]
+    fn fixed_work_recipes_without_craft_time_fail_validation() {
+        let mut raw_data = raw_recipe("pollen", "honey");
+        raw_data.craft_time = 0.;
+        raw_data.work_units = Some(4.);
+        raw_data.conditions = Some(RecipeConditions {
+            workers_required: 1,
+            allowable_light_range: None,
+        });
+        let raw_recipe_manifest = RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([("make_honey".to_string(), raw_data)]),
+        };
+
+        assert_eq!(
+            raw_recipe_manifest.zero_craft_time_recipes(),
+            vec!["make_honey".to_string()]
+        );
     }
-}
 
-/// The [`RecipeManifest`] as seen in the manifest file.
-#[derive(Debug, Clone, Serialize, Deserialize, TypeUuid, PartialEq)]
-#[uuid = "c711b30c-c3ff-4b86-92d0-f1aff2ec7818"]
-pub struct RawRecipeManifest {
-    /// The data for each item.
-    pub recipes: HashMap<String, RawRecipeData>,
-}
+    #[test]
+    fn workers_required_determines_the_work_kind() {
+        assert_eq!(dirt_recycling().work_kind(), WorkKind::Passive);
+        assert_eq!(worked_recipe(1).work_kind(), WorkKind::ManualWork);
+        assert_eq!(worked_recipe(4).work_kind(), WorkKind::ManualWork);
+    }
 
-impl IsRawManifest for RawRecipeManifest {
-    const EXTENSION: &'static str = "recipe_manifest.json";
+    #[test]
+    fn partial_crews_satisfy_worked_recipes() {
+        let recipe = worked_recipe(4);
+        let received_light = ReceivedLight::default();
 
-    type Marker = Recipe;
-    type Data = RecipeData;
+        assert!(!recipe.satisfied(0, &received_light, TimeOfDay::Day));
+        assert!(recipe.satisfied(1, &received_light, TimeOfDay::Day));
+        assert!(recipe.satisfied(4, &received_light, TimeOfDay::Day));
+    }
 
-    fn process(&self) -> Manifest<Self::Marker, Self::Data> {
-        let mut manifest = Manifest::new();
+    #[test]
+    fn phases_are_active_at_their_time_of_day() {
+        assert!(TimePhase::Always.is_active(TimeOfDay::Day));
+        assert!(TimePhase::Always.is_active(TimeOfDay::Night));
+        assert!(TimePhase::Day.is_active(TimeOfDay::Day));
+        assert!(!TimePhase::Day.is_active(TimeOfDay::Night));
+        assert!(!TimePhase::Night.is_active(TimeOfDay::Day));
+        assert!(TimePhase::Night.is_active(TimeOfDay::Night));
+    }
 
-        for (raw_id, raw_data) in self.recipes.clone() {
-            let data = raw_data.into();
+    #[test]
+    fn day_recipes_only_advance_during_the_day() {
+        let mut recipe = dirt_recycling();
+        recipe.active_phase = TimePhase::Day;
+        let received_light = ReceivedLight::default();
+        let period = Duration::from_secs(1);
+        let mut crafting_progress = CraftingProgress::default();
 
-            manifest.insert(raw_id, data)
+        // Alternate between three ticks of day and three ticks of night
+        for tick in 0..12 {
+            let time_of_day = if (tick / 3) % 2 == 0 {
+                TimeOfDay::Day
+            } else {
+                TimeOfDay::Night
+            };
+            let before = crafting_progress.elapsed();
+
+            if recipe.satisfied(0, &received_light, time_of_day) {
+                crafting_progress.add(period);
+            }
+
+            match time_of_day {
+                TimeOfDay::Day => assert_eq!(crafting_progress.elapsed(), before + period),
+                TimeOfDay::Night => assert_eq!(crafting_progress.elapsed(), before),
+            }
         }
 
+        assert_eq!(crafting_progress.elapsed(), period * 6);
+    }
+
+    #[test]
+    fn recipes_are_active_at_any_time_by_default() {
+        let raw_data = raw_recipe("pollen", "honey");
+        assert_eq!(raw_data.active_phase, TimePhase::Always);
+
+        let recipe = RecipeData::try_from(raw_data).unwrap();
+        let received_light = ReceivedLight::default();
+        assert!(recipe.satisfied(0, &received_light, TimeOfDay::Day));
+        assert!(recipe.satisfied(0, &received_light, TimeOfDay::Night));
+    }
+
+    /// An environment manifest where tiles are 20 degrees warm unless stated otherwise.
+    fn environment_manifest() -> EnvironmentManifest {
+        let mut manifest = Manifest::new();
+        manifest.insert("temperature".to_string(), EnvironmentData { default: 20. });
         manifest
     }
-}
 
-/// The recipe that is currently being crafted, if any.
-#[derive(Component, Debug, Default, PartialEq, Eq, Clone, Serialize, Deserialize)]
-pub struct ActiveRecipe(pub(super) Option<Id<Recipe>>);
+    /// A recipe that only makes progress while the temperature is between `min` and `max`.
+    fn temperature_gated_recipe(key: &str, min: f32, max: f32) -> RawRecipeData {
+        RawRecipeData {
+            requires_environment: Some(RawEnvironmentRequirement {
+                key: key.to_string(),
+                min,
+                max,
+            }),
+            ..raw_recipe("pollen", "honey")
+        }
+    }
 
-/// The raw version of [`ActiveRecipe`].
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub struct RawActiveRecipe(Option<String>);
+    #[test]
+    fn recipes_only_advance_within_their_temperature_range() {
+        let recipe =
+            RecipeData::try_from(temperature_gated_recipe("temperature", 25., 40.)).unwrap();
+        let environment_manifest = environment_manifest();
+        let temperature = Id::from_name("temperature".to_string());
+        let period = Duration::from_secs(1);
+        let mut environment_sample = EnvironmentSample::default();
+        let mut crafting_progress = CraftingProgress::default();
 
-impl RawActiveRecipe {
-    /// Creates a new [`RawActiveRecipe`], set to `recipe_name`.
-    pub fn new(recipe_name: &str) -> Self {
-        RawActiveRecipe(Some(recipe_name.to_string()))
+        // Tiles that were never warmed up are too cold
+        assert!(!recipe.environment_satisfied(&environment_sample, &environment_manifest));
+
+        // The tile warms up past the range, then cools down below it again
+        for (degrees, in_range) in [
+            (25., true),
+            (32.5, true),
+            (40., true),
+            (45., false),
+            (30., true),
+            (10., false),
+        ] {
+            environment_sample.set(temperature, degrees);
+            let before = crafting_progress.elapsed();
+
+            if recipe.environment_satisfied(&environment_sample, &environment_manifest) {
+                crafting_progress.add(period);
+            }
+
+            if in_range {
+                assert_eq!(crafting_progress.elapsed(), before + period);
+            } else {
+                assert_eq!(crafting_progress.elapsed(), before);
+            }
+        }
+
+        assert_eq!(crafting_progress.elapsed(), period * 4);
     }
-}
 
-impl From<RawActiveRecipe> for ActiveRecipe {
-    fn from(raw: RawActiveRecipe) -> Self {
-        ActiveRecipe(raw.0.map(Id::from_name))
+    #[test]
+    fn recipes_without_environmental_requirements_advance_anywhere() {
+        let recipe = RecipeData::try_from(raw_recipe("pollen", "honey")).unwrap();
+        let mut environment_sample = EnvironmentSample::default();
+        environment_sample.set(Id::from_name("temperature".to_string()), -100.);
+
+        assert!(recipe.environment_satisfied(&environment_sample, &environment_manifest()));
     }
-}
 
-impl ActiveRecipe {
-    /// The un-set [`ActiveRecipe`].
-    pub const NONE: ActiveRecipe = ActiveRecipe(None);
+    #[test]
+    fn environment_keys_must_be_in_the_environment_manifest() {
+        let raw_recipe_manifest = RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([
+                (
+                    "make_honey".to_string(),
+                    temperature_gated_recipe("temperature", 25., 40.),
+                ),
+                (
+                    "make_mead".to_string(),
+                    temperature_gated_recipe("humidity", 0.5, 1.),
+                ),
+            ]),
+        };
+        let mut world = World::new();
+        world.insert_resource(item_manifest());
+        world.insert_resource(environment_manifest());
 
-    /// Creates a new [`ActiveRecipe`], set to `recipe_id`
-    pub fn new(recipe_id: Id<Recipe>) -> Self {
-        ActiveRecipe(Some(recipe_id))
+        assert_eq!(
+            raw_recipe_manifest.validate(&world),
+            Err(ManifestError::UnknownReference(vec![DanglingReference {
+                entry: "make_mead".to_string(),
+                name: "humidity".to_string(),
+            }]))
+        );
     }
 
-    /// The ID of the currently active recipe, if one has been selected.
-    pub fn recipe_id(&self) -> &Option<Id<Recipe>> {
-        &self.0
+    #[test]
+    fn inverted_environment_ranges_are_out_of_range() {
+        let raw_data = temperature_gated_recipe("temperature", 40., 25.);
+
+        assert_eq!(
+            raw_data.out_of_range_values("make_honey"),
+            vec![OutOfRangeValue {
+                entry: "make_honey".to_string(),
+                field: "requires_environment.max".to_string(),
+                value: "25".to_string(),
+                expected: "at least `min`",
+            }]
+        );
     }
 
-    /// The pretty formatting for this type
-    pub(crate) fn display(&self, recipe_manifest: &RecipeManifest) -> String {
-        match self.0 {
-            Some(recipe_id) => recipe_manifest.name(recipe_id).to_string(),
-            None => "None".to_string(),
+    /// An energy pool that currently holds `current` energy.
+    fn energy_pool_with(current: f32) -> EnergyPool {
+        let mut energy_pool = EnergyPool::simple(100.);
+        energy_pool.set_current(Energy(current));
+        energy_pool
+    }
+
+    #[test]
+    fn energy_producing_recipes_are_always_affordable() {
+        let mut recipe = dirt_recycling();
+        recipe.energy = Some(Energy(20.));
+
+        assert!(recipe.can_afford_energy(Some(&energy_pool_with(0.))));
+        assert!(recipe.can_afford_energy(None));
+    }
+
+    #[test]
+    fn energy_consuming_recipes_stall_without_enough_energy() {
+        let mut recipe = dirt_recycling();
+        recipe.energy = Some(Energy(-20.));
+
+        assert!(!recipe.can_afford_energy(Some(&energy_pool_with(10.))));
+        assert!(recipe.can_afford_energy(Some(&energy_pool_with(20.))));
+        // Structures that are not alive do not pay for energy
+        assert!(recipe.can_afford_energy(None));
+    }
+
+    #[test]
+    fn disabled_recipes_are_left_out() {
+        let raw_recipe_manifest = RawRecipeManifest {
+            version: RawRecipeManifest::CURRENT_VERSION,
+            recipes: HashMap::from_iter([
+                ("make_honey".to_string(), raw_recipe("pollen", "honey")),
+                (
+                    "make_nectar".to_string(),
+                    RawRecipeData {
+                        enabled: false,
+                        ..raw_recipe("pollen", "nectar")
+                    },
+                ),
+            ]),
+        };
+
+        let recipe_manifest = raw_recipe_manifest.process().unwrap();
+        assert_eq!(recipe_manifest.names().collect_vec(), vec!["make_honey"]);
+        // The unknown nectar is not reported, as the recipe is switched off
+        assert_eq!(raw_recipe_manifest.validate_items(&item_manifest()), Ok(()));
+    }
+
+    #[test]
+    fn recipes_are_enabled_by_default() {
+        let raw_data: RawRecipeData = serde_json::from_str(
+            r#"{
+                "inputs": { "Exact": { "pollen": 1 } },
+                "outputs": { "honey": 1 },
+                "craft_time": 1.0,
+                "conditions": null,
+                "energy": null
+            }"#,
+        )
+        .unwrap();
+
+        assert!(raw_data.enabled);
+    }
+
+    /// A recipe that turns three pollen into honey, using one honey as a catalyst.
+    fn seeded_honey() -> RecipeData {
+        RecipeData {
+            inputs: RecipeInput::Exact(vec![ItemCount::new(
+                Id::from_name("pollen".to_string()),
+                3,
+            )]),
+            catalysts: vec![ItemCount::new(Id::from_name("honey".to_string()), 1)],
+            ..dirt_recycling()
+        }
+    }
+
+    /// An inventory holding the provided items.
+    fn inventory_with(item_counts: &[ItemCount]) -> Inventory {
+        let item_manifest = item_manifest();
+        let mut inventory = Inventory::new(4, None);
+        for item_count in item_counts {
+            inventory.try_add_item(item_count, &item_manifest).unwrap();
         }
+        inventory
+    }
+
+    #[test]
+    fn recipes_can_be_crafted_with_all_inputs_available() {
+        let recipe = seeded_honey();
+        let inventory = inventory_with(&[
+            ItemCount::new(Id::from_name("pollen".to_string()), 5),
+            ItemCount::new(Id::from_name("honey".to_string()), 1),
+        ]);
+
+        assert!(recipe.missing_inputs(&inventory).is_empty());
+        assert!(recipe.can_craft_with(&inventory, &item_manifest()));
+    }
+
+    #[test]
+    fn partially_available_inputs_report_the_shortfall() {
+        let recipe = seeded_honey();
+        let inventory = inventory_with(&[ItemCount::new(Id::from_name("pollen".to_string()), 1)]);
+
+        assert_eq!(
+            recipe.missing_inputs(&inventory),
+            vec![
+                ItemCount::new(Id::from_name("pollen".to_string()), 2),
+                ItemCount::new(Id::from_name("honey".to_string()), 1),
+            ]
+        );
+        assert!(!recipe.can_craft_with(&inventory, &item_manifest()));
+    }
+
+    #[test]
+    fn empty_inventories_miss_every_input() {
+        let recipe = seeded_honey();
+        let inventory = Inventory::new(4, None);
+
+        assert_eq!(
+            recipe.missing_inputs(&inventory),
+            vec![
+                ItemCount::new(Id::from_name("pollen".to_string()), 3),
+                ItemCount::new(Id::from_name("honey".to_string()), 1),
+            ]
+        );
+        assert!(!recipe.can_craft_with(&inventory, &item_manifest()));
+    }
+
+    #[test]
+    fn flexible_inputs_are_counted_by_tag() {
+        let recipe = RecipeData {
+            inputs: RecipeInput::Flexible {
+                tag: ItemTag::Compostable,
+                count: 3,
+            },
+            ..dirt_recycling()
+        };
+        let pollen =
+            |count| inventory_with(&[ItemCount::new(Id::from_name("pollen".to_string()), count)]);
+
+        assert!(recipe.missing_inputs(&pollen(2)).is_empty());
+        assert!(!recipe.can_craft_with(&pollen(2), &item_manifest()));
+        assert!(recipe.can_craft_with(&pollen(3), &item_manifest()));
     }
 }
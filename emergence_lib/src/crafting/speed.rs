@@ -0,0 +1,124 @@
+//! Multipliers on how quickly structures craft, independent of the recipe being crafted.
+
+use bevy::prelude::*;
+
+use std::{fmt::Display, time::Duration};
+
+/// How quickly a structure crafts, relative to the `craft_time` of its recipes.
+///
+/// A speed of 2.0 halves the effective craft time, while a speed of 0.0 stalls crafting entirely.
+/// Upgrades and buffs change this multiplier, rather than the recipes themselves.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct CraftingSpeed(f32);
+
+impl CraftingSpeed {
+    /// Creates a new [`CraftingSpeed`] with the provided multiplier.
+    ///
+    /// Negative or non-finite multipliers are treated as 0.0, stalling crafting.
+    pub fn new(multiplier: f32) -> Self {
+        if multiplier.is_finite() {
+            CraftingSpeed(multiplier.max(0.))
+        } else {
+            CraftingSpeed(0.)
+        }
+    }
+
+    /// The multiplier applied to the time spent crafting.
+    pub fn multiplier(&self) -> f32 {
+        self.0
+    }
+
+    /// Sets the multiplier applied to the time spent crafting.
+    ///
+    /// The same rules as [`CraftingSpeed::new`] apply.
+    pub fn set_multiplier(&mut self, multiplier: f32) {
+        *self = CraftingSpeed::new(multiplier);
+    }
+
+    /// Is crafting stalled, making no progress at all?
+    pub fn is_stalled(&self) -> bool {
+        self.0 == 0.
+    }
+
+    /// The effective crafting time gained when `elapsed` time passes.
+    pub fn scale(&self, elapsed: Duration) -> Duration {
+        elapsed.mul_f32(self.0)
+    }
+}
+
+impl Default for CraftingSpeed {
+    fn default() -> Self {
+        CraftingSpeed(1.)
+    }
+}
+
+impl Display for CraftingSpeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.2}x", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crafting::inventories::CraftingProgress;
+
+    /// The number of fixed ticks of `period` needed to complete a single cycle taking `craft_time` at the given `speed`.
+    fn ticks_to_complete(
+        speed: CraftingSpeed,
+        craft_time: Duration,
+        period: Duration,
+    ) -> Option<u32> {
+        let mut crafting_progress = CraftingProgress::default();
+        for tick in 1..=10_000 {
+            crafting_progress.add(speed.scale(period));
+            if crafting_progress.try_complete(craft_time) {
+                return Some(tick);
+            }
+        }
+
+        None
+    }
+
+    #[test]
+    fn default_speed_leaves_time_unchanged() {
+        let period = Duration::from_millis(125);
+        assert_eq!(CraftingSpeed::default().scale(period), period);
+    }
+
+    #[test]
+    fn completion_time_scales_inversely_with_speed() {
+        let craft_time = Duration::from_secs(4);
+        // Exactly representable as a float, so that no ticks are lost to rounding
+        let period = Duration::from_millis(125);
+
+        let base = ticks_to_complete(CraftingSpeed::default(), craft_time, period).unwrap();
+        assert_eq!(base, 32);
+
+        for (multiplier, expected) in [(2., 16), (4., 8), (0.5, 64)] {
+            assert_eq!(
+                ticks_to_complete(CraftingSpeed::new(multiplier), craft_time, period),
+                Some(expected),
+                "A speed of {multiplier} should take {expected} ticks"
+            );
+        }
+    }
+
+    #[test]
+    fn zero_speed_stalls_crafting() {
+        let speed = CraftingSpeed::new(0.);
+
+        assert!(speed.is_stalled());
+        assert_eq!(
+            ticks_to_complete(speed, Duration::from_secs(1), Duration::from_millis(100)),
+            None
+        );
+    }
+
+    #[test]
+    fn invalid_speeds_stall_crafting() {
+        for multiplier in [-1., f32::NAN, f32::INFINITY] {
+            assert!(CraftingSpeed::new(multiplier).is_stalled());
+        }
+    }
+}
@@ -0,0 +1,373 @@
+//! Headless simulation of recipe throughput, for balance testing.
+//!
+//! Each recipe is crafted by its own crafter, which is assumed to always have workers present.
+//! All crafters share a single stock of items, from which inputs are paid and into which outputs are placed.
+//!
+//! The simulation leaves out everything else:
+//! energy, environmental conditions, fluid inputs and outputs and storage limits are all ignored.
+
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use rand::Rng;
+
+use crate::{
+    asset_management::manifest::Id,
+    items::item_manifest::{Item, ItemManifest},
+};
+
+use super::{
+    inventories::CraftingProgress,
+    recipe::{Recipe, RecipeData, RecipeInput, RecipeManifest},
+};
+
+/// The outcome of [`simulate_throughput`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ThroughputReport {
+    /// The items in stock at the end of the simulation.
+    ///
+    /// Items that ran out are left out.
+    pub inventory: HashMap<Id<Item>, u32>,
+    /// The net number of each item produced per second, compared to the starting inventory.
+    ///
+    /// Items that were consumed on balance have a negative rate.
+    pub items_per_second: HashMap<Id<Item>, f64>,
+    /// The number of times each recipe was completed.
+    pub completions: HashMap<Id<Recipe>, u32>,
+}
+
+/// The state of the crafter for a single recipe.
+#[derive(Debug, Default)]
+struct Crafter {
+    /// Have the inputs for the current cycle been paid?
+    active: bool,
+    /// The time spent crafting the current cycle.
+    progress: CraftingProgress,
+}
+
+/// Simulates crafting every recipe in the `recipe_manifest` for the given `duration`, starting from the `starting_inventory`.
+///
+/// Time advances in steps of `time_step`, just like the fixed timestep of the game,
+/// and crafting progress carries over between steps in the same way.
+/// Outputs can be used as inputs from the next step onwards.
+/// When several recipes compete for the same items, recipes with a lower [`Id`] are served first.
+///
+/// Recipes that take no time at all never complete, as they would complete infinitely often.
+/// The `rng` is used for random outputs; pass a seeded generator to get reproducible results.
+pub fn simulate_throughput(
+    recipe_manifest: &RecipeManifest,
+    item_manifest: &ItemManifest,
+    starting_inventory: &HashMap<Id<Item>, u32>,
+    duration: Duration,
+    time_step: Duration,
+    rng: &mut impl Rng,
+) -> ThroughputReport {
+    assert!(!time_step.is_zero(), "The time step must be positive");
+
+    let mut recipes: Vec<(Id<Recipe>, &RecipeData)> = recipe_manifest
+        .data_map()
+        .iter()
+        .map(|(&recipe_id, recipe)| (recipe_id, recipe))
+        .collect();
+    recipes.sort_by_key(|&(recipe_id, _)| recipe_id);
+
+    let mut stock = starting_inventory.clone();
+    let mut crafters: HashMap<Id<Recipe>, Crafter> = HashMap::new();
+    let mut completions: HashMap<Id<Recipe>, u32> = HashMap::new();
+    let mut simulated = Duration::ZERO;
+
+    while simulated < duration {
+        let delta = time_step.min(duration - simulated);
+        let mut produced: Vec<(Id<Item>, u32)> = Vec::new();
+
+        for &(recipe_id, recipe) in &recipes {
+            if recipe.craft_time.is_zero() {
+                continue;
+            }
+            let crafter = crafters.entry(recipe_id).or_default();
+
+            if !crafter.active {
+                if !try_pay_inputs(recipe, item_manifest, &mut stock) {
+                    crafter.progress.reset();
+                    continue;
+                }
+                crafter.active = true;
+            }
+
+            crafter.progress.add(delta);
+            while crafter.progress.try_complete(recipe.craft_time) {
+                *completions.entry(recipe_id).or_default() += 1;
+                produced.extend(
+                    recipe
+                        .roll_outputs(rng)
+                        .into_iter()
+                        .map(|output| (output.item_id, output.count)),
+                );
+
+                if !try_pay_inputs(recipe, item_manifest, &mut stock) {
+                    crafter.active = false;
+                    crafter.progress.reset();
+                    break;
+                }
+            }
+        }
+
+        for (item_id, count) in produced {
+            *stock.entry(item_id).or_default() += count;
+        }
+        simulated += delta;
+    }
+
+    let seconds = duration.as_secs_f64();
+    let mut items_per_second = HashMap::new();
+    for &item_id in stock.keys().chain(starting_inventory.keys()) {
+        let start = starting_inventory
+            .get(&item_id)
+            .copied()
+            .unwrap_or_default();
+        let end = stock.get(&item_id).copied().unwrap_or_default();
+        let rate = if seconds > 0. {
+            (f64::from(end) - f64::from(start)) / seconds
+        } else {
+            0.
+        };
+        items_per_second.insert(item_id, rate);
+    }
+
+    stock.retain(|_, count| *count > 0);
+
+    ThroughputReport {
+        inventory: stock,
+        items_per_second,
+        completions,
+    }
+}
+
+/// Removes the inputs of the `recipe` from the `stock`, if all of them and its catalysts are available.
+///
+/// Returns `false` and leaves the stock unchanged otherwise.
+fn try_pay_inputs(
+    recipe: &RecipeData,
+    item_manifest: &ItemManifest,
+    stock: &mut HashMap<Id<Item>, u32>,
+) -> bool {
+    let in_stock = |stock: &HashMap<Id<Item>, u32>, item_id| -> u32 {
+        stock.get(&item_id).copied().unwrap_or_default()
+    };
+
+    if recipe
+        .catalysts
+        .iter()
+        .any(|catalyst| in_stock(stock, catalyst.item_id) < catalyst.count)
+    {
+        return false;
+    }
+
+    match &recipe.inputs {
+        RecipeInput::Exact(inputs) => {
+            if inputs
+                .iter()
+                .any(|input| in_stock(stock, input.item_id) < input.count)
+            {
+                return false;
+            }
+
+            for input in inputs {
+                *stock.entry(input.item_id).or_default() -= input.count;
+            }
+            true
+        }
+        RecipeInput::Flexible { tag, count } => {
+            let mut item_ids: Vec<Id<Item>> = stock
+                .keys()
+                .copied()
+                .filter(|&item_id| {
+                    item_manifest.get(item_id).is_ok() && item_manifest.has_tag(item_id, *tag)
+                })
+                .collect();
+            item_ids.sort();
+
+            take_greedily(stock, &item_ids, *count)
+        }
+        RecipeInput::Category { category, count } => {
+            take_greedily(stock, &item_manifest.items_with_tag(*category), *count)
+        }
+        RecipeInput::AnyOf(alternatives) => {
+            match RecipeInput::first_available_alternative(alternatives, |item_id| {
+                in_stock(stock, item_id)
+            }) {
+                Some(alternative) => {
+                    *stock.entry(alternative.item_id).or_default() -= alternative.count;
+                    true
+                }
+                None => false,
+            }
+        }
+    }
+}
+
+/// Removes `count` items from the `stock`, using up each of the `item_ids` in order.
+///
+/// Returns `false` and leaves the stock unchanged if there are not enough of them in total.
+fn take_greedily(stock: &mut HashMap<Id<Item>, u32>, item_ids: &[Id<Item>], count: u32) -> bool {
+    let available: u32 = item_ids
+        .iter()
+        .map(|item_id| stock.get(item_id).copied().unwrap_or_default())
+        .sum();
+    if available < count {
+        return false;
+    }
+
+    let mut remaining = count;
+    for item_id in item_ids {
+        let Some(in_stock) = stock.get_mut(item_id) else {
+            continue;
+        };
+        let taken = remaining.min(*in_stock);
+        *in_stock -= taken;
+        remaining -= taken;
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        asset_management::manifest::Manifest,
+        crafting::recipe::{
+            ConsumptionMode, OverflowPolicy, QualityRule, RawOutputCount, RawRecipeData,
+            RawRecipeInput, TimePhase,
+        },
+        items::item_manifest::ItemData,
+        organisms::energy::Energy,
+        simulation::rng::GlobalRng,
+    };
+    use bevy::utils::HashSet;
+
+    /// A recipe that turns one `input` into one `output`, taking `craft_time` seconds.
+    fn recipe(input: &str, input_count: u32, output: &str, craft_time: f32) -> RecipeData {
+        RecipeData::try_from(RawRecipeData {
+            inputs: RawRecipeInput::single(input, input_count),
+            catalysts: HashMap::new(),
+            byproducts: HashMap::new(),
+            output_one_of: Vec::new(),
+            outputs: HashMap::from_iter([(output.to_string(), RawOutputCount::Count(1.))]),
+            craft_time,
+            work_units: None,
+            conditions: None,
+            energy: None,
+            allow_mass_gain: false,
+            allow_self_production: false,
+            requires: Vec::new(),
+            research_cost: HashMap::new(),
+            fluid_inputs: HashMap::new(),
+            fluid_outputs: HashMap::new(),
+            consumption: ConsumptionMode::AtStart,
+            overflow: OverflowPolicy::Block,
+            output_slots: HashMap::new(),
+            max_batch: 1,
+            next_recipe: None,
+            output_tier: None,
+            output_quality: QualityRule::default(),
+            category: RawRecipeData::DEFAULT_CATEGORY.to_string(),
+            active_phase: TimePhase::Always,
+            requires_environment: None,
+            enabled: true,
+        })
+        .unwrap()
+    }
+
+    /// The manifests for the items `a`, `b` and `c`, and the chain of recipes `a -> b` and `2 b -> c`.
+    fn chain() -> (RecipeManifest, ItemManifest) {
+        let mut item_manifest = Manifest::new();
+        for name in ["a", "b", "c"] {
+            item_manifest.insert(
+                name.to_string(),
+                ItemData {
+                    stack_size: 10,
+                    compostable: false,
+                    fluid: false,
+                    buoyant: false,
+                    seed: None,
+                    energy_content: Energy(0.),
+                    tags: HashSet::new(),
+                    mass: 1.,
+                    decay_time: None,
+                    decays_into: None,
+                    tier: 0,
+                    name: None,
+                    description: None,
+                    icon: None,
+                },
+            );
+        }
+
+        let mut recipe_manifest = Manifest::new();
+        recipe_manifest.insert("make_b".to_string(), recipe("a", 1, "b", 2.));
+        recipe_manifest.insert("make_c".to_string(), recipe("b", 2, "c", 3.));
+
+        (recipe_manifest, item_manifest)
+    }
+
+    /// Simulates the crafting chain for 20 seconds, starting with 10 `a`.
+    fn simulate_chain(time_step: Duration) -> ThroughputReport {
+        let (recipe_manifest, item_manifest) = chain();
+        let starting_inventory = HashMap::from_iter([(Id::from_name("a".to_string()), 10)]);
+
+        simulate_throughput(
+            &recipe_manifest,
+            &item_manifest,
+            &starting_inventory,
+            Duration::from_secs(20),
+            time_step,
+            GlobalRng::new(42).get_mut(),
+        )
+    }
+
+    #[test]
+    fn chained_recipes_produce_the_expected_counts() {
+        let report = simulate_chain(Duration::from_secs(1));
+
+        // Every 2 seconds, an `a` becomes a `b`, until all 10 are used up after 20 seconds.
+        // Each `c` needs two of those `b`s and another 3 seconds, which leaves time for 4 of them.
+        assert_eq!(
+            report.inventory,
+            HashMap::from_iter([
+                (Id::from_name("b".to_string()), 2),
+                (Id::from_name("c".to_string()), 4),
+            ])
+        );
+        assert_eq!(
+            report.completions,
+            HashMap::from_iter([
+                (Id::from_name("make_b".to_string()), 10),
+                (Id::from_name("make_c".to_string()), 4),
+            ])
+        );
+        assert_eq!(
+            report.items_per_second,
+            HashMap::from_iter([
+                (Id::from_name("a".to_string()), -0.5),
+                (Id::from_name("b".to_string()), 0.1),
+                (Id::from_name("c".to_string()), 0.2),
+            ])
+        );
+    }
+
+    #[test]
+    fn smaller_time_steps_complete_the_same_cycles() {
+        let coarse = simulate_chain(Duration::from_secs(1));
+        let fine = simulate_chain(Duration::from_millis(250));
+
+        assert_eq!(
+            coarse.completions[&Id::from_name("make_b".to_string())],
+            fine.completions[&Id::from_name("make_b".to_string())]
+        );
+        assert_eq!(
+            coarse.inventory[&Id::from_name("c".to_string())],
+            fine.inventory[&Id::from_name("c".to_string())]
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Environmental values that vary from tile to tile, such as temperature and humidity.
+//!
+//! The kinds of values that exist are defined by the [`EnvironmentManifest`],
+//! while the current value at each tile is stored in its [`EnvironmentSample`].
+
+use bevy::{prelude::*, reflect::TypeUuid, utils::HashMap};
+use emergence_macros::RawManifest;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate as emergence_lib;
+use crate::asset_management::manifest::{plugin::ManifestPlugin, Id, Manifest};
+
+/// Loads the [`EnvironmentManifest`].
+pub(crate) struct EnvironmentPlugin;
+
+impl Plugin for EnvironmentPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(ManifestPlugin::<RawEnvironmentManifest>::new());
+    }
+}
+
+/// The marker type for [`Id<EnvironmentKey>`](crate::asset_management::manifest::Id).
+#[derive(Reflect, FromReflect, Clone, Copy, PartialEq, Eq)]
+pub struct EnvironmentKey;
+/// Stores the read-only definitions for all kinds of environmental values.
+pub type EnvironmentManifest = Manifest<EnvironmentKey, EnvironmentData>;
+
+/// The data associated with each kind of environmental value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict_manifests", serde(deny_unknown_fields))]
+pub struct EnvironmentData {
+    /// The value at tiles that have not been given a different one.
+    pub default: f32,
+}
+
+/// The [`EnvironmentManifest`] as seen in the manifest file.
+#[derive(Debug, Clone, Serialize, Deserialize, TypeUuid, PartialEq, RawManifest)]
+#[uuid = "0e5b3b7e-5a61-4c3e-9d0b-8f0f2b9a7c14"]
+#[raw_manifest(
+    extension = "environment_manifest",
+    marker = EnvironmentKey,
+    data = EnvironmentData
+)]
+pub struct RawEnvironmentManifest {
+    /// The version of the manifest format that this file is written in.
+    ///
+    /// This must match [`IsRawManifest::CURRENT_VERSION`](crate::asset_management::manifest::loader::IsRawManifest::CURRENT_VERSION).
+    pub version: u32,
+    /// The data for each kind of environmental value, keyed by its name, such as "temperature".
+    pub keys: HashMap<String, EnvironmentData>,
+}
+
+/// The environmental values at a single tile.
+///
+/// Values that are not stored here take the [default](EnvironmentData::default) of their key.
+#[derive(Component, Debug, Clone, Default, PartialEq)]
+pub struct EnvironmentSample {
+    /// The values that differ from their default, keyed by the kind of value.
+    values: HashMap<Id<EnvironmentKey>, f32>,
+}
+
+impl EnvironmentSample {
+    /// Sets the value of the given `key` at this tile.
+    pub fn set(&mut self, key: Id<EnvironmentKey>, value: f32) {
+        self.values.insert(key, value);
+    }
+
+    /// The value of the given `key` at this tile.
+    ///
+    /// Returns [`None`] if the key is neither set here nor part of the `environment_manifest`.
+    pub fn value(
+        &self,
+        key: Id<EnvironmentKey>,
+        environment_manifest: &EnvironmentManifest,
+    ) -> Option<f32> {
+        self.values.get(&key).copied().or_else(|| {
+            environment_manifest
+                .get(key)
+                .ok()
+                .map(|environment_data| environment_data.default)
+        })
+    }
+}
+
+/// A range that an environmental value must be in, as seen in the manifest files.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "strict_manifests", serde(deny_unknown_fields))]
+pub struct RawEnvironmentRequirement {
+    /// The name of the kind of value, as defined in the [`EnvironmentManifest`].
+    pub key: String,
+    /// The lowest allowed value.
+    pub min: f32,
+    /// The highest allowed value.
+    pub max: f32,
+}
+
+/// A range that an environmental value must be in.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EnvironmentRequirement {
+    /// The kind of value that is checked.
+    pub key: Id<EnvironmentKey>,
+    /// The lowest allowed value.
+    pub min: f32,
+    /// The highest allowed value.
+    pub max: f32,
+}
+
+impl EnvironmentRequirement {
+    /// Is the value of the [`key`](Self::key) in the `environment_sample` within the allowed range?
+    ///
+    /// Unknown keys never satisfy the requirement.
+    pub fn satisfied_by(
+        &self,
+        environment_sample: &EnvironmentSample,
+        environment_manifest: &EnvironmentManifest,
+    ) -> bool {
+        environment_sample
+            .value(self.key, environment_manifest)
+            .is_some_and(|value| (self.min..=self.max).contains(&value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset_management::manifest::loader::IsRawManifest;
+
+    /// An environment manifest with a mild default temperature.
+    fn environment_manifest() -> EnvironmentManifest {
+        let raw_environment_manifest: RawEnvironmentManifest = serde_json::from_str(
+            r#"{
+                "version": 1,
+                "keys": {
+                    "temperature": { "default": 20.0 }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        raw_environment_manifest.process().unwrap()
+    }
+
+    #[test]
+    fn unset_values_take_their_default() {
+        let environment_manifest = environment_manifest();
+        let temperature = Id::from_name("temperature".to_string());
+        let mut environment_sample = EnvironmentSample::default();
+
+        assert_eq!(
+            environment_sample.value(temperature, &environment_manifest),
+            Some(20.)
+        );
+
+        environment_sample.set(temperature, 35.);
+        assert_eq!(
+            environment_sample.value(temperature, &environment_manifest),
+            Some(35.)
+        );
+        assert_eq!(
+            environment_sample.value(Id::from_name("humidity".to_string()), &environment_manifest),
+            None
+        );
+    }
+
+    #[test]
+    fn unknown_keys_never_satisfy_requirements() {
+        let requirement = EnvironmentRequirement {
+            key: Id::from_name("humidity".to_string()),
+            min: f32::MIN,
+            max: f32::MAX,
+        };
+
+        assert!(!requirement.satisfied_by(&EnvironmentSample::default(), &environment_manifest()));
+    }
+}
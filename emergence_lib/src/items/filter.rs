@@ -0,0 +1,70 @@
+//! Restrictions on which items can be stored in an inventory.
+
+use bevy::utils::HashSet;
+
+use crate::asset_management::manifest::Id;
+
+use super::item_manifest::Item;
+
+/// Decides which items may be added to the storage inventory of a structure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ItemFilter {
+    /// Only the listed items are accepted.
+    ///
+    /// An empty list accepts nothing.
+    Allow(HashSet<Id<Item>>),
+    /// Every item except the listed ones is accepted.
+    Deny(HashSet<Id<Item>>),
+}
+
+impl Default for ItemFilter {
+    /// Accepts every item.
+    fn default() -> Self {
+        ItemFilter::Deny(HashSet::new())
+    }
+}
+
+impl ItemFilter {
+    /// Does this filter let the item through?
+    pub fn accepts(&self, item_id: Id<Item>) -> bool {
+        match self {
+            ItemFilter::Allow(allowed) => allowed.contains(&item_id),
+            ItemFilter::Deny(denied) => !denied.contains(&item_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Shorthand for the ID of the item `name`.
+    fn id(name: &str) -> Id<Item> {
+        Id::from_name(name.to_string())
+    }
+
+    #[test]
+    fn allow_lists_only_accept_listed_items() {
+        let filter = ItemFilter::Allow(HashSet::from_iter([id("leaf"), id("acorn")]));
+
+        assert!(filter.accepts(id("leaf")));
+        assert!(filter.accepts(id("acorn")));
+        assert!(!filter.accepts(id("mud")));
+    }
+
+    #[test]
+    fn deny_lists_reject_listed_items() {
+        let filter = ItemFilter::Deny(HashSet::from_iter([id("mud")]));
+
+        assert!(!filter.accepts(id("mud")));
+        assert!(filter.accepts(id("leaf")));
+    }
+
+    #[test]
+    fn empty_allow_lists_accept_nothing() {
+        let filter = ItemFilter::Allow(HashSet::new());
+
+        assert!(!filter.accepts(id("leaf")));
+        assert!(ItemFilter::default().accepts(id("leaf")));
+    }
+}
@@ -0,0 +1,238 @@
+//! Fractional amounts of items that are handled in bulk, such as fluids.
+//!
+//! Most items are counted in whole units, using [`ItemCount`](super::ItemCount).
+//! Items that are moved in bulk can instead be measured in fractional amounts with a [`FluidCount`].
+//!
+//! Amounts are stored as whole thousandths of a unit rather than as floating point numbers,
+//! so that adding and removing them over and over never drifts away from the exact total.
+//! Amounts written as [`f64`] are rounded to the nearest thousandth.
+//!
+//! The two representations are kept apart rather than converted into each other:
+//! a recipe can use both whole and fractional items, and needs each of its inputs in the form that it is listed in.
+
+use std::{
+    fmt::Display,
+    ops::{Add, AddAssign},
+};
+
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::asset_management::manifest::Id;
+
+use super::item_manifest::{Item, ItemManifest};
+
+/// A non-negative, fractional amount of an item, precise to a thousandth of a unit.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct FluidAmount {
+    /// The amount, in thousandths of a unit.
+    thousandths: u64,
+}
+
+impl FluidAmount {
+    /// No amount at all.
+    pub const ZERO: FluidAmount = FluidAmount { thousandths: 0 };
+
+    /// The number of thousandths in a whole unit.
+    const PER_UNIT: f64 = 1000.;
+
+    /// Creates an amount of the given `units`, rounded to the nearest thousandth.
+    ///
+    /// Negative and invalid values are treated as zero.
+    pub fn from_f64(units: f64) -> Self {
+        let thousandths = (units * Self::PER_UNIT).round();
+
+        FluidAmount {
+            // Casting saturates, and maps NaN to zero
+            thousandths: thousandths.max(0.) as u64,
+        }
+    }
+
+    /// The amount, in units.
+    pub fn as_f64(&self) -> f64 {
+        self.thousandths as f64 / Self::PER_UNIT
+    }
+
+    /// Is this amount zero?
+    pub fn is_zero(&self) -> bool {
+        self.thousandths == 0
+    }
+
+    /// Subtracts the `other` amount, returning [`None`] if this amount is too small.
+    pub fn checked_sub(self, other: FluidAmount) -> Option<FluidAmount> {
+        self.thousandths
+            .checked_sub(other.thousandths)
+            .map(|thousandths| FluidAmount { thousandths })
+    }
+}
+
+impl Add for FluidAmount {
+    type Output = FluidAmount;
+
+    fn add(self, rhs: FluidAmount) -> FluidAmount {
+        FluidAmount {
+            thousandths: self.thousandths + rhs.thousandths,
+        }
+    }
+}
+
+impl AddAssign for FluidAmount {
+    fn add_assign(&mut self, rhs: FluidAmount) {
+        self.thousandths += rhs.thousandths;
+    }
+}
+
+impl Display for FluidAmount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.3}", self.as_f64())
+    }
+}
+
+/// A specific, fractional amount of a given item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FluidCount {
+    /// The unique identifier of the item being measured.
+    pub item_id: Id<Item>,
+
+    /// The amount of the item.
+    pub amount: FluidAmount,
+}
+
+impl FluidCount {
+    /// Creates a new fluid count with the given amount of the item.
+    pub fn new(item_id: Id<Item>, amount: FluidAmount) -> Self {
+        Self { item_id, amount }
+    }
+
+    /// The pretty text formatting of this type.
+    pub fn display(&self, item_manifest: &ItemManifest) -> String {
+        let name = item_manifest.name(self.item_id);
+        format!("{}, ({})", name, self.amount)
+    }
+}
+
+/// Stores fractional amounts of any number of items.
+///
+/// Unlike an [`Inventory`](super::inventory::Inventory), this has no slots or stack sizes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FluidInventory {
+    /// The amount stored of each item.
+    amounts: HashMap<Id<Item>, FluidAmount>,
+}
+
+impl FluidInventory {
+    /// The amount of the item that is stored.
+    pub fn amount(&self, item_id: Id<Item>) -> FluidAmount {
+        self.amounts.get(&item_id).copied().unwrap_or_default()
+    }
+
+    /// Adds the fluid to the inventory.
+    pub fn add(&mut self, fluid_count: &FluidCount) {
+        *self.amounts.entry(fluid_count.item_id).or_default() += fluid_count.amount;
+    }
+
+    /// Removes all of the `fluid_counts`, if there is enough of each.
+    ///
+    /// Otherwise, nothing is removed, and the missing amounts are returned.
+    pub fn remove_all_or_nothing(
+        &mut self,
+        fluid_counts: &[FluidCount],
+    ) -> Result<(), Vec<FluidCount>> {
+        // Sum up the requests, in case the same item is listed twice
+        let mut requested: HashMap<Id<Item>, FluidAmount> = HashMap::new();
+        for fluid_count in fluid_counts {
+            *requested.entry(fluid_count.item_id).or_default() += fluid_count.amount;
+        }
+
+        let mut missing: Vec<FluidCount> = requested
+            .iter()
+            .filter_map(|(&item_id, &amount)| {
+                let stored = self.amount(item_id);
+                stored.checked_sub(amount).is_none().then(|| {
+                    FluidCount::new(item_id, amount.checked_sub(stored).unwrap_or_default())
+                })
+            })
+            .collect();
+
+        if !missing.is_empty() {
+            missing.sort_by_key(|fluid_count| fluid_count.item_id);
+            return Err(missing);
+        }
+
+        for (item_id, amount) in requested {
+            let stored = self.amount(item_id);
+            self.amounts.insert(
+                item_id,
+                stored
+                    .checked_sub(amount)
+                    .expect("Enough of each fluid should be stored"),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Shorthand for the ID of the item `name`.
+    fn id(name: &str) -> Id<Item> {
+        Id::from_name(name.to_string())
+    }
+
+    #[test]
+    fn repeated_additions_do_not_drift() {
+        let mut total = FluidAmount::ZERO;
+        for _ in 0..1000 {
+            total += FluidAmount::from_f64(0.1);
+        }
+
+        assert_eq!(total, FluidAmount::from_f64(100.));
+        assert_eq!(total.as_f64(), 100.);
+    }
+
+    #[test]
+    fn amounts_are_rounded_to_thousandths() {
+        assert_eq!(FluidAmount::from_f64(0.0004), FluidAmount::ZERO);
+        assert_eq!(FluidAmount::from_f64(1.2345).as_f64(), 1.235);
+        assert_eq!(FluidAmount::from_f64(-3.), FluidAmount::ZERO);
+        assert_eq!(FluidAmount::from_f64(f64::NAN), FluidAmount::ZERO);
+    }
+
+    #[test]
+    fn fluids_can_be_consumed_down_to_exactly_zero() {
+        let mut fluid_inventory = FluidInventory::default();
+        for _ in 0..3 {
+            fluid_inventory.add(&FluidCount::new(id("nectar"), FluidAmount::from_f64(0.1)));
+        }
+
+        let sip = [FluidCount::new(id("nectar"), FluidAmount::from_f64(0.15))];
+        assert_eq!(fluid_inventory.remove_all_or_nothing(&sip), Ok(()));
+        assert_eq!(fluid_inventory.remove_all_or_nothing(&sip), Ok(()));
+        assert!(fluid_inventory.amount(id("nectar")).is_zero());
+    }
+
+    #[test]
+    fn missing_fluids_are_not_consumed() {
+        let mut fluid_inventory = FluidInventory::default();
+        fluid_inventory.add(&FluidCount::new(id("nectar"), FluidAmount::from_f64(1.)));
+        let original = fluid_inventory.clone();
+
+        let request = [
+            FluidCount::new(id("nectar"), FluidAmount::from_f64(0.5)),
+            FluidCount::new(id("sap"), FluidAmount::from_f64(0.25)),
+        ];
+        assert_eq!(
+            fluid_inventory.remove_all_or_nothing(&request),
+            Err(vec![FluidCount::new(
+                id("sap"),
+                FluidAmount::from_f64(0.25)
+            )])
+        );
+        assert_eq!(fluid_inventory, original);
+    }
+}
@@ -1,5 +1,7 @@
 //! Storage of multiple items with a capacity.
 
+use std::time::Duration;
+
 use bevy::prelude::warn;
 use itertools::rev;
 use serde::{Deserialize, Serialize};
@@ -260,7 +262,7 @@ impl Inventory {
         // We can fill up the remaining space in the slots for this item...
         self.remaining_reserved_space_for_item(item_id)
             // ...and use up the remaining free slots
-            + self.free_slot_count() as u32 * item_manifest.get(item_id).stack_size
+            + self.free_slot_count() as u32 * item_manifest.get_unchecked(item_id).stack_size
     }
 
     /// Clears any inventory stacks with 0 items in them.
@@ -283,6 +285,37 @@ impl Inventory {
         }
     }
 
+    /// Ages all decaying items in this inventory by `delta`.
+    ///
+    /// Once the items in a slot have existed for their [`decay_time`](super::item_manifest::ItemData::decay_time),
+    /// the slot is converted into the same number of fresh decay products, in place.
+    /// Decay products that exceed their stack size or are not permitted by this inventory are lost,
+    /// as are items that decay into nothing.
+    pub(crate) fn decay(&mut self, delta: Duration, item_manifest: &ItemManifest) {
+        let reserved_for = self.reserved_for;
+
+        for slot in self.slots.iter_mut().filter(|slot| !slot.is_empty()) {
+            let item_data = item_manifest.get_unchecked(slot.item_id());
+            let Some(decay_time) = item_data.decay_time else {
+                continue;
+            };
+
+            if slot.advance_age(delta, decay_time) < decay_time {
+                continue;
+            }
+
+            match item_data.decays_into {
+                Some(product_id) if reserved_for.is_none() || reserved_for == Some(product_id) => {
+                    slot.convert_into(
+                        product_id,
+                        item_manifest.get_unchecked(product_id).stack_size,
+                    );
+                }
+                _ => slot.clear(),
+            }
+        }
+    }
+
     /// Adds an empty slot that is reserved for the provided `item_id`.
     ///
     /// # Warning
@@ -298,7 +331,7 @@ impl Inventory {
             self.reserved_for = None;
         }
 
-        let stack_size = item_manifest.get(item_id).stack_size;
+        let stack_size = item_manifest.get_unchecked(item_id).stack_size;
         let empty_stack = ItemSlot::empty(item_id, stack_size);
 
         // Suppose we have a 3 slot inventory, of which 2 are filled.
@@ -318,16 +351,31 @@ impl Inventory {
     /// Try to add as many items to the inventory as possible, up to the given count.
     ///
     /// Items can spill over, filling multiple inventory slots at once if the amount to add is greater than the stack size.
+    /// Slots that already hold the item are filled up first, and new slots are only opened for the rest.
+    /// Each slot holds up to the [`ItemData::stack_size`](super::item_manifest::ItemData::stack_size) of its item.
     ///
     /// - If all items can fit in the inventory, they are all added and `Ok` is returned.
     /// - Otherwise, all items that can fit are added and `Err` is returned.
     ///
     /// # Warning
     ///
-    /// Adding 0 of an item will not create an empty slot. Instead, use [`Inventory::add_empty_slot`].
-    pub(crate) fn try_add_item(
+    /// Adding 0 of an item will not create an empty slot.
+    pub fn try_add_item(
+        &mut self,
+        item_count: &ItemCount,
+        item_manifest: &ItemManifest,
+    ) -> Result<(), AddOneItemError> {
+        self.try_add_item_with_quality(item_count, ItemSlot::FULL_QUALITY, item_manifest)
+    }
+
+    /// Try to add as many items of the given `quality` to the inventory as possible, up to the given count.
+    ///
+    /// This works like [`Inventory::try_add_item`], but the added items are not fresh.
+    /// Their quality is averaged with the quality of the items already in the slots they join.
+    pub fn try_add_item_with_quality(
         &mut self,
         item_count: &ItemCount,
+        quality: f32,
         item_manifest: &ItemManifest,
     ) -> Result<(), AddOneItemError> {
         if !self.permits(item_count.item_id) {
@@ -344,7 +392,7 @@ impl Inventory {
             .iter_mut()
             .filter(|slot| slot.is_for_item(item_count.item_id))
         {
-            match slot.add_until_full(items_to_add) {
+            match slot.add_until_full_with_quality(items_to_add, quality) {
                 Ok(_) => {
                     items_to_add = 0;
                     break;
@@ -357,10 +405,10 @@ impl Inventory {
         while items_to_add > 0 && self.slots.len() < self.max_slot_count {
             let mut new_slot = ItemSlot::empty(
                 item_count.item_id,
-                item_manifest.get(item_count.item_id).stack_size,
+                item_manifest.get_unchecked(item_count.item_id).stack_size,
             );
 
-            match new_slot.add_until_full(items_to_add) {
+            match new_slot.add_until_full_with_quality(items_to_add, quality) {
                 Ok(_) => {
                     items_to_add = 0;
                 }
@@ -439,7 +487,7 @@ impl Inventory {
         let excess_counts: Vec<ItemCount> = item_counts
             .iter()
             .filter_map(|item_count| {
-                let stack_size = item_manifest.get(item_count.item_id).stack_size;
+                let stack_size = item_manifest.get_unchecked(item_count.item_id).stack_size;
 
                 let remaining_reserved_space =
                     self.remaining_reserved_space_for_item(item_count.item_id);
@@ -535,6 +583,30 @@ impl Inventory {
         }
     }
 
+    /// Takes up to `count` of the item out of the inventory, such as for handing it to a hauler.
+    ///
+    /// Returns the items that were taken, which are all the items of that type if there are fewer than `count`.
+    /// Slots emptied this way are kept until the empty slots are cleared.
+    pub fn withdraw(&mut self, item_id: Id<Item>, count: u32) -> ItemCount {
+        let withdrawn = ItemCount::new(item_id, count.min(self.item_count(item_id)));
+
+        // No more items are removed than are available, so this cannot fail
+        self.try_remove_item(&withdrawn).unwrap();
+        withdrawn
+    }
+
+    /// Puts as many of the `item_count` into the inventory as fit, returning the overflow.
+    ///
+    /// Partially filled slots of the item are topped up first, and then new slots are started,
+    /// each holding up to the [`ItemData::stack_size`](super::item_manifest::ItemData::stack_size) of the item.
+    /// The returned overflow has a count of 0 if all items fit.
+    pub fn deposit(&mut self, item_count: ItemCount, item_manifest: &ItemManifest) -> ItemCount {
+        match self.try_add_item(&item_count, item_manifest) {
+            Ok(()) => ItemCount::new(item_count.item_id, 0),
+            Err(AddOneItemError { excess_count }) => excess_count,
+        }
+    }
+
     /// Try to remove the given count of items from the inventory, together.
     ///
     /// - If there are enough items in the slot, they are all removed and `Ok` is returned.
@@ -700,7 +772,12 @@ impl FromIterator<ItemSlot> for Inventory {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{asset_management::manifest::Manifest, items::item_manifest::ItemData};
+    use crate::{
+        asset_management::manifest::{export::NameLookup, Manifest},
+        items::item_manifest::ItemData,
+        organisms::energy::Energy,
+    };
+    use bevy::utils::HashSet;
 
     /// Create a simple item manifest for testing purposes.
     fn item_manifest() -> ItemManifest {
@@ -713,6 +790,15 @@ mod tests {
                 fluid: false,
                 buoyant: true,
                 seed: None,
+                energy_content: Energy(0.),
+                tags: HashSet::new(),
+                mass: 1.,
+                decay_time: None,
+                decays_into: None,
+                tier: 0,
+                name: None,
+                description: None,
+                icon: None,
             },
         );
         manifest.insert(
@@ -723,6 +809,15 @@ mod tests {
                 fluid: false,
                 buoyant: true,
                 seed: None,
+                energy_content: Energy(0.),
+                tags: HashSet::new(),
+                mass: 1.,
+                decay_time: None,
+                decays_into: None,
+                tier: 0,
+                name: None,
+                description: None,
+                icon: None,
             },
         );
         manifest
@@ -900,6 +995,97 @@ mod tests {
         );
     }
 
+    #[test]
+    fn withdrawing_more_than_available_returns_what_is_there() {
+        let mut inventory = partial_inventory();
+        let mushroom = Id::from_name("mushroom".to_string());
+
+        assert_eq!(
+            inventory.withdraw(mushroom, 10),
+            ItemCount::new(mushroom, 7)
+        );
+        assert_eq!(inventory.item_count(mushroom), 0);
+        assert_eq!(inventory.withdraw(mushroom, 1), ItemCount::new(mushroom, 0));
+    }
+
+    #[test]
+    fn withdrawing_part_of_a_stack_leaves_the_rest() {
+        let mut inventory = partial_inventory();
+        let mushroom = Id::from_name("mushroom".to_string());
+
+        assert_eq!(inventory.withdraw(mushroom, 3), ItemCount::new(mushroom, 3));
+        assert_eq!(inventory.item_count(mushroom), 4);
+    }
+
+    #[test]
+    fn deposits_top_up_partially_filled_slots_first() {
+        let mushroom = Id::from_name("mushroom".to_string());
+        let mut inventory = Inventory {
+            reserved_for: None,
+            max_slot_count: 2,
+            slots: vec![ItemSlot::new_with_count(mushroom, 10, 7)],
+        };
+
+        let overflow = inventory.deposit(ItemCount::new(mushroom, 5), &item_manifest());
+
+        assert_eq!(overflow, ItemCount::new(mushroom, 0));
+        let slot_counts: Vec<u32> = inventory.iter().map(|slot| slot.count()).collect();
+        assert_eq!(slot_counts, vec![10, 2]);
+    }
+
+    #[test]
+    fn deposits_return_what_does_not_fit() {
+        let mut inventory = partial_inventory();
+        let mushroom = Id::from_name("mushroom".to_string());
+
+        let overflow = inventory.deposit(ItemCount::new(mushroom, 5), &item_manifest());
+
+        assert_eq!(overflow, ItemCount::new(mushroom, 2));
+        assert_eq!(inventory.item_count(mushroom), 10);
+    }
+
+    #[test]
+    fn saved_inventories_refer_to_items_by_name() {
+        let original_manifest = item_manifest();
+        let inventory = Inventory {
+            reserved_for: Some(Id::from_name("mushroom".to_string())),
+            max_slot_count: 4,
+            slots: vec![
+                ItemSlot::new_with_count(Id::from_name("leaf".to_string()), 10, 10),
+                ItemSlot::new_with_count(Id::from_name("mushroom".to_string()), 10, 3),
+            ],
+        };
+        let mut names = NameLookup::new();
+        names.add_manifest(&original_manifest);
+
+        let json = names.scope(|| serde_json::to_string(&inventory)).unwrap();
+        assert!(json.contains(r#""leaf""#));
+        assert!(json.contains(r#""mushroom""#));
+
+        // The same items, defined in a different order and alongside a new one
+        let mut reordered_manifest = Manifest::new();
+        for (name, copied_from) in [
+            ("mushroom", "mushroom"),
+            ("acorn", "leaf"),
+            ("leaf", "leaf"),
+        ] {
+            let data = original_manifest.get_unchecked(Id::from_name(copied_from.to_string()));
+            reordered_manifest.insert(name.to_string(), data.clone());
+        }
+
+        let loaded: Inventory = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, inventory);
+        let item_names: Vec<&str> = loaded
+            .iter()
+            .map(|slot| reordered_manifest.name(slot.item_id()))
+            .collect();
+        assert_eq!(item_names, vec!["leaf", "mushroom"]);
+        assert_eq!(
+            reordered_manifest.name(loaded.reserved_for().unwrap()),
+            "mushroom"
+        );
+    }
+
     mod add {
         mod until_full_one_item {
             use super::super::item_manifest;
@@ -932,6 +1118,36 @@ mod tests {
                 );
             }
 
+            #[test]
+            fn should_fill_partial_stacks_before_opening_new_slots() {
+                let mut inventory = Inventory {
+                    reserved_for: None,
+                    max_slot_count: 4,
+                    slots: vec![
+                        ItemSlot::new_with_count(Id::from_name("leaf".to_string()), 10, 5),
+                        ItemSlot::new_with_count(Id::from_name("mushroom".to_string()), 10, 3),
+                    ],
+                };
+
+                assert_eq!(
+                    inventory.try_add_item(
+                        &ItemCount::new(Id::from_name("leaf".to_string()), 12),
+                        &item_manifest()
+                    ),
+                    Ok(())
+                );
+
+                let counts: Vec<_> = inventory.iter().map(|slot| slot.item_count()).collect();
+                assert_eq!(
+                    counts,
+                    vec![
+                        ItemCount::new(Id::from_name("leaf".to_string()), 10),
+                        ItemCount::new(Id::from_name("mushroom".to_string()), 3),
+                        ItemCount::new(Id::from_name("leaf".to_string()), 7),
+                    ]
+                );
+            }
+
             #[test]
             fn should_fill_up_when_not_all_fit() {
                 let mut inventory = Inventory {
@@ -1391,4 +1607,62 @@ mod tests {
             );
         }
     }
+
+    mod decay {
+        use super::*;
+
+        /// The test item manifest, where leaves decay into mushrooms after ten seconds.
+        fn decaying_item_manifest() -> ItemManifest {
+            let mut manifest = item_manifest();
+            let leaf_data = manifest
+                .get_unchecked(Id::from_name("leaf".to_string()))
+                .clone();
+            manifest.insert(
+                "leaf".to_string(),
+                ItemData {
+                    decay_time: Some(Duration::from_secs(10)),
+                    decays_into: Some(Id::from_name("mushroom".to_string())),
+                    tier: 0,
+                    name: None,
+                    description: None,
+                    icon: None,
+                    ..leaf_data
+                },
+            );
+            manifest
+        }
+
+        #[test]
+        fn items_turn_into_their_product_after_the_decay_time() {
+            let item_manifest = decaying_item_manifest();
+            let leaf = Id::from_name("leaf".to_string());
+            let mushroom = Id::from_name("mushroom".to_string());
+            let mut inventory = Inventory::new(1, None);
+            inventory
+                .add_item_all_or_nothing(&ItemCount::new(leaf, 5), &item_manifest)
+                .unwrap();
+
+            inventory.decay(Duration::from_secs(6), &item_manifest);
+            assert_eq!(inventory.item_count(leaf), 5);
+            assert_eq!(inventory.item_count(mushroom), 0);
+
+            inventory.decay(Duration::from_secs(6), &item_manifest);
+            assert_eq!(inventory.item_count(leaf), 0);
+            assert_eq!(inventory.item_count(mushroom), 5);
+        }
+
+        #[test]
+        fn decay_products_are_lost_if_not_permitted() {
+            let item_manifest = decaying_item_manifest();
+            let leaf = Id::from_name("leaf".to_string());
+            let mut inventory = Inventory::new_from_item(leaf, 10);
+            inventory
+                .add_item_all_or_nothing(&ItemCount::new(leaf, 5), &item_manifest)
+                .unwrap();
+
+            inventory.decay(Duration::from_secs(10), &item_manifest);
+            assert!(inventory.is_empty());
+            assert!(!inventory.contains(Id::from_name("mushroom".to_string())));
+        }
+    }
 }
@@ -1,15 +1,27 @@
 //! Defines write-only data for each variety of item.
 
+use std::time::Duration;
+
 use bevy::{
+    prelude::World,
     reflect::{FromReflect, Reflect, TypeUuid},
-    utils::HashMap,
+    utils::{HashMap, HashSet},
 };
+use emergence_macros::RawManifest;
+use schemars::{schema::RootSchema, schema_for, JsonSchema};
 use serde::{Deserialize, Serialize};
 
+use crate as emergence_lib;
 use crate::{
-    asset_management::manifest::{loader::IsRawManifest, Id, Manifest},
-    crafting::item_tags::{ItemKind, ItemTag},
-    organisms::{OrganismId, RawOrganismId},
+    asset_management::manifest::{
+        diff::Diffable,
+        errors::{DanglingReference, ManifestError, OutOfRangeValue},
+        export::{NameLookup, ToRaw},
+        loader::IsRawManifest,
+        Id, Manifest,
+    },
+    crafting::item_tags::{ItemCategory, ItemKind, ItemTag},
+    organisms::{energy::Energy, OrganismId, RawOrganismId},
 };
 
 /// The marker type for [`Id<Item>`](super::Id).
@@ -21,7 +33,7 @@ pub type ItemManifest = Manifest<Item, ItemData>;
 impl ItemManifest {
     /// Does the provided `item_id` meet the requirements of the given `tag`?
     pub fn has_tag(&self, item_id: Id<Item>, tag: ItemTag) -> bool {
-        let data = self.get(item_id);
+        let data = self.get_unchecked(item_id);
 
         match tag {
             ItemTag::Compostable => data.compostable,
@@ -33,7 +45,7 @@ impl ItemManifest {
 
     /// Returns the complete list of tags that the given item belongs to.
     pub fn tags(&self, item_id: Id<Item>) -> Vec<ItemTag> {
-        let data = self.get(item_id);
+        let data = self.get_unchecked(item_id);
 
         let mut tags = Vec::new();
 
@@ -71,6 +83,30 @@ impl ItemManifest {
         kinds
     }
 
+    /// Returns all items that are labeled with the given `tag` in the manifest, sorted by their [`Id`].
+    pub fn items_with_tag(&self, tag: Id<ItemCategory>) -> Vec<Id<Item>> {
+        let mut item_ids: Vec<Id<Item>> = self
+            .data_map()
+            .iter()
+            .filter(|(_, data)| data.tags.contains(&tag))
+            .map(|(&item_id, _)| item_id)
+            .collect();
+
+        item_ids.sort();
+        item_ids
+    }
+
+    /// Returns the highest [`ItemData::tier`] of any item in the manifest.
+    ///
+    /// This is 0 if the manifest is empty.
+    pub fn max_tier(&self) -> u8 {
+        self.data_map()
+            .values()
+            .map(|data| data.tier)
+            .max()
+            .unwrap_or_default()
+    }
+
     /// Returns the human-readable name associated with the provided `item_kind`.
     ///
     /// # Panics
@@ -85,7 +121,7 @@ impl ItemManifest {
 }
 
 /// The data associated with each item.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ItemData {
     /// The number of items that can fit in a single item slot.
     pub stack_size: u32,
@@ -99,12 +135,136 @@ pub struct ItemData {
     ///
     /// If so, what does it grow into when left as litter?
     pub seed: Option<OrganismId>,
+    /// The amount of [`Energy`] contained in this item.
+    ///
+    /// This is gained by organisms that eat the item,
+    /// and is used to check that recipes do not create more energy than they consume.
+    pub energy_content: Energy,
+    /// The free-form categories that this item belongs to.
+    pub tags: HashSet<Id<ItemCategory>>,
+    /// The mass of a single item, in kilograms.
+    ///
+    /// This limits how many items units can carry.
+    pub mass: f32,
+    /// How long this item lasts in storage or as litter before it decays, if it decays at all.
+    pub decay_time: Option<Duration>,
+    /// The item that this item turns into once it has decayed.
+    ///
+    /// If this is `None`, decayed items simply disappear.
+    pub decays_into: Option<Id<Item>>,
+    /// The rarity of this item, used to pace progression.
+    ///
+    /// Higher tiers are rarer and more advanced.
+    pub tier: u8,
+    /// The name shown to players, if it differs from the name used to identify the item.
+    ///
+    /// Use [`ItemData::display_name`] to get the name to show.
+    pub name: Option<String>,
+    /// A description of the item, shown to players.
+    pub description: Option<String>,
+    /// The path to the icon shown for this item in the UI, relative to the asset folder.
+    ///
+    /// Use [`ItemData::icon_path`] to get the path to load.
+    pub icon: Option<String>,
+}
+
+impl ItemData {
+    /// The icon shown for items that do not specify one.
+    pub const PLACEHOLDER_ICON: &'static str = "icons/items/placeholder.png";
+
+    /// The name to show to players for this item, whose manifest name is `id_name`.
+    ///
+    /// Falls back to the manifest name if no display name has been set.
+    pub fn display_name<'a>(&'a self, id_name: &'a str) -> &'a str {
+        self.name.as_deref().unwrap_or(id_name)
+    }
+
+    /// The path to the icon to load for this item, relative to the asset folder.
+    ///
+    /// Falls back to [`ItemData::PLACEHOLDER_ICON`] if no icon has been set.
+    pub fn icon_path(&self) -> &str {
+        self.icon.as_deref().unwrap_or(Self::PLACEHOLDER_ICON)
+    }
+}
+
+/// A change to an [`ItemData`], as found by [`Manifest::diff`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemChange {
+    /// The number of items that fit in a single slot changed.
+    StackSize {
+        /// The old stack size.
+        before: u32,
+        /// The new stack size.
+        after: u32,
+    },
+    /// The field with this name changed.
+    Other(&'static str),
+}
+
+impl Diffable for ItemData {
+    type Change = ItemChange;
+
+    fn changes_to(&self, other: &Self) -> Vec<ItemChange> {
+        // Destructured, so that new fields cannot be forgotten here
+        let ItemData {
+            stack_size,
+            compostable,
+            fluid,
+            buoyant,
+            seed,
+            energy_content,
+            tags,
+            mass,
+            decay_time,
+            decays_into,
+            tier,
+            name,
+            description,
+            icon,
+        } = self;
+
+        let mut changes = Vec::new();
+        if *stack_size != other.stack_size {
+            changes.push(ItemChange::StackSize {
+                before: *stack_size,
+                after: other.stack_size,
+            });
+        }
+
+        let other_fields = [
+            ("compostable", *compostable != other.compostable),
+            ("fluid", *fluid != other.fluid),
+            ("buoyant", *buoyant != other.buoyant),
+            ("seed", *seed != other.seed),
+            ("energy_content", *energy_content != other.energy_content),
+            ("tags", *tags != other.tags),
+            ("mass", *mass != other.mass),
+            ("decay_time", *decay_time != other.decay_time),
+            ("decays_into", *decays_into != other.decays_into),
+            ("tier", *tier != other.tier),
+            ("name", *name != other.name),
+            ("description", *description != other.description),
+            ("icon", *icon != other.icon),
+        ];
+        changes.extend(
+            other_fields
+                .into_iter()
+                .filter(|(_, changed)| *changed)
+                .map(|(field, _)| ItemChange::Other(field)),
+        );
+
+        changes
+    }
 }
 
 /// The unprocessed [`ItemData`] as seen in the manifest file.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "strict_manifests", serde(deny_unknown_fields))]
 pub struct RawItemData {
     /// The number of items that can fit in a single item slot.
+    ///
+    /// Defaults to [`RawItemData::DEFAULT_STACK_SIZE`] when left out.
+    #[serde(default = "RawItemData::default_stack_size")]
     pub stack_size: u32,
     /// Can this item be composted?
     pub compostable: bool,
@@ -116,6 +276,63 @@ pub struct RawItemData {
     ///
     /// If so, what does it grow into when left as litter?
     pub seed: Option<RawOrganismId>,
+    /// The amount of [`Energy`] contained in this item.
+    ///
+    /// Defaults to no energy at all when left out.
+    #[serde(default)]
+    pub energy_content: Energy,
+    /// The names of the free-form categories that this item belongs to.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// The mass of a single item, in kilograms.
+    ///
+    /// Defaults to [`RawItemData::DEFAULT_MASS`] when left out.
+    #[serde(default = "RawItemData::default_mass")]
+    pub mass: f32,
+    /// How long this item lasts in storage or as litter before it decays, in seconds.
+    ///
+    /// Items without a decay time never decay.
+    #[serde(default)]
+    pub decay_time: Option<f32>,
+    /// The name of the item that this item turns into once it has decayed.
+    #[serde(default)]
+    pub decays_into: Option<String>,
+    /// The rarity of this item, used to pace progression.
+    ///
+    /// Defaults to 0, the most common tier, when left out.
+    #[serde(default)]
+    pub tier: u8,
+    /// The name shown to players.
+    ///
+    /// Defaults to the name of the item in the manifest when left out.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// A description of the item, shown to players.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The path to the icon shown for this item in the UI, relative to the asset folder.
+    ///
+    /// Defaults to [`ItemData::PLACEHOLDER_ICON`] when left out.
+    #[serde(default)]
+    pub icon: Option<String>,
+}
+
+impl RawItemData {
+    /// The stack size of items that do not specify one.
+    pub const DEFAULT_STACK_SIZE: u32 = 100;
+
+    /// Returns [`RawItemData::DEFAULT_STACK_SIZE`], for use as a serde default.
+    fn default_stack_size() -> u32 {
+        Self::DEFAULT_STACK_SIZE
+    }
+
+    /// The mass of items that do not specify one, in kilograms.
+    pub const DEFAULT_MASS: f32 = 1.;
+
+    /// Returns [`RawItemData::DEFAULT_MASS`], for use as a serde default.
+    fn default_mass() -> f32 {
+        Self::DEFAULT_MASS
+    }
 }
 
 impl From<RawItemData> for ItemData {
@@ -126,33 +343,403 @@ impl From<RawItemData> for ItemData {
             fluid: raw.fluid,
             buoyant: raw.buoyant,
             seed: raw.seed.map(OrganismId::from),
+            energy_content: raw.energy_content,
+            tags: raw.tags.into_iter().map(Id::from_name).collect(),
+            mass: raw.mass,
+            decay_time: raw.decay_time.map(Duration::from_secs_f32),
+            decays_into: raw.decays_into.map(Id::from_name),
+            tier: raw.tier,
+            name: raw.name,
+            description: raw.description,
+            icon: raw.icon,
+        }
+    }
+}
+
+impl ToRaw for ItemData {
+    type RawManifest = RawItemManifest;
+
+    fn to_raw(&self, names: &NameLookup) -> Result<RawItemData, ManifestError> {
+        let seed = match self.seed {
+            Some(OrganismId::Structure(structure_id)) => {
+                Some(RawOrganismId::Structure(names.name(structure_id)?))
+            }
+            Some(OrganismId::Unit(unit_id)) => Some(RawOrganismId::Unit(names.name(unit_id)?)),
+            None => None,
+        };
+
+        let mut tags = self
+            .tags
+            .iter()
+            .map(|&tag| names.name(tag))
+            .collect::<Result<Vec<String>, ManifestError>>()?;
+        tags.sort();
+
+        Ok(RawItemData {
+            stack_size: self.stack_size,
+            compostable: self.compostable,
+            fluid: self.fluid,
+            buoyant: self.buoyant,
+            seed,
+            energy_content: self.energy_content,
+            tags,
+            mass: self.mass,
+            decay_time: self.decay_time.map(|decay_time| decay_time.as_secs_f32()),
+            decays_into: self
+                .decays_into
+                .map(|item_id| names.name(item_id))
+                .transpose()?,
+            tier: self.tier,
+            name: self.name.clone(),
+            description: self.description.clone(),
+            icon: self.icon.clone(),
+        })
+    }
+
+    fn raw_manifest(items: HashMap<String, RawItemData>) -> RawItemManifest {
+        RawItemManifest {
+            version: RawItemManifest::CURRENT_VERSION,
+            items,
         }
     }
 }
 
 /// The [`ItemManifest`] as seen in the manifest file.
-#[derive(Debug, Clone, Serialize, Deserialize, TypeUuid, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, TypeUuid, PartialEq, JsonSchema, RawManifest)]
 #[uuid = "cd9f4571-b0c4-4641-8d27-1c9c5ad4c812"]
+#[raw_manifest(
+    extension = "item_manifest",
+    marker = Item,
+    data = ItemData,
+    check = Self::check_stack_sizes,
+    validate = Self::validate_decay_products
+)]
 pub struct RawItemManifest {
+    /// The version of the manifest format that this file is written in.
+    ///
+    /// This must match [`IsRawManifest::CURRENT_VERSION`].
+    pub version: u32,
     /// The data for each item.
+    #[schemars(with = "std::collections::HashMap<String, RawItemData>")]
     pub items: HashMap<String, RawItemData>,
 }
 
-impl IsRawManifest for RawItemManifest {
-    const EXTENSION: &'static str = "item_manifest.json";
+impl RawItemManifest {
+    /// A JSON Schema describing the item manifest files, for use by editors.
+    pub fn json_schema() -> RootSchema {
+        schema_for!(RawItemManifest)
+    }
+
+    /// Checks that every item fits at least one item into each slot.
+    fn check_stack_sizes(&self) -> Result<(), ManifestError> {
+        let mut out_of_range_values: Vec<OutOfRangeValue> = self
+            .items
+            .iter()
+            .filter(|(_, raw_data)| raw_data.stack_size == 0)
+            .map(|(item_name, raw_data)| OutOfRangeValue {
+                entry: item_name.clone(),
+                field: "stack_size".to_string(),
+                value: raw_data.stack_size.to_string(),
+                expected: "at least 1",
+            })
+            .collect();
+
+        if out_of_range_values.is_empty() {
+            Ok(())
+        } else {
+            out_of_range_values.sort();
+            Err(ManifestError::OutOfRange(out_of_range_values))
+        }
+    }
+
+    /// Checks that every item decays into an item that exists.
+    fn validate_decay_products(&self, _world: &World) -> Result<(), ManifestError> {
+        let mut dangling_references: Vec<DanglingReference> = self
+            .items
+            .iter()
+            .filter_map(|(item_name, raw_data)| {
+                let product_name = raw_data.decays_into.as_ref()?;
 
-    type Marker = Item;
-    type Data = ItemData;
+                (!self.items.contains_key(product_name)).then(|| DanglingReference {
+                    entry: item_name.clone(),
+                    name: product_name.clone(),
+                })
+            })
+            .collect();
 
-    fn process(&self) -> Manifest<Self::Marker, Self::Data> {
-        let mut manifest = Manifest::new();
+        if dangling_references.is_empty() {
+            Ok(())
+        } else {
+            dangling_references.sort();
+            Err(ManifestError::UnknownReference(dangling_references))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset_management::manifest::diff::ModifiedEntry;
+
+    /// A raw item that belongs to the categories named by `tags`.
+    fn raw_item(tags: &[&str]) -> RawItemData {
+        RawItemData {
+            stack_size: 10,
+            compostable: false,
+            fluid: false,
+            buoyant: false,
+            seed: None,
+            energy_content: Energy(0.),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            mass: 1.,
+            decay_time: None,
+            decays_into: None,
+            tier: 0,
+            name: None,
+            description: None,
+            icon: None,
+        }
+    }
 
-        for (raw_id, raw_data) in self.items.clone() {
-            let data = ItemData::from(raw_data);
+    /// Shorthand for the [`Id`] of the item or category `name`.
+    fn id<T>(name: &str) -> Id<T> {
+        Id::from_name(name.to_string())
+    }
 
-            manifest.insert(raw_id, data)
+    #[test]
+    fn items_with_several_tags_are_found_by_each() {
+        let item_manifest = RawItemManifest {
+            version: RawItemManifest::CURRENT_VERSION,
+            items: HashMap::from_iter([
+                ("log".to_string(), raw_item(&["construction", "fuel"])),
+                ("leaf".to_string(), raw_item(&["fuel"])),
+                ("pebble".to_string(), raw_item(&[])),
+            ]),
         }
+        .process()
+        .unwrap();
+
+        let mut fuel = vec![id("log"), id("leaf")];
+        fuel.sort();
+
+        assert_eq!(
+            item_manifest.items_with_tag(id("construction")),
+            vec![id("log")]
+        );
+        assert_eq!(item_manifest.items_with_tag(id("fuel")), fuel);
+        assert!(item_manifest.items_with_tag(id("food")).is_empty());
+    }
+
+    #[test]
+    fn diffs_list_added_items_and_stack_size_changes() {
+        let mut old = ItemManifest::new();
+        old.insert("leaf".to_string(), raw_item(&["fuel"]).into());
+        let mut new = ItemManifest::new();
+        new.insert(
+            "leaf".to_string(),
+            RawItemData {
+                stack_size: 20,
+                mass: 2.,
+                ..raw_item(&["fuel"])
+            }
+            .into(),
+        );
+        new.insert("log".to_string(), raw_item(&["fuel"]).into());
+
+        let diff = old.diff(&new);
+
+        assert_eq!(diff.added, vec!["log".to_string()]);
+        assert!(diff.removed.is_empty());
+        assert_eq!(
+            diff.modified,
+            vec![ModifiedEntry {
+                name: "leaf".to_string(),
+                changes: vec![
+                    ItemChange::StackSize {
+                        before: 10,
+                        after: 20
+                    },
+                    ItemChange::Other("mass"),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn mapped_manifests_are_looked_up_by_the_same_ids() {
+        let mut item_manifest = ItemManifest::new();
+        item_manifest.insert("leaf".to_string(), raw_item(&[]).into());
+        item_manifest.insert(
+            "log".to_string(),
+            RawItemData {
+                stack_size: 5,
+                ..raw_item(&[])
+            }
+            .into(),
+        );
+
+        let stack_sizes = item_manifest.map(|item_data| item_data.stack_size);
+
+        assert_eq!(stack_sizes.len(), 2);
+        assert_eq!(stack_sizes.get(id("leaf")), Ok(&10));
+        assert_eq!(stack_sizes.get(id("log")), Ok(&5));
+        assert_eq!(stack_sizes.name(id("log")), "log");
+    }
+
+    #[test]
+    fn tags_mass_and_decay_are_optional() {
+        let raw_item_data: RawItemData =
+            serde_json::from_str(r#"{ "compostable": true, "fluid": false, "buoyant": false }"#)
+                .unwrap();
+
+        let item_data = ItemData::from(raw_item_data);
+        assert!(item_data.tags.is_empty());
+        assert_eq!(item_data.mass, RawItemData::DEFAULT_MASS);
+        assert_eq!(item_data.decay_time, None);
+    }
+
+    #[test]
+    fn energy_content_defaults_to_zero() {
+        let raw_item_data: RawItemData =
+            serde_json::from_str(r#"{ "compostable": true, "fluid": false, "buoyant": false }"#)
+                .unwrap();
+        assert_eq!(ItemData::from(raw_item_data).energy_content, Energy(0.));
+
+        let raw_item_data: RawItemData = serde_json::from_str(
+            r#"{ "compostable": true, "fluid": false, "buoyant": false, "energy_content": 25.0 }"#,
+        )
+        .unwrap();
+        assert_eq!(ItemData::from(raw_item_data).energy_content, Energy(25.));
+    }
+
+    #[test]
+    fn decaying_into_unknown_items_is_rejected() {
+        let raw_manifest = RawItemManifest {
+            version: RawItemManifest::CURRENT_VERSION,
+            items: HashMap::from_iter([(
+                "leaf".to_string(),
+                RawItemData {
+                    decay_time: Some(30.),
+                    decays_into: Some("compost".to_string()),
+                    ..raw_item(&[])
+                },
+            )]),
+        };
+
+        assert_eq!(
+            raw_manifest.validate(&World::new()),
+            Err(ManifestError::UnknownReference(vec![DanglingReference {
+                entry: "leaf".to_string(),
+                name: "compost".to_string(),
+            }]))
+        );
+    }
+
+    #[test]
+    fn zero_stack_sizes_are_rejected() {
+        let raw_manifest = RawItemManifest {
+            version: RawItemManifest::CURRENT_VERSION,
+            items: HashMap::from_iter([
+                (
+                    "leaf".to_string(),
+                    RawItemData {
+                        stack_size: 0,
+                        ..raw_item(&[])
+                    },
+                ),
+                ("log".to_string(), raw_item(&[])),
+            ]),
+        };
+
+        let error = raw_manifest.process().err().unwrap();
+        assert_eq!(
+            error,
+            ManifestError::OutOfRange(vec![OutOfRangeValue {
+                entry: "leaf".to_string(),
+                field: "stack_size".to_string(),
+                value: "0".to_string(),
+                expected: "at least 1",
+            }])
+        );
+        assert!(error
+            .to_string()
+            .contains("`leaf`: stack_size is 0, but must be at least 1"));
+    }
+
+    #[test]
+    fn tiers_default_to_zero() {
+        let raw_item_manifest: RawItemManifest = serde_json::from_str(
+            r#"{
+                "version": 1,
+                "items": {
+                    "leaf": { "compostable": true, "fluid": false, "buoyant": false },
+                    "amber": { "compostable": false, "fluid": false, "buoyant": false, "tier": 3 }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let item_manifest = raw_item_manifest.process().unwrap();
+        assert_eq!(item_manifest.get_unchecked(id("leaf")).tier, 0);
+        assert_eq!(item_manifest.get_unchecked(id("amber")).tier, 3);
+        assert_eq!(item_manifest.max_tier(), 3);
+    }
+
+    #[test]
+    fn empty_manifests_have_tier_zero() {
+        assert_eq!(ItemManifest::new().max_tier(), 0);
+    }
+
+    #[test]
+    fn display_names_fall_back_to_the_manifest_name() {
+        let item_data = ItemData::from(raw_item(&[]));
+
+        assert_eq!(item_data.display_name("acacia_leaf"), "acacia_leaf");
+        assert_eq!(item_data.description, None);
+    }
+
+    #[test]
+    fn display_names_and_descriptions_are_read() {
+        let raw_item_data: RawItemData = serde_json::from_str(
+            r#"{
+                "compostable": true,
+                "fluid": false,
+                "buoyant": false,
+                "name": "Acacia leaf",
+                "description": "A tough, waxy leaf."
+            }"#,
+        )
+        .unwrap();
+
+        let item_data = ItemData::from(raw_item_data);
+        assert_eq!(item_data.display_name("acacia_leaf"), "Acacia leaf");
+        assert_eq!(
+            item_data.description.as_deref(),
+            Some("A tough, waxy leaf.")
+        );
+    }
+
+    #[test]
+    fn icons_fall_back_to_the_placeholder() {
+        let item_data = ItemData::from(raw_item(&[]));
+
+        assert_eq!(item_data.icon, None);
+        assert_eq!(item_data.icon_path(), ItemData::PLACEHOLDER_ICON);
+    }
+
+    #[test]
+    fn icons_are_read() {
+        let raw_item_data: RawItemData = serde_json::from_str(
+            r#"{
+                "compostable": true,
+                "fluid": false,
+                "buoyant": false,
+                "icon": "icons/items/acacia_leaf.png"
+            }"#,
+        )
+        .unwrap();
 
-        manifest
+        let item_data = ItemData::from(raw_item_data);
+        assert_eq!(item_data.icon_path(), "icons/items/acacia_leaf.png");
     }
 }
@@ -1,15 +1,24 @@
 //! Everything related to items and crafting.
 
+use std::ops::{Add, AddAssign};
+
 use serde::{Deserialize, Serialize};
 
 use crate::asset_management::manifest::Id;
 
-use self::item_manifest::{Item, ItemManifest};
+use self::{
+    errors::RemoveOneItemError,
+    item_manifest::{Item, ItemManifest},
+};
 
 pub mod errors;
+pub mod filter;
+pub mod fluid;
 pub mod inventory;
 pub mod item_manifest;
+pub mod modifier;
 pub mod slot;
+pub mod starter_manifest;
 
 /// A specific amount of a given item.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -32,9 +41,197 @@ impl ItemCount {
         Self { item_id, count: 1 }
     }
 
+    /// The combined mass of these items, in kilograms.
+    pub fn total_mass(&self, item_manifest: &ItemManifest) -> f32 {
+        item_manifest.get_unchecked(self.item_id).mass * self.count as f32
+    }
+
     /// The pretty text formatting of this type.
     pub fn display(&self, item_manifest: &ItemManifest) -> String {
         let name = item_manifest.name(self.item_id);
         format!("{}, ({})", name, self.count)
     }
+
+    /// Try to remove the `other` items from this count.
+    ///
+    /// - If there are enough items, they are all removed and `Ok` is returned.
+    /// - If there are not enough items, _no_ item is removed and `Err` is returned.
+    ///
+    /// Items of a different type are never available, so they are missing in their entirety.
+    pub fn try_subtract(&mut self, other: &ItemCount) -> Result<(), RemoveOneItemError> {
+        if other.item_id != self.item_id {
+            return Err(RemoveOneItemError {
+                missing_count: other.clone(),
+            });
+        }
+
+        if other.count > self.count {
+            let missing_count = ItemCount::new(self.item_id, other.count - self.count);
+            Err(RemoveOneItemError { missing_count })
+        } else {
+            self.count -= other.count;
+            Ok(())
+        }
+    }
+
+    /// Removes up to `count` items from this count, returning them.
+    ///
+    /// At most a single stack is split off, as limited by the [`ItemData::stack_size`](item_manifest::ItemData::stack_size).
+    /// If there are fewer items than that, all of them are taken.
+    pub fn split(&mut self, count: u32, item_manifest: &ItemManifest) -> ItemCount {
+        let stack_size = item_manifest.get_unchecked(self.item_id).stack_size;
+        let split_count = count.min(stack_size).min(self.count);
+
+        self.count -= split_count;
+        ItemCount::new(self.item_id, split_count)
+    }
+}
+
+impl Add for ItemCount {
+    type Output = ItemCount;
+
+    /// Combines two counts of the same item.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the counts are of different items.
+    fn add(mut self, rhs: ItemCount) -> ItemCount {
+        self += rhs;
+        self
+    }
+}
+
+impl AddAssign for ItemCount {
+    /// Adds the items of another count of the same item to this one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the counts are of different items.
+    fn add_assign(&mut self, rhs: ItemCount) {
+        assert_eq!(
+            self.item_id, rhs.item_id,
+            "Only counts of the same item can be added together"
+        );
+
+        self.count += rhs.count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        asset_management::manifest::Manifest, items::item_manifest::ItemData,
+        organisms::energy::Energy,
+    };
+    use bevy::utils::HashSet;
+
+    /// An item manifest with a light feather and a heavy rock, which both come in stacks of 10.
+    fn item_manifest() -> ItemManifest {
+        let mut manifest = Manifest::new();
+        for (name, mass) in [("feather", 0.5), ("rock", 4.)] {
+            manifest.insert(
+                name.to_string(),
+                ItemData {
+                    stack_size: 10,
+                    compostable: false,
+                    fluid: false,
+                    buoyant: false,
+                    seed: None,
+                    energy_content: Energy(0.),
+                    tags: HashSet::new(),
+                    mass,
+                    decay_time: None,
+                    decays_into: None,
+                    tier: 0,
+                    name: None,
+                    description: None,
+                    icon: None,
+                },
+            );
+        }
+        manifest
+    }
+
+    #[test]
+    fn total_mass_scales_with_count() {
+        let item_manifest = item_manifest();
+        let rock_id = Id::from_name("rock".to_string());
+
+        assert_eq!(ItemCount::new(rock_id, 0).total_mass(&item_manifest), 0.);
+        assert_eq!(ItemCount::one(rock_id).total_mass(&item_manifest), 4.);
+        assert_eq!(ItemCount::new(rock_id, 3).total_mass(&item_manifest), 12.);
+    }
+
+    #[test]
+    fn total_mass_of_mixed_items() {
+        let item_manifest = item_manifest();
+        let items = [
+            ItemCount::new(Id::from_name("feather".to_string()), 4),
+            ItemCount::new(Id::from_name("rock".to_string()), 2),
+        ];
+
+        let total_mass: f32 = items
+            .iter()
+            .map(|item_count| item_count.total_mass(&item_manifest))
+            .sum();
+
+        assert_eq!(total_mass, 4. * 0.5 + 2. * 4.);
+    }
+
+    /// Shorthand for an [`ItemCount`] of the item `name`.
+    fn item_count(name: &str, count: u32) -> ItemCount {
+        ItemCount::new(Id::from_name(name.to_string()), count)
+    }
+
+    #[test]
+    fn counts_of_the_same_item_can_be_added() {
+        let mut rocks = item_count("rock", 3) + item_count("rock", 4);
+        assert_eq!(rocks, item_count("rock", 7));
+
+        rocks += ItemCount::one(Id::from_name("rock".to_string()));
+        assert_eq!(rocks, item_count("rock", 8));
+    }
+
+    #[test]
+    #[should_panic]
+    fn counts_of_different_items_cannot_be_added() {
+        let _ = item_count("rock", 3) + item_count("feather", 4);
+    }
+
+    #[test]
+    fn subtracting_too_many_items_is_rejected() {
+        let mut rocks = item_count("rock", 3);
+
+        assert_eq!(rocks.try_subtract(&item_count("rock", 2)), Ok(()));
+        assert_eq!(rocks, item_count("rock", 1));
+
+        assert_eq!(
+            rocks.try_subtract(&item_count("rock", 4)),
+            Err(RemoveOneItemError {
+                missing_count: item_count("rock", 3)
+            })
+        );
+        assert_eq!(
+            rocks.try_subtract(&item_count("feather", 1)),
+            Err(RemoveOneItemError {
+                missing_count: item_count("feather", 1)
+            })
+        );
+        assert_eq!(rocks, item_count("rock", 1));
+    }
+
+    #[test]
+    fn splitting_is_limited_to_a_single_stack() {
+        let item_manifest = item_manifest();
+        let mut rocks = item_count("rock", 25);
+
+        assert_eq!(rocks.split(4, &item_manifest), item_count("rock", 4));
+        assert_eq!(rocks.split(10, &item_manifest), item_count("rock", 10));
+        assert_eq!(rocks.split(50, &item_manifest), item_count("rock", 10));
+        assert_eq!(rocks, item_count("rock", 1));
+
+        assert_eq!(rocks.split(10, &item_manifest), item_count("rock", 1));
+        assert_eq!(rocks, item_count("rock", 0));
+    }
 }
@@ -0,0 +1,247 @@
+//! Modifiers that adjust the data of individual items, such as "dense pollen", on top of the data of their base item.
+
+use serde::{Deserialize, Serialize};
+
+use crate::asset_management::manifest::Id;
+
+use super::item_manifest::{Item, ItemData};
+
+/// Multiplicative adjustments to the [`ItemData`] of a base item.
+///
+/// A multiplier of 1.0 leaves the value unchanged.
+/// Negative or non-finite multipliers are treated as 0.0.
+///
+/// Several modifiers on the same item stack by multiplying their multipliers together,
+/// so the order in which they are applied does not matter.
+/// The stack size is rounded only once, after all multipliers have been combined,
+/// and is never less than 1.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemModifier {
+    /// The item whose data is adjusted.
+    pub base: Id<Item>,
+    /// The multiplier on the number of items that fit in a single slot.
+    pub stack_size: f32,
+    /// The multiplier on the mass of a single item.
+    pub mass: f32,
+    /// The multiplier on the energy contained in a single item.
+    pub energy_content: f32,
+    /// The multiplier on how long the item lasts before it decays.
+    pub decay_time: f32,
+}
+
+impl ItemModifier {
+    /// Creates a modifier of the `base` item that does not change anything yet.
+    pub fn new(base: Id<Item>) -> Self {
+        ItemModifier {
+            base,
+            stack_size: 1.,
+            mass: 1.,
+            energy_content: 1.,
+            decay_time: 1.,
+        }
+    }
+
+    /// The single modifier that has the same effect as applying all `modifiers`.
+    ///
+    /// Returns [`None`] if the `modifiers` are empty, or do not all modify the same base item.
+    pub fn stack<'a>(modifiers: impl IntoIterator<Item = &'a ItemModifier>) -> Option<Self> {
+        let mut modifiers = modifiers.into_iter();
+        let mut stacked = modifiers.next()?.sanitized();
+
+        for modifier in modifiers {
+            if modifier.base != stacked.base {
+                return None;
+            }
+
+            let modifier = modifier.sanitized();
+            stacked.stack_size *= modifier.stack_size;
+            stacked.mass *= modifier.mass;
+            stacked.energy_content *= modifier.energy_content;
+            stacked.decay_time *= modifier.decay_time;
+        }
+
+        Some(stacked)
+    }
+
+    /// A copy of this modifier, with invalid multipliers replaced by 0.0.
+    fn sanitized(&self) -> Self {
+        let sanitize = |multiplier: f32| {
+            if multiplier.is_finite() {
+                multiplier.max(0.)
+            } else {
+                0.
+            }
+        };
+
+        ItemModifier {
+            base: self.base,
+            stack_size: sanitize(self.stack_size),
+            mass: sanitize(self.mass),
+            energy_content: sanitize(self.energy_content),
+            decay_time: sanitize(self.decay_time),
+        }
+    }
+}
+
+impl ItemData {
+    /// The data of an item of this kind that carries the `modifier`.
+    ///
+    /// This does not check that the [`base`](ItemModifier::base) of the modifier is this item.
+    /// To apply several modifiers at once, combine them with [`ItemModifier::stack`] first,
+    /// so that the stack size is only rounded once.
+    pub fn with_modifier(&self, modifier: &ItemModifier) -> ItemData {
+        let modifier = modifier.sanitized();
+
+        ItemData {
+            stack_size: ((self.stack_size as f32 * modifier.stack_size).round() as u32).max(1),
+            mass: self.mass * modifier.mass,
+            energy_content: self.energy_content * modifier.energy_content,
+            decay_time: self
+                .decay_time
+                .map(|decay_time| decay_time.mul_f32(modifier.decay_time)),
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        asset_management::manifest::loader::IsRawManifest, items::item_manifest::RawItemManifest,
+        organisms::energy::Energy,
+    };
+    use std::time::Duration;
+
+    /// The data of pollen, which stacks to 10, weighs half a kilogram and decays after a minute.
+    fn pollen() -> ItemData {
+        let raw_item_manifest: RawItemManifest = serde_json::from_str(
+            r#"{
+                "version": 1,
+                "items": {
+                    "pollen": {
+                        "stack_size": 10,
+                        "compostable": true,
+                        "fluid": false,
+                        "buoyant": true,
+                        "mass": 0.5,
+                        "energy_content": 20.0,
+                        "decay_time": 60.0
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        raw_item_manifest
+            .process()
+            .unwrap()
+            .get_unchecked(pollen_id())
+            .clone()
+    }
+
+    /// The [`Id`] of pollen.
+    fn pollen_id() -> Id<Item> {
+        Id::from_name("pollen".to_string())
+    }
+
+    /// Dense items weigh 50% more, but 20% fewer of them fit in a slot.
+    fn dense() -> ItemModifier {
+        ItemModifier {
+            stack_size: 0.8,
+            mass: 1.5,
+            ..ItemModifier::new(pollen_id())
+        }
+    }
+
+    /// Rich items contain twice the energy, but decay twice as fast.
+    fn rich() -> ItemModifier {
+        ItemModifier {
+            energy_content: 2.,
+            decay_time: 0.5,
+            ..ItemModifier::new(pollen_id())
+        }
+    }
+
+    #[test]
+    fn modifiers_adjust_the_base_data() {
+        let dense_pollen = pollen().with_modifier(&dense());
+
+        assert_eq!(dense_pollen.stack_size, 8);
+        assert_eq!(dense_pollen.mass, 0.75);
+        assert_eq!(dense_pollen.energy_content, Energy(20.));
+        assert_eq!(dense_pollen.decay_time, Some(Duration::from_secs(60)));
+        // Everything that cannot be modified is kept
+        assert_eq!(
+            ItemData {
+                stack_size: 10,
+                mass: 0.5,
+                ..dense_pollen
+            },
+            pollen()
+        );
+    }
+
+    #[test]
+    fn modifiers_without_changes_leave_the_data_unchanged() {
+        assert_eq!(
+            pollen().with_modifier(&ItemModifier::new(pollen_id())),
+            pollen()
+        );
+    }
+
+    #[test]
+    fn stacked_modifiers_multiply_together() {
+        let stacked = ItemModifier::stack([&dense(), &rich(), &dense()]).unwrap();
+        let modified = pollen().with_modifier(&stacked);
+
+        // 10 * 0.8 * 0.8 = 6.4
+        assert_eq!(modified.stack_size, 6);
+        assert_eq!(modified.mass, 0.5 * 1.5 * 1.5);
+        assert_eq!(modified.energy_content, Energy(40.));
+        assert_eq!(modified.decay_time, Some(Duration::from_secs(30)));
+
+        // The order does not matter
+        let reordered = ItemModifier::stack([&rich(), &dense(), &dense()]).unwrap();
+        assert_eq!(pollen().with_modifier(&reordered), modified);
+    }
+
+    #[test]
+    fn stack_sizes_are_rounded_once_and_never_reach_zero() {
+        let slightly_smaller = ItemModifier {
+            stack_size: 0.85,
+            ..ItemModifier::new(pollen_id())
+        };
+
+        // Rounding after each modifier would give 9 and then 8
+        let stacked = ItemModifier::stack([&slightly_smaller, &slightly_smaller]).unwrap();
+        assert_eq!(pollen().with_modifier(&stacked).stack_size, 7);
+
+        let crushed = ItemModifier {
+            stack_size: 0.,
+            ..ItemModifier::new(pollen_id())
+        };
+        assert_eq!(pollen().with_modifier(&crushed).stack_size, 1);
+    }
+
+    #[test]
+    fn only_modifiers_of_the_same_item_stack() {
+        let leafy = ItemModifier::new(Id::from_name("leaf".to_string()));
+
+        assert_eq!(ItemModifier::stack([&dense(), &leafy]), None);
+        assert_eq!(ItemModifier::stack([]), None);
+    }
+
+    #[test]
+    fn invalid_multipliers_are_treated_as_zero() {
+        let broken = ItemModifier {
+            mass: f32::NAN,
+            energy_content: -1.,
+            ..ItemModifier::new(pollen_id())
+        };
+        let modified = pollen().with_modifier(&broken);
+
+        assert_eq!(modified.mass, 0.);
+        assert_eq!(modified.energy_content, Energy(0.));
+    }
+}
@@ -1,5 +1,7 @@
 //! A container for a single item type, with a capacity.
 
+use std::time::Duration;
+
 use rand::{distributions::Uniform, prelude::Distribution, rngs::ThreadRng};
 use serde::{Deserialize, Serialize};
 
@@ -13,7 +15,7 @@ use super::{
 };
 
 /// Multiple items of the same type.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ItemSlot {
     /// The unique identifier of the item that occupies the slot.
     item_id: Id<Item>,
@@ -25,16 +27,43 @@ pub struct ItemSlot {
     ///
     /// This is guaranteed to be smaller than or equal to the `max_item_count`.
     count: u32,
+
+    /// How long the items in this slot have been decaying.
+    ///
+    /// Items in a slot share a single age.
+    /// When fresh items are added to a partially decayed stack,
+    /// the age becomes the average age of all items in the slot, weighted by their count.
+    /// Removing items does not change the age of the remaining items.
+    #[serde(default)]
+    age: Duration,
+
+    /// How good the items in this slot are, between 0 and [`ItemSlot::FULL_QUALITY`].
+    ///
+    /// Like the age, items in a slot share a single quality.
+    /// When items are added, the quality becomes the average quality of all items in the slot, weighted by their count.
+    /// Decaying items lose quality as they age, reaching 0 once they decay.
+    #[serde(default = "ItemSlot::full_quality")]
+    quality: f32,
 }
 
 #[allow(dead_code)]
 impl ItemSlot {
+    /// The quality of fresh items, which is the best quality possible.
+    pub const FULL_QUALITY: f32 = 1.;
+
+    /// Returns [`ItemSlot::FULL_QUALITY`], for use as a serde default.
+    fn full_quality() -> f32 {
+        Self::FULL_QUALITY
+    }
+
     /// Create an empty slot for the given item.
     pub const fn empty(item_id: Id<Item>, max_item_count: u32) -> Self {
         Self {
             item_id,
             max_item_count,
             count: 0,
+            age: Duration::ZERO,
+            quality: ItemSlot::FULL_QUALITY,
         }
     }
 
@@ -44,6 +73,8 @@ impl ItemSlot {
             item_id,
             max_item_count,
             count: max_item_count,
+            age: Duration::ZERO,
+            quality: ItemSlot::FULL_QUALITY,
         }
     }
 
@@ -60,6 +91,8 @@ impl ItemSlot {
             item_id,
             max_item_count,
             count,
+            age: Duration::ZERO,
+            quality: ItemSlot::FULL_QUALITY,
         }
     }
 
@@ -89,6 +122,16 @@ impl ItemSlot {
         self.count
     }
 
+    /// How long the items in this slot have been decaying.
+    pub fn age(&self) -> Duration {
+        self.age
+    }
+
+    /// How good the items in this slot are, between 0 and [`ItemSlot::FULL_QUALITY`].
+    pub fn quality(&self) -> f32 {
+        self.quality
+    }
+
     /// The maximum number of items that can fit in this slot.
     pub fn max_item_count(&self) -> u32 {
         self.max_item_count
@@ -119,15 +162,28 @@ impl ItemSlot {
     /// - If all items can fit in the slot, they are all added and `Ok` is returned.
     /// - Otherwise, all items that can fit are added and `Err` is returned.
     pub fn add_until_full(&mut self, count: u32) -> Result<(), AddOneItemError> {
+        self.add_until_full_with_quality(count, Self::FULL_QUALITY)
+    }
+
+    /// Try to add as many items of the given `quality` to the inventory as possible, up to the given count.
+    ///
+    /// This works like [`ItemSlot::add_until_full`], but the added items are not fresh.
+    pub fn add_until_full_with_quality(
+        &mut self,
+        count: u32,
+        quality: f32,
+    ) -> Result<(), AddOneItemError> {
         let new_count = self.count + count;
 
         if new_count > self.max_item_count {
+            self.mix_in_items(self.max_item_count - self.count, quality);
             self.count = self.max_item_count;
 
             Err(AddOneItemError {
                 excess_count: ItemCount::new(self.item_id, new_count - self.max_item_count),
             })
         } else {
+            self.mix_in_items(count, quality);
             self.count = new_count;
             Ok(())
         }
@@ -146,6 +202,7 @@ impl ItemSlot {
                 ),
             })
         } else {
+            self.mix_in_items(count, Self::FULL_QUALITY);
             self.count += count;
             Ok(())
         }
@@ -181,6 +238,54 @@ impl ItemSlot {
         }
     }
 
+    /// Updates the age and quality of the slot to account for `added_count` new items of the given `quality` joining the current ones.
+    ///
+    /// Both become the average of the current and the added items, weighted by their count.
+    /// This must be called before the new items are counted.
+    fn mix_in_items(&mut self, added_count: u32, quality: f32) {
+        let total_count = self.count + added_count;
+
+        if total_count > 0 {
+            self.age = self.age.mul_f64(self.count as f64 / total_count as f64);
+            self.quality = (self.quality * self.count as f32 + quality * added_count as f32)
+                / total_count as f32;
+        }
+    }
+
+    /// Ages the items in this slot by `delta`, returning the new age.
+    ///
+    /// The items lose quality at a steady pace, so that no quality is left once they have existed for their `decay_time`.
+    pub fn advance_age(&mut self, delta: Duration, decay_time: Duration) -> Duration {
+        self.age += delta;
+
+        let lost_quality = if decay_time.is_zero() {
+            Self::FULL_QUALITY
+        } else {
+            Self::FULL_QUALITY * delta.as_secs_f32() / decay_time.as_secs_f32()
+        };
+        self.quality = (self.quality - lost_quality).max(0.);
+
+        self.age
+    }
+
+    /// Turns the items in this slot into the same number of fresh `item_id` items.
+    ///
+    /// Items that do not fit within the new `max_item_count` are lost.
+    pub fn convert_into(&mut self, item_id: Id<Item>, max_item_count: u32) {
+        self.item_id = item_id;
+        self.max_item_count = max_item_count;
+        self.count = self.count.min(max_item_count);
+        self.age = Duration::ZERO;
+        self.quality = Self::FULL_QUALITY;
+    }
+
+    /// Removes all items from this slot, resetting its age and quality.
+    pub fn clear(&mut self) {
+        self.count = 0;
+        self.age = Duration::ZERO;
+        self.quality = Self::FULL_QUALITY;
+    }
+
     /// Randomizes the quantity of items in this slot, return `self`.
     ///
     /// The new value will be chosen uniformly between 0 and `max_item_count`.
@@ -210,6 +315,8 @@ mod tests {
             item_id: Id::from_name("leaf".to_string()),
             max_item_count: 10,
             count: 0,
+            age: Duration::ZERO,
+            quality: ItemSlot::FULL_QUALITY,
         };
 
         assert!(item_slot.is_empty());
@@ -221,6 +328,8 @@ mod tests {
             item_id: Id::from_name("leaf".to_string()),
             max_item_count: 10,
             count: 1,
+            age: Duration::ZERO,
+            quality: ItemSlot::FULL_QUALITY,
         };
 
         assert!(!item_slot.is_empty());
@@ -232,6 +341,8 @@ mod tests {
             item_id: Id::from_name("leaf".to_string()),
             max_item_count: 10,
             count: 10,
+            age: Duration::ZERO,
+            quality: ItemSlot::FULL_QUALITY,
         };
 
         assert!(item_slot.is_full());
@@ -243,6 +354,8 @@ mod tests {
             item_id: Id::from_name("leaf".to_string()),
             max_item_count: 10,
             count: 9,
+            age: Duration::ZERO,
+            quality: ItemSlot::FULL_QUALITY,
         };
 
         assert!(!item_slot.is_full());
@@ -254,6 +367,8 @@ mod tests {
             item_id: Id::from_name("leaf".to_string()),
             max_item_count: 10,
             count: 0,
+            age: Duration::ZERO,
+            quality: ItemSlot::FULL_QUALITY,
         };
 
         assert_eq!(item_slot.remaining_space(), 10);
@@ -265,6 +380,8 @@ mod tests {
             item_id: Id::from_name("leaf".to_string()),
             max_item_count: 10,
             count: 5,
+            age: Duration::ZERO,
+            quality: ItemSlot::FULL_QUALITY,
         };
 
         assert_eq!(item_slot.remaining_space(), 5);
@@ -280,6 +397,8 @@ mod tests {
                     item_id: Id::from_name("leaf".to_string()),
                     max_item_count: 10,
                     count: 0,
+                    age: Duration::ZERO,
+                    quality: ItemSlot::FULL_QUALITY,
                 };
 
                 assert_eq!(item_slot.add_until_full(10), Ok(()));
@@ -292,6 +411,8 @@ mod tests {
                     item_id: Id::from_name("leaf".to_string()),
                     max_item_count: 10,
                     count: 5,
+                    age: Duration::ZERO,
+                    quality: ItemSlot::FULL_QUALITY,
                 };
 
                 assert_eq!(
@@ -313,6 +434,8 @@ mod tests {
                     item_id: Id::from_name("leaf".to_string()),
                     max_item_count: 10,
                     count: 0,
+                    age: Duration::ZERO,
+                    quality: ItemSlot::FULL_QUALITY,
                 };
 
                 assert_eq!(item_slot.add_all_or_nothing(10), Ok(()));
@@ -325,6 +448,8 @@ mod tests {
                     item_id: Id::from_name("leaf".to_string()),
                     max_item_count: 10,
                     count: 5,
+                    age: Duration::ZERO,
+                    quality: ItemSlot::FULL_QUALITY,
                 };
 
                 assert_eq!(
@@ -348,6 +473,8 @@ mod tests {
                     item_id: Id::from_name("leaf".to_string()),
                     max_item_count: 10,
                     count: 10,
+                    age: Duration::ZERO,
+                    quality: ItemSlot::FULL_QUALITY,
                 };
 
                 assert_eq!(item_slot.remove_until_empty(10), Ok(()));
@@ -360,6 +487,8 @@ mod tests {
                     item_id: Id::from_name("leaf".to_string()),
                     max_item_count: 10,
                     count: 5,
+                    age: Duration::ZERO,
+                    quality: ItemSlot::FULL_QUALITY,
                 };
 
                 assert_eq!(
@@ -381,6 +510,8 @@ mod tests {
                     item_id: Id::from_name("leaf".to_string()),
                     max_item_count: 10,
                     count: 10,
+                    age: Duration::ZERO,
+                    quality: ItemSlot::FULL_QUALITY,
                 };
 
                 assert_eq!(item_slot.remove_all_or_nothing(10), Ok(()));
@@ -393,6 +524,8 @@ mod tests {
                     item_id: Id::from_name("leaf".to_string()),
                     max_item_count: 10,
                     count: 5,
+                    age: Duration::ZERO,
+                    quality: ItemSlot::FULL_QUALITY,
                 };
 
                 assert_eq!(
@@ -405,4 +538,105 @@ mod tests {
             }
         }
     }
+
+    mod decay {
+        use super::*;
+
+        #[test]
+        fn fresh_items_lower_the_age_of_a_stack() {
+            let mut item_slot = ItemSlot {
+                item_id: Id::from_name("leaf".to_string()),
+                max_item_count: 10,
+                count: 2,
+                age: Duration::from_secs(8),
+                quality: ItemSlot::FULL_QUALITY,
+            };
+
+            assert_eq!(item_slot.add_all_or_nothing(6), Ok(()));
+            assert_eq!(item_slot.age(), Duration::from_secs(2));
+        }
+
+        #[test]
+        fn only_added_items_count_towards_the_age() {
+            let mut item_slot = ItemSlot {
+                item_id: Id::from_name("leaf".to_string()),
+                max_item_count: 10,
+                count: 5,
+                age: Duration::from_secs(10),
+                quality: ItemSlot::FULL_QUALITY,
+            };
+
+            assert!(item_slot.add_until_full(20).is_err());
+            assert_eq!(item_slot.age(), Duration::from_secs(5));
+        }
+
+        #[test]
+        fn removing_items_keeps_the_age() {
+            let mut item_slot = ItemSlot {
+                item_id: Id::from_name("leaf".to_string()),
+                max_item_count: 10,
+                count: 5,
+                age: Duration::from_secs(10),
+                quality: ItemSlot::FULL_QUALITY,
+            };
+
+            assert_eq!(item_slot.remove_all_or_nothing(3), Ok(()));
+            assert_eq!(item_slot.age(), Duration::from_secs(10));
+        }
+    }
+
+    mod quality {
+        use super::*;
+
+        /// A slot holding `count` leaves of the given `quality`.
+        fn leaves(count: u32, quality: f32) -> ItemSlot {
+            ItemSlot {
+                item_id: Id::from_name("leaf".to_string()),
+                max_item_count: 10,
+                count,
+                age: Duration::ZERO,
+                quality,
+            }
+        }
+
+        #[test]
+        fn merged_stacks_take_the_weighted_average_quality() {
+            let mut item_slot = leaves(2, 0.5);
+
+            assert_eq!(item_slot.add_all_or_nothing(2), Ok(()));
+            assert_eq!(item_slot.quality(), 0.75);
+
+            assert_eq!(item_slot.add_until_full_with_quality(4, 0.), Ok(()));
+            assert_eq!(item_slot.quality(), 0.375);
+        }
+
+        #[test]
+        fn only_added_items_count_towards_the_quality() {
+            let mut item_slot = leaves(5, 1.);
+
+            assert!(item_slot.add_until_full_with_quality(20, 0.).is_err());
+            assert_eq!(item_slot.quality(), 0.5);
+        }
+
+        #[test]
+        fn aging_items_lose_quality_until_they_decay() {
+            let mut item_slot = leaves(5, ItemSlot::FULL_QUALITY);
+            let decay_time = Duration::from_secs(8);
+
+            item_slot.advance_age(Duration::from_secs(2), decay_time);
+            assert_eq!(item_slot.quality(), 0.75);
+
+            item_slot.advance_age(Duration::from_secs(10), decay_time);
+            assert_eq!(item_slot.quality(), 0.);
+        }
+
+        #[test]
+        fn emptied_slots_are_fresh_again() {
+            let mut item_slot = leaves(5, 0.25);
+
+            item_slot.clear();
+            assert_eq!(item_slot.add_all_or_nothing(1), Ok(()));
+            assert_eq!(item_slot.quality(), ItemSlot::FULL_QUALITY);
+        }
+    }
 }
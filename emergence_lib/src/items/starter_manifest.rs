@@ -0,0 +1,166 @@
+//! Defines the items that a new colony starts with.
+
+use bevy::{prelude::World, reflect::TypeUuid, utils::HashMap};
+use emergence_macros::RawManifest;
+use serde::{Deserialize, Serialize};
+
+use crate as emergence_lib;
+use crate::asset_management::manifest::{
+    errors::{DanglingReference, ManifestError, OutOfRangeValue},
+    Manifest,
+};
+
+use super::item_manifest::{Item, ItemManifest};
+
+/// Stores the number of each item that a new colony starts with.
+///
+/// The entries share their [`Id<Item>`](crate::asset_management::manifest::Id) with the [`ItemManifest`].
+/// The items are placed in the storage of the colony during world generation.
+pub type StarterInventory = Manifest<Item, u32>;
+
+/// The [`StarterInventory`] as seen in the manifest file.
+#[derive(Debug, Clone, Serialize, Deserialize, TypeUuid, PartialEq, RawManifest)]
+#[uuid = "6498e914-0f09-4d32-8cfe-30f1d21c4deb"]
+#[raw_manifest(
+    extension = "starter_manifest",
+    marker = Item,
+    data = u32,
+    check = Self::check_counts,
+    validate = Self::validate_items
+)]
+pub struct RawStarterManifest {
+    /// The version of the manifest format that this file is written in.
+    ///
+    /// This must match [`IsRawManifest::CURRENT_VERSION`](crate::asset_management::manifest::loader::IsRawManifest::CURRENT_VERSION).
+    pub version: u32,
+    /// The number of each item that a new colony starts with, keyed by the name of the item.
+    pub items: HashMap<String, u32>,
+}
+
+impl RawStarterManifest {
+    /// Checks that at least one of each listed item is given.
+    fn check_counts(&self) -> Result<(), ManifestError> {
+        let mut out_of_range_values: Vec<OutOfRangeValue> = self
+            .items
+            .iter()
+            .filter(|(_, &count)| count == 0)
+            .map(|(item_name, count)| OutOfRangeValue {
+                entry: item_name.clone(),
+                field: "count".to_string(),
+                value: count.to_string(),
+                expected: "at least 1",
+            })
+            .collect();
+
+        if out_of_range_values.is_empty() {
+            Ok(())
+        } else {
+            out_of_range_values.sort();
+            Err(ManifestError::OutOfRange(out_of_range_values))
+        }
+    }
+
+    /// Checks that every starting item exists in the [`ItemManifest`].
+    fn validate_items(&self, world: &World) -> Result<(), ManifestError> {
+        let item_manifest = world.resource::<ItemManifest>();
+
+        let mut dangling_references: Vec<DanglingReference> = self
+            .items
+            .keys()
+            .filter(|item_name| !item_manifest.contains_name(item_name))
+            .map(|item_name| DanglingReference {
+                entry: item_name.clone(),
+                name: item_name.clone(),
+            })
+            .collect();
+
+        if dangling_references.is_empty() {
+            Ok(())
+        } else {
+            dangling_references.sort();
+            Err(ManifestError::UnknownReference(dangling_references))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset_management::manifest::{loader::IsRawManifest, Id};
+    use crate::items::item_manifest::RawItemManifest;
+
+    /// A starter manifest with some leaves and soil.
+    const STARTER_MANIFEST: &str = r#"{
+        "version": 1,
+        "items": {
+            "leaf": 12,
+            "soil": 5
+        }
+    }"#;
+
+    /// A world with an item manifest that contains leaves and soil.
+    fn world_with_items() -> World {
+        let raw_item_manifest: RawItemManifest = serde_json::from_str(
+            r#"{
+                "version": 1,
+                "items": {
+                    "leaf": { "stack_size": 10, "compostable": true, "fluid": false, "buoyant": true },
+                    "soil": { "stack_size": 5, "compostable": false, "fluid": false, "buoyant": false }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let mut world = World::new();
+        world.insert_resource(raw_item_manifest.process().unwrap());
+        world
+    }
+
+    #[test]
+    fn starter_manifests_are_loaded_into_the_starter_inventory() {
+        let raw_starter_manifest: RawStarterManifest =
+            serde_json::from_str(STARTER_MANIFEST).unwrap();
+
+        let starter_inventory: StarterInventory = raw_starter_manifest.process().unwrap();
+
+        assert_eq!(starter_inventory.len(), 2);
+        assert_eq!(
+            starter_inventory.get_unchecked(Id::from_name("leaf".to_string())),
+            &12
+        );
+        assert_eq!(
+            starter_inventory.get_unchecked(Id::from_name("soil".to_string())),
+            &5
+        );
+        assert_eq!(raw_starter_manifest.validate(&world_with_items()), Ok(()));
+    }
+
+    #[test]
+    fn unknown_starting_items_are_rejected() {
+        let raw_starter_manifest = RawStarterManifest {
+            version: RawStarterManifest::CURRENT_VERSION,
+            items: HashMap::from_iter([("leaf".to_string(), 1), ("acorn".to_string(), 3)]),
+        };
+
+        assert_eq!(
+            raw_starter_manifest.validate(&world_with_items()),
+            Err(ManifestError::UnknownReference(vec![DanglingReference {
+                entry: "acorn".to_string(),
+                name: "acorn".to_string(),
+            }]))
+        );
+    }
+
+    #[test]
+    fn starting_with_none_of_an_item_is_rejected() {
+        let raw_starter_manifest = RawStarterManifest {
+            version: RawStarterManifest::CURRENT_VERSION,
+            items: HashMap::from_iter([("leaf".to_string(), 0)]),
+        };
+
+        assert!(matches!(
+            raw_starter_manifest.process(),
+            Err(ManifestError::OutOfRange(_))
+        ));
+    }
+}
@@ -12,6 +12,7 @@ pub mod asset_management;
 pub mod construction;
 pub mod crafting;
 pub mod enum_iter;
+pub mod environment;
 pub mod filtered_array_iter;
 pub mod geometry;
 pub mod graphics;
@@ -4,6 +4,7 @@ use bevy::prelude::*;
 use core::fmt::Display;
 
 use emergence_macros::IterableEnum;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate as emergence_lib;
@@ -55,6 +56,7 @@ impl Display for TotalLight {
     Serialize,
     Deserialize,
     IterableEnum,
+    JsonSchema,
 )]
 pub enum Illuminance {
     /// The tile is in complete darkness.
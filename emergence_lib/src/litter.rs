@@ -113,6 +113,17 @@ pub(super) fn set_litter_emitters(mut query: Query<(&mut Emitter, Ref<Litter>)>)
 #[derive(SystemSet, Default, Debug, Clone, PartialEq, Eq, Hash)]
 pub(crate) struct LitterEmitters;
 
+/// Ages the littered items, turning them into their decay products over time.
+pub(super) fn decay_litter(
+    time: Res<FixedTime>,
+    item_manifest: Res<ItemManifest>,
+    mut query: Query<&mut Litter>,
+) {
+    for mut litter in query.iter_mut() {
+        litter.contents.decay(time.period, &item_manifest);
+    }
+}
+
 /// Litter entities with empty content should be despawned.
 pub(super) fn clear_empty_litter(query: Query<(Entity, &Litter)>, mut commands: Commands) {
     for (entity, litter) in query.iter() {
@@ -3,12 +3,17 @@
 use bevy::prelude::*;
 use core::fmt::Display;
 use core::ops::{Div, Mul};
+use core::time::Duration;
 use derive_more::{Add, AddAssign, Sub, SubAssign};
 use leafwing_abilities::{pool::MaxPoolLessThanZero, prelude::Pool};
 use rand::Rng;
+use rand_distr::{Distribution, Normal};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 
 use crate::asset_management::manifest::Id;
+use crate::items::item_manifest::{Item, ItemManifest};
 use crate::structures::structure_manifest::Structure;
 use crate::{geometry::VoxelPos, structures::commands::StructureCommandsExt};
 
@@ -25,6 +30,9 @@ pub struct EnergyPool {
     /// The threshold at which no more action is taken to gain energy.
     satiation_threshold: Energy,
     /// The amount of energy regenerated per second.
+    ///
+    /// This is the metabolism of the organism, as set by its variety in the organism manifest.
+    /// It is almost always negative, draining the pool until the organism eats or crafts enough to make up for it.
     pub(crate) regen_per_second: Energy,
 }
 
@@ -60,6 +68,32 @@ impl EnergyPool {
     pub(crate) fn is_full(&self) -> bool {
         self.current >= self.max
     }
+
+    /// Eats a single item of type `item_id`, adding its [`ItemData::energy_content`](crate::items::item_manifest::ItemData::energy_content) to the pool.
+    ///
+    /// The pool never fills beyond its maximum, so any excess energy is wasted.
+    /// Returns the energy that was actually gained.
+    ///
+    /// # Panics
+    /// This function panics when the given ID does not exist in the manifest.
+    pub fn feed(&mut self, item_id: Id<Item>, item_manifest: &ItemManifest) -> Energy {
+        let energy_content = item_manifest.get_unchecked(item_id).energy_content;
+        let previous = self.current;
+
+        self.set_current(previous + energy_content) - previous
+    }
+
+    /// Applies the [regeneration](Pool::regen_per_second) of this pool over the `delta` time.
+    ///
+    /// Returns `true` if this drained the last of the energy, so that the organism starves.
+    /// Pools that were already empty do not starve again.
+    pub fn metabolize(&mut self, delta: Duration) -> bool {
+        let was_empty = self.is_empty();
+        let current = self.current;
+        self.set_current(current + self.regen_per_second * delta.as_secs_f32());
+
+        !was_empty && self.is_empty()
+    }
 }
 
 impl Display for EnergyPool {
@@ -77,6 +111,10 @@ impl Default for EnergyPool {
 /// A quantity of energy, used to modify a [`EnergyPool`].
 ///
 /// Organisms produce energy by crafting recipes.
+///
+/// Energy may be negative when it describes a change, such as the cost of a recipe,
+/// but the energy stored in an [`EnergyPool`] never drops below zero.
+/// Use [`Energy::saturating_sub`] or [`Energy::checked_sub`] when spending stored energy.
 #[derive(
     Debug,
     Clone,
@@ -90,9 +128,45 @@ impl Default for EnergyPool {
     SubAssign,
     Serialize,
     Deserialize,
+    JsonSchema,
 )]
 pub struct Energy(pub f32);
 
+impl Energy {
+    /// No energy at all.
+    pub const ZERO: Energy = Energy(0.);
+
+    /// Subtracts `cost` from this energy, returning [`None`] if there is not enough energy to pay for it.
+    pub fn checked_sub(self, cost: Energy) -> Option<Energy> {
+        let remaining = self - cost;
+        (remaining >= Energy::ZERO).then_some(remaining)
+    }
+
+    /// Subtracts `cost` from this energy, stopping at zero.
+    pub fn saturating_sub(self, cost: Energy) -> Energy {
+        (self - cost).max(Energy::ZERO)
+    }
+
+    /// Returns the larger of this energy and `other`.
+    pub fn max(self, other: Energy) -> Energy {
+        Energy(self.0.max(other.0))
+    }
+
+    /// Returns the smaller of this energy and `other`.
+    pub fn min(self, other: Energy) -> Energy {
+        Energy(self.0.min(other.0))
+    }
+
+    /// Restricts this energy to the range between `min` and `max`, inclusive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min` is larger than `max`, like [`f32::clamp`].
+    pub fn clamp(self, min: Energy, max: Energy) -> Energy {
+        Energy(self.0.clamp(min.0, max.0))
+    }
+}
+
 impl Display for Energy {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:.1}", self.0)
@@ -147,8 +221,7 @@ impl Pool for EnergyPool {
     }
 
     fn set_current(&mut self, new_quantity: Self::Quantity) -> Self::Quantity {
-        let actual_value = Energy(new_quantity.0.clamp(0., self.max.0));
-        self.current = actual_value;
+        self.current = new_quantity.clamp(Energy::ZERO, self.max);
         self.current
     }
 
@@ -175,6 +248,86 @@ impl Pool for EnergyPool {
     }
 }
 
+/// The energy produced and consumed by the recipes of all living structures.
+///
+/// Positive recipe [`Energy`] counts as produced, while negative recipe energy counts as consumed.
+/// This shows players at a glance whether their colony is gaining or losing energy overall.
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct EnergyLedger {
+    /// The total energy produced so far.
+    produced: Energy,
+    /// The total energy consumed so far, as a positive quantity.
+    consumed: Energy,
+    /// The net energy recorded during the current tick.
+    pending: Energy,
+    /// The duration and net energy of each recent tick, oldest first.
+    recent: VecDeque<(Duration, Energy)>,
+    /// The combined duration of the [`recent`](Self::recent) ticks.
+    recent_duration: Duration,
+}
+
+impl EnergyLedger {
+    /// How far back [`EnergyLedger::net_per_second`] looks.
+    ///
+    /// Recipes complete only every so often, so a single tick would be far too noisy.
+    pub const WINDOW: Duration = Duration::from_secs(10);
+
+    /// Records `energy` gained or spent by completing a recipe during the current tick.
+    pub fn record(&mut self, energy: Energy) {
+        if energy > Energy(0.) {
+            self.produced += energy;
+        } else {
+            self.consumed -= energy;
+        }
+        self.pending += energy;
+    }
+
+    /// Ends the current tick, which lasted for `delta`.
+    pub fn finish_tick(&mut self, delta: Duration) {
+        self.recent
+            .push_back((delta, std::mem::take(&mut self.pending)));
+        self.recent_duration += delta;
+
+        while let Some(&(oldest, _)) = self.recent.front() {
+            if self.recent_duration - oldest < Self::WINDOW {
+                break;
+            }
+            self.recent.pop_front();
+            self.recent_duration -= oldest;
+        }
+    }
+
+    /// The total energy produced so far.
+    pub fn produced(&self) -> Energy {
+        self.produced
+    }
+
+    /// The total energy consumed so far, as a positive quantity.
+    pub fn consumed(&self) -> Energy {
+        self.consumed
+    }
+
+    /// The net energy gained by the colony so far.
+    ///
+    /// This is negative if more energy was consumed than produced.
+    pub fn total(&self) -> Energy {
+        self.produced - self.consumed
+    }
+
+    /// The average net energy gained per second, over the last [`EnergyLedger::WINDOW`].
+    pub fn net_per_second(&self) -> Energy {
+        if self.recent_duration.is_zero() {
+            return Energy(0.);
+        }
+
+        let net = self
+            .recent
+            .iter()
+            .fold(Energy(0.), |net, (_, energy)| net + *energy);
+        net / self.recent_duration.as_secs_f32()
+    }
+}
+
 /// The amount of energy that an organism should start with.
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub(crate) enum StartingEnergy {
@@ -188,26 +341,70 @@ pub(crate) enum StartingEnergy {
     NotAnOrganism,
 }
 
+/// The spread of energy that newly spawned organisms of a variety start with.
+///
+/// Samples are drawn from a normal distribution, and clamped so that no organism starts with negative energy.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EnergyDistribution {
+    /// The average starting energy.
+    pub mean: Energy,
+    /// The standard deviation of the starting energy.
+    ///
+    /// A standard deviation of 0 always gives the [`mean`](Self::mean).
+    pub std: f32,
+}
+
+impl EnergyDistribution {
+    /// Draws a starting energy from this distribution, using the provided `rng`.
+    ///
+    /// Invalid standard deviations, such as negative or NaN ones, always give the mean.
+    pub fn sample(&self, rng: &mut impl Rng) -> Energy {
+        // `Normal` accepts negative standard deviations, so these are caught here
+        if self.std.is_nan() || self.std <= 0. {
+            return self.mean.max(Energy::ZERO);
+        }
+
+        let energy = match Normal::new(self.mean.0, self.std) {
+            Ok(normal) => normal.sample(rng),
+            Err(_) => self.mean.0,
+        };
+
+        Energy(energy).max(Energy::ZERO)
+    }
+}
+
 /// Steadily depletes [`Energy`] over time.
 pub(super) fn consume_energy(fixed_time: Res<FixedTime>, mut energy_query: Query<&mut EnergyPool>) {
-    let delta_time = fixed_time.period.as_secs_f32();
-
     for mut energy_pool in energy_query.iter_mut() {
-        // Note that regen rates are almost always negative.
-        let regen_rate = energy_pool.regen_per_second;
-        let current = energy_pool.current();
-
-        energy_pool.set_current(current + regen_rate * delta_time);
+        energy_pool.metabolize(fixed_time.period);
     }
 }
 
+/// An event sent when an organism runs out of energy and dies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrganismStarved {
+    /// The organism that starved, which is despawned in the same tick.
+    pub entity: Entity,
+    /// Where the organism was.
+    pub voxel_pos: VoxelPos,
+}
+
 /// Despawns organisms when they run out of energy
+///
+/// Organisms that were fed enough to refill their pool before this runs survive,
+/// even if their energy briefly hit zero.
 pub(super) fn kill_organisms_when_out_of_energy(
     organism_query: Query<(Entity, &EnergyPool, &VoxelPos, Option<&Id<Structure>>)>,
+    mut starved_events: EventWriter<OrganismStarved>,
     mut commands: Commands,
 ) {
     for (entity, energy_pool, voxel_pos, maybe_structure) in organism_query.iter() {
         if energy_pool.is_empty() {
+            starved_events.send(OrganismStarved {
+                entity,
+                voxel_pos: *voxel_pos,
+            });
+
             match maybe_structure {
                 Some(_) => commands.despawn_structure(*voxel_pos),
                 None => commands.entity(entity).despawn_recursive(),
@@ -215,3 +412,280 @@ pub(super) fn kill_organisms_when_out_of_energy(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset_management::manifest::Manifest;
+    use crate::items::item_manifest::ItemData;
+    use bevy::utils::HashSet;
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    /// An item manifest where each `leaf` contains 10 energy, while `stone` contains none.
+    fn item_manifest() -> ItemManifest {
+        let mut manifest = Manifest::new();
+        for (name, energy_content) in [("leaf", 10.), ("stone", 0.)] {
+            manifest.insert(
+                name.to_string(),
+                ItemData {
+                    stack_size: 10,
+                    compostable: false,
+                    fluid: false,
+                    buoyant: false,
+                    seed: None,
+                    energy_content: Energy(energy_content),
+                    tags: HashSet::new(),
+                    mass: 1.,
+                    decay_time: None,
+                    decays_into: None,
+                    tier: 0,
+                    name: None,
+                    description: None,
+                    icon: None,
+                },
+            );
+        }
+        manifest
+    }
+
+    #[test]
+    fn feeding_adds_the_energy_content_of_the_item() {
+        let item_manifest = item_manifest();
+        let mut energy_pool = EnergyPool::new(Energy(50.), Energy(100.), Energy(0.));
+
+        let gained = energy_pool.feed(Id::from_name("leaf".to_string()), &item_manifest);
+
+        assert_eq!(gained, Energy(10.));
+        assert_eq!(energy_pool.current(), Energy(60.));
+
+        let gained = energy_pool.feed(Id::from_name("stone".to_string()), &item_manifest);
+
+        assert_eq!(gained, Energy(0.));
+        assert_eq!(energy_pool.current(), Energy(60.));
+    }
+
+    #[test]
+    fn saturating_subtraction_stops_at_zero() {
+        assert_eq!(Energy(10.).saturating_sub(Energy(4.)), Energy(6.));
+        assert_eq!(Energy(10.).saturating_sub(Energy(10.)), Energy::ZERO);
+        assert_eq!(Energy(10.).saturating_sub(Energy(25.)), Energy::ZERO);
+    }
+
+    #[test]
+    fn checked_subtraction_fails_without_enough_energy() {
+        assert_eq!(Energy(10.).checked_sub(Energy(4.)), Some(Energy(6.)));
+        assert_eq!(Energy(10.).checked_sub(Energy(10.)), Some(Energy::ZERO));
+        assert_eq!(Energy(10.).checked_sub(Energy(25.)), None);
+    }
+
+    #[test]
+    fn energy_is_ordered_by_amount() {
+        assert!(Energy(-5.) < Energy::ZERO);
+        assert!(Energy(3.) > Energy(2.5));
+        assert_eq!(Energy(3.).max(Energy(7.)), Energy(7.));
+        assert_eq!(Energy(3.).min(Energy(7.)), Energy(3.));
+    }
+
+    #[test]
+    fn clamping_keeps_energy_within_bounds() {
+        let (min, max) = (Energy::ZERO, Energy(100.));
+
+        assert_eq!(Energy(-20.).clamp(min, max), min);
+        assert_eq!(Energy(50.).clamp(min, max), Energy(50.));
+        assert_eq!(Energy(120.).clamp(min, max), max);
+    }
+
+    #[test]
+    fn ledger_sums_producing_and_consuming_structures() {
+        let mut ledger = EnergyLedger::default();
+        // The energy of each structure's recipe, and how many ticks it takes to complete
+        let structures = [
+            (Energy(8.), 2),
+            (Energy(4.), 1),
+            (Energy(-2.), 1),
+            (Energy(-16.), 4),
+        ];
+        let period = Duration::from_millis(250);
+        let ticks = 40;
+
+        let mut expected_produced = Energy(0.);
+        let mut expected_consumed = Energy(0.);
+        for tick in 1..=ticks {
+            for (energy, interval) in structures {
+                if tick % interval == 0 {
+                    ledger.record(energy);
+                    if energy > Energy(0.) {
+                        expected_produced += energy;
+                    } else {
+                        expected_consumed -= energy;
+                    }
+                }
+            }
+            ledger.finish_tick(period);
+        }
+
+        assert_eq!(ledger.produced(), expected_produced);
+        assert_eq!(ledger.consumed(), expected_consumed);
+        assert_eq!(ledger.total(), expected_produced - expected_consumed);
+        // All ticks fit in the window, so the rate covers the whole simulation
+        let elapsed = (period * ticks).as_secs_f32();
+        assert_eq!(ledger.net_per_second(), ledger.total() / elapsed);
+    }
+
+    #[test]
+    fn ledger_rate_only_covers_the_window() {
+        let mut ledger = EnergyLedger::default();
+        let period = Duration::from_secs(1);
+
+        // An early burst of production, followed by steady consumption
+        ledger.record(Energy(100.));
+        ledger.finish_tick(period);
+        for _ in 0..20 {
+            ledger.record(Energy(-1.));
+            ledger.finish_tick(period);
+        }
+
+        assert_eq!(ledger.total(), Energy(80.));
+        assert_eq!(ledger.net_per_second(), Energy(-1.));
+    }
+
+    #[test]
+    fn empty_ledgers_are_balanced() {
+        let ledger = EnergyLedger::default();
+
+        assert_eq!(ledger.total(), Energy(0.));
+        assert_eq!(ledger.net_per_second(), Energy(0.));
+    }
+
+    #[test]
+    fn metabolism_drains_energy_until_the_organism_starves() {
+        let mut energy_pool = EnergyPool::new(Energy(10.), Energy(100.), Energy(-2.));
+
+        for _ in 0..4 {
+            assert!(!energy_pool.metabolize(Duration::from_secs(1)));
+        }
+        assert_eq!(energy_pool.current(), Energy(2.));
+
+        assert!(energy_pool.metabolize(Duration::from_secs(1)));
+        assert!(energy_pool.is_empty());
+        // Starving only happens once
+        assert!(!energy_pool.metabolize(Duration::from_secs(1)));
+        assert_eq!(energy_pool.current(), Energy::ZERO);
+    }
+
+    #[test]
+    fn eating_faster_than_the_metabolism_drains_keeps_organisms_alive() {
+        let item_manifest = item_manifest();
+        let leaf = Id::from_name("leaf".to_string());
+        // Enough energy to last until the first leaf
+        let mut energy_pool = EnergyPool::new(Energy(6.), Energy(20.), Energy(-1.));
+
+        // A leaf every five seconds more than makes up for the drain
+        for second in 1..=100 {
+            assert!(!energy_pool.metabolize(Duration::from_secs(1)));
+            if second % 5 == 0 {
+                energy_pool.feed(leaf, &item_manifest);
+            }
+        }
+
+        assert!(energy_pool.is_full());
+    }
+
+    #[test]
+    fn starving_organisms_are_despawned_unless_fed_in_time() {
+        let mut app = App::new();
+        app.add_event::<OrganismStarved>()
+            .insert_resource(FixedTime::new(Duration::from_secs(1)))
+            .add_systems((consume_energy, kill_organisms_when_out_of_energy).chain());
+
+        let energy_pool = EnergyPool::new(Energy(1.5), Energy(100.), Energy(-1.));
+        let starving = app.world.spawn((energy_pool.clone(), VoxelPos::ZERO)).id();
+        let fed = app.world.spawn((energy_pool, VoxelPos::ZERO)).id();
+
+        app.update();
+        assert_eq!(
+            app.world.get::<EnergyPool>(starving).unwrap().current(),
+            Energy(0.5)
+        );
+
+        app.world
+            .get_mut::<EnergyPool>(fed)
+            .unwrap()
+            .feed(Id::from_name("leaf".to_string()), &item_manifest());
+        app.update();
+
+        assert!(app.world.get_entity(starving).is_none());
+        assert_eq!(
+            app.world.get::<EnergyPool>(fed).unwrap().current(),
+            Energy(9.5)
+        );
+
+        let starved_events = app.world.resource::<Events<OrganismStarved>>();
+        let starved: Vec<OrganismStarved> = starved_events
+            .get_reader()
+            .iter(starved_events)
+            .copied()
+            .collect();
+        assert_eq!(
+            starved,
+            vec![OrganismStarved {
+                entity: starving,
+                voxel_pos: VoxelPos::ZERO,
+            }]
+        );
+    }
+
+    #[test]
+    fn feeding_never_overfills_the_pool() {
+        let item_manifest = item_manifest();
+        let mut energy_pool = EnergyPool::new(Energy(95.), Energy(100.), Energy(0.));
+
+        let gained = energy_pool.feed(Id::from_name("leaf".to_string()), &item_manifest);
+
+        assert_eq!(gained, Energy(5.));
+        assert!(energy_pool.is_full());
+    }
+
+    #[test]
+    fn starting_energy_samples_average_to_the_mean() {
+        let distribution = EnergyDistribution {
+            mean: Energy(60.),
+            std: 10.,
+        };
+        let rng = &mut SmallRng::seed_from_u64(0);
+        let samples = 10_000;
+
+        let total: f32 = (0..samples).map(|_| distribution.sample(rng).0).sum();
+        let mean = total / samples as f32;
+
+        assert!(
+            (mean - 60.).abs() < 0.5,
+            "Samples averaged to {mean}, but 60 was expected"
+        );
+    }
+
+    #[test]
+    fn starting_energy_is_never_negative() {
+        let distribution = EnergyDistribution {
+            mean: Energy(1.),
+            std: 20.,
+        };
+        let rng = &mut SmallRng::seed_from_u64(0);
+
+        assert!((0..1000).all(|_| distribution.sample(rng) >= Energy::ZERO));
+    }
+
+    #[test]
+    fn invalid_spreads_start_at_the_mean() {
+        let rng = &mut SmallRng::seed_from_u64(0);
+
+        for std in [0., -5., f32::NAN] {
+            let distribution = EnergyDistribution {
+                mean: Energy(30.),
+                std,
+            };
+
+            assert_eq!(distribution.sample(rng), Energy(30.));
+        }
+    }
+}
@@ -226,7 +226,7 @@ pub(super) fn transform_when_lifecycle_complete(
         for new_form in lifecycle.new_forms() {
             // Make sure that there's a valid place to spawn the new form.
             if let OrganismId::Structure(structure_id) = new_form {
-                let variety = structure_manifest.get(structure_id);
+                let variety = structure_manifest.get_unchecked(structure_id);
 
                 if !map_geometry.can_transform(entity, voxel_pos, &variety.footprint, facing) {
                     // Look for another viable form to transform into.
@@ -247,7 +247,7 @@ pub(super) fn transform_when_lifecycle_complete(
                         structure_id,
                         facing,
                         active_recipe: structure_manifest
-                            .get(structure_id)
+                            .get_unchecked(structure_id)
                             .starting_recipe()
                             .clone(),
                     };
@@ -257,7 +257,7 @@ pub(super) fn transform_when_lifecycle_complete(
                     commands.spawn_structure(voxel_pos, data, starting_energy);
                 }
                 OrganismId::Unit(unit_id) => {
-                    let unit_data = unit_manifest.get(unit_id).clone();
+                    let unit_data = unit_manifest.get_unchecked(unit_id).clone();
 
                     commands.spawn(UnitBundle::newborn(
                         unit_id,
@@ -298,14 +298,14 @@ pub(super) fn sprout_seeds(
 
         for item_slot in litter.contents.iter_mut() {
             let item_id = item_slot.item_id();
-            let Some(organism_id) = item_manifest.get(item_id).seed else { continue };
+            let Some(organism_id) = item_manifest.get_unchecked(item_id).seed else { continue };
 
             // Generate a random facing now, so we can verify that the new organism fits.
             let facing = Facing::random(rng);
 
             // Make sure that there's a valid place to spawn the new form.
             if let OrganismId::Structure(structure_id) = organism_id {
-                let structure_data = structure_manifest.get(structure_id);
+                let structure_data = structure_manifest.get_unchecked(structure_id);
 
                 if map_geometry
                     .is_space_available(voxel_pos, &structure_data.footprint, facing)
@@ -330,14 +330,14 @@ pub(super) fn sprout_seeds(
                         structure_id,
                         facing,
                         active_recipe: structure_manifest
-                            .get(structure_id)
+                            .get_unchecked(structure_id)
                             .starting_recipe()
                             .clone(),
                     };
                     commands.spawn_structure(voxel_pos, data, StartingEnergy::Full);
                 }
                 OrganismId::Unit(unit_id) => {
-                    let unit_data = unit_manifest.get(unit_id).clone();
+                    let unit_data = unit_manifest.get_unchecked(unit_id).clone();
 
                     commands.spawn(UnitBundle::newborn(
                         unit_id,
@@ -1,6 +1,9 @@
 //! Models organisms, which have two primary types: units (organisms that can move around freely)
 //! and structures (organisms that are fixed in place).
 use bevy::prelude::*;
+use leafwing_abilities::prelude::Pool;
+use rand::Rng;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -11,7 +14,10 @@ use crate::{
 };
 
 use self::{
-    energy::{consume_energy, kill_organisms_when_out_of_energy, EnergyPool},
+    energy::{
+        consume_energy, kill_organisms_when_out_of_energy, EnergyDistribution, EnergyPool,
+        OrganismStarved,
+    },
     lifecycle::{sprout_seeds, transform_when_lifecycle_complete, Lifecycle, RawLifecycle},
     oxygen::{manage_oxygen, Oxygen, OxygenPool},
     vegetative_reproduction::vegetative_spread,
@@ -32,7 +38,7 @@ pub enum OrganismId {
 }
 
 /// The unprocessed equivalent of [`OrganismId`].
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
 pub enum RawOrganismId {
     /// Represents a [`Structure`].
     Structure(String),
@@ -114,6 +120,10 @@ pub struct OrganismVariety {
     pub lifecycle: Lifecycle,
     /// Controls the maximum energy, and the rate at which it drains.
     pub energy_pool: EnergyPool,
+    /// The spread of energy that newly spawned organisms start with.
+    ///
+    /// If this is [`None`], organisms start with a random amount of energy above their warning threshold.
+    pub starting_energy: Option<EnergyDistribution>,
 }
 
 impl OrganismVariety {
@@ -126,7 +136,23 @@ impl OrganismVariety {
             prototypical_form: OrganismId::Unit(Id::from_name(name.to_string())),
             lifecycle: Lifecycle::default(),
             energy_pool: EnergyPool::default(),
+            starting_energy: None,
+        }
+    }
+
+    /// The energy pool of a newly spawned organism of this variety, using the `rng` to pick its starting energy.
+    ///
+    /// The starting energy is drawn from the [`starting_energy`](Self::starting_energy) distribution if there is one,
+    /// and never exceeds the maximum of the pool.
+    pub fn spawn_energy_pool(&self, rng: &mut impl Rng) -> EnergyPool {
+        let mut energy_pool = self.energy_pool.clone();
+        match self.starting_energy {
+            Some(distribution) => {
+                energy_pool.set_current(distribution.sample(rng));
+            }
+            None => energy_pool.randomize(rng),
         }
+        energy_pool
     }
 }
 
@@ -139,6 +165,9 @@ pub struct RawOrganismVariety {
     pub lifecycle: RawLifecycle,
     /// Controls the maximum energy, and the rate at which it drains.
     pub energy_pool: EnergyPool,
+    /// The spread of energy that newly spawned organisms start with.
+    #[serde(default)]
+    pub starting_energy: Option<EnergyDistribution>,
 }
 
 impl From<RawOrganismVariety> for OrganismVariety {
@@ -147,6 +176,7 @@ impl From<RawOrganismVariety> for OrganismVariety {
             prototypical_form: raw.prototypical_form.into(),
             lifecycle: raw.lifecycle.into(),
             energy_pool: raw.energy_pool,
+            starting_energy: raw.starting_energy,
         }
     }
 }
@@ -160,7 +190,7 @@ pub struct OrganismPlugin;
 
 impl Plugin for OrganismPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
+        app.add_event::<OrganismStarved>().add_systems(
             (
                 consume_energy,
                 kill_organisms_when_out_of_energy,
@@ -101,7 +101,7 @@ pub(super) fn vegetative_spread(
             structure_id,
             facing: Facing::random(&mut rng),
             active_recipe: structure_manifest
-                .get(structure_id)
+                .get_unchecked(structure_id)
                 .starting_recipe()
                 .clone(),
         };
@@ -97,7 +97,7 @@ impl ClipboardData {
             structure_id,
             facing: Facing::default(),
             active_recipe: structure_manifest
-                .get(structure_id)
+                .get_unchecked(structure_id)
                 .starting_recipe()
                 .clone(),
         }
@@ -693,7 +693,7 @@ fn emit_signals(
     for (&center, emitter, maybe_structure_id, maybe_facing) in emitter_query.iter() {
         // When the water is too deep, disable the flooded buildings to avoid drowning units constantly
         if let Some(structure_id) = maybe_structure_id {
-            let structure_data = structure_manifest.get(*structure_id);
+            let structure_data = structure_manifest.get_unchecked(*structure_id);
             let terrain_entity = map_geometry.get_terrain(center.hex).unwrap();
             let water_depth = terrain_query.get(terrain_entity).unwrap();
             let structure_height = structure_data.footprint.max_height();
@@ -707,7 +707,7 @@ fn emit_signals(
             // Signals should be emitted from all tiles in the footprint of a structure.
             Some(structure_id) => {
                 let facing = *maybe_facing.expect("Structures must have a facing");
-                let footprint = &structure_manifest.get(*structure_id).footprint;
+                let footprint = &structure_manifest.get_unchecked(*structure_id).footprint;
 
                 let n_tiles = footprint.set.len();
 
@@ -765,6 +765,8 @@ fn degrade_signals(mut signals: ResMut<Signals>) {
 #[cfg(test)]
 mod tests {
     use crate::items::item_manifest::ItemData;
+    use crate::organisms::energy::Energy;
+    use bevy::utils::HashSet;
 
     use super::*;
 
@@ -786,6 +788,15 @@ mod tests {
                 fluid: false,
                 buoyant: true,
                 seed: None,
+                energy_content: Energy(0.),
+                tags: HashSet::new(),
+                mass: 1.,
+                decay_time: None,
+                decays_into: None,
+                tier: 0,
+                name: None,
+                description: None,
+                icon: None,
             },
         );
         manifest
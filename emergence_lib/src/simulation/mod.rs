@@ -5,6 +5,7 @@
 use crate::asset_management::AssetState;
 use crate::construction::ConstructionPlugin;
 use crate::crafting::CraftingPlugin;
+use crate::environment::EnvironmentPlugin;
 use crate::geometry::sync_rotation_to_facing;
 use crate::light::LightPlugin;
 use crate::organisms::OrganismPlugin;
@@ -56,6 +57,7 @@ impl Plugin for SimulationPlugin {
                 config: self.gen_config.clone(),
             })
             .add_plugin(CraftingPlugin)
+            .add_plugin(EnvironmentPlugin)
             .add_plugin(ConstructionPlugin)
             .add_plugin(StructuresPlugin)
             .add_plugin(TerrainPlugin)
@@ -1,6 +1,7 @@
 //! Controls random number generation.
 //!
 //! Storing the random number generator in a resource allows us to generate worlds deterministically.
+//! Crafting rolls draw from the same source, so that the same seed also produces the same yields.
 // TODO: replace with bevy_turborand.
 
 use bevy::prelude::*;
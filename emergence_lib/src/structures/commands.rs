@@ -17,6 +17,7 @@ use crate::{
     organisms::{energy::StartingEnergy, OrganismBundle},
     player_interaction::clipboard::ClipboardData,
     signals::Emitter,
+    simulation::rng::GlobalRng,
 };
 
 use super::{
@@ -117,7 +118,7 @@ impl Command for SpawnStructureCommand {
         let structure_id = self.data.structure_id;
 
         let manifest = world.resource::<StructureManifest>();
-        let structure_data = manifest.get(structure_id).clone();
+        let structure_data = manifest.get_unchecked(structure_id).clone();
 
         // Check that the tiles needed are appropriate.
         let geometry = world.resource_mut::<MapGeometry>();
@@ -176,8 +177,11 @@ impl Command for SpawnStructureCommand {
                     energy_pool.set_current(energy);
                 },
                 StartingEnergy::Random => {
-                    let rng = &mut rand::thread_rng();
-                    energy_pool.randomize(rng)
+                    // Draw from the seeded RNG when there is one, so that generated worlds are reproducible
+                    energy_pool = match world.get_resource_mut::<GlobalRng>() {
+                        Some(mut rng) => organism_details.spawn_energy_pool(rng.get_mut()),
+                        None => organism_details.spawn_energy_pool(&mut rand::thread_rng()),
+                    };
                 },
                 StartingEnergy::Full => {},
                 StartingEnergy::NotAnOrganism => panic!("All organisms must have energy pools, and this variant should never be constructed for organisms."),
@@ -282,7 +286,7 @@ impl Command for DespawnStructureCommand {
             .entity(structure_entity)
             .get::<Id<Structure>>() else { return; };
         let structure_manifest = world.resource::<StructureManifest>();
-        let structure_data = structure_manifest.get(structure_id);
+        let structure_data = structure_manifest.get_unchecked(structure_id);
         let footprint = structure_data.footprint.clone();
 
         let mut geometry = world.resource_mut::<MapGeometry>();
@@ -319,7 +323,7 @@ impl Command for SpawnStructureGhostCommand {
 
         let manifest = world.resource::<StructureManifest>();
         let footprint = manifest.footprint(structure_id).clone();
-        let structure_data = manifest.get(structure_id);
+        let structure_data = manifest.get_unchecked(structure_id);
         let facing = self.data.facing;
 
         let world_pos = structure_data
@@ -391,7 +395,7 @@ impl Command for SpawnStructureGhostCommand {
         // Update the index to reflect the new state
         world.resource_scope(|world, mut map_geometry: Mut<MapGeometry>| {
             let structure_manifest = world.resource::<StructureManifest>();
-            let structure_variety = structure_manifest.get(structure_id);
+            let structure_variety = structure_manifest.get_unchecked(structure_id);
             let footprint = &structure_variety.footprint;
 
             map_geometry
@@ -446,7 +450,7 @@ impl Command for SpawnStructurePreviewCommand {
         }
 
         let manifest = world.resource::<StructureManifest>();
-        let structure_data = manifest.get(structure_id).clone();
+        let structure_data = manifest.get_unchecked(structure_id).clone();
 
         let geometry = world.resource::<MapGeometry>();
 
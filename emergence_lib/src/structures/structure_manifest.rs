@@ -1,7 +1,8 @@
 //! Defines write-only data for each variety of structure.
 
+use crate as emergence_lib;
 use crate::{
-    asset_management::manifest::{loader::IsRawManifest, Id, Manifest},
+    asset_management::manifest::{Id, Manifest},
     construction::{ConstructionData, ConstructionStrategy, RawConstructionStrategy},
     crafting::recipe::{ActiveRecipe, RawActiveRecipe},
     items::item_manifest::Item,
@@ -15,6 +16,7 @@ use bevy::{
     reflect::{FromReflect, Reflect, TypeUuid},
     utils::HashMap,
 };
+use emergence_macros::RawManifest;
 use serde::{Deserialize, Serialize};
 
 use super::Footprint;
@@ -31,7 +33,7 @@ impl StructureManifest {
     /// If the structure uses a seedling, this will recursively fetch the data for the seedling.
     /// If the structure uses a landmark, this will return [`None`].
     pub fn construction_data(&self, structure_id: Id<Structure>) -> Option<&ConstructionData> {
-        let initial_strategy = &self.get(structure_id).construction_strategy;
+        let initial_strategy = &self.get_unchecked(structure_id).construction_strategy;
         match initial_strategy {
             ConstructionStrategy::Seedling(seedling_id) => self.construction_data(*seedling_id),
             ConstructionStrategy::Direct(data) => Some(data),
@@ -41,11 +43,11 @@ impl StructureManifest {
 
     /// Fetches the [`Footprint`] for the initial form of a given structure type.
     pub fn footprint(&self, structure_id: Id<Structure>) -> &Footprint {
-        let strategy = &self.get(structure_id).construction_strategy;
+        let strategy = &self.get_unchecked(structure_id).construction_strategy;
         match strategy {
             ConstructionStrategy::Seedling(seedling_id) => self.footprint(*seedling_id),
             ConstructionStrategy::Direct(..) | ConstructionStrategy::Landmark => {
-                &self.get(structure_id).footprint
+                &self.get_unchecked(structure_id).footprint
             }
         }
     }
@@ -126,6 +128,7 @@ impl StructureData {
 
 /// The unprocessed equivalent of [`StructureData`].
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict_manifests", serde(deny_unknown_fields))]
 pub struct RawStructureData {
     /// Data needed for living structures
     pub organism_variety: Option<RawOrganismVariety>,
@@ -274,28 +277,14 @@ impl StructureManifest {
 }
 
 /// The [`StructureManifest`] as seen in the manifest file.
-#[derive(Debug, Clone, Serialize, Deserialize, TypeUuid, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, TypeUuid, PartialEq, RawManifest)]
 #[uuid = "77ddfe49-be99-4fea-bbba-0c085821f6b8"]
+#[raw_manifest(extension = "structure_manifest", marker = Structure, data = StructureData)]
 pub struct RawStructureManifest {
+    /// The version of the manifest format that this file is written in.
+    ///
+    /// This must match [`IsRawManifest::CURRENT_VERSION`](crate::asset_management::manifest::loader::IsRawManifest::CURRENT_VERSION).
+    pub version: u32,
     /// The data for each structure.
     pub structure_types: HashMap<String, RawStructureData>,
 }
-
-impl IsRawManifest for RawStructureManifest {
-    const EXTENSION: &'static str = "structure_manifest.json";
-
-    type Marker = Structure;
-    type Data = StructureData;
-
-    fn process(&self) -> Manifest<Self::Marker, Self::Data> {
-        let mut manifest = Manifest::new();
-
-        for (raw_id, raw_data) in self.structure_types.clone() {
-            let data = raw_data.into();
-
-            manifest.insert(raw_id, data)
-        }
-
-        manifest
-    }
-}
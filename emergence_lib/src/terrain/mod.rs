@@ -7,6 +7,7 @@ use crate::asset_management::manifest::plugin::ManifestPlugin;
 use crate::asset_management::manifest::Id;
 use crate::asset_management::AssetCollectionExt;
 use crate::construction::zoning::Zoning;
+use crate::environment::EnvironmentSample;
 use crate::geometry::{MapGeometry, VoxelPos};
 use crate::light::shade::{ReceivedLight, Shade};
 use crate::player_interaction::selection::ObjectInteraction;
@@ -17,8 +18,8 @@ use crate::water::{WaterBundle, WaterSet};
 use self::terrain_assets::TerrainHandles;
 use self::terrain_manifest::{RawTerrainManifest, Terrain, TerrainManifest};
 use crate::litter::{
-    carry_floating_litter_with_current, clear_empty_litter, make_litter_float, set_litter_emitters,
-    LitterEmitters,
+    carry_floating_litter_with_current, clear_empty_litter, decay_litter, make_litter_float,
+    set_litter_emitters, LitterEmitters,
 };
 
 pub(crate) mod commands;
@@ -35,6 +36,7 @@ impl Plugin for TerrainPlugin {
             .add_systems(
                 (
                     respond_to_height_changes,
+                    decay_litter.before(clear_empty_litter),
                     make_litter_float.after(respond_to_height_changes),
                     carry_floating_litter_with_current
                         .after(make_litter_float)
@@ -77,6 +79,8 @@ pub(crate) struct TerrainBundle {
     shade: Shade,
     /// The amount of light currently being received by this tile.
     received_light: ReceivedLight,
+    /// The environmental values at this tile, such as its temperature.
+    environment_sample: EnvironmentSample,
     /// The components used to track the water table at this tile.
     water_bundle: WaterBundle,
 }
@@ -97,7 +101,7 @@ impl TerrainBundle {
             ..Default::default()
         };
 
-        let terrain_data = terrain_manifest.get(terrain_id);
+        let terrain_data = terrain_manifest.get_unchecked(terrain_id);
 
         TerrainBundle {
             terrain_id,
@@ -110,6 +114,7 @@ impl TerrainBundle {
             emitter: Emitter::default(),
             shade: Shade::default(),
             received_light: ReceivedLight::default(),
+            environment_sample: EnvironmentSample::default(),
             water_bundle: WaterBundle {
                 soil_water_capacity: terrain_data.soil_water_capacity,
                 soil_water_evaporation_rate: terrain_data.soil_water_evaporation_rate,
@@ -132,6 +137,7 @@ impl TerrainBundle {
             emitter: Emitter::default(),
             shade: Shade::default(),
             received_light: ReceivedLight::default(),
+            environment_sample: EnvironmentSample::default(),
             water_bundle: WaterBundle::default(),
         }
     }
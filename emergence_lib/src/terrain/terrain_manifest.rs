@@ -4,10 +4,12 @@ use bevy::{
     reflect::{FromReflect, Reflect, TypeUuid},
     utils::HashMap,
 };
+use emergence_macros::RawManifest;
 use serde::{Deserialize, Serialize};
 
+use crate as emergence_lib;
 use crate::{
-    asset_management::manifest::{loader::IsRawManifest, Manifest},
+    asset_management::manifest::Manifest,
     water::{
         water_dynamics::{SoilWaterEvaporationRate, SoilWaterFlowRate},
         SoilWaterCapacity,
@@ -60,27 +62,14 @@ impl Default for TerrainData {
 }
 
 /// The [`TerrainManifest`] as seen in the manifest file.
-#[derive(Debug, Clone, Serialize, Deserialize, TypeUuid, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, TypeUuid, PartialEq, RawManifest)]
 #[uuid = "8d6b3b65-9b11-42a9-a795-f95b06653070"]
+#[raw_manifest(extension = "terrain_manifest", marker = Terrain, data = TerrainData)]
 pub struct RawTerrainManifest {
+    /// The version of the manifest format that this file is written in.
+    ///
+    /// This must match [`IsRawManifest::CURRENT_VERSION`](crate::asset_management::manifest::loader::IsRawManifest::CURRENT_VERSION).
+    pub version: u32,
     /// The data for each item.
     pub terrain_types: HashMap<String, TerrainData>,
 }
-
-impl IsRawManifest for RawTerrainManifest {
-    const EXTENSION: &'static str = "terrain_manifest.json";
-
-    type Marker = Terrain;
-    type Data = TerrainData;
-
-    fn process(&self) -> Manifest<Self::Marker, Self::Data> {
-        let mut manifest = Manifest::new();
-
-        for (raw_id, raw_data) in self.terrain_types.clone() {
-            // No additional preprocessing is needed.
-            manifest.insert(raw_id, raw_data)
-        }
-
-        manifest
-    }
-}
@@ -84,7 +84,7 @@ fn handle_selection(
                     structure_id: *element.data(),
                     facing: Facing::default(),
                     active_recipe: structure_manifest
-                        .get(*element.data())
+                        .get_unchecked(*element.data())
                         .starting_recipe()
                         .clone(),
                 };
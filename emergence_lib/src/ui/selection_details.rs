@@ -282,7 +282,7 @@ pub(crate) enum SelectionDetails {
     /// A ghost of a structure is selected
     GhostStructure(GhostStructureDetails),
     /// A structure is selected
-    Structure(StructureDetails),
+    Structure(Box<StructureDetails>),
     /// A tile is selected.
     Terrain(TerrainDetails),
     /// A unit is selected
@@ -328,7 +328,7 @@ fn get_details(
                     .ok()
                     .map(|query_item| OrganismDetails {
                         prototypical_form: structure_manifest
-                            .get(*structure_query_item.structure_id)
+                            .get_unchecked(*structure_query_item.structure_id)
                             .organism_variety.as_ref()
                             .expect("All structures with organism components must be registered in the manifest as organisms")
                             .prototypical_form,
@@ -337,7 +337,7 @@ fn get_details(
                         oxygen_pool: query_item.oxygen_pool.clone(),
                     });
 
-            SelectionDetails::Structure(StructureDetails {
+            SelectionDetails::Structure(Box::new(StructureDetails {
                 entity: structure_query_item.entity,
                 voxel_pos: *structure_query_item.voxel_pos,
                 structure_id: *structure_query_item.structure_id,
@@ -351,7 +351,7 @@ fn get_details(
                 active_recipe: structure_query_item.active_recipe.cloned(),
                 workers_present: structure_query_item.workers_present.cloned(),
                 vegetative_reproduction: structure_query_item.vegetative_reproduction.cloned(),
-            })
+            }))
         }
         CurrentSelection::Terrain(selected_tiles) => {
             // FIXME: display info about multiple tiles correctly
@@ -387,7 +387,7 @@ fn get_details(
             let organism_query_item = organism_query.get(*unit_entity)?;
             let organism_details = OrganismDetails {
                 prototypical_form: unit_manifest
-                    .get(*unit_query_item.unit_id)
+                    .get_unchecked(*unit_query_item.unit_id)
                     .organism_variety
                     .prototypical_form,
                 lifecycle: organism_query_item.lifecycle.clone(),
@@ -395,7 +395,7 @@ fn get_details(
                 oxygen_pool: organism_query_item.oxygen_pool.clone(),
             };
 
-            let unit_data = unit_manifest.get(*unit_query_item.unit_id);
+            let unit_data = unit_manifest.get_unchecked(*unit_query_item.unit_id);
 
             SelectionDetails::Unit(UnitDetails {
                 entity: unit_query_item.entity,
@@ -664,7 +664,7 @@ mod structure_details {
             let entity = self.entity;
             let structure_type = structure_manifest.name(self.structure_id);
             let height = structure_manifest
-                .get(self.structure_id)
+                .get_unchecked(self.structure_id)
                 .footprint
                 .max_height();
             let voxel_pos = &self.voxel_pos;
@@ -704,7 +704,9 @@ Height: {height}"
                 if let Some(recipe_id) = recipe.recipe_id() {
                     string += &format!(
                         "\nRecipe data: {}",
-                        recipe_manifest.get(*recipe_id).display(item_manifest)
+                        recipe_manifest
+                            .get_unchecked(*recipe_id)
+                            .display(item_manifest)
                     );
                 }
             }
@@ -717,7 +719,10 @@ Height: {height}"
                 string += &format!("\nWorkers present: {workers_present}");
             }
 
-            if let Some(root_zone) = &structure_manifest.get(self.structure_id).root_zone {
+            if let Some(root_zone) = &structure_manifest
+                .get_unchecked(self.structure_id)
+                .root_zone
+            {
                 string += &format!("\n{root_zone}",);
             }
 
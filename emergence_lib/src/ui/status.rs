@@ -115,7 +115,7 @@ impl From<&CraftingState> for CraftingProgress {
                 let n_segments = (fraction * 6.0).round() as u8;
                 CraftingProgress::InProgress(n_segments)
             }
-            CraftingState::FullAndBlocked => CraftingProgress::FullAndBlocked,
+            CraftingState::FullAndBlocked { .. } => CraftingProgress::FullAndBlocked,
             CraftingState::RecipeComplete => CraftingProgress::InProgress(6),
             CraftingState::Overproduction => CraftingProgress::InProgress(6),
             CraftingState::NoRecipe => CraftingProgress::NoRecipe,
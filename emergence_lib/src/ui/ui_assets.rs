@@ -64,14 +64,11 @@ impl FromWorld for Icons<Id<Item>> {
     fn from_world(world: &mut World) -> Self {
         let asset_server = world.resource::<AssetServer>();
         let item_manifest = world.resource::<ItemManifest>();
-        let item_names = item_manifest.names();
 
         let mut map = HashMap::new();
 
-        for id in item_names {
-            let item_id = Id::from_name(id.to_string());
-            let item_path = format!("icons/items/{id}.png");
-            let icon = asset_server.load(item_path);
+        for (item_id, item_data) in item_manifest {
+            let icon = asset_server.load(item_data.icon_path());
             map.insert(item_id, icon);
         }
 
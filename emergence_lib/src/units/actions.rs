@@ -397,7 +397,7 @@ pub(super) fn finish_actions(
                                         maybe_storage_inventory
                                     {
                                         let storage_result = storage_inventory
-                                            .add_item_all_or_nothing(&item_count, item_manifest);
+                                            .store_item_all_or_nothing(&item_count, item_manifest);
                                         match storage_result {
                                             Ok(()) => Ok(()),
                                             Err(AddOneItemError { excess_count }) => {
@@ -472,7 +472,7 @@ pub(super) fn finish_actions(
                 }
                 UnitAction::Eat => {
                     if let Some(held_item) = unit.unit_inventory.held_item {
-                        let unit_data = unit_manifest.get(*unit.unit_id);
+                        let unit_data = unit_manifest.get_unchecked(*unit.unit_id);
 
                         let diet = &unit_data.diet;
 
@@ -1013,7 +1013,9 @@ impl CurrentAction {
             PATH_MULTIPLIER
         } else {
             let terrain_standing_on = terrain_query.get(entity_standing_on).unwrap();
-            terrain_manifest.get(*terrain_standing_on).walking_speed
+            terrain_manifest
+                .get_unchecked(*terrain_standing_on)
+                .walking_speed
         };
 
         let walking_duration = UnitAction::MoveForward.duration().as_secs_f32() / walking_speed;
@@ -123,7 +123,7 @@ pub(super) fn check_for_hunger(
                 };
             }
 
-            let diet = &unit_manifest.get(*unit_id).diet;
+            let diet = &unit_manifest.get_unchecked(*unit_id).diet;
             *goal = Goal::Eat(diet.item_kind);
         } else if matches!(*goal, Goal::Eat(..)) && energy_pool.is_satiated() {
             *goal = Goal::Wander {
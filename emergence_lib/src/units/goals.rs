@@ -246,7 +246,7 @@ pub(super) fn choose_goal(
         }
 
         if let Goal::Wander { remaining_actions } = *goal {
-            let wandering_behavior = &unit_manifest.get(unit_id).wandering_behavior;
+            let wandering_behavior = &unit_manifest.get_unchecked(unit_id).wandering_behavior;
             *goal = compute_new_goal(
                 unit_id,
                 remaining_actions,
@@ -162,8 +162,7 @@ impl UnitBundle {
         rng: &mut impl Rng,
     ) -> Self {
         let scene_handle = unit_handles.scenes.get(&unit_id).unwrap();
-        let mut energy_pool = unit_data.organism_variety.energy_pool;
-        energy_pool.randomize(rng);
+        let energy_pool = unit_data.organism_variety.spawn_energy_pool(rng);
         let age = Age::randomized(rng, unit_data.max_age);
 
         UnitBundle {
@@ -200,8 +199,7 @@ impl UnitBundle {
         rng: &mut impl Rng,
     ) -> Self {
         let scene_handle = Handle::default();
-        let mut energy_pool = unit_data.organism_variety.energy_pool;
-        energy_pool.randomize(rng);
+        let energy_pool = unit_data.organism_variety.spawn_energy_pool(rng);
         let age = Age::randomized(rng, unit_data.max_age);
 
         UnitBundle {
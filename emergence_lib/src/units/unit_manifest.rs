@@ -4,10 +4,11 @@ use bevy::{
     reflect::{FromReflect, Reflect, TypeUuid},
     utils::HashMap,
 };
+use emergence_macros::RawManifest;
 use serde::{Deserialize, Serialize};
 
+use crate as emergence_lib;
 use crate::{
-    asset_management::manifest::loader::IsRawManifest,
     organisms::{OrganismVariety, RawOrganismVariety},
     simulation::time::Days,
     units::{basic_needs::Diet, WanderingBehavior},
@@ -54,6 +55,7 @@ impl UnitData {
 
 /// The unprocessed equivalent of [`UnitData`].
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "strict_manifests", serde(deny_unknown_fields))]
 pub struct RawUnitData {
     /// The data shared by all organisms
     pub organism_variety: RawOrganismVariety,
@@ -88,29 +90,14 @@ impl From<RawUnitData> for UnitData {
 }
 
 /// The [`UnitManifest`] as seen in the manifest file.
-#[derive(Debug, Clone, Serialize, Deserialize, TypeUuid, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, TypeUuid, PartialEq, RawManifest)]
 #[uuid = "c8f6e1a1-20a0-4629-8df1-2e1fa313fcb9"]
+#[raw_manifest(extension = "unit_manifest", marker = Unit, data = UnitData)]
 pub struct RawUnitManifest {
+    /// The version of the manifest format that this file is written in.
+    ///
+    /// This must match [`IsRawManifest::CURRENT_VERSION`](crate::asset_management::manifest::loader::IsRawManifest::CURRENT_VERSION).
+    pub version: u32,
     /// The data for each item.
     pub unit_types: HashMap<String, RawUnitData>,
 }
-
-impl IsRawManifest for RawUnitManifest {
-    const EXTENSION: &'static str = "unit_manifest.json";
-
-    type Marker = Unit;
-    type Data = UnitData;
-
-    fn process(&self) -> Manifest<Self::Marker, Self::Data> {
-        let mut manifest = Manifest::new();
-
-        for (raw_id, raw_data) in self.unit_types.clone() {
-            let data = raw_data.into();
-
-            // No additional preprocessing is needed.
-            manifest.insert(raw_id, data)
-        }
-
-        manifest
-    }
-}
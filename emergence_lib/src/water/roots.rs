@@ -102,7 +102,7 @@ pub(super) fn draw_water_from_roots(
 
         let water_tiles_requested = water_config.items_to_tiles(water_items_requested);
 
-        let root_zone = match &structure_manifest.get(structure_id).root_zone {
+        let root_zone = match &structure_manifest.get_unchecked(structure_id).root_zone {
             Some(root_zone) => root_zone,
             None => continue,
         };
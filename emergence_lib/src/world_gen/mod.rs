@@ -6,6 +6,7 @@ use crate::terrain::terrain_manifest::Terrain;
 use crate::units::unit_manifest::Unit;
 use crate::utils::noise::SimplexSettings;
 use crate::world_gen::organism_generation::{generate_organisms, randomize_starting_organisms};
+use crate::world_gen::starting_items::stock_starting_storage;
 use crate::world_gen::terrain_generation::{
     generate_landmarks, generate_terrain, initialize_water_table,
 };
@@ -15,6 +16,7 @@ use bevy::utils::HashMap;
 use bevy_framepace::{FramepaceSettings, Limiter};
 
 mod organism_generation;
+mod starting_items;
 mod terrain_generation;
 
 /// Generate the world.
@@ -38,6 +40,7 @@ impl Plugin for GenerationPlugin {
                     generate_organisms,
                     apply_system_buffers,
                     randomize_starting_organisms,
+                    stock_starting_storage,
                 )
                     .chain()
                     .in_schedule(OnEnter(WorldGenState::Generating)),
@@ -366,6 +369,40 @@ mod tests {
         app.update();
     }
 
+    #[test]
+    fn starting_items_are_placed_in_storage() {
+        use crate::asset_management::manifest::loader::IsRawManifest;
+        use crate::crafting::inventories::StorageInventory;
+        use crate::items::item_manifest::{ItemManifest, RawItemManifest};
+        use crate::items::starter_manifest::{RawStarterManifest, StarterInventory};
+
+        let raw_item_manifest: RawItemManifest = serde_json::from_str(
+            r#"{
+                "version": 1,
+                "items": {
+                    "leaf": { "stack_size": 10, "compostable": true, "fluid": false, "buoyant": true }
+                }
+            }"#,
+        )
+        .unwrap();
+        let raw_starter_manifest: RawStarterManifest =
+            serde_json::from_str(r#"{ "version": 1, "items": { "leaf": 15 } }"#).unwrap();
+
+        let mut app = App::new();
+        app.insert_resource::<ItemManifest>(raw_item_manifest.process().unwrap());
+        app.insert_resource::<StarterInventory>(raw_starter_manifest.process().unwrap());
+        let storage = app.world.spawn(StorageInventory::new(3, None)).id();
+        app.add_startup_system(stock_starting_storage);
+
+        app.update();
+
+        let storage_inventory = app.world.get::<StorageInventory>(storage).unwrap();
+        assert_eq!(
+            storage_inventory.item_count(Id::from_name("leaf".to_string())),
+            15
+        );
+    }
+
     #[test]
     fn can_generate_world() {
         let mut app = App::new();
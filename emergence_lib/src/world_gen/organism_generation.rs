@@ -1,7 +1,9 @@
 //! Initializes organisms in the world.
 
 use crate::asset_management::manifest::Id;
-use crate::crafting::inventories::{CraftingState, InputInventory, OutputInventory};
+use crate::crafting::inventories::{
+    CraftingProgress, CraftingState, InputInventory, OutputInventory,
+};
 use crate::crafting::recipe::{ActiveRecipe, RecipeManifest};
 use crate::geometry::{Facing, MapGeometry};
 use crate::organisms::energy::{EnergyPool, StartingEnergy};
@@ -39,7 +41,7 @@ pub(super) fn generate_organisms(
                     ClipboardData::generate_from_id(structure_id, &structure_manifest);
                 let facing = Facing::random(rng.get_mut());
                 clipboard_data.facing = facing;
-                let footprint = &structure_manifest.get(structure_id).footprint;
+                let footprint = &structure_manifest.get_unchecked(structure_id).footprint;
 
                 // Only try to spawn a structure if the location is valid and there is space
                 if map_geometry.is_footprint_valid(voxel_pos, footprint, facing)
@@ -62,7 +64,7 @@ pub(super) fn generate_organisms(
                     UnitBundle::randomized(
                         unit_id,
                         voxel_pos,
-                        unit_manifest.get(unit_id).clone(),
+                        unit_manifest.get_unchecked(unit_id).clone(),
                         unit_handles,
                         rng.get_mut(),
                     )
@@ -70,7 +72,7 @@ pub(super) fn generate_organisms(
                     UnitBundle::testing(
                         unit_id,
                         voxel_pos,
-                        unit_manifest.get(unit_id).clone(),
+                        unit_manifest.get_unchecked(unit_id).clone(),
                         rng.get_mut(),
                     )
                 };
@@ -87,7 +89,7 @@ pub(super) fn randomize_starting_organisms(
     mut energy_pool_query: Query<&mut EnergyPool, Without<Id<Structure>>>,
     mut input_inventory_query: Query<&mut InputInventory>,
     mut output_inventory_query: Query<&mut OutputInventory>,
-    mut crafting_state_query: Query<(&mut CraftingState, &ActiveRecipe)>,
+    mut crafting_state_query: Query<(&mut CraftingState, &mut CraftingProgress, &ActiveRecipe)>,
     recipe_manifest: Res<RecipeManifest>,
 ) {
     let rng = &mut thread_rng();
@@ -104,10 +106,16 @@ pub(super) fn randomize_starting_organisms(
         output_inventory.randomize(rng)
     }
 
-    for (mut crafting_state, active_recipe) in crafting_state_query.iter_mut() {
+    for (mut crafting_state, mut crafting_progress, active_recipe) in
+        crafting_state_query.iter_mut()
+    {
         if let Some(recipe_id) = active_recipe.recipe_id() {
-            let recipe_data = recipe_manifest.get(*recipe_id);
+            let recipe_data = recipe_manifest.get_unchecked(*recipe_id);
             crafting_state.randomize(rng, recipe_data);
+
+            if let CraftingState::InProgress { progress, .. } = *crafting_state {
+                *crafting_progress = CraftingProgress::new(progress);
+            }
         }
     }
 }
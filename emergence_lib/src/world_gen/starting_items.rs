@@ -0,0 +1,42 @@
+//! Places the items that a new colony starts with.
+
+use bevy::prelude::*;
+
+use crate::crafting::inventories::StorageInventory;
+use crate::items::item_manifest::ItemManifest;
+use crate::items::starter_manifest::StarterInventory;
+use crate::items::ItemCount;
+
+/// Deposits the items of the [`StarterInventory`] into the storage structures of the generated world.
+///
+/// Each item fills up the storage that accepts it, one structure after another.
+/// Items that do not fit anywhere are lost, with a warning.
+pub(super) fn stock_starting_storage(
+    starter_inventory: Res<StarterInventory>,
+    item_manifest: Res<ItemManifest>,
+    mut storage_query: Query<&mut StorageInventory>,
+) {
+    info!("Stocking starting storage...");
+
+    for (item_id, &count) in starter_inventory.iter() {
+        let mut remaining = ItemCount::new(item_id, count);
+
+        for mut storage in storage_query.iter_mut() {
+            if remaining.count == 0 {
+                break;
+            }
+
+            if storage.currently_accepts(item_id, &item_manifest) {
+                remaining = storage.deposit(remaining, &item_manifest);
+            }
+        }
+
+        if remaining.count > 0 {
+            warn!(
+                "No storage left for {} starting {}",
+                remaining.count,
+                item_manifest.name(item_id)
+            );
+        }
+    }
+}
@@ -109,7 +109,7 @@ pub(super) fn generate_landmarks(
                     ClipboardData::generate_from_id(structure_id, &structure_manifest);
                 let facing = Facing::random(rng.get_mut());
                 clipboard_data.facing = facing;
-                let footprint = &structure_manifest.get(structure_id).footprint;
+                let footprint = &structure_manifest.get_unchecked(structure_id).footprint;
 
                 // Only try to spawn a structure if the location is valid and there is space
                 if map_geometry.is_footprint_valid(voxel_pos, footprint, facing)
@@ -0,0 +1,67 @@
+//! Generates the JSON Schemas for the manifest files, so that editors can offer autocomplete.
+//!
+//! Running the tests writes the schemas to `emergence_game/assets/schemas`,
+//! keeping them in sync with the raw manifest types.
+
+use std::path::PathBuf;
+
+use emergence_lib::{crafting::recipe::RawRecipeManifest, items::item_manifest::RawItemManifest};
+use schemars::schema::{RootSchema, Schema};
+
+/// The folder that the manifest files refer to for their schemas.
+fn schema_folder() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../emergence_game/assets/schemas")
+}
+
+/// Writes the `schema` to the file `name` in the [`schema_folder`].
+fn write_schema(name: &str, schema: &RootSchema) {
+    let folder = schema_folder();
+    std::fs::create_dir_all(&folder).unwrap();
+
+    let mut contents = serde_json::to_string_pretty(schema).unwrap();
+    contents.push('\n');
+    std::fs::write(folder.join(name), contents).unwrap();
+}
+
+/// The names of the required properties of the type `name` defined in the `schema`.
+fn required_properties(schema: &RootSchema, name: &str) -> Vec<String> {
+    let Some(Schema::Object(definition)) = schema.definitions.get(name) else {
+        panic!("{name} is not defined by the schema");
+    };
+
+    let mut required: Vec<String> = definition
+        .object
+        .as_ref()
+        .unwrap()
+        .required
+        .iter()
+        .cloned()
+        .collect();
+    required.sort();
+    required
+}
+
+#[test]
+fn write_item_manifest_schema() {
+    let schema = RawItemManifest::json_schema();
+
+    assert_eq!(
+        required_properties(&schema, "RawItemData"),
+        vec!["buoyant", "compostable", "fluid"]
+    );
+
+    write_schema("items.schema.json", &schema);
+}
+
+#[test]
+fn write_recipe_manifest_schema() {
+    let schema = RawRecipeManifest::json_schema();
+
+    // `conditions` and `energy` may be left out
+    assert_eq!(
+        required_properties(&schema, "RawRecipeData"),
+        vec!["craft_time", "inputs", "outputs"]
+    );
+
+    write_schema("recipes.schema.json", &schema);
+}
@@ -1,11 +1,18 @@
+use std::path::Path;
+
 use bevy::utils::HashMap;
 use emergence_lib::{
+    asset_management::manifest::{
+        loader::{IsRawManifest, ManifestFormat},
+        Id,
+    },
     construction::RawConstructionStrategy,
     crafting::{
         item_tags::ItemTag,
         recipe::{
-            RawActiveRecipe, RawRecipeData, RawRecipeInput, RawRecipeManifest, RecipeConditions,
-            Threshold,
+            ConsumptionMode, OverflowPolicy, QualityRule, RawActiveRecipe, RawOutputCount,
+            RawRecipeData, RawRecipeInput, RawRecipeManifest, RecipeConditions, Threshold,
+            TimePhase,
         },
     },
     geometry::Height,
@@ -39,6 +46,7 @@ use leafwing_abilities::prelude::Pool;
 fn can_serialize_item_manifest() {
     // Create a new raw item manifest
     let raw_item_manifest = RawItemManifest {
+        version: RawItemManifest::CURRENT_VERSION,
         items: HashMap::from_iter(vec![
             (
                 "test_item".to_string(),
@@ -48,6 +56,15 @@ fn can_serialize_item_manifest() {
                     fluid: false,
                     buoyant: true,
                     seed: None,
+                    energy_content: Energy(0.),
+                    tags: Vec::new(),
+                    mass: 1.,
+                    decay_time: None,
+                    decays_into: None,
+                    tier: 0,
+                    name: None,
+                    description: None,
+                    icon: None,
                 },
             ),
             (
@@ -58,6 +75,15 @@ fn can_serialize_item_manifest() {
                     fluid: false,
                     buoyant: false,
                     seed: Some(RawOrganismId::Structure("test_organism".to_string())),
+                    energy_content: Energy(0.),
+                    tags: Vec::new(),
+                    mass: 1.,
+                    decay_time: None,
+                    decays_into: None,
+                    tier: 0,
+                    name: None,
+                    description: None,
+                    icon: None,
                 },
             ),
             (
@@ -68,6 +94,15 @@ fn can_serialize_item_manifest() {
                     fluid: true,
                     buoyant: false,
                     seed: None,
+                    energy_content: Energy(0.),
+                    tags: Vec::new(),
+                    mass: 1.,
+                    decay_time: None,
+                    decays_into: None,
+                    tier: 0,
+                    name: None,
+                    description: None,
+                    icon: None,
                 },
             ),
         ]),
@@ -84,10 +119,50 @@ fn can_serialize_item_manifest() {
     assert_eq!(raw_item_manifest, deserialized);
 }
 
+#[test]
+fn json_and_ron_item_manifests_are_equivalent() {
+    let json_manifest: RawItemManifest = ManifestFormat::Json
+        .deserialize(
+            Path::new("fixtures/test.item_manifest.json"),
+            include_bytes!("fixtures/test.item_manifest.json"),
+        )
+        .unwrap();
+    let ron_manifest: RawItemManifest = ManifestFormat::Ron
+        .deserialize(
+            Path::new("fixtures/test.item_manifest.ron"),
+            include_bytes!("fixtures/test.item_manifest.ron"),
+        )
+        .unwrap();
+
+    assert_eq!(json_manifest, ron_manifest);
+
+    let json_processed = json_manifest.process().unwrap();
+    let ron_processed = ron_manifest.process().unwrap();
+    assert_eq!(json_processed.data_map(), ron_processed.data_map());
+    assert_eq!(json_processed.name_map(), ron_processed.name_map());
+}
+
+#[test]
+fn items_without_a_stack_size_use_the_default() {
+    let json = r#"{
+        "version": 1,
+        "items": {
+            "pebble": { "compostable": false, "fluid": false, "buoyant": false }
+        }
+    }"#;
+
+    let raw_item_manifest: RawItemManifest = serde_json::from_str(json).unwrap();
+    let item_manifest = raw_item_manifest.process().unwrap();
+    let pebble = item_manifest.get_unchecked(Id::from_name("pebble".to_string()));
+
+    assert_eq!(pebble.stack_size, RawItemData::DEFAULT_STACK_SIZE);
+}
+
 #[test]
 fn can_serialize_terrain_manifest() {
     // Create a new raw terrain manifest
     let raw_terrain_manifest = RawTerrainManifest {
+        version: RawTerrainManifest::CURRENT_VERSION,
         terrain_types: HashMap::from_iter(vec![(
             "test_terrain".to_string(),
             TerrainData {
@@ -114,6 +189,7 @@ fn can_serialize_terrain_manifest() {
 fn can_serialize_unit_manifest() {
     // Create a new raw unit manifest
     let raw_unit_manifest = RawUnitManifest {
+        version: RawUnitManifest::CURRENT_VERSION,
         unit_types: HashMap::from_iter(vec![
             (
                 "ant".to_string(),
@@ -122,6 +198,7 @@ fn can_serialize_unit_manifest() {
                         prototypical_form: RawOrganismId::unit("ant"),
                         lifecycle: RawLifecycle::STATIC,
                         energy_pool: EnergyPool::new_full(Energy(100.), Energy(-1.)),
+                        starting_energy: None,
                     },
                     diet: RawDiet::new("leuco_chunk", 50.),
                     max_impatience: 10,
@@ -140,6 +217,7 @@ fn can_serialize_unit_manifest() {
                         prototypical_form: RawOrganismId::unit("test_unit"),
                         lifecycle: RawLifecycle::STATIC,
                         energy_pool: EnergyPool::new_full(Energy(50.), Energy(0.)),
+                        starting_energy: None,
                     },
                     diet: RawDiet::new("acacia_leaf", 0.),
                     max_impatience: 0,
@@ -165,22 +243,52 @@ fn can_serialize_unit_manifest() {
 fn can_serialize_recipe_manifest() {
     // Create a new raw recipe manifest
     let raw_recipe_manifest = RawRecipeManifest {
+        version: RawRecipeManifest::CURRENT_VERSION,
         recipes: HashMap::from_iter(vec![
             (
                 "mature_acacia_production".to_string(),
                 RawRecipeData {
                     inputs: RawRecipeInput::empty(),
+                    catalysts: HashMap::new(),
+                    byproducts: HashMap::new(),
+                    output_one_of: Vec::new(),
                     outputs: HashMap::from_iter([
-                        ("acacia_leaf".to_string(), 1.),
+                        ("acacia_leaf".to_string(), RawOutputCount::Count(1.)),
                         // Output can be stochastic
-                        ("acacia_seed".to_string(), 0.1),
+                        ("acacia_seed".to_string(), RawOutputCount::Count(0.1)),
+                        // Or only produced some of the time
+                        (
+                            "acacia_flower".to_string(),
+                            RawOutputCount::Chance {
+                                count: 2,
+                                chance: 0.25,
+                            },
+                        ),
                     ]),
                     craft_time: 3.,
+                    work_units: None,
                     conditions: Some(RecipeConditions::new(
                         0,
                         Threshold::new(Illuminance::DimlyLit, Illuminance::BrightlyLit),
                     )),
                     energy: Some(Energy(20.)),
+                    allow_mass_gain: false,
+                    allow_self_production: false,
+                    requires: Vec::new(),
+                    research_cost: HashMap::new(),
+                    fluid_inputs: HashMap::new(),
+                    fluid_outputs: HashMap::new(),
+                    consumption: ConsumptionMode::AtStart,
+                    overflow: OverflowPolicy::Block,
+                    output_slots: HashMap::new(),
+                    max_batch: 1,
+                    next_recipe: None,
+                    output_tier: None,
+                    output_quality: QualityRule::default(),
+                    category: RawRecipeData::DEFAULT_CATEGORY.to_string(),
+                    active_phase: TimePhase::Always,
+                    requires_environment: None,
+                    enabled: true,
                 },
             ),
             (
@@ -190,23 +298,71 @@ fn can_serialize_recipe_manifest() {
                         tag: ItemTag::Compostable,
                         count: 1,
                     },
-                    outputs: HashMap::from_iter([("leuco_chunk".to_string(), 1.)]),
+                    catalysts: HashMap::new(),
+                    byproducts: HashMap::new(),
+                    output_one_of: Vec::new(),
+                    outputs: HashMap::from_iter([(
+                        "leuco_chunk".to_string(),
+                        RawOutputCount::Count(1.),
+                    )]),
                     craft_time: 2.,
+                    work_units: None,
                     conditions: None,
                     energy: Some(Energy(40.)),
+                    allow_mass_gain: false,
+                    allow_self_production: false,
+                    requires: Vec::new(),
+                    research_cost: HashMap::new(),
+                    fluid_inputs: HashMap::new(),
+                    fluid_outputs: HashMap::new(),
+                    consumption: ConsumptionMode::AtStart,
+                    overflow: OverflowPolicy::Block,
+                    output_slots: HashMap::new(),
+                    max_batch: 1,
+                    next_recipe: None,
+                    output_tier: None,
+                    output_quality: QualityRule::default(),
+                    category: RawRecipeData::DEFAULT_CATEGORY.to_string(),
+                    active_phase: TimePhase::Always,
+                    requires_environment: None,
+                    enabled: true,
                 },
             ),
             (
                 "ant_egg_production".to_string(),
                 RawRecipeData {
                     inputs: RawRecipeInput::single("leuco_chunk", 1),
-                    outputs: HashMap::from_iter([("ant_egg".to_string(), 1.)]),
+                    catalysts: HashMap::new(),
+                    byproducts: HashMap::new(),
+                    output_one_of: Vec::new(),
+                    outputs: HashMap::from_iter([(
+                        "ant_egg".to_string(),
+                        RawOutputCount::Count(1.),
+                    )]),
                     craft_time: 10.,
+                    work_units: None,
                     conditions: Some(RecipeConditions {
                         workers_required: 2,
                         allowable_light_range: None,
                     }),
                     energy: None,
+                    allow_mass_gain: false,
+                    allow_self_production: false,
+                    requires: Vec::new(),
+                    research_cost: HashMap::new(),
+                    fluid_inputs: HashMap::new(),
+                    fluid_outputs: HashMap::new(),
+                    consumption: ConsumptionMode::AtStart,
+                    overflow: OverflowPolicy::Block,
+                    output_slots: HashMap::new(),
+                    max_batch: 1,
+                    next_recipe: None,
+                    output_tier: None,
+                    output_quality: QualityRule::default(),
+                    category: RawRecipeData::DEFAULT_CATEGORY.to_string(),
+                    active_phase: TimePhase::Always,
+                    requires_environment: None,
+                    enabled: true,
                 },
             ),
         ]),
@@ -227,6 +383,7 @@ fn can_serialize_recipe_manifest() {
 fn can_serialize_structure_manifest() {
     // Create a new raw structure manifest
     let raw_structure_manifest = RawStructureManifest {
+        version: RawStructureManifest::CURRENT_VERSION,
         structure_types: HashMap::from_iter(vec![
             (
                 "leuco".to_string(),
@@ -235,6 +392,7 @@ fn can_serialize_structure_manifest() {
                         prototypical_form: RawOrganismId::structure("leuco"),
                         lifecycle: RawLifecycle::STATIC,
                         energy_pool: EnergyPool::new_full(Energy(100.), Energy(-1.)),
+                        starting_energy: None,
                     }),
                     kind: RawStructureKind::Crafting {
                         starting_recipe: RawActiveRecipe::new("leuco_chunk_production"),
@@ -293,6 +451,7 @@ fn can_serialize_structure_manifest() {
                             time_required: Some(1.),
                         }]),
                         energy_pool: EnergyPool::new_full(Energy(50.), Energy(-1.)),
+                        starting_energy: None,
                     }),
                     kind: RawStructureKind::Crafting {
                         starting_recipe: RawActiveRecipe::new("acacia_leaf_production"),
@@ -316,6 +475,7 @@ fn can_serialize_structure_manifest() {
                         prototypical_form: RawOrganismId::structure("acacia"),
                         lifecycle: RawLifecycle::STATIC,
                         energy_pool: EnergyPool::new_full(Energy(300.), Energy(-1.)),
+                        starting_energy: None,
                     }),
                     kind: RawStructureKind::Crafting {
                         starting_recipe: RawActiveRecipe::new("acacia_leaf_production"),
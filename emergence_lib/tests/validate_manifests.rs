@@ -0,0 +1,241 @@
+use std::path::Path;
+
+use emergence_lib::{
+    asset_management::manifest::{
+        errors::{DanglingReference, ManifestError, OutOfRangeValue},
+        export::NameLookup,
+        loader::IsRawManifest,
+        plugin::ManifestLayers,
+        registry::ManifestRegistry,
+        validation::{read_raw_manifest, validate_manifests, ManifestProblem},
+    },
+    crafting::recipe::{RawRecipeManifest, RecipeManifest},
+    items::item_manifest::{ItemManifest, RawItemManifest},
+    structures::structure_manifest::RawStructureManifest,
+    units::unit_manifest::RawUnitManifest,
+};
+
+#[test]
+fn game_manifests_are_valid() {
+    let asset_folder = Path::new(env!("CARGO_MANIFEST_DIR")).join("../emergence_game/assets");
+
+    assert_eq!(
+        validate_manifests(&asset_folder, &ManifestLayers::default()),
+        Vec::new()
+    );
+}
+
+#[test]
+fn broken_recipes_are_reported() {
+    let asset_folder = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/broken_recipe");
+
+    assert_eq!(
+        validate_manifests(&asset_folder, &ManifestLayers::default()),
+        vec![ManifestProblem {
+            manifest: RawRecipeManifest::path(),
+            error: ManifestError::UnknownReference(vec![DanglingReference {
+                entry: "make_honey".to_string(),
+                name: "honey".to_string(),
+            }]),
+        }]
+    );
+}
+
+#[test]
+fn problems_name_the_manifest_and_describe_the_error_once() {
+    let problem = ManifestProblem {
+        manifest: RawRecipeManifest::path(),
+        error: ManifestError::NoItems,
+    };
+
+    assert_eq!(
+        problem.to_string(),
+        format!(
+            "{}: No items are defined, but recipes require them",
+            RawRecipeManifest::path().display()
+        )
+    );
+    // Error reporters print the whole chain, which would repeat the error
+    assert!(std::error::Error::source(&problem).is_none());
+}
+
+#[test]
+fn empty_manifests_are_valid() {
+    let asset_folder = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/empty");
+    let layers = ManifestLayers::default();
+
+    assert_eq!(validate_manifests(&asset_folder, &layers), Vec::new());
+
+    let raw_items: RawItemManifest = read_raw_manifest(&asset_folder, &layers).unwrap();
+    let raw_recipes: RawRecipeManifest = read_raw_manifest(&asset_folder, &layers).unwrap();
+    assert!(raw_items.process().unwrap().is_empty());
+    assert!(raw_recipes.process().unwrap().is_empty());
+}
+
+#[test]
+fn processing_the_game_recipes_twice_gives_equal_manifests() {
+    let asset_folder = Path::new(env!("CARGO_MANIFEST_DIR")).join("../emergence_game/assets");
+    let layers = ManifestLayers::default();
+    let process = || {
+        let raw_recipes: RawRecipeManifest = read_raw_manifest(&asset_folder, &layers).unwrap();
+        raw_recipes.process().unwrap()
+    };
+
+    assert!(process() == process());
+}
+
+#[test]
+fn recipes_without_any_items_are_reported() {
+    let asset_folder = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/empty_items");
+
+    assert_eq!(
+        validate_manifests(&asset_folder, &ManifestLayers::default()),
+        vec![ManifestProblem {
+            manifest: RawRecipeManifest::path(),
+            error: ManifestError::NoItems,
+        }]
+    );
+}
+
+#[test]
+fn missing_manifests_are_reported() {
+    let asset_folder = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/missing");
+
+    let problems = validate_manifests(&asset_folder, &ManifestLayers::default());
+
+    assert_eq!(problems.len(), 7);
+    assert!(problems
+        .iter()
+        .all(|problem| problem.error == ManifestError::FileNotFound(problem.manifest.clone())));
+}
+
+#[test]
+fn registered_manifests_are_loaded_together() {
+    let asset_folder = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/empty");
+    let registry = ManifestRegistry::new()
+        .register::<RawItemManifest>()
+        .register::<RawRecipeManifest>()
+        // Registering a type twice does not load it twice
+        .register::<RawItemManifest>();
+
+    assert_eq!(
+        registry.paths().collect::<Vec<_>>(),
+        vec![RawItemManifest::path(), RawRecipeManifest::path()]
+    );
+
+    let world = registry
+        .load_all(&asset_folder, &ManifestLayers::default())
+        .unwrap();
+    assert!(world.resource::<ItemManifest>().is_empty());
+    assert!(world.resource::<RecipeManifest>().is_empty());
+}
+
+#[test]
+fn registered_manifests_report_all_of_their_problems() {
+    let asset_folder =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/broken_items_and_recipes");
+    let registry = ManifestRegistry::new()
+        .register::<RawItemManifest>()
+        .register::<RawRecipeManifest>();
+
+    let problems = registry
+        .load_all(&asset_folder, &ManifestLayers::default())
+        .unwrap_err();
+
+    assert_eq!(
+        problems,
+        vec![
+            ManifestProblem {
+                manifest: RawItemManifest::path(),
+                error: ManifestError::OutOfRange(vec![OutOfRangeValue {
+                    entry: "leaf".to_string(),
+                    field: "stack_size".to_string(),
+                    value: "0".to_string(),
+                    expected: "at least 1",
+                }]),
+            },
+            ManifestProblem {
+                manifest: RawRecipeManifest::path(),
+                error: ManifestError::OutOfRange(vec![OutOfRangeValue {
+                    entry: "make_mulch".to_string(),
+                    field: "craft_time".to_string(),
+                    value: "-1".to_string(),
+                    expected: "between 0 and 3600 seconds",
+                }]),
+            },
+        ]
+    );
+}
+
+#[test]
+fn manifests_in_directories_are_discovered_and_merged() {
+    let asset_folder = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/discovered");
+    let layers = ManifestLayers {
+        layers: Vec::new(),
+        directories: vec!["manifests/items".to_string()],
+        strict: false,
+    };
+
+    let raw_items: RawItemManifest = read_raw_manifest(&asset_folder, &layers).unwrap();
+
+    let mut names: Vec<&str> = raw_items.items.keys().map(String::as_str).collect();
+    names.sort_unstable();
+    assert_eq!(names, vec!["acacia_leaf", "pollen", "soil"]);
+    // `b_mod` is sorted after `a_base`, so it wins
+    assert_eq!(raw_items.items["acacia_leaf"].stack_size, 16);
+}
+
+#[test]
+fn game_manifests_survive_being_written_back_to_json() {
+    let asset_folder = Path::new(env!("CARGO_MANIFEST_DIR")).join("../emergence_game/assets");
+    let layers = ManifestLayers::default();
+    let raw_items: RawItemManifest = read_raw_manifest(&asset_folder, &layers).unwrap();
+    let raw_recipes: RawRecipeManifest = read_raw_manifest(&asset_folder, &layers).unwrap();
+    let raw_structures: RawStructureManifest = read_raw_manifest(&asset_folder, &layers).unwrap();
+    let raw_units: RawUnitManifest = read_raw_manifest(&asset_folder, &layers).unwrap();
+
+    let item_manifest = raw_items.process().unwrap();
+    let recipe_manifest = raw_recipes.process().unwrap();
+
+    // Seeds refer to organisms, and category inputs to the tags of items
+    let mut names = NameLookup::new();
+    names.add_manifest(&item_manifest);
+    names.add_names(raw_structures.entries().keys().cloned());
+    names.add_names(raw_units.entries().keys().cloned());
+    names.add_names(
+        raw_items
+            .items
+            .values()
+            .flat_map(|raw_data| raw_data.tags.iter().cloned()),
+    );
+
+    let item_json = item_manifest.to_raw_json(&names).unwrap();
+    let reloaded_items = serde_json::from_str::<RawItemManifest>(&item_json)
+        .unwrap()
+        .process()
+        .unwrap();
+    assert!(item_manifest.diff(&reloaded_items).is_empty());
+    assert_eq!(item_manifest.name_map(), reloaded_items.name_map());
+
+    let recipe_json = recipe_manifest.to_raw_json(&names).unwrap();
+    let reloaded_recipes = serde_json::from_str::<RawRecipeManifest>(&recipe_json)
+        .unwrap()
+        .process()
+        .unwrap();
+    assert!(recipe_manifest.diff(&reloaded_recipes).is_empty());
+    assert_eq!(recipe_manifest.name_map(), reloaded_recipes.name_map());
+}
+
+#[test]
+fn manifests_with_unknown_names_cannot_be_written() {
+    let asset_folder = Path::new(env!("CARGO_MANIFEST_DIR")).join("../emergence_game/assets");
+    let raw_recipes: RawRecipeManifest =
+        read_raw_manifest(&asset_folder, &ManifestLayers::default()).unwrap();
+    let recipe_manifest = raw_recipes.process().unwrap();
+
+    // The names of the items are missing
+    assert!(matches!(
+        recipe_manifest.to_raw_json(&NameLookup::new()),
+        Err(ManifestError::UnknownId { .. })
+    ));
+}
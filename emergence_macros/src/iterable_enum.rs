@@ -1,8 +1,6 @@
-use proc_macro2::Span;
 use proc_macro2::TokenStream;
-use proc_macro_crate::{crate_name, FoundCrate};
 use quote::quote;
-use syn::{Data, DeriveInput, Ident};
+use syn::{Data, DeriveInput};
 
 /// This approach and implementation is inspired by the `strum` crate,
 /// Copyright (c) 2019 Peter Glotfelty
@@ -13,26 +11,7 @@ pub(crate) fn iterable_enum_inner(ast: &DeriveInput) -> TokenStream {
     let enum_name = &ast.ident;
     let (impl_generics, type_generics, where_clause) = &ast.generics.split_for_impl();
 
-    let crate_path = if let Ok(found_crate) = crate_name("emergence_lib") {
-        // The crate was found in the Cargo.toml
-        match found_crate {
-            FoundCrate::Itself => quote!(emergence_lib),
-            FoundCrate::Name(name) => {
-                let ident = Ident::new(&name, Span::call_site());
-                quote!(#ident)
-            }
-        }
-    } else {
-        // The crate was not found in the Cargo.toml,
-        // so we assume that we are in the owning_crate itself
-        //
-        // In order for this to play nicely with unit tests within the crate itself,
-        // `use crate as emergence_lib` at the top of each test module
-        //
-        // Note that doc tests, integration tests and examples want the full standard import,
-        // as they are evaluated as if they were external
-        quote!(emergence_lib)
-    };
+    let crate_path = crate::emergence_lib_path();
 
     let variants = match &ast.data {
         Data::Enum(v) => &v.variants,
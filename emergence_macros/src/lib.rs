@@ -1,14 +1,18 @@
-//! Derives the [`IterableEnum`] trait
+//! Derives the [`IterableEnum`] and [`RawManifest`] traits
 //
-//! This derive macro was inspired by the `strum` crate's `EnumIter` macro.
+//! The `IterableEnum` derive macro was inspired by the `strum` crate's `EnumIter` macro.
 //! Original source: <https://github.com/Peternator7/strum>,
 //! Copyright (c) 2019 Peter Glotfelty under the MIT License
 
 mod iterable_enum;
+mod raw_manifest;
 
 extern crate proc_macro;
 use proc_macro::TokenStream;
-use syn::DeriveInput;
+use proc_macro2::Span;
+use proc_macro_crate::{crate_name, FoundCrate};
+use quote::quote;
+use syn::{DeriveInput, Ident};
 
 #[proc_macro_derive(IterableEnum)]
 pub fn iterable_enum(input: TokenStream) -> TokenStream {
@@ -16,3 +20,45 @@ pub fn iterable_enum(input: TokenStream) -> TokenStream {
 
     crate::iterable_enum::iterable_enum_inner(&ast).into()
 }
+
+/// Implements `IsRawManifest` for a raw manifest made of a `version` and a single `HashMap<String, RawData>` of entries.
+///
+/// The `#[raw_manifest(...)]` attribute takes the following arguments:
+/// - `extension`: the file extension of the manifest type, as a string.
+/// - `marker`: the marker type for the manifest ID.
+/// - `data`: the type of the processed data, which must implement `From<RawData>` unless `process` is given.
+/// - `check` (optional): a function taking `&self` that may fail processing before any entry is converted.
+/// - `process` (optional): a function taking `&self` that replaces the default processing entirely.
+/// - `validate` (optional): a function taking `&self` and `&World` that replaces the default validation.
+#[proc_macro_derive(RawManifest, attributes(raw_manifest))]
+pub fn raw_manifest(input: TokenStream) -> TokenStream {
+    let ast = syn::parse_macro_input!(input as DeriveInput);
+
+    crate::raw_manifest::raw_manifest_inner(&ast)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// The path to the `emergence_lib` crate, as seen from the crate that uses the macro.
+fn emergence_lib_path() -> proc_macro2::TokenStream {
+    if let Ok(found_crate) = crate_name("emergence_lib") {
+        // The crate was found in the Cargo.toml
+        match found_crate {
+            FoundCrate::Itself => quote!(emergence_lib),
+            FoundCrate::Name(name) => {
+                let ident = Ident::new(&name, Span::call_site());
+                quote!(#ident)
+            }
+        }
+    } else {
+        // The crate was not found in the Cargo.toml,
+        // so we assume that we are in the owning_crate itself
+        //
+        // In order for this to play nicely with unit tests within the crate itself,
+        // `use crate as emergence_lib` at the top of each test module
+        //
+        // Note that doc tests, integration tests and examples want the full standard import,
+        // as they are evaluated as if they were external
+        quote!(emergence_lib)
+    }
+}
@@ -0,0 +1,212 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    Data, DeriveInput, Fields, GenericArgument, Ident, LitStr, Path, PathArguments, Token, Type,
+};
+
+/// The arguments of the `#[raw_manifest(...)]` attribute.
+struct RawManifestArgs {
+    /// The file extension of the manifest type.
+    extension: LitStr,
+    /// The marker type for the manifest ID.
+    marker: Type,
+    /// The type of the processed manifest data.
+    data: Type,
+    /// A function that checks the raw manifest before its entries are converted.
+    check: Option<Path>,
+    /// A function that replaces the default processing.
+    process: Option<Path>,
+    /// A function that replaces the default validation.
+    validate: Option<Path>,
+}
+
+impl Parse for RawManifestArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut extension = None;
+        let mut marker = None;
+        let mut data = None;
+        let mut check = None;
+        let mut process = None;
+        let mut validate = None;
+
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+
+            match key.to_string().as_str() {
+                "extension" => extension = Some(input.parse()?),
+                "marker" => marker = Some(input.parse()?),
+                "data" => data = Some(input.parse()?),
+                "check" => check = Some(input.parse()?),
+                "process" => process = Some(input.parse()?),
+                "validate" => validate = Some(input.parse()?),
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!("unknown `raw_manifest` argument `{key}`"),
+                    ))
+                }
+            }
+
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+
+        let missing = |name: &str| input.error(format!("missing `raw_manifest` argument `{name}`"));
+
+        Ok(RawManifestArgs {
+            extension: extension.ok_or_else(|| missing("extension"))?,
+            marker: marker.ok_or_else(|| missing("marker"))?,
+            data: data.ok_or_else(|| missing("data"))?,
+            check,
+            process,
+            validate,
+        })
+    }
+}
+
+/// Finds the value type `V` of a `HashMap<String, V>`.
+fn map_value_type(ty: &Type) -> Option<&Type> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let PathArguments::AngleBracketed(arguments) = &type_path.path.segments.last()?.arguments
+    else {
+        return None;
+    };
+
+    match arguments.args.iter().nth(1)? {
+        GenericArgument::Type(value_type) => Some(value_type),
+        _ => None,
+    }
+}
+
+pub(crate) fn raw_manifest_inner(ast: &DeriveInput) -> syn::Result<TokenStream> {
+    let struct_name = &ast.ident;
+    let (impl_generics, type_generics, where_clause) = &ast.generics.split_for_impl();
+    let crate_path = crate::emergence_lib_path();
+
+    let attribute = ast
+        .attrs
+        .iter()
+        .find(|attribute| attribute.path.is_ident("raw_manifest"))
+        .ok_or_else(|| {
+            syn::Error::new_spanned(
+                struct_name,
+                "`RawManifest` requires a `#[raw_manifest(extension = \"...\", marker = ..., data = ...)]` attribute",
+            )
+        })?;
+    let RawManifestArgs {
+        extension,
+        marker,
+        data,
+        check,
+        process,
+        validate,
+    } = attribute.parse_args()?;
+
+    // Besides the version, the manifest holds a single map of its entries
+    let Data::Struct(data_struct) = &ast.data else {
+        return Err(syn::Error::new_spanned(
+            struct_name,
+            "`RawManifest` can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data_struct.fields else {
+        return Err(syn::Error::new_spanned(
+            struct_name,
+            "`RawManifest` can only be derived for structs with named fields",
+        ));
+    };
+    let mut entry_fields = fields
+        .named
+        .iter()
+        .filter(|field| field.ident.as_ref().is_none_or(|ident| ident != "version"));
+    let (Some(entry_field), None) = (entry_fields.next(), entry_fields.next()) else {
+        return Err(syn::Error::new_spanned(
+            struct_name,
+            "`RawManifest` requires a `version` field and exactly one map of entries",
+        ));
+    };
+    let entries = entry_field.ident.as_ref().unwrap();
+    let entries_type = &entry_field.ty;
+    let raw_data = map_value_type(entries_type).ok_or_else(|| {
+        syn::Error::new_spanned(
+            entries_type,
+            "the entries of a `RawManifest` must be a `HashMap<String, RawData>`",
+        )
+    })?;
+
+    let process_body = match (process, check) {
+        (Some(process), _) => quote! { #process(self) },
+        (None, check) => {
+            let check = check.map(|check| quote! { #check(self)?; });
+
+            quote! {
+                #check
+
+                let mut manifest = #crate_path::asset_management::manifest::Manifest::new();
+
+                // Insert the entries in order of their name, so that the result does not depend on the order of the map
+                let mut entries: ::std::vec::Vec<_> = self.#entries.iter().collect();
+                entries.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+                for (raw_id, raw_data) in entries {
+                    manifest.insert(
+                        raw_id.clone(),
+                        <#data as ::core::convert::From<#raw_data>>::from(raw_data.clone()),
+                    );
+                }
+
+                Ok(manifest)
+            }
+        }
+    };
+
+    let validate = validate.map(|validate| {
+        quote! {
+            fn validate(
+                &self,
+                world: &::bevy::prelude::World,
+            ) -> ::core::result::Result<(), #crate_path::asset_management::manifest::errors::ManifestError> {
+                #validate(self, world)
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics #crate_path::asset_management::manifest::loader::IsRawManifest for #struct_name #type_generics #where_clause {
+            const EXTENSION: &'static str = #extension;
+
+            type Marker = #marker;
+            type Data = #data;
+            type RawData = #raw_data;
+
+            fn process(
+                &self,
+            ) -> ::core::result::Result<
+                #crate_path::asset_management::manifest::Manifest<Self::Marker, Self::Data>,
+                #crate_path::asset_management::manifest::errors::ManifestError,
+            > {
+                #process_body
+            }
+
+            fn version(&self) -> u32 {
+                self.version
+            }
+
+            fn entries(&self) -> &#entries_type {
+                &self.#entries
+            }
+
+            fn entries_mut(&mut self) -> &mut #entries_type {
+                &mut self.#entries
+            }
+
+            #validate
+        }
+    })
+}